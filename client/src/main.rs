@@ -1,11 +1,13 @@
 use core::client_functions::*;
 use core::request_types::{
     Condition, CreateRequests, CreateTableRequests, DropTableRequest, InsertColumnRequest,
-    InsertRowRequest, RenameTableRequest, SelectRequest, UpdateColumnRequest, UpdateRequest,
+    InsertRowRequest, RenameTableRequest, SelectRequest, StreamFormat, UpdateColumnRequest,
+    UpdateRequest,
 };
 use core::row::Row;
 use core::value::Value;
-use log::{error, LevelFilter};
+use futures::StreamExt;
+use log::{error, info, LevelFilter};
 use reqwest::Client;
 
 /// This main function demonstrates the usage of various client functions with example values.
@@ -17,6 +19,7 @@ use reqwest::Client;
 /// - `insert_column`: Inserts a new column into an existing table.
 /// - `insert_row`: Inserts a new row into an existing table.
 /// - `select`: Selects rows from a table based on a condition.
+/// - `select_stream`: Streams the selected rows as they arrive.
 /// - `update_table`: Updates rows in a table based on a condition.
 ///
 /// The example values used in this function are:
@@ -26,16 +29,20 @@ use reqwest::Client;
 /// - `test_value`, `test_value_3`: The values to be inserted into the table.
 /// - `13`, `27.55`, `128`, `17.78`: The values to be inserted into the table.
 ///
-/// The following curl commands do exactly the same as the rust code in this function
+/// The requests are authenticated as the user in `RUST_DB_USER` with the password in
+/// `RUST_DB_PASSWORD`, which default to `admin` and no password.
+///
+/// The following curl commands do exactly the same as the rust code in this function, given
+/// `-u admin:<PASSWORD>` to authenticate
 /// ```sh
 /// curl -X POST http://localhost:3000/drop_table -H "Content-Type: application/json" -d '{"name":"test_table"}'
 /// curl -X POST http://localhost:3000/drop_table -H "Content-Type: application/json" -d '{"name":"test_table2"}'
 /// curl -X POST http://localhost:3000/drop_table -H "Content-Type: application/json" -d '{"name":"test_drop_table"}'
 /// curl -X POST http://localhost:3000/create -H "Content-Type: application/json" -d '{"name":"test_table"}'
 /// curl -X POST http://localhost:3000/insert_column -H "Content-Type: application/json" -d '{"table_name":"test_table","key":"test_key","primary_key":true,"non_null":true,"unique":true,"foreign_key":null}'
-/// curl -X POST http://localhost:3000/insert_column -H "Content-Type: application/json" -d '{"table_name":"test_table","key":"test_key2","primary_key":true,"non_null":true,"unique":true,"foreign_key":null}'
-/// curl -X POST http://localhost:3000/insert_column -H "Content-Type: application/json" -d '{"table_name":"test_table","key":"test_key3","primary_key":true,"non_null":false,"unique":true,"foreign_key":null}'
-/// curl -X POST http://localhost:3000/create_table -H "Content-Type: application/json" -d '{"name":"test_table2","insert_column_requests":[{"table_name":"test_table","key":"test_key3","primary_key":true,"non_null":false,"unique":true,"foreign_key":null}]}'
+/// curl -X POST http://localhost:3000/insert_column -H "Content-Type: application/json" -d '{"table_name":"test_table","key":"test_key2","primary_key":false,"non_null":true,"unique":true,"foreign_key":null}'
+/// curl -X POST http://localhost:3000/insert_column -H "Content-Type: application/json" -d '{"table_name":"test_table","key":"test_key3","primary_key":false,"non_null":false,"unique":true,"foreign_key":null}'
+/// curl -X POST http://localhost:3000/create_table -H "Content-Type: application/json" -d '{"name":"test_table2","insert_column_requests":[{"table_name":"test_table","key":"test_key3","primary_key":false,"non_null":false,"unique":true,"foreign_key":null}]}'
 /// curl -X POST http://localhost:3000/rename_table -H "Content-Type: application/json" -d '{"current_name":"test_table2","new_name":"test_drop_table"}'
 /// curl -X POST http://localhost:3000/drop_table -H "Content-Type: application/json" -d '{"name":"test_drop_table"}'
 /// curl -X POST http://localhost:3000/insert_row -H "Content-Type: application/json" -d '{"table_name":"test_table","row":{"values":[{"Str":"test_value"},{"Int":13}]}}'
@@ -43,6 +50,7 @@ use reqwest::Client;
 /// curl -X POST http://localhost:3000/insert_row -H "Content-Type: application/json" -d '{"table_name":"test_table","row":{"values":[{"Str":"test_value_3"},{"Float":17.78}]}}'
 /// curl -X POST http://localhost:3000/select -H "Content-Type: application/json" -d '{"table_name":"test_table","columns":["test_key","test_key3"],"condition":null}'
 /// curl -X POST http://localhost:3000/select -H "Content-Type: application/json" -d '{"table_name":"test_table","columns":["test_key","test_key3"],"condition":{"column":"test_key","value":"true"}}'
+/// curl -X POST "http://localhost:3000/select?stream=chunked" -H "Content-Type: application/json" -d '{"table_name":"test_table","columns":null,"condition":null}'
 /// curl -X POST http://localhost:3000/update_table -H "Content-Type: application/json" -d '{"table_name":"test_table","condition":{"column":"test_key","value":"true"},"updates":[{"column":"test_key3","value":"updated_value"},{"column":"test_key2","value":"17.78"}]}'
 /// ```
 #[tokio::main]
@@ -53,8 +61,12 @@ async fn main() {
         .init();
 
     let client = Client::new();
+    set_credentials(Some(Credentials::Basic {
+        username: std::env::var("RUST_DB_USER").unwrap_or_else(|_| "admin".to_string()),
+        password: std::env::var("RUST_DB_PASSWORD").unwrap_or_default(),
+    }));
 
-    if let Err(e) = client.post("http://localhost:3000").send().await {
+    if let Err(e) = ping(&client).await {
         error!("Error, is the server on? :{}", e);
         return;
     }
@@ -103,24 +115,36 @@ async fn main() {
         non_null: true,
         unique: true,
         foreign_key: None,
+        default: None,
+        check: None,
+        auto_increment: false,
+        data_type: None,
     };
 
     let insert_column_request2 = InsertColumnRequest {
         table_name: "test_table".to_string(),
         key: "test_key2".to_string(),
-        primary_key: true,
+        primary_key: false,
         non_null: true,
         unique: true,
         foreign_key: None,
+        default: None,
+        check: None,
+        auto_increment: false,
+        data_type: None,
     };
 
     let insert_column_request3 = InsertColumnRequest {
         table_name: "test_table".to_string(),
         key: "test_key3".to_string(),
-        primary_key: true,
+        primary_key: false,
         non_null: false,
         unique: true,
         foreign_key: None,
+        default: None,
+        check: None,
+        auto_increment: false,
+        data_type: None,
     };
 
     insert_column(&client, &insert_column_request)
@@ -139,6 +163,8 @@ async fn main() {
         &CreateTableRequests {
             name: "test_table2".to_string(),
             insert_column_requests: vec![insert_column_request3],
+            primary_key: Vec::new(),
+            unique_constraints: Vec::new(),
         },
     )
     .await
@@ -202,7 +228,8 @@ async fn main() {
         condition: None, // Add conditions if needed
     };
 
-    select(&client, &select_request).await.unwrap();
+    let rows = select(&client, &select_request).await.unwrap();
+    info!("Selected rows: {:?}", rows);
 
     // Select from the table with a condition
     let select_request = SelectRequest {
@@ -214,7 +241,23 @@ async fn main() {
         }),
     };
 
-    select(&client, &select_request).await.unwrap();
+    let rows = select(&client, &select_request).await.unwrap();
+    info!("Selected rows: {:?}", rows);
+
+    // Stream all rows of the table
+    let select_request = SelectRequest {
+        table_name: "test_table".to_string(),
+        columns: None,
+        condition: None,
+    };
+
+    let mut rows = select_stream(&client, &select_request, StreamFormat::Chunked)
+        .await
+        .unwrap();
+    info!("Streamed columns: {:?}", rows.columns());
+    while let Some(row) = rows.next().await {
+        info!("Streamed row: {:?}", row.unwrap());
+    }
 
     // Update rows in the table
     let update_request = UpdateRequest {
@@ -226,11 +269,11 @@ async fn main() {
         updates: vec![
             UpdateColumnRequest {
                 column: "test_key3".to_string(),
-                value: "updated_value".to_string(),
+                value: Some("updated_value".to_string()),
             },
             UpdateColumnRequest {
                 column: "test_key2".to_string(),
-                value: "17.78".to_string(),
+                value: Some("17.78".to_string()),
             },
         ],
     };
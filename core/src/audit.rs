@@ -0,0 +1,113 @@
+//! Entries of the audit log, which records who changed the schema and data of the database.
+//!
+//! The server appends an entry for every request that may change something, whether it
+//! succeeds or not, to an append-only file. The entries can be selected like a table from the
+//! read-only view `system.audit_log`, see [`catalog`](crate::catalog).
+use crate::row::Row;
+use crate::value::Value;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How a request recorded in the audit log ended.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// The request succeeded.
+    Success,
+    /// The request was rejected because the user lacks a privilege or violates a policy.
+    Denied,
+    /// The request failed for any other reason.
+    Failure,
+}
+
+impl Outcome {
+    /// Returns the outcome of a request answered with an HTTP status code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::audit::Outcome;
+    ///
+    /// assert_eq!(Outcome::from_status(200), Outcome::Success);
+    /// assert_eq!(Outcome::from_status(403), Outcome::Denied);
+    /// assert_eq!(Outcome::from_status(404), Outcome::Failure);
+    /// ```
+    pub fn from_status(status: u16) -> Outcome {
+        match status {
+            200..=399 => Outcome::Success,
+            401 | 403 => Outcome::Denied,
+            _ => Outcome::Failure,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Outcome::Success => "success",
+            Outcome::Denied => "denied",
+            Outcome::Failure => "failure",
+        })
+    }
+}
+
+/// An entry of the audit log, written as one line of JSON.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AuditEntry {
+    /// When the request was answered, in RFC 3339 format.
+    pub timestamp: String,
+    /// The user who sent the request.
+    pub principal: String,
+    /// The address the request came from, if known.
+    pub client_address: Option<String>,
+    /// The method and route of the request, e.g. `POST /drop_table`.
+    pub request: String,
+    /// The table the request targets, if any.
+    pub table_name: Option<String>,
+    /// The number of rows inserted, updated or imported, if the request changed rows.
+    pub row_count: Option<u64>,
+    pub outcome: Outcome,
+    /// The HTTP status code of the response.
+    pub status: u16,
+}
+
+impl AuditEntry {
+    /// Returns the entry as a row of `system.audit_log`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::audit::{AuditEntry, Outcome};
+    /// use core::value::Value;
+    ///
+    /// let entry = AuditEntry {
+    ///     timestamp: "2024-05-01T12:00:00.000Z".to_string(),
+    ///     principal: "alice".to_string(),
+    ///     client_address: Some("127.0.0.1:50412".to_string()),
+    ///     request: "POST /drop_table".to_string(),
+    ///     table_name: Some("orders".to_string()),
+    ///     row_count: None,
+    ///     outcome: Outcome::Success,
+    ///     status: 200,
+    /// };
+    /// let row = entry.to_row();
+    /// assert_eq!(row.values[1], Value::from("alice"));
+    /// assert_eq!(row.values[5], Value::Null);
+    /// assert_eq!(row.values[6], Value::from("success"));
+    /// ```
+    pub fn to_row(&self) -> Row {
+        let optional_str =
+            |value: &Option<String>| value.as_deref().map_or(Value::Null, Value::from);
+        Row::new(vec![
+            Value::from(self.timestamp.as_str()),
+            Value::from(self.principal.as_str()),
+            optional_str(&self.client_address),
+            Value::from(self.request.as_str()),
+            optional_str(&self.table_name),
+            self.row_count
+                .map_or(Value::Null, |count| Value::Int(count as i64)),
+            Value::Str(self.outcome.to_string()),
+            Value::Int(i64::from(self.status)),
+        ])
+    }
+}
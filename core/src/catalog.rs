@@ -0,0 +1,425 @@
+//! Read-only `information_schema` views describing the tables of the database, and `system`
+//! views describing the server.
+//!
+//! The views are built on demand from the current tables, so they can be queried with the
+//! same select requests as ordinary tables, e.g. `SELECT column_name FROM information_schema.columns
+//! WHERE table_name = 'users'`.
+//!
+//! - `information_schema.tables`: One row per table and view, with its column and row count.
+//! - `information_schema.columns`: One row per column, with its type, nullability and default.
+//! - `information_schema.table_constraints`: Primary keys, unique constraints, foreign keys and checks.
+//! - `information_schema.indexes`: The unique indexes backing primary keys and unique constraints.
+//! - `system.audit_log`: The entries of the [audit log](crate::audit), oldest first.
+//! - `system.statement_stats`: The [execution statistics](crate::statistics) of statements.
+use crate::audit::AuditEntry;
+use crate::column::{Column, ForeignKey};
+use crate::row::Row;
+use crate::sql::ast::Expr;
+use crate::statistics::StatementStats;
+use crate::table::{Table, TableSchema};
+use crate::value::{DataType, Value};
+
+/// The name of the schema containing the views.
+pub const SCHEMA_NAME: &str = "information_schema";
+
+/// The names of the views, without the schema prefix.
+pub const VIEW_NAMES: [&str; 4] = ["tables", "columns", "table_constraints", "indexes"];
+
+/// The name of the schema containing the views about the server, which the server fills.
+pub const SYSTEM_SCHEMA_NAME: &str = "system";
+
+/// The names of the views of the `system` schema, without the schema prefix.
+pub const SYSTEM_VIEW_NAMES: [&str; 2] = ["audit_log", "statement_stats"];
+
+/// The kind of a table constraint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConstraintType {
+    PrimaryKey,
+    Unique,
+    ForeignKey,
+    Check,
+}
+
+impl ConstraintType {
+    /// Returns the SQL name of the constraint type, e.g. `PRIMARY KEY`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConstraintType::PrimaryKey => "PRIMARY KEY",
+            ConstraintType::Unique => "UNIQUE",
+            ConstraintType::ForeignKey => "FOREIGN KEY",
+            ConstraintType::Check => "CHECK",
+        }
+    }
+}
+
+/// A constraint of a table, as listed by `information_schema.table_constraints`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstraintInfo {
+    pub name: String,
+    pub constraint_type: ConstraintType,
+    pub columns: Vec<String>,
+    /// The referenced column of a foreign key.
+    pub references: Option<ForeignKey>,
+    /// The expression of a check constraint.
+    pub check: Option<Expr>,
+}
+
+/// Returns whether `name` refers to the `information_schema` or `system` schema.
+///
+/// Such names cannot be used for ordinary tables.
+///
+/// # Arguments
+///
+/// * `name` - A table name, possibly qualified with a schema.
+pub fn is_catalog_name(name: &str) -> bool {
+    name.split_once('.').is_some_and(|(schema, _)| {
+        schema.eq_ignore_ascii_case(SCHEMA_NAME) || schema.eq_ignore_ascii_case(SYSTEM_SCHEMA_NAME)
+    })
+}
+
+/// Returns the name of the `system` view `name` refers to, without the schema prefix.
+///
+/// # Arguments
+///
+/// * `name` - A table name, possibly qualified with a schema. Both parts are matched
+///   case-insensitively.
+///
+/// # Examples
+///
+/// ```
+/// use core::catalog::system_view_name;
+///
+/// assert_eq!(system_view_name("SYSTEM.Audit_Log"), Some("audit_log"));
+/// assert_eq!(system_view_name("system.users"), None);
+/// assert_eq!(system_view_name("audit_log"), None);
+/// ```
+pub fn system_view_name(name: &str) -> Option<&'static str> {
+    let (schema, view_name) = name.split_once('.')?;
+    if !schema.eq_ignore_ascii_case(SYSTEM_SCHEMA_NAME) {
+        return None;
+    }
+    SYSTEM_VIEW_NAMES
+        .into_iter()
+        .find(|candidate| candidate.eq_ignore_ascii_case(view_name))
+}
+
+/// Builds the `system.audit_log` view.
+///
+/// # Arguments
+///
+/// * `entries` - The entries of the audit log, oldest first.
+pub fn audit_log(entries: &[AuditEntry]) -> Table {
+    let mut view = empty_view("audit_log");
+    for entry in entries {
+        view.add_row(entry.to_row());
+    }
+    view
+}
+
+/// Builds the `system.statement_stats` view.
+///
+/// # Arguments
+///
+/// * `stats` - The statistics of the statements.
+pub fn statement_stats(stats: &[StatementStats]) -> Table {
+    let mut view = empty_view("statement_stats");
+    for stats in stats {
+        view.add_row(stats.to_row());
+    }
+    view
+}
+
+/// Lists the constraints of a table.
+///
+/// The primary key is named `<table>_pkey`, foreign keys `<table>_<column>_fkey` and check
+/// constraints `<table>_<column>_check`. Unique constraints keep their own names.
+///
+/// # Arguments
+///
+/// * `table` - The schema of the table whose constraints are listed.
+///
+/// # Examples
+///
+/// ```
+/// use core::catalog::{table_constraints, ConstraintType};
+/// use core::column::Column;
+/// use core::table::Table;
+///
+/// let mut table = Table::new("users".to_string());
+/// table.add_column(Column::new("id".to_string(), true, true, false, None));
+/// table.add_column(Column::new("email".to_string(), false, false, true, None));
+///
+/// let constraints = table_constraints(&table.schema());
+/// assert_eq!(constraints[0].name, "users_pkey");
+/// assert_eq!(constraints[1].name, "users_email_key");
+/// assert_eq!(constraints[1].constraint_type, ConstraintType::Unique);
+/// ```
+pub fn table_constraints(table: &TableSchema) -> Vec<ConstraintInfo> {
+    let mut constraints = Vec::new();
+    if !table.primary_key.is_empty() {
+        constraints.push(ConstraintInfo {
+            name: format!("{}_pkey", table.name),
+            constraint_type: ConstraintType::PrimaryKey,
+            columns: table.primary_key.clone(),
+            references: None,
+            check: None,
+        });
+    }
+    for constraint in &table.unique_constraints {
+        constraints.push(ConstraintInfo {
+            name: constraint.name.clone(),
+            constraint_type: ConstraintType::Unique,
+            columns: constraint.columns.clone(),
+            references: None,
+            check: None,
+        });
+    }
+    for column in &table.columns {
+        if let Some(foreign_key) = &column.foreign_key {
+            constraints.push(ConstraintInfo {
+                name: format!("{}_{}_fkey", table.name, column.key),
+                constraint_type: ConstraintType::ForeignKey,
+                columns: vec![column.key.clone()],
+                references: Some(foreign_key.clone()),
+                check: None,
+            });
+        }
+    }
+    for column in &table.columns {
+        if let Some(check) = &column.check {
+            constraints.push(ConstraintInfo {
+                name: format!("{}_{}_check", table.name, column.key),
+                constraint_type: ConstraintType::Check,
+                columns: vec![column.key.clone()],
+                references: None,
+                check: Some(check.clone()),
+            });
+        }
+    }
+    constraints
+}
+
+/// Builds an `information_schema` view.
+///
+/// # Arguments
+///
+/// * `name` - The qualified name of the view, e.g. `information_schema.columns`. Both parts are
+///   matched case-insensitively.
+/// * `tables` - The schemas of the tables of the database.
+///
+/// # Returns
+///
+/// Returns the view as a table, or `None` if `name` is not a view.
+///
+/// # Examples
+///
+/// ```
+/// use core::catalog::view;
+/// use core::column::Column;
+/// use core::table::Table;
+/// use core::value::Value;
+///
+/// let mut table = Table::new("users".to_string());
+/// table.add_column(Column::new("id".to_string(), true, true, false, None));
+///
+/// let columns = view("information_schema.columns", &[table.schema()]).unwrap();
+/// assert_eq!(columns.rows[0].values[0], Value::Str("users".to_string()));
+/// assert_eq!(columns.rows[0].values[1], Value::Str("id".to_string()));
+/// ```
+pub fn view(name: &str, tables: &[TableSchema]) -> Option<Table> {
+    let (schema, view_name) = name.split_once('.')?;
+    if !schema.eq_ignore_ascii_case(SCHEMA_NAME) {
+        return None;
+    }
+    let view_name = VIEW_NAMES
+        .into_iter()
+        .find(|candidate| candidate.eq_ignore_ascii_case(view_name))?;
+
+    let mut view = empty_view(view_name);
+    let rows = match view_name {
+        "tables" => tables_rows(tables),
+        "columns" => columns_rows(tables),
+        "table_constraints" => table_constraints_rows(tables),
+        _ => indexes_rows(tables),
+    };
+    for row in rows {
+        view.add_row(row);
+    }
+    Some(view)
+}
+
+/// Creates a view without rows, with the columns of `view_name`.
+fn empty_view(view_name: &str) -> Table {
+    let columns: &[(&str, DataType)] = match view_name {
+        "tables" => &[
+            ("table_name", DataType::Str),
+            ("table_type", DataType::Str),
+            ("column_count", DataType::Int),
+            ("row_count", DataType::Int),
+        ],
+        "columns" => &[
+            ("table_name", DataType::Str),
+            ("column_name", DataType::Str),
+            ("ordinal_position", DataType::Int),
+            ("data_type", DataType::Str),
+            ("is_nullable", DataType::Str),
+            ("column_default", DataType::Str),
+            ("is_auto_increment", DataType::Bool),
+        ],
+        "table_constraints" => &[
+            ("constraint_name", DataType::Str),
+            ("table_name", DataType::Str),
+            ("constraint_type", DataType::Str),
+            ("column_names", DataType::Str),
+            ("referenced_table", DataType::Str),
+            ("referenced_column", DataType::Str),
+            ("check_clause", DataType::Str),
+        ],
+        "indexes" => &[
+            ("index_name", DataType::Str),
+            ("table_name", DataType::Str),
+            ("column_names", DataType::Str),
+            ("is_unique", DataType::Bool),
+            ("is_primary", DataType::Bool),
+        ],
+        "statement_stats" => &[
+            ("principal", DataType::Str),
+            ("statement", DataType::Str),
+            ("calls", DataType::Int),
+            ("total_time_ms", DataType::Float),
+            ("mean_time_ms", DataType::Float),
+            ("min_time_ms", DataType::Float),
+            ("max_time_ms", DataType::Float),
+            ("rows_scanned", DataType::Int),
+            ("rows_returned", DataType::Int),
+        ],
+        _ => &[
+            ("timestamp", DataType::Str),
+            ("principal", DataType::Str),
+            ("client_address", DataType::Str),
+            ("request", DataType::Str),
+            ("table_name", DataType::Str),
+            ("row_count", DataType::Int),
+            ("outcome", DataType::Str),
+            ("status", DataType::Int),
+        ],
+    };
+
+    let schema = match SYSTEM_VIEW_NAMES.contains(&view_name) {
+        true => SYSTEM_SCHEMA_NAME,
+        false => SCHEMA_NAME,
+    };
+    let mut view = Table::new(format!("{}.{}", schema, view_name));
+    for (name, data_type) in columns {
+        view.add_column(Column {
+            data_type: Some(*data_type),
+            ..Column::new(name.to_string(), false, false, false, None)
+        });
+    }
+    view
+}
+
+/// Returns every view without rows.
+fn all_views() -> impl Iterator<Item = Table> {
+    VIEW_NAMES
+        .into_iter()
+        .chain(SYSTEM_VIEW_NAMES)
+        .map(empty_view)
+}
+
+/// Returns the names and columns of the tables followed by those of the views.
+fn all_definitions(tables: &[TableSchema]) -> Vec<(String, Vec<Column>)> {
+    let mut definitions: Vec<(String, Vec<Column>)> = tables
+        .iter()
+        .map(|table| (table.name.clone(), table.columns.clone()))
+        .collect();
+    definitions.extend(all_views().map(|view| (view.name, view.columns)));
+    definitions
+}
+
+fn optional_str(value: Option<String>) -> Value {
+    value.map_or(Value::Null, Value::Str)
+}
+
+fn tables_rows(tables: &[TableSchema]) -> Vec<Row> {
+    let mut rows: Vec<Row> = tables
+        .iter()
+        .map(|table| {
+            Row::new(vec![
+                Value::from(table.name.as_str()),
+                Value::from("BASE TABLE"),
+                Value::Int(table.columns.len() as i64),
+                Value::Int(table.row_count as i64),
+            ])
+        })
+        .collect();
+    for view in all_views() {
+        rows.push(Row::new(vec![
+            Value::Str(view.name),
+            Value::from("SYSTEM VIEW"),
+            Value::Int(view.columns.len() as i64),
+            Value::Null,
+        ]));
+    }
+    rows
+}
+
+fn columns_rows(tables: &[TableSchema]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (table_name, columns) in all_definitions(tables) {
+        for (index, column) in columns.iter().enumerate() {
+            rows.push(Row::new(vec![
+                Value::from(table_name.as_str()),
+                Value::from(column.key.as_str()),
+                Value::Int(index as i64 + 1),
+                optional_str(column.data_type.map(|data_type| data_type.to_string())),
+                Value::from(match column.non_null {
+                    true => "NO",
+                    false => "YES",
+                }),
+                optional_str(column.default.as_ref().map(Value::to_sql)),
+                Value::Bool(column.auto_increment),
+            ]));
+        }
+    }
+    rows
+}
+
+fn table_constraints_rows(tables: &[TableSchema]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for table in tables {
+        for constraint in table_constraints(table) {
+            rows.push(Row::new(vec![
+                Value::Str(constraint.name),
+                Value::from(table.name.as_str()),
+                Value::from(constraint.constraint_type.as_str()),
+                Value::Str(constraint.columns.join(", ")),
+                optional_str(constraint.references.as_ref().map(|r| r.table_name.clone())),
+                optional_str(constraint.references.map(|r| r.column)),
+                optional_str(constraint.check.map(|check| check.to_string())),
+            ]));
+        }
+    }
+    rows
+}
+
+fn indexes_rows(tables: &[TableSchema]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for table in tables {
+        for constraint in table_constraints(table) {
+            let is_primary = match constraint.constraint_type {
+                ConstraintType::PrimaryKey => true,
+                ConstraintType::Unique => false,
+                ConstraintType::ForeignKey | ConstraintType::Check => continue,
+            };
+            rows.push(Row::new(vec![
+                Value::Str(constraint.name),
+                Value::from(table.name.as_str()),
+                Value::Str(constraint.columns.join(", ")),
+                Value::Bool(true),
+                Value::Bool(is_primary),
+            ]));
+        }
+    }
+    rows
+}
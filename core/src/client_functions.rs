@@ -1,204 +1,2383 @@
 //! Client Functions to interact with the server's API.
+//!
+//! Requests are sent to `http://localhost:3000` unless another address is set with
+//! [`set_server_url`], with the credentials set by [`set_credentials`] and the session settings
+//! set by [`set_setting`].
+use crate::csv::CsvOptions;
+use crate::explain::Explanation;
+use crate::policy::SETTING_HEADER_PREFIX;
+use crate::privilege::Grant;
 use crate::request_types::{
-    CreateRequests, CreateTableRequests, DropTableRequest, InsertColumnRequest, InsertRowRequest,
-    RenameTableRequest, SelectRequest, UpdateRequest,
+    AlterUserRequest, ColumnHeader, CreateIndexRequest, CreatePolicyRequest, CreateRequests,
+    CreateTableRequests, CreateUserRequest, CsvImportOptions, CsvImportResult, DeleteRequest,
+    DropPolicyRequest, DropTableRequest, DropUserRequest, DumpOptions, ExplainMode, GrantRequest,
+    GrantRoleRequest, GrantsQuery, InsertColumnRequest, InsertRowRequest, JoinRequest,
+    LoginRequest, LoginResponse, RenameTableRequest, RestoreResult, RoleInfo, RoleRequest,
+    RowsPage, RowsQuery, SelectFrame, SelectOptions, SelectRequest, ServerInfo, SnapshotInfo,
+    StreamFormat, UpdateRequest, UserInfo, WalStatus,
 };
+use crate::row::Row;
+use crate::table::TableSchema;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
 use log::{debug, error, info};
-use reqwest::Client;
+use reqwest::{Certificate, Client, Identity, RequestBuilder, Response};
 use serde_json::json;
+use std::collections::{BTreeMap, VecDeque};
 use std::error;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::RwLock;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Address of the server used by all client functions.
+static SERVER_URL: RwLock<Option<String>> = RwLock::new(None);
+
+/// The address used when [`set_server_url`] has not been called.
+pub const DEFAULT_SERVER_URL: &str = "http://localhost:3000";
+
+/// Sets the address of the server the client functions send their requests to.
+///
+/// # Arguments
+///
+/// * `url` - The base URL of the server, e.g. `http://db.example.com:3000`. A trailing `/` is ignored.
+///
+/// # Examples
+///
+/// ```
+/// use core::client_functions::{server_url, set_server_url};
+///
+/// set_server_url("http://127.0.0.1:4000/");
+/// assert_eq!(server_url(), "http://127.0.0.1:4000");
+/// ```
+pub fn set_server_url(url: &str) {
+    let mut server_url = SERVER_URL.write().unwrap_or_else(|e| e.into_inner());
+    *server_url = Some(url.trim_end_matches('/').to_string());
+}
+
+/// Returns the address of the server the client functions send their requests to.
+pub fn server_url() -> String {
+    SERVER_URL
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| DEFAULT_SERVER_URL.to_string())
+}
+
+/// Options for connecting to a server over HTTPS.
+#[derive(Clone, Debug, Default)]
+pub struct TlsOptions {
+    /// A PEM file of CA certificates to trust in addition to the system's, e.g. the
+    /// self-signed certificate of the server.
+    pub ca_cert: Option<PathBuf>,
+    /// A PEM file of the certificate presented to servers that verify clients.
+    pub client_cert: Option<PathBuf>,
+    /// The PEM file of the client certificate's private key, in PKCS #8 format.
+    pub client_key: Option<PathBuf>,
+}
+
+/// Builds an HTTP client that connects to servers as configured by `options`.
+///
+/// Pass it to the client functions after setting an `https://` address with
+/// [`set_server_url`].
+///
+/// # Arguments
+///
+/// * `options` - The CA certificates to trust and the client certificate to present.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or does not contain a certificate or key, or if
+/// only one of the client certificate and key is given.
+///
+/// # Examples
+///
+/// ```
+/// use core::client_functions::{build_client, set_server_url, TlsOptions};
+///
+/// let client = build_client(&TlsOptions::default()).unwrap();
+/// set_server_url("https://db.example.com:3000");
+///
+/// let options = TlsOptions {
+///     ca_cert: Some("missing-ca.pem".into()),
+///     ..TlsOptions::default()
+/// };
+/// assert!(build_client(&options).is_err());
+/// ```
+pub fn build_client(options: &TlsOptions) -> Result<Client, Box<dyn error::Error>> {
+    let read = |path: &PathBuf| {
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+    let mut builder = Client::builder();
+    if let Some(path) = &options.ca_cert {
+        builder = builder.add_root_certificate(Certificate::from_pem(&read(path)?)?);
+    }
+    match (&options.client_cert, &options.client_key) {
+        (Some(cert), Some(key)) => {
+            builder = builder.identity(Identity::from_pkcs8_pem(&read(cert)?, &read(key)?)?);
+        }
+        (None, None) => {}
+        _ => return Err("A client certificate needs both a certificate and a key".into()),
+    }
+    Ok(builder.build()?)
+}
+
+/// Credentials sent with every request.
+#[derive(Clone)]
+pub enum Credentials {
+    /// A user name and password, sent with HTTP Basic authentication. The server verifies the
+    /// password on every request, so prefer a token from [`login`] for many requests.
+    Basic { username: String, password: String },
+    /// A token issued by [`login`].
+    Bearer(String),
+}
+
+/// Credentials of all client functions.
+static CREDENTIALS: RwLock<Option<Credentials>> = RwLock::new(None);
+
+/// Sets the credentials the client functions send with their requests.
+///
+/// # Arguments
+///
+/// * `credentials` - The credentials, or `None` to send requests without any.
+///
+/// # Examples
+///
+/// ```
+/// use core::client_functions::{set_credentials, Credentials};
+///
+/// set_credentials(Some(Credentials::Basic {
+///     username: "admin".to_string(),
+///     password: "s3cret".to_string(),
+/// }));
+/// ```
+pub fn set_credentials(credentials: Option<Credentials>) {
+    let mut current = CREDENTIALS.write().unwrap_or_else(|e| e.into_inner());
+    *current = credentials;
+}
+
+/// Session settings sent with every request.
+static SETTINGS: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+/// Sets a session setting, which row-level security policies read with `current_setting`.
+///
+/// Settings are sent with every request as headers, so names may only contain letters, digits,
+/// `.`, `_` and `-`, and values may not contain control characters. Names are not case
+/// sensitive.
+///
+/// # Arguments
+///
+/// * `name` - The name of the setting, e.g. `app.tenant`.
+/// * `value` - The value, or `None` to reset the setting.
+///
+/// # Examples
+///
+/// ```
+/// use core::client_functions::{set_setting, settings};
+///
+/// set_setting("App.Tenant", Some("7")).unwrap();
+/// assert_eq!(settings().get("app.tenant").map(String::as_str), Some("7"));
+/// assert!(set_setting("app tenant", Some("7")).is_err());
+///
+/// set_setting("app.tenant", None).unwrap();
+/// assert!(settings().is_empty());
+/// ```
+pub fn set_setting(name: &str, value: Option<&str>) -> Result<(), String> {
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if !valid_name {
+        return Err(format!("Invalid setting name '{}'", name));
+    }
+    if value.is_some_and(|value| value.chars().any(char::is_control)) {
+        return Err(format!("Invalid value for setting '{}'", name));
+    }
+    let mut settings = SETTINGS.write().unwrap_or_else(|e| e.into_inner());
+    match value {
+        Some(value) => settings.insert(name.to_lowercase(), value.to_string()),
+        None => settings.remove(&name.to_lowercase()),
+    };
+    Ok(())
+}
+
+/// Resets all session settings.
+pub fn reset_settings() {
+    SETTINGS.write().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Returns the session settings sent with every request.
+pub fn settings() -> BTreeMap<String, String> {
+    SETTINGS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Attaches the credentials set with [`set_credentials`] and the settings set with
+/// [`set_setting`] to a request.
+fn authorize(mut request: RequestBuilder) -> RequestBuilder {
+    for (name, value) in SETTINGS.read().unwrap_or_else(|e| e.into_inner()).iter() {
+        request = request.header(format!("{}{}", SETTING_HEADER_PREFIX, name), value);
+    }
+    let credentials = CREDENTIALS.read().unwrap_or_else(|e| e.into_inner());
+    match credentials.as_ref() {
+        Some(Credentials::Basic { username, password }) => {
+            request.basic_auth(username, Some(password))
+        }
+        Some(Credentials::Bearer(token)) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+/// Checks that the server is up, without credentials.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+///
+/// # Returns
+///
+/// Returns the round-trip time of the request to the server's `/health` endpoint.
+///
+/// # Errors
+///
+/// Returns an error if the server cannot be reached or does not answer with a success.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::ping;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let round_trip = ping(&client).await.unwrap();
+///     println!("The server answered in {:?}", round_trip);
+/// }
+/// ```
+pub async fn ping(client: &Client) -> Result<Duration, Box<dyn error::Error>> {
+    let url = format!("{}/health", server_url());
+
+    let start = Instant::now();
+    let resp = client.get(&url).send().await?;
+    let round_trip = start.elapsed();
+
+    match resp.status().is_success() {
+        true => Ok(round_trip),
+        false => {
+            debug!("Ping Response: {:?}", resp);
+            let error_message = format!("The server answered with {}", resp.status());
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Gets the version, uptime and storage of the server.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::server_info;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let info = server_info(&client).await.unwrap();
+///     println!("Server {} with {} tables", info.version, info.table_count);
+/// }
+/// ```
+pub async fn server_info(client: &Client) -> Result<ServerInfo, Box<dyn error::Error>> {
+    let url = format!("{}/info", server_url());
+
+    let resp = authorize(client.get(&url)).send().await?;
+
+    match resp.status().is_success() {
+        true => Ok(resp.json::<ServerInfo>().await?),
+        false => {
+            debug!("Server Info Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Logs in and returns a bearer token.
+///
+/// The token is not used until it is passed to [`set_credentials`].
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `username` - The name of the user.
+/// * `password` - The password of the user.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::{login, set_credentials, Credentials};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let login_response = login(&client, "admin", "s3cret").await.unwrap();
+///     set_credentials(Some(Credentials::Bearer(login_response.token)));
+/// }
+/// ```
+pub async fn login(
+    client: &Client,
+    username: &str,
+    password: &str,
+) -> Result<LoginResponse, Box<dyn error::Error>> {
+    let url = format!("{}/login", server_url());
+    let login_request = LoginRequest {
+        username: username.to_string(),
+        password: password.to_string(),
+    };
+
+    let resp = client.post(&url).json(&login_request).send().await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Login Response: {:?}", resp);
+            let login_response = resp.json::<LoginResponse>().await?;
+            info!("Logged in as '{}'", login_response.username);
+            Ok(login_response)
+        }
+        false => {
+            debug!("Login Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Revokes the bearer token set with [`set_credentials`].
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::{logout, set_credentials};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     logout(&client).await.unwrap();
+///     set_credentials(None);
+/// }
+/// ```
+pub async fn logout(client: &Client) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/logout", server_url());
+
+    let resp = authorize(client.post(&url)).send().await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Logout Response: {:?}", resp);
+            Ok(())
+        }
+        false => {
+            debug!("Logout Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Creates a user on the server. Only admins may create users.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `create_user_request` - The name and password of the user, and whether it is an admin.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::create_user;
+/// use core::request_types::CreateUserRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let create_user_request = CreateUserRequest {
+///         username: "alice".to_string(),
+///         password: "correct horse battery staple".to_string(),
+///         admin: false,
+///     };
+///     create_user(&client, &create_user_request).await.unwrap();
+/// }
+/// ```
+pub async fn create_user(
+    client: &Client,
+    create_user_request: &CreateUserRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/create_user", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(create_user_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Create User Response: {:?}", resp);
+            info!("Created user '{}'", create_user_request.username);
+            Ok(())
+        }
+        false => {
+            debug!("Create User Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Changes the password of a user. Users may change their own password, admins any password.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `alter_user_request` - The name of the user and the new password.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::alter_user;
+/// use core::request_types::AlterUserRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let alter_user_request = AlterUserRequest {
+///         username: "alice".to_string(),
+///         password: "another secret".to_string(),
+///     };
+///     alter_user(&client, &alter_user_request).await.unwrap();
+/// }
+/// ```
+pub async fn alter_user(
+    client: &Client,
+    alter_user_request: &AlterUserRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/alter_user", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(alter_user_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Alter User Response: {:?}", resp);
+            info!("Changed the password of '{}'", alter_user_request.username);
+            Ok(())
+        }
+        false => {
+            debug!("Alter User Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Drops a user on the server. Only admins may drop users.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `drop_user_request` - The name of the user.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::drop_user;
+/// use core::request_types::DropUserRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let drop_user_request = DropUserRequest { username: "alice".to_string() };
+///     drop_user(&client, &drop_user_request).await.unwrap();
+/// }
+/// ```
+pub async fn drop_user(
+    client: &Client,
+    drop_user_request: &DropUserRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/drop_user", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(drop_user_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Drop User Response: {:?}", resp);
+            info!("Dropped user '{}'", drop_user_request.username);
+            Ok(())
+        }
+        false => {
+            debug!("Drop User Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Lists the users on the server. Only admins may list users.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::get_users;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     for user in get_users(&client).await.unwrap() {
+///         println!("{} (admin: {})", user.username, user.admin);
+///     }
+/// }
+/// ```
+pub async fn get_users(client: &Client) -> Result<Vec<UserInfo>, Box<dyn error::Error>> {
+    let url = format!("{}/users", server_url());
+
+    let resp = authorize(client.get(&url)).send().await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Get Users Response: {:?}", resp);
+            Ok(resp.json::<Vec<UserInfo>>().await?)
+        }
+        false => {
+            debug!("Get Users Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Grants privileges on the database, a table or some of its columns to a user or role. Only
+/// admins may grant privileges.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `grant_request` - The privileges, the table or `None` for the database, and the grantee.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::grant;
+/// use core::privilege::{Privilege, PrivilegeSpec};
+/// use core::request_types::GrantRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let grant_request = GrantRequest {
+///         privileges: vec![PrivilegeSpec {
+///             privilege: Privilege::Select,
+///             columns: Some(vec!["id".to_string(), "name".to_string()]),
+///         }],
+///         table: Some("users".to_string()),
+///         grantee: "analyst".to_string(),
+///     };
+///     grant(&client, &grant_request).await.unwrap();
+/// }
+/// ```
+pub async fn grant(
+    client: &Client,
+    grant_request: &GrantRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/grant", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(grant_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Grant Response: {:?}", resp);
+            info!("Granted privileges to '{}'", grant_request.grantee);
+            Ok(())
+        }
+        false => {
+            debug!("Grant Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Revokes privileges of a user or role. Only admins may revoke privileges.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `revoke_request` - The privileges, the table or `None` for the database, and the grantee.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::revoke;
+/// use core::privilege::{Privilege, PrivilegeSpec};
+/// use core::request_types::GrantRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let revoke_request = GrantRequest {
+///         privileges: vec![PrivilegeSpec { privilege: Privilege::Insert, columns: None }],
+///         table: None,
+///         grantee: "analyst".to_string(),
+///     };
+///     revoke(&client, &revoke_request).await.unwrap();
+/// }
+/// ```
+pub async fn revoke(
+    client: &Client,
+    revoke_request: &GrantRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/revoke", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(revoke_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Revoke Response: {:?}", resp);
+            info!("Revoked privileges of '{}'", revoke_request.grantee);
+            Ok(())
+        }
+        false => {
+            debug!("Revoke Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Creates a role, which privileges can be granted to and which can be granted to users. Only
+/// admins may create roles.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `role_request` - The name of the role.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::create_role;
+/// use core::request_types::RoleRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let role_request = RoleRequest { name: "analyst".to_string() };
+///     create_role(&client, &role_request).await.unwrap();
+/// }
+/// ```
+pub async fn create_role(
+    client: &Client,
+    role_request: &RoleRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/create_role", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(role_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Create Role Response: {:?}", resp);
+            info!("Created role '{}'", role_request.name);
+            Ok(())
+        }
+        false => {
+            debug!("Create Role Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Drops a role with its privileges. Only admins may drop roles.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `role_request` - The name of the role.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::drop_role;
+/// use core::request_types::RoleRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let role_request = RoleRequest { name: "analyst".to_string() };
+///     drop_role(&client, &role_request).await.unwrap();
+/// }
+/// ```
+pub async fn drop_role(
+    client: &Client,
+    role_request: &RoleRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/drop_role", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(role_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Drop Role Response: {:?}", resp);
+            info!("Dropped role '{}'", role_request.name);
+            Ok(())
+        }
+        false => {
+            debug!("Drop Role Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Grants a role to a user, who gets the privileges of the role. Only admins may grant roles.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `grant_role_request` - The role and the user.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::grant_role;
+/// use core::request_types::GrantRoleRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let grant_role_request = GrantRoleRequest {
+///         role: "analyst".to_string(),
+///         username: "alice".to_string(),
+///     };
+///     grant_role(&client, &grant_role_request).await.unwrap();
+/// }
+/// ```
+pub async fn grant_role(
+    client: &Client,
+    grant_role_request: &GrantRoleRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/grant_role", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(grant_role_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Grant Role Response: {:?}", resp);
+            info!(
+                "Granted role '{}' to '{}'",
+                grant_role_request.role, grant_role_request.username
+            );
+            Ok(())
+        }
+        false => {
+            debug!("Grant Role Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Revokes a role from a user. Only admins may revoke roles.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `revoke_role_request` - The role and the user.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::revoke_role;
+/// use core::request_types::GrantRoleRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let grant_role_request = GrantRoleRequest {
+///         role: "analyst".to_string(),
+///         username: "alice".to_string(),
+///     };
+///     revoke_role(&client, &grant_role_request).await.unwrap();
+/// }
+/// ```
+pub async fn revoke_role(
+    client: &Client,
+    revoke_role_request: &GrantRoleRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/revoke_role", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(revoke_role_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Revoke Role Response: {:?}", resp);
+            info!(
+                "Revoked role '{}' from '{}'",
+                revoke_role_request.role, revoke_role_request.username
+            );
+            Ok(())
+        }
+        false => {
+            debug!("Revoke Role Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Lists the roles with the users they are granted to. Only admins may list roles.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::get_roles;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     for role in get_roles(&client).await.unwrap() {
+///         println!("{}: {}", role.name, role.members.join(", "));
+///     }
+/// }
+/// ```
+pub async fn get_roles(client: &Client) -> Result<Vec<RoleInfo>, Box<dyn error::Error>> {
+    let url = format!("{}/roles", server_url());
+
+    let resp = authorize(client.get(&url)).send().await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Get Roles Response: {:?}", resp);
+            Ok(resp.json::<Vec<RoleInfo>>().await?)
+        }
+        false => {
+            debug!("Get Roles Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Lists granted privileges.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `query` - The user or role whose grants are listed. Without one, admins get all grants and
+///   other users their own grants and those of their roles. Only admins may list the grants of
+///   others.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::get_grants;
+/// use core::request_types::GrantsQuery;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let query = GrantsQuery { grantee: Some("analyst".to_string()) };
+///     for grant in get_grants(&client, &query).await.unwrap() {
+///         println!("{}", grant);
+///     }
+/// }
+/// ```
+pub async fn get_grants(
+    client: &Client,
+    query: &GrantsQuery,
+) -> Result<Vec<Grant>, Box<dyn error::Error>> {
+    let url = format!("{}/grants", server_url());
+
+    let resp = authorize(client.get(&url)).query(query).send().await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Get Grants Response: {:?}", resp);
+            Ok(resp.json::<Vec<Grant>>().await?)
+        }
+        false => {
+            debug!("Get Grants Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
 
 /// Creates a new table on the server.
 ///
 /// # Arguments
 ///
 /// * `client` - A reference to the HTTP client.
-/// * `create_table_request` - The request object containing the name of the table to create.
+/// * `create_table_request` - The request object containing the name of the table to create.
+///
+/// # Examples
+///
+/// ```
+/// use log::LevelFilter;
+/// use reqwest::Client;
+/// use core::request_types::{CreateRequests};
+/// use core::client_functions::create;
+///
+/// #[tokio::main]
+/// async fn main() {
+///
+///     env_logger::builder()
+///         .filter_level(LevelFilter::Info)
+///         .format_timestamp_millis()
+///         .init();
+///
+///     let client = Client::new();
+///
+///     let create_request = CreateRequests { name: "test_table".to_string() };
+///     create(&client, &create_request).await.unwrap();
+/// }
+/// ```
+pub async fn create(
+    client: &Client,
+    create_table_request: &CreateRequests,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/create", server_url());
+    let body = json!({
+        "name": create_table_request.name,
+    });
+
+    let resp = authorize(client.post(&url)).json(&body).send().await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Create Response: {:?}", resp);
+            info!("Created Table {:?}", create_table_request.name);
+            Ok(())
+        }
+        false => {
+            debug!("Create Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Creates a new table with specified columns on the server.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `create_table_request` - The request object containing the table name and columns.
+///
+/// # Examples
+///
+/// ```
+/// use log::LevelFilter;
+/// use reqwest::Client;
+/// use core::request_types::{CreateTableRequests, InsertColumnRequest};
+/// use core::client_functions::create_table;
+///
+/// #[tokio::main]
+/// async fn main() {
+///
+///     env_logger::builder()
+///         .filter_level(LevelFilter::Info)
+///         .format_timestamp_millis()
+///         .init();
+///
+///     let client = Client::new();
+/// // Insert columns
+///     let insert_column_request = InsertColumnRequest {
+///         table_name: "test_table".to_string(),
+///         key: "test_key".to_string(),
+///         primary_key: true,
+///         non_null: true,
+///         unique: true,
+///         foreign_key: None,
+///         default: None,
+///         check: None,
+///         auto_increment: false,
+///         data_type: None,
+///     };
+///
+/// // Create new table to be dropped
+///     create_table(&client, &CreateTableRequests {
+///        name: "test_table2".to_string(),
+///         insert_column_requests: vec![insert_column_request],
+///         primary_key: Vec::new(),
+///         unique_constraints: Vec::new(),
+///     }).await.unwrap();
+/// }
+/// ```
+pub async fn create_table(
+    client: &Client,
+    create_table_request: &CreateTableRequests,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/create_table", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(create_table_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Create Table Response: {:?}", resp);
+            info!("Created Table {:?}", create_table_request.name);
+            Ok(())
+        }
+        false => {
+            debug!("Create Table Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Drops a table on the server.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `drop_table_request` - The request object containing the name of the table to drop.
+///
+/// # Examples
+///
+/// ```
+/// use log::LevelFilter;
+/// use reqwest::Client;
+/// use core::request_types::{DropTableRequest};
+/// use core::client_functions::drop_table;
+///
+/// #[tokio::main]
+/// async fn main() {
+///
+///     env_logger::builder()
+///         .filter_level(LevelFilter::Info)
+///         .format_timestamp_millis()
+///         .init();
+///
+///     let client = Client::new();
+///
+///     let drop_table_request = DropTableRequest { name: "test_table".to_string() };
+///     drop_table(&client, &drop_table_request).await.unwrap();
+/// }
+/// ```
+pub async fn drop_table(
+    client: &Client,
+    drop_table_request: &DropTableRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/drop_table", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(drop_table_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Drop Table Response: {:?}", resp);
+            info!("Dropped Table {:?}", drop_table_request.name);
+        }
+        false => {
+            debug!("Drop Table Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            error!("Drop Table Response: {}", error_message);
+        }
+    }
+    Ok(())
+}
+
+/// Renames a table's name on the server.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `rename_table_request` - The request object containing the current and new names of the table.
+///
+/// # Examples
+///
+/// ```
+/// use log::LevelFilter;
+/// use reqwest::Client;
+/// use core::request_types::{RenameTableRequest};
+/// use core::client_functions::rename_table;
+///
+/// #[tokio::main]
+/// async fn main() {
+///
+///     env_logger::builder()
+///         .filter_level(LevelFilter::Info)
+///         .format_timestamp_millis()
+///         .init();
+///
+///     let client = Client::new();
+///
+///     let rename_table_request = RenameTableRequest { current_name: "test_table2".to_string(), new_name: "test_drop_table".to_string() };
+///     rename_table(&client, &rename_table_request).await.unwrap();
+/// }
+/// ```
+pub async fn rename_table(
+    client: &Client,
+    rename_table_request: &RenameTableRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/rename_table", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(rename_table_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Rename Table Response: {:?}", resp);
+            info!("Renamed Table {:?}", rename_table_request);
+            Ok(())
+        }
+        false => {
+            debug!("Rename Table Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Inserts a new column into a table on the server.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `insert_column_request` - The request object containing the table name and column details.
+///
+/// # Examples
+///
+/// ```
+/// use log::LevelFilter;
+/// use reqwest::Client;
+/// use core::request_types::{InsertColumnRequest};
+/// use core::client_functions::insert_column;
+///
+/// #[tokio::main]
+/// async fn main() {
+///
+///     env_logger::builder()
+///         .filter_level(LevelFilter::Info)
+///         .format_timestamp_millis()
+///         .init();
+///
+///     let client = Client::new();
+///
+///     // Insert columns
+///     let insert_column_request = InsertColumnRequest {
+///         table_name: "test_table".to_string(),
+///         key: "test_key".to_string(),
+///         primary_key: true,
+///         non_null: true,
+///         unique: true,
+///         foreign_key: None,
+///         default: None,
+///         check: None,
+///         auto_increment: false,
+///         data_type: None,
+///     };
+///     insert_column(&client, &insert_column_request).await.unwrap();
+/// }
+/// ```
+pub async fn insert_column(
+    client: &Client,
+    insert_column_request: &InsertColumnRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/insert_column", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(insert_column_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Insert Column Response: {:?}", resp);
+            info!("Inserted Column {:?}", insert_column_request);
+            Ok(())
+        }
+        false => {
+            debug!("Insert Column Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Inserts a new row into a table on the server.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `insert_row_request` - The request object containing the table name and row data.
+///
+/// # Examples
+///
+/// ```
+/// use log::LevelFilter;
+/// use reqwest::Client;
+/// use core::request_types::{InsertRowRequest};
+/// use core::client_functions::insert_row;
+/// use core::row::Row;
+/// use core::value::Value;
+///
+/// #[tokio::main]
+/// async fn main() {
+///
+///     env_logger::builder()
+///         .filter_level(LevelFilter::Info)
+///         .format_timestamp_millis()
+///         .init();
+///
+///     let client = Client::new();
+///
+///  // Insert a row
+///     let insert_row_request = InsertRowRequest {
+///         table_name: "test_table".to_string(),
+///         row: Row::new(vec![Value::from("test_value".to_string()), Value::from(13)])
+///     };
+///
+///     insert_row(&client, &insert_row_request).await.unwrap();
+/// }
+/// ```
+pub async fn insert_row(
+    client: &Client,
+    insert_row_request: &InsertRowRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/insert_row", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(insert_row_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Insert Row Response: {:?}", resp);
+            info!("Inserted Row {:?}", insert_row_request);
+            Ok(())
+        }
+        false => {
+            debug!("Insert Row Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Sends a select query to the server.
+///
+/// Returns the selected rows, with the values in the order of the requested columns.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `select_request` - The request object containing the select query details.
+///
+/// # Examples
+///
+/// ```
+/// use log::LevelFilter;
+/// use reqwest::Client;
+/// use core::request_types::{SelectRequest, Condition};
+/// use core::client_functions::select;
+///
+/// #[tokio::main]
+/// async fn main() {
+///
+///     env_logger::builder()
+///         .filter_level(LevelFilter::Info)
+///         .format_timestamp_millis()
+///         .init();
+///
+///     let client = Client::new();
+///
+/// // Select from the table with a condition
+///     let select_request = SelectRequest {
+///         table_name: "test_table".to_string(),
+///         columns: Option::from(vec!["test_key".to_string(), "test_key3".to_string()]), // Empty vec would mean *
+///         condition: Option::from(Condition {
+///             column: "test_key".to_string(),
+///             value: "true".to_string(),
+///         }),
+///     };
+///
+///     let rows = select(&client, &select_request).await.unwrap();
+///     println!("Selected {} rows", rows.len());
+/// }
+/// ```
+pub async fn select(
+    client: &Client,
+    select_request: &SelectRequest,
+) -> Result<Vec<Row>, Box<dyn std::error::Error>> {
+    let url = format!("{}/select", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(select_request)
+        .send()
+        .await?;
+
+    // Extract the status code before consuming `resp`
+    let status = resp.status();
+    match status.is_success() {
+        true => {
+            let body = resp.text().await?;
+            debug!("Select Response: {}", body); // Log the body content
+            Ok(serde_json::from_str(&body)?)
+        }
+        false => {
+            debug!("Select Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Asks the server for the plan of a select query, like `EXPLAIN`.
+///
+/// With `analyze` the query is run and every node of the plan is annotated with the rows it
+/// produced and the time it took, like `EXPLAIN ANALYZE`. The selected rows are discarded.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `select_request` - The select query to explain.
+/// * `analyze` - Whether to run the query.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::request_types::{SelectRequest, Condition};
+/// use core::client_functions::explain;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let select_request = SelectRequest {
+///         table_name: "test_table".to_string(),
+///         columns: None,
+///         condition: Option::from(Condition {
+///             column: "test_key".to_string(),
+///             value: "true".to_string(),
+///         }),
+///     };
+///
+///     let explanation = explain(&client, &select_request, true).await.unwrap();
+///     for line in explanation.lines() {
+///         println!("{}", line);
+///     }
+/// }
+/// ```
+pub async fn explain(
+    client: &Client,
+    select_request: &SelectRequest,
+    analyze: bool,
+) -> Result<Explanation, Box<dyn std::error::Error>> {
+    let url = format!("{}/select", server_url());
+    let options = SelectOptions {
+        explain: Some(match analyze {
+            true => ExplainMode::Analyze,
+            false => ExplainMode::Plan,
+        }),
+        ..Default::default()
+    };
+
+    let resp = authorize(client.post(&url))
+        .query(&options)
+        .json(select_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            let body = resp.text().await?;
+            debug!("Explain Response: {}", body);
+            Ok(serde_json::from_str(&body)?)
+        }
+        false => {
+            debug!("Explain Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Selects the joined rows of two tables from the server.
+///
+/// Returns the joined rows, with the values in the order of the requested columns.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `join_request` - The request object containing the tables, the joined columns and the
+///   select query details.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::request_types::{JoinCondition, JoinRequest};
+/// use core::client_functions::select_join;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     // Select the orders of every user
+///     let join_request = JoinRequest {
+///         columns: Some(vec!["users.name".to_string(), "orders.total".to_string()]),
+///         table_name: "users".to_string(),
+///         join_table: "orders".to_string(),
+///         on: JoinCondition {
+///             left_column: "id".to_string(),
+///             right_column: "user_id".to_string(),
+///         },
+///         condition: None,
+///     };
+///
+///     let rows = select_join(&client, &join_request).await.unwrap();
+///     println!("Selected {} rows", rows.len());
+/// }
+/// ```
+pub async fn select_join(
+    client: &Client,
+    join_request: &JoinRequest,
+) -> Result<Vec<Row>, Box<dyn std::error::Error>> {
+    let url = format!("{}/join", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(join_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            let body = resp.text().await?;
+            debug!("Join Response: {}", body);
+            Ok(serde_json::from_str(&body)?)
+        }
+        false => {
+            debug!("Join Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Asks the server for the plan of a join, like `EXPLAIN`.
+///
+/// With `analyze` the join is run and every node of the plan is annotated with the rows it
+/// produced and the time it took, like `EXPLAIN ANALYZE`. The joined rows are discarded.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `join_request` - The join to explain.
+/// * `analyze` - Whether to run the join.
+pub async fn explain_join(
+    client: &Client,
+    join_request: &JoinRequest,
+    analyze: bool,
+) -> Result<Explanation, Box<dyn std::error::Error>> {
+    let url = format!("{}/join", server_url());
+    let options = SelectOptions {
+        explain: Some(match analyze {
+            true => ExplainMode::Analyze,
+            false => ExplainMode::Plan,
+        }),
+        ..Default::default()
+    };
+
+    let resp = authorize(client.post(&url))
+        .query(&options)
+        .json(join_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            let body = resp.text().await?;
+            debug!("Explain Response: {}", body);
+            Ok(serde_json::from_str(&body)?)
+        }
+        false => {
+            debug!("Explain Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// The error type of the rows of a [`RowStream`].
+pub type StreamError = Box<dyn error::Error + Send + Sync>;
+
+/// The rows of a streamed select result, returned by [`select_stream`].
+///
+/// Rows are yielded as they arrive from the server. An error is yielded if the connection
+/// fails or the server cannot complete the result, after which the stream ends.
+pub struct RowStream {
+    columns: Option<Vec<ColumnHeader>>,
+    rows: BoxStream<'static, Result<Row, StreamError>>,
+}
+
+impl RowStream {
+    /// Returns the column metadata sent in the header frame of a chunked stream, or `None`
+    /// for an NDJSON stream.
+    pub fn columns(&self) -> Option<&[ColumnHeader]> {
+        self.columns.as_deref()
+    }
+}
+
+impl Stream for RowStream {
+    type Item = Result<Row, StreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rows.poll_next_unpin(cx)
+    }
+}
+
+/// Reads the body of a response line by line as it arrives.
+struct LineReader {
+    response: Response,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl LineReader {
+    /// Returns the next non-empty line, or `None` at the end of the body.
+    async fn next_line(&mut self) -> Result<Option<Vec<u8>>, StreamError> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                return Ok(Some(line));
+            }
+            if self.done {
+                if self.buffer.iter().all(u8::is_ascii_whitespace) {
+                    return Ok(None);
+                }
+                return Ok(Some(std::mem::take(&mut self.buffer)));
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+/// The state of a [`RowStream`] between two rows.
+struct StreamState {
+    reader: LineReader,
+    format: StreamFormat,
+    pending: VecDeque<Row>,
+    row_count: usize,
+    finished: bool,
+}
+
+impl StreamState {
+    /// Returns the next row, reading further frames from the server as needed.
+    async fn next_row(&mut self) -> Option<Result<Row, StreamError>> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Some(Ok(row));
+            }
+            if self.finished {
+                return None;
+            }
+            let line = match self.reader.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => {
+                    self.finished = true;
+                    return match self.format {
+                        StreamFormat::Ndjson => None,
+                        StreamFormat::Chunked => {
+                            Some(Err("The select stream ended without an end frame".into()))
+                        }
+                    };
+                }
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            };
+            if let Err(e) = self.read_line(&line) {
+                self.finished = true;
+                return Some(Err(e));
+            }
+        }
+    }
+
+    /// Parses a line of the response into pending rows.
+    fn read_line(&mut self, line: &[u8]) -> Result<(), StreamError> {
+        match self.format {
+            StreamFormat::Ndjson => self.pending.push_back(serde_json::from_slice(line)?),
+            StreamFormat::Chunked => match serde_json::from_slice(line)? {
+                SelectFrame::Rows(rows) => {
+                    self.row_count += rows.len();
+                    self.pending.extend(rows);
+                }
+                SelectFrame::End { row_count } => {
+                    self.finished = true;
+                    if row_count != self.row_count {
+                        return Err(format!(
+                            "Expected {} rows but received {}",
+                            row_count, self.row_count
+                        )
+                        .into());
+                    }
+                }
+                SelectFrame::Error(message) => return Err(message.into()),
+                SelectFrame::Header { .. } => return Err("Unexpected header frame".into()),
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Selects rows from a table on the server and streams them as they arrive.
+///
+/// Unlike [`select`], the rows are not collected into memory by the server or the client, so
+/// this is suited for large results.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `select_request` - The request object containing the select query details.
+/// * `format` - How the server streams the result. [`StreamFormat::Chunked`] also provides the
+///   column metadata through [`RowStream::columns`].
+///
+/// # Errors
+///
+/// Returns an error if the request fails, e.g. because the table or a column does not exist.
+/// Errors while streaming are yielded by the stream.
+///
+/// # Examples
+///
+/// ```
+/// use futures::StreamExt;
+/// use reqwest::Client;
+/// use core::client_functions::select_stream;
+/// use core::request_types::{SelectRequest, StreamFormat};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let select_request = SelectRequest {
+///         table_name: "test_table".to_string(),
+///         columns: None,
+///         condition: None,
+///     };
+///
+///     let mut rows = select_stream(&client, &select_request, StreamFormat::Chunked)
+///         .await
+///         .unwrap();
+///     println!("Columns: {:?}", rows.columns());
+///     while let Some(row) = rows.next().await {
+///         println!("{:?}", row.unwrap());
+///     }
+/// }
+/// ```
+pub async fn select_stream(
+    client: &Client,
+    select_request: &SelectRequest,
+    format: StreamFormat,
+) -> Result<RowStream, Box<dyn error::Error>> {
+    let url = format!("{}/select", server_url());
+    let options = SelectOptions {
+        stream: Some(format),
+        chunk_size: None,
+        explain: None,
+    };
+
+    let resp = authorize(client.post(&url))
+        .query(&options)
+        .json(select_request)
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        debug!("Select Stream Response: {:?}", resp);
+        let error_body = resp.json::<serde_json::Value>().await?;
+        let error_message = error_body.as_str().unwrap_or("Unknown error");
+        return Err(Box::new(std::io::Error::other(error_message)));
+    }
+    debug!("Select Stream Response: {:?}", resp);
+
+    let mut reader = LineReader {
+        response: resp,
+        buffer: Vec::new(),
+        done: false,
+    };
+    let columns = match format {
+        StreamFormat::Ndjson => None,
+        StreamFormat::Chunked => {
+            let line = reader
+                .next_line()
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or("The select stream ended without a header frame")?;
+            match serde_json::from_slice(&line)? {
+                SelectFrame::Header { columns } => Some(columns),
+                SelectFrame::Error(message) => return Err(message.into()),
+                _ => return Err("The select stream did not start with a header frame".into()),
+            }
+        }
+    };
+
+    let state = StreamState {
+        reader,
+        format,
+        pending: VecDeque::new(),
+        row_count: 0,
+        finished: false,
+    };
+    let rows = stream::unfold(state, |mut state| async move {
+        state.next_row().await.map(|row| (row, state))
+    });
+    Ok(RowStream {
+        columns,
+        rows: rows.boxed(),
+    })
+}
+
+/// Imports the records of a CSV file as rows of a table on the server.
+///
+/// Either all rows are imported or none.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `table_name` - The name of the table.
+/// * `csv` - The contents of the CSV file.
+/// * `options` - The dialect of the file.
+/// * `import` - The columns and types of the fields, and whether a missing table is created.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::import_csv;
+/// use core::csv::CsvOptions;
+/// use core::request_types::CsvImportOptions;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let csv = "id,name\n1,Alice\n2,Bob\n".to_string();
+///     let import = CsvImportOptions {
+///         create: true,
+///         ..CsvImportOptions::default()
+///     };
+///     let result = import_csv(&client, "people", csv, &CsvOptions::default(), &import)
+///         .await
+///         .unwrap();
+///     println!("Imported {} rows", result.row_count);
+/// }
+/// ```
+pub async fn import_csv(
+    client: &Client,
+    table_name: &str,
+    csv: String,
+    options: &CsvOptions,
+    import: &CsvImportOptions,
+) -> Result<CsvImportResult, Box<dyn error::Error>> {
+    let url = format!("{}/tables/{}/csv", server_url(), table_name);
+
+    let resp = authorize(client.post(&url))
+        .query(options)
+        .query(import)
+        .header(reqwest::header::CONTENT_TYPE, "text/csv")
+        .body(csv)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Import CSV Response: {:?}", resp);
+            let result = resp.json::<CsvImportResult>().await?;
+            info!(
+                "Imported {} rows into table {:?}",
+                result.row_count, result.table_name
+            );
+            Ok(result)
+        }
+        false => {
+            debug!("Import CSV Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Exports all rows of a table on the server as CSV.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `table_name` - The name of the table.
+/// * `options` - The dialect to write.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::export_csv;
+/// use core::csv::CsvOptions;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let csv = export_csv(&client, "test_table", &CsvOptions::default()).await.unwrap();
+///     std::fs::write("test_table.csv", csv).unwrap();
+/// }
+/// ```
+pub async fn export_csv(
+    client: &Client,
+    table_name: &str,
+    options: &CsvOptions,
+) -> Result<String, Box<dyn error::Error>> {
+    let url = format!("{}/tables/{}/csv", server_url(), table_name);
+
+    let resp = authorize(client.get(&url)).query(options).send().await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Export CSV Response: {:?}", resp);
+            Ok(resp.text().await?)
+        }
+        false => {
+            debug!("Export CSV Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Exports the result of a select request as CSV.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `select_request` - The request object containing the select query details.
+/// * `options` - The dialect to write.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::select_csv;
+/// use core::csv::CsvOptions;
+/// use core::request_types::SelectRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let select_request = SelectRequest {
+///         table_name: "test_table".to_string(),
+///         columns: Some(vec!["test_key".to_string()]),
+///         condition: None,
+///     };
+///     let csv = select_csv(&client, &select_request, &CsvOptions::default()).await.unwrap();
+///     print!("{}", csv);
+/// }
+/// ```
+pub async fn select_csv(
+    client: &Client,
+    select_request: &SelectRequest,
+    options: &CsvOptions,
+) -> Result<String, Box<dyn error::Error>> {
+    let url = format!("{}/select/csv", server_url());
+
+    let resp = authorize(client.post(&url))
+        .query(options)
+        .json(select_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Select CSV Response: {:?}", resp);
+            Ok(resp.text().await?)
+        }
+        false => {
+            debug!("Select CSV Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Adds a unique index to a table on the server.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `create_index_request` - The table, name and columns of the index.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::create_index;
+/// use core::request_types::CreateIndexRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let request = CreateIndexRequest {
+///         table_name: "test_table".to_string(),
+///         name: "test_table_test_key_key".to_string(),
+///         columns: vec!["test_key".to_string()],
+///     };
+///     create_index(&client, &request).await.unwrap();
+/// }
+/// ```
+pub async fn create_index(
+    client: &Client,
+    create_index_request: &CreateIndexRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/create_index", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(create_index_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Create Index Response: {:?}", resp);
+            info!("Created Index {:?}", create_index_request.name);
+            Ok(())
+        }
+        false => {
+            debug!("Create Index Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Adds a row-level security policy to a table on the server.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `create_policy_request` - The table and the policy.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::create_policy;
+/// use core::policy::Policy;
+/// use core::request_types::CreatePolicyRequest;
+/// use core::sql::parse_expression;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let request = CreatePolicyRequest {
+///         table_name: "orders".to_string(),
+///         policy: Policy {
+///             name: "tenant_isolation".to_string(),
+///             roles: Vec::new(),
+///             using: Some(parse_expression("tenant = current_setting('app.tenant')").unwrap()),
+///             check: None,
+///         },
+///     };
+///     create_policy(&client, &request).await.unwrap();
+/// }
+/// ```
+pub async fn create_policy(
+    client: &Client,
+    create_policy_request: &CreatePolicyRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/create_policy", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(create_policy_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Create Policy Response: {:?}", resp);
+            info!("Created Policy {:?}", create_policy_request.policy.name);
+            Ok(())
+        }
+        false => {
+            debug!("Create Policy Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Drops a row-level security policy of a table on the server.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `drop_policy_request` - The table and the name of the policy.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::drop_policy;
+/// use core::request_types::DropPolicyRequest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let request = DropPolicyRequest {
+///         table_name: "orders".to_string(),
+///         name: "tenant_isolation".to_string(),
+///     };
+///     drop_policy(&client, &request).await.unwrap();
+/// }
+/// ```
+pub async fn drop_policy(
+    client: &Client,
+    drop_policy_request: &DropPolicyRequest,
+) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("{}/drop_policy", server_url());
+
+    let resp = authorize(client.post(&url))
+        .json(drop_policy_request)
+        .send()
+        .await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Drop Policy Response: {:?}", resp);
+            info!("Dropped Policy {:?}", drop_policy_request.name);
+            Ok(())
+        }
+        false => {
+            debug!("Drop Policy Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Writes an SQL dump of tables on the server.
+///
+/// The dump is a script of `CREATE TABLE`, `INSERT` and `CREATE UNIQUE INDEX` statements that
+/// recreates the tables, see [`dump`](crate::dump).
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `options` - The tables to dump, all by default, and the number of rows per `INSERT`.
+///
+/// # Examples
+///
+/// ```
+/// use reqwest::Client;
+/// use core::client_functions::dump;
+/// use core::request_types::DumpOptions;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new();
+///
+///     let script = dump(&client, &DumpOptions::default()).await.unwrap();
+///     std::fs::write("dump.sql", script).unwrap();
+/// }
+/// ```
+pub async fn dump(client: &Client, options: &DumpOptions) -> Result<String, Box<dyn error::Error>> {
+    let url = format!("{}/dump", server_url());
+
+    let resp = authorize(client.get(&url)).query(options).send().await?;
+
+    match resp.status().is_success() {
+        true => {
+            debug!("Dump Response: {:?}", resp);
+            Ok(resp.text().await?)
+        }
+        false => {
+            debug!("Dump Response: {:?}", resp);
+            let error_body = resp.json::<serde_json::Value>().await?;
+            let error_message = error_body.as_str().unwrap_or("Unknown error");
+            Err(Box::new(std::io::Error::other(error_message)))
+        }
+    }
+}
+
+/// Restores an SQL dump on the server.
+///
+/// The script is executed as a whole: if any statement fails, none of the tables are created.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the HTTP client.
+/// * `script` - The SQL script, as written by [`dump`].
 ///
 /// # Examples
 ///
 /// ```
-/// use log::LevelFilter;
 /// use reqwest::Client;
-/// use core::request_types::{CreateRequests};
-/// use core::client_functions::create;
+/// use core::client_functions::restore;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///
-///     env_logger::builder()
-///         .filter_level(LevelFilter::Info)
-///         .format_timestamp_millis()
-///         .init();
-///
 ///     let client = Client::new();
 ///
-///     let create_request = CreateRequests { name: "test_table".to_string() };
-///     create(&client, &create_request).await.unwrap();
+///     let script = std::fs::read_to_string("dump.sql").unwrap();
+///     let result = restore(&client, script).await.unwrap();
+///     println!("Restored {} rows", result.row_count);
 /// }
 /// ```
-pub async fn create(
+pub async fn restore(
     client: &Client,
-    create_table_request: &CreateRequests,
-) -> Result<(), Box<dyn error::Error>> {
-    let url = "http://localhost:3000/create".to_string();
-    let body = json!({
-        "name": create_table_request.name,
-    });
+    script: String,
+) -> Result<RestoreResult, Box<dyn error::Error>> {
+    let url = format!("{}/restore", server_url());
 
-    let resp = client.post(&url).json(&body).send().await?;
+    let resp = authorize(client.post(&url))
+        .header(reqwest::header::CONTENT_TYPE, "application/sql")
+        .body(script)
+        .send()
+        .await?;
 
     match resp.status().is_success() {
         true => {
-            debug!("Create Response: {:?}", resp);
-            info!("Created Table {:?}", create_table_request.name);
-            Ok(())
+            debug!("Restore Response: {:?}", resp);
+            let result = resp.json::<RestoreResult>().await?;
+            info!(
+                "Restored {} tables with {} rows",
+                result.tables.len(),
+                result.row_count
+            );
+            Ok(result)
         }
         false => {
-            debug!("Create Response: {:?}", resp);
+            debug!("Restore Response: {:?}", resp);
             let error_body = resp.json::<serde_json::Value>().await?;
             let error_message = error_body.as_str().unwrap_or("Unknown error");
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                error_message,
-            )))
+            Err(Box::new(std::io::Error::other(error_message)))
         }
     }
 }
 
-/// Creates a new table with specified columns on the server.
+/// Writes a snapshot of all tables on the server.
+///
+/// The snapshot is consistent as of one log sequence number while the server keeps serving
+/// requests. Together with the archived write-ahead log it can be restored with
+/// `server restore`.
 ///
 /// # Arguments
 ///
 /// * `client` - A reference to the HTTP client.
-/// * `create_table_request` - The request object containing the table name and columns.
 ///
 /// # Examples
 ///
 /// ```
-/// use log::LevelFilter;
 /// use reqwest::Client;
-/// use core::request_types::{CreateTableRequests, InsertColumnRequest};
-/// use core::client_functions::create_table;
+/// use core::client_functions::create_snapshot;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///
-///     env_logger::builder()
-///         .filter_level(LevelFilter::Info)
-///         .format_timestamp_millis()
-///         .init();
-///
 ///     let client = Client::new();
-/// // Insert columns
-///     let insert_column_request = InsertColumnRequest {
-///         table_name: "test_table".to_string(),
-///         key: "test_key".to_string(),
-///         primary_key: true,
-///         non_null: true,
-///         unique: true,
-///         foreign_key: None,
-///     };
 ///
-/// // Create new table to be dropped
-///     create_table(&client, &CreateTableRequests {
-///        name: "test_table2".to_string(),
-///         insert_column_requests: vec![insert_column_request],
-///     }).await.unwrap();
+///     let snapshot = create_snapshot(&client).await.unwrap();
+///     println!("Wrote {} at LSN {}", snapshot.path, snapshot.lsn);
 /// }
 /// ```
-pub async fn create_table(
-    client: &Client,
-    create_table_request: &CreateTableRequests,
-) -> Result<(), Box<dyn error::Error>> {
-    let url = "http://localhost:3000/create_table".to_string();
+pub async fn create_snapshot(client: &Client) -> Result<SnapshotInfo, Box<dyn error::Error>> {
+    let url = format!("{}/admin/snapshot", server_url());
 
-    let resp = client.post(&url).json(create_table_request).send().await?;
+    let resp = authorize(client.post(&url)).send().await?;
 
     match resp.status().is_success() {
         true => {
-            debug!("Create Table Response: {:?}", resp);
-            info!("Created Table {:?}", create_table_request.name);
-            Ok(())
+            debug!("Snapshot Response: {:?}", resp);
+            let snapshot = resp.json::<SnapshotInfo>().await?;
+            info!("Wrote snapshot {} at LSN {}", snapshot.path, snapshot.lsn);
+            Ok(snapshot)
         }
         false => {
-            debug!("Create Table Response: {:?}", resp);
+            debug!("Snapshot Response: {:?}", resp);
             let error_body = resp.json::<serde_json::Value>().await?;
             let error_message = error_body.as_str().unwrap_or("Unknown error");
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                error_message,
-            )))
+            Err(Box::new(std::io::Error::other(error_message)))
         }
     }
 }
 
-/// Drops a table on the server.
+/// Completes the server's current write-ahead log segment, so that it is archived.
 ///
 /// # Arguments
 ///
 /// * `client` - A reference to the HTTP client.
-/// * `drop_table_request` - The request object containing the name of the table to drop.
 ///
 /// # Examples
 ///
 /// ```
-/// use log::LevelFilter;
 /// use reqwest::Client;
-/// use core::request_types::{DropTableRequest};
-/// use core::client_functions::drop_table;
+/// use core::client_functions::switch_wal;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///
-///     env_logger::builder()
-///         .filter_level(LevelFilter::Info)
-///         .format_timestamp_millis()
-///         .init();
-///
 ///     let client = Client::new();
 ///
-///     let drop_table_request = DropTableRequest { name: "test_table".to_string() };
-///     drop_table(&client, &drop_table_request).await.unwrap();
+///     let status = switch_wal(&client).await.unwrap();
+///     println!("Writing to {} after LSN {}", status.segment, status.last_lsn);
 /// }
 /// ```
-pub async fn drop_table(
-    client: &Client,
-    drop_table_request: &DropTableRequest,
-) -> Result<(), Box<dyn error::Error>> {
-    let url = "http://localhost:3000/drop_table".to_string();
+pub async fn switch_wal(client: &Client) -> Result<WalStatus, Box<dyn error::Error>> {
+    let url = format!("{}/admin/wal/switch", server_url());
 
-    let resp = client.post(&url).json(drop_table_request).send().await?;
+    let resp = authorize(client.post(&url)).send().await?;
 
     match resp.status().is_success() {
         true => {
-            debug!("Drop Table Response: {:?}", resp);
-            info!("Dropped Table {:?}", drop_table_request.name);
+            debug!("Switch WAL Response: {:?}", resp);
+            Ok(resp.json::<WalStatus>().await?)
         }
         false => {
-            debug!("Drop Table Response: {:?}", resp);
+            debug!("Switch WAL Response: {:?}", resp);
             let error_body = resp.json::<serde_json::Value>().await?;
             let error_message = error_body.as_str().unwrap_or("Unknown error");
-            error!("Drop Table Response: {}", error_message);
+            Err(Box::new(std::io::Error::other(error_message)))
         }
     }
-    Ok(())
 }
 
-/// Renames a table's name on the server.
+/// Updates rows in a table on the server based on specified conditions.
 ///
 /// # Arguments
 ///
 /// * `client` - A reference to the HTTP client.
-/// * `rename_table_request` - The request object containing the current and new names of the table.
+/// * `update_request` - The request object containing the table name, condition, and updates.
 ///
 /// # Examples
 ///
 /// ```
 /// use log::LevelFilter;
 /// use reqwest::Client;
-/// use core::request_types::{RenameTableRequest};
-/// use core::client_functions::rename_table;
+/// use core::request_types::{UpdateRequest, Condition, UpdateColumnRequest};
+/// use core::client_functions::update_table;
 ///
 /// #[tokio::main]
 /// async fn main() {
@@ -210,297 +2389,259 @@ pub async fn drop_table(
 ///
 ///     let client = Client::new();
 ///
-///     let rename_table_request = RenameTableRequest { current_name: "test_table2".to_string(), new_name: "test_drop_table".to_string() };
-///     rename_table(&client, &rename_table_request).await.unwrap();
+/// // Update rows in the table
+///     let update_request = UpdateRequest {
+///         table_name: "test_table".to_string(),
+///         condition: Option::from(Condition {
+///             column: "test_key".to_string(),
+///             value: "true".to_string(),
+///         }),
+///         updates: vec![
+///             UpdateColumnRequest {
+///                 column: "test_key3".to_string(),
+///                 value: Some("updated_value".to_string()),
+///             },
+///             UpdateColumnRequest {
+///                 column: "test_key2".to_string(),
+///                 value: Some("17.78".to_string()),
+///             },
+///         ],
+///     };
+///
+///     update_table(&client, &update_request).await.unwrap();
 /// }
 /// ```
-pub async fn rename_table(
+pub async fn update_table(
     client: &Client,
-    rename_table_request: &RenameTableRequest,
+    update_request: &UpdateRequest,
 ) -> Result<(), Box<dyn error::Error>> {
-    let url = "http://localhost:3000/rename_table".to_string();
+    let url = format!("{}/update_table", server_url());
 
-    let resp = client.post(&url).json(rename_table_request).send().await?;
+    let resp = authorize(client.post(&url))
+        .json(update_request)
+        .send()
+        .await?;
 
     match resp.status().is_success() {
         true => {
-            debug!("Rename Table Response: {:?}", resp);
-            info!("Renamed Table {:?}", rename_table_request);
+            debug!("Update Table Response: {:?}", resp);
+            info!("Updated Table {:?}", update_request.table_name);
             Ok(())
         }
         false => {
-            debug!("Rename Table Response: {:?}", resp);
+            debug!("Update Table Response: {:?}", resp);
             let error_body = resp.json::<serde_json::Value>().await?;
             let error_message = error_body.as_str().unwrap_or("Unknown error");
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                error_message,
-            )))
+            Err(Box::new(std::io::Error::other(error_message)))
         }
     }
 }
 
-/// Inserts a new column into a table on the server.
+/// Deletes rows from a table on the server based on a specified condition.
 ///
 /// # Arguments
 ///
 /// * `client` - A reference to the HTTP client.
-/// * `insert_column_request` - The request object containing the table name and column details.
+/// * `delete_request` - The request object containing the table name and condition.
+///
+/// # Returns
+///
+/// Returns the number of deleted rows.
 ///
 /// # Examples
 ///
 /// ```
-/// use log::LevelFilter;
 /// use reqwest::Client;
-/// use core::request_types::{InsertColumnRequest};
-/// use core::client_functions::insert_column;
+/// use core::request_types::{Condition, DeleteRequest};
+/// use core::client_functions::delete_rows;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///
-///     env_logger::builder()
-///         .filter_level(LevelFilter::Info)
-///         .format_timestamp_millis()
-///         .init();
-///
 ///     let client = Client::new();
 ///
-///     // Insert columns
-///     let insert_column_request = InsertColumnRequest {
+///     // Delete the matching rows of the table
+///     let delete_request = DeleteRequest {
 ///         table_name: "test_table".to_string(),
-///         key: "test_key".to_string(),
-///         primary_key: true,
-///         non_null: true,
-///         unique: true,
-///         foreign_key: None,
+///         condition: Some(Condition {
+///             column: "test_key".to_string(),
+///             value: "true".to_string(),
+///         }),
 ///     };
-///     insert_column(&client, &insert_column_request).await.unwrap();
+///
+///     let deleted = delete_rows(&client, &delete_request).await.unwrap();
+///     println!("Deleted {} rows", deleted);
 /// }
 /// ```
-pub async fn insert_column(
+pub async fn delete_rows(
     client: &Client,
-    insert_column_request: &InsertColumnRequest,
-) -> Result<(), Box<dyn error::Error>> {
-    let url = "http://localhost:3000/insert_column".to_string();
+    delete_request: &DeleteRequest,
+) -> Result<usize, Box<dyn error::Error>> {
+    let url = format!("{}/delete", server_url());
 
-    let resp = client.post(&url).json(insert_column_request).send().await?;
+    let resp = authorize(client.post(&url))
+        .json(delete_request)
+        .send()
+        .await?;
 
     match resp.status().is_success() {
         true => {
-            debug!("Insert Column Response: {:?}", resp);
-            info!("Inserted Column {:?}", insert_column_request);
-            Ok(())
+            debug!("Delete Response: {:?}", resp);
+            let deleted = resp.json::<usize>().await?;
+            info!(
+                "Deleted {} rows from table {:?}",
+                deleted, delete_request.table_name
+            );
+            Ok(deleted)
         }
         false => {
-            debug!("Insert Column Response: {:?}", resp);
+            debug!("Delete Response: {:?}", resp);
             let error_body = resp.json::<serde_json::Value>().await?;
             let error_message = error_body.as_str().unwrap_or("Unknown error");
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                error_message,
-            )))
+            Err(Box::new(std::io::Error::other(error_message)))
         }
     }
 }
 
-/// Inserts a new row into a table on the server.
+/// Fetches the schemas of all tables from the server.
+///
+/// The schemas contain the column definitions and row counts, but no rows. Use [`get_rows`] to
+/// fetch the rows of a table.
 ///
 /// # Arguments
 ///
 /// * `client` - A reference to the HTTP client.
-/// * `insert_row_request` - The request object containing the table name and row data.
 ///
 /// # Examples
 ///
 /// ```
-/// use log::LevelFilter;
 /// use reqwest::Client;
-/// use core::request_types::{InsertRowRequest};
-/// use core::client_functions::insert_row;
-/// use core::row::Row;
-/// use core::value::Value;
+/// use core::client_functions::get_tables;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///
-///     env_logger::builder()
-///         .filter_level(LevelFilter::Info)
-///         .format_timestamp_millis()
-///         .init();
-///
 ///     let client = Client::new();
 ///
-///  // Insert a row
-///     let insert_row_request = InsertRowRequest {
-///         table_name: "test_table".to_string(),
-///         row: Row::new(vec![Value::from("test_value".to_string()), Value::from(13)])
-///     };
-///
-///     insert_row(&client, &insert_row_request).await.unwrap();
+///     let tables = get_tables(&client).await.unwrap();
+///     for table in tables {
+///         println!("{}: {} columns, {} rows", table.name, table.columns.len(), table.row_count);
+///     }
 /// }
 /// ```
-pub async fn insert_row(
-    client: &Client,
-    insert_row_request: &InsertRowRequest,
-) -> Result<(), Box<dyn error::Error>> {
-    let url = "http://localhost:3000/insert_row".to_string();
+pub async fn get_tables(client: &Client) -> Result<Vec<TableSchema>, Box<dyn error::Error>> {
+    let url = format!("{}/tables", server_url());
 
-    let resp = client.post(&url).json(insert_row_request).send().await?;
+    let resp = authorize(client.get(&url)).send().await?;
 
     match resp.status().is_success() {
         true => {
-            debug!("Insert Row Response: {:?}", resp);
-            info!("Inserted Row {:?}", insert_row_request);
-            Ok(())
+            debug!("Get Tables Response: {:?}", resp);
+            Ok(resp.json::<Vec<TableSchema>>().await?)
         }
         false => {
-            debug!("Insert Row Response: {:?}", resp);
+            debug!("Get Tables Response: {:?}", resp);
             let error_body = resp.json::<serde_json::Value>().await?;
             let error_message = error_body.as_str().unwrap_or("Unknown error");
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                error_message,
-            )))
+            Err(Box::new(std::io::Error::other(error_message)))
         }
     }
 }
 
-/// Sends a select query to the server.
+/// Fetches the schema of a single table from the server.
 ///
 /// # Arguments
 ///
 /// * `client` - A reference to the HTTP client.
-/// * `select_request` - The request object containing the select query details.
+/// * `table_name` - The name of the table.
 ///
 /// # Examples
 ///
 /// ```
-/// use log::LevelFilter;
 /// use reqwest::Client;
-/// use core::request_types::{SelectRequest, Condition};
-/// use core::client_functions::select;
+/// use core::client_functions::get_table;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///
-///     env_logger::builder()
-///         .filter_level(LevelFilter::Info)
-///         .format_timestamp_millis()
-///         .init();
-///
 ///     let client = Client::new();
 ///
-/// // Select from the table with a condition
-///     let select_request = SelectRequest {
-///         table_name: "test_table".to_string(),
-///         columns: Option::from(vec!["test_key".to_string(), "test_key3".to_string()]), // Empty vec would mean *
-///         condition: Option::from(Condition {
-///             column: "test_key".to_string(),
-///             value: "true".to_string(),
-///         }),
-///     };
-///
-///     select(&client, &select_request).await.unwrap();
+///     let table = get_table(&client, "test_table").await.unwrap();
+///     println!("{} has {} rows", table.name, table.row_count);
 /// }
 /// ```
-pub async fn select(
+pub async fn get_table(
     client: &Client,
-    select_request: &SelectRequest,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let url = "http://localhost:3000/select".to_string();
+    table_name: &str,
+) -> Result<TableSchema, Box<dyn error::Error>> {
+    let url = format!("{}/tables/{}", server_url(), table_name);
 
-    let resp = client.post(&url).json(select_request).send().await?;
+    let resp = authorize(client.get(&url)).send().await?;
 
-    // Extract the status code before consuming `resp`
-    let status = resp.status();
-    match status.is_success() {
+    match resp.status().is_success() {
         true => {
-            let body = resp.text().await?;
-            debug!("Select Response: {}", body); // Log the body content
-            info!("Select result from 'test_create_table': {}", body);
-            Ok(())
+            debug!("Get Table Response: {:?}", resp);
+            Ok(resp.json::<TableSchema>().await?)
         }
         false => {
-            debug!("Select Response: {:?}", resp);
+            debug!("Get Table Response: {:?}", resp);
             let error_body = resp.json::<serde_json::Value>().await?;
             let error_message = error_body.as_str().unwrap_or("Unknown error");
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                error_message,
-            )))
+            Err(Box::new(std::io::Error::other(error_message)))
         }
     }
 }
 
-/// Updates rows in a table on the server based on specified conditions.
+/// Fetches a page of a table's rows from the server.
 ///
 /// # Arguments
 ///
 /// * `client` - A reference to the HTTP client.
-/// * `update_request` - The request object containing the table name, condition, and updates.
+/// * `table_name` - The name of the table.
+/// * `query` - The limit, offset, order and cursor of the page.
 ///
 /// # Examples
 ///
 /// ```
-/// use log::LevelFilter;
 /// use reqwest::Client;
-/// use core::request_types::{UpdateRequest, Condition, UpdateColumnRequest};
-/// use core::client_functions::update_table;
+/// use core::client_functions::get_rows;
+/// use core::request_types::RowsQuery;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///
-///     env_logger::builder()
-///         .filter_level(LevelFilter::Info)
-///         .format_timestamp_millis()
-///         .init();
-///
 ///     let client = Client::new();
 ///
-/// // Update rows in the table
-///     let update_request = UpdateRequest {
-///         table_name: "test_table".to_string(),
-///         condition: Option::from(Condition {
-///             column: "test_key".to_string(),
-///             value: "true".to_string(),
-///         }),
-///         updates: vec![
-///             UpdateColumnRequest {
-///                 column: "test_key3".to_string(),
-///                 value: "updated_value".to_string(),
-///             },
-///             UpdateColumnRequest {
-///                 column: "test_key2".to_string(),
-///                 value: "17.78".to_string(),
-///             },
-///         ],
+///     let mut query = RowsQuery {
+///         limit: Some(50),
+///         order_by: Some("test_key".to_string()),
+///         ..RowsQuery::default()
 ///     };
-///
-///     update_table(&client, &update_request).await.unwrap();
+///     loop {
+///         let page = get_rows(&client, "test_table", &query).await.unwrap();
+///         println!("{:?}", page.rows);
+///         match page.next_cursor {
+///             Some(cursor) => query.cursor = Some(cursor),
+///             None => break,
+///         }
+///     }
 /// }
 /// ```
-pub async fn update_table(
+pub async fn get_rows(
     client: &Client,
-    update_request: &UpdateRequest,
-) -> Result<(), Box<dyn error::Error>> {
-    let url = "http://localhost:3000/update_table".to_string();
+    table_name: &str,
+    query: &RowsQuery,
+) -> Result<RowsPage, Box<dyn error::Error>> {
+    let url = format!("{}/tables/{}/rows", server_url(), table_name);
 
-    let resp = client.post(&url).json(update_request).send().await?;
+    let resp = authorize(client.get(&url)).query(query).send().await?;
 
     match resp.status().is_success() {
         true => {
-            debug!("Update Table Response: {:?}", resp);
-            info!("Updated Table {:?}", update_request.table_name);
-            Ok(())
+            debug!("Get Rows Response: {:?}", resp);
+            Ok(resp.json::<RowsPage>().await?)
         }
         false => {
-            debug!("Update Table Response: {:?}", resp);
+            debug!("Get Rows Response: {:?}", resp);
             let error_body = resp.json::<serde_json::Value>().await?;
             let error_message = error_body.as_str().unwrap_or("Unknown error");
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                error_message,
-            )))
+            Err(Box::new(std::io::Error::other(error_message)))
         }
     }
 }
@@ -1,3 +1,5 @@
+use crate::sql::ast::Expr;
+use crate::value::{DataType, Value};
 use serde::{Deserialize, Serialize};
 
 /// Represents a column in a database table.
@@ -7,12 +9,34 @@ pub struct Column {
     pub primary_key: bool,
     pub non_null: bool,
     pub unique: bool,
-    pub foreign_key: Option<Vec<Box<Column>>>,
+    pub foreign_key: Option<ForeignKey>,
+    /// Value used when an inserted row does not provide this column.
+    #[serde(default)]
+    pub default: Option<Value>,
+    /// Boolean expression every stored value has to satisfy.
+    #[serde(default)]
+    pub check: Option<Expr>,
+    /// Whether missing or NULL values are replaced by the next integer in sequence.
+    #[serde(default)]
+    pub auto_increment: bool,
+    /// The declared type of the column, if any.
+    #[serde(default)]
+    pub data_type: Option<DataType>,
+}
+
+/// Reference from a column to a column of another table.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ForeignKey {
+    pub table_name: String,
+    pub column: String,
 }
 
 impl Column {
     /// Creates a new `Column` instance.
     ///
+    /// The column has no declared type, no default value, no check expression and is not
+    /// auto-incremented.
+    ///
     /// # Arguments
     ///
     /// * `key` - The key or name of the column.
@@ -25,7 +49,7 @@ impl Column {
         primary_key: bool,
         non_null: bool,
         unique: bool,
-        foreign_key: Option<Vec<Box<Column>>>,
+        foreign_key: Option<ForeignKey>,
     ) -> Self {
         Self {
             key,
@@ -33,6 +57,10 @@ impl Column {
             non_null,
             unique,
             foreign_key,
+            default: None,
+            check: None,
+            auto_increment: false,
+            data_type: None,
         }
     }
 }
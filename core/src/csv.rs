@@ -0,0 +1,295 @@
+//! Reading and writing CSV, used to import rows into tables and to export tables and select
+//! results.
+//!
+//! NULL is written as the unquoted NULL marker, which is empty by default. Any other value
+//! equal to the marker is quoted, so an empty string is written as `""` and read back as an
+//! empty string rather than NULL.
+use crate::row::Row;
+use crate::value::{DataType, Value};
+use serde::{Deserialize, Serialize};
+
+/// The dialect of a CSV file.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CsvOptions {
+    /// Whether the first record holds the column names.
+    pub header: bool,
+    /// The character separating the fields of a record.
+    pub delimiter: char,
+    /// The character enclosing fields that contain the delimiter, quotes or line breaks.
+    /// It is escaped by doubling it.
+    pub quote: char,
+    /// The text of an unquoted field that stands for NULL.
+    pub null: String,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            header: true,
+            delimiter: ',',
+            quote: '"',
+            null: String::new(),
+        }
+    }
+}
+
+/// A record read from a CSV file.
+#[derive(Debug, PartialEq)]
+pub struct CsvRecord {
+    /// The 1-based line the record starts on.
+    pub line: usize,
+    /// The fields of the record, with `None` for NULL.
+    pub fields: Vec<Option<String>>,
+}
+
+/// Parses CSV text into records.
+///
+/// Blank lines are skipped. Both `\n` and `\r\n` line endings are accepted, and quoted
+/// fields may span several lines.
+///
+/// # Arguments
+///
+/// * `text` - The CSV text.
+/// * `options` - The dialect of the text. `header` is ignored, the header is returned as the
+///   first record.
+///
+/// # Errors
+///
+/// Returns an error if a quoted field is not terminated or is followed by other characters.
+///
+/// # Examples
+///
+/// ```
+/// use core::csv::{parse, CsvOptions};
+///
+/// let records = parse("id,name\n1,\"Smith, J.\"\n2,\n", &CsvOptions::default()).unwrap();
+/// assert_eq!(records.len(), 3);
+/// assert_eq!(records[1].fields[1], Some("Smith, J.".to_string()));
+/// assert_eq!(records[2].fields[1], None);
+/// ```
+pub fn parse(text: &str, options: &CsvOptions) -> Result<Vec<CsvRecord>, String> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = text.chars().peekable();
+
+    // Ends the current field; unquoted fields equal to the NULL marker are NULL
+    let finish_field = |fields: &mut Vec<Option<String>>, field: &mut String, quoted: &mut bool| {
+        let text = std::mem::take(field);
+        fields.push(match !*quoted && text == options.null {
+            true => None,
+            false => Some(text),
+        });
+        *quoted = false;
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c == options.quote && field.is_empty() && !quoted => {
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some(inner) if inner == options.quote => {
+                            if chars.peek() == Some(&options.quote) {
+                                field.push(inner);
+                                chars.next();
+                            } else {
+                                break;
+                            }
+                        }
+                        Some(inner) => {
+                            if inner == '\n' {
+                                line += 1;
+                            }
+                            field.push(inner);
+                        }
+                        None => {
+                            return Err(format!(
+                                "Unterminated quoted field starting on line {}",
+                                record_line
+                            ))
+                        }
+                    }
+                }
+                match chars.peek() {
+                    Some(next) if *next == options.delimiter || *next == '\n' || *next == '\r' => {}
+                    None => {}
+                    Some(next) => {
+                        return Err(format!(
+                            "Unexpected character '{}' after a quoted field on line {}",
+                            next, line
+                        ))
+                    }
+                }
+            }
+            c if c == options.delimiter => finish_field(&mut fields, &mut field, &mut quoted),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                if fields.is_empty() && field.is_empty() && !quoted {
+                    // A blank line
+                } else {
+                    finish_field(&mut fields, &mut field, &mut quoted);
+                    records.push(CsvRecord {
+                        line: record_line,
+                        fields: std::mem::take(&mut fields),
+                    });
+                }
+                line += 1;
+                record_line = line;
+            }
+            c => field.push(c),
+        }
+    }
+    if !fields.is_empty() || !field.is_empty() || quoted {
+        finish_field(&mut fields, &mut field, &mut quoted);
+        records.push(CsvRecord {
+            line: record_line,
+            fields,
+        });
+    }
+    Ok(records)
+}
+
+/// Infers the type of a column from its non-NULL values.
+///
+/// The first of `INT`, `FLOAT` and `BOOL` that all values can be parsed as is chosen, and
+/// `STRING` otherwise, including for columns without values.
+///
+/// # Arguments
+///
+/// * `values` - The non-NULL values of the column.
+///
+/// # Examples
+///
+/// ```
+/// use core::csv::infer_type;
+/// use core::value::DataType;
+///
+/// assert_eq!(infer_type(["1", "2.5"]), DataType::Float);
+/// assert_eq!(infer_type(["true", "no"]), DataType::Str);
+/// ```
+pub fn infer_type<'a>(values: impl IntoIterator<Item = &'a str> + Clone) -> DataType {
+    let has_values = values.clone().into_iter().next().is_some();
+    [DataType::Int, DataType::Float, DataType::Bool]
+        .into_iter()
+        .find(|data_type| {
+            has_values
+                && values
+                    .clone()
+                    .into_iter()
+                    .all(|value| parse_value(value, *data_type).is_ok())
+        })
+        .unwrap_or(DataType::Str)
+}
+
+/// Converts the text of a field to a value of the given type.
+///
+/// Booleans are `true`/`false`, `t`/`f` or `1`/`0`, in any case. Numbers may be surrounded
+/// by spaces.
+///
+/// # Arguments
+///
+/// * `text` - The text of the field.
+/// * `data_type` - The type of the column.
+///
+/// # Errors
+///
+/// Returns an error if the text is not a valid value of the type.
+///
+/// # Examples
+///
+/// ```
+/// use core::csv::parse_value;
+/// use core::value::{DataType, Value};
+///
+/// assert_eq!(parse_value("42", DataType::Int), Ok(Value::Int(42)));
+/// assert_eq!(parse_value("T", DataType::Bool), Ok(Value::Bool(true)));
+/// assert!(parse_value("4.2", DataType::Int).is_err());
+/// ```
+pub fn parse_value(text: &str, data_type: DataType) -> Result<Value, String> {
+    let invalid = || format!("Invalid {} value '{}'", data_type, text);
+    match data_type {
+        DataType::Str => Ok(Value::Str(text.to_string())),
+        DataType::Int => text.trim().parse().map(Value::Int).map_err(|_| invalid()),
+        DataType::Float => text.trim().parse().map(Value::Float).map_err(|_| invalid()),
+        DataType::Bool => match text.trim().to_lowercase().as_str() {
+            "true" | "t" | "1" => Ok(Value::Bool(true)),
+            "false" | "f" | "0" => Ok(Value::Bool(false)),
+            _ => Err(invalid()),
+        },
+    }
+}
+
+/// Formats a field, quoting it if needed.
+///
+/// A field is quoted if it equals the NULL marker, contains the delimiter, the quote
+/// character or a line break, or starts or ends with a space.
+fn field(text: &str, options: &CsvOptions) -> String {
+    if text == options.null
+        || text.contains([options.delimiter, options.quote, '\n', '\r'])
+        || text.starts_with(' ')
+        || text.ends_with(' ')
+    {
+        let quote = options.quote.to_string();
+        format!(
+            "{}{}{}",
+            quote,
+            text.replace(&quote, &quote.repeat(2)),
+            quote
+        )
+    } else {
+        text.to_string()
+    }
+}
+
+/// Writes rows as CSV.
+///
+/// Floats always show a decimal point, so their type is inferred correctly when the file is
+/// imported again.
+///
+/// # Arguments
+///
+/// * `columns` - The column names, written as the first record if `options.header` is set.
+/// * `rows` - The rows, with one value per column.
+/// * `options` - The dialect to write.
+///
+/// # Examples
+///
+/// ```
+/// use core::csv::{write, CsvOptions};
+/// use core::row::Row;
+/// use core::value::Value;
+///
+/// let columns = vec!["name".to_string(), "score".to_string()];
+/// let rows = vec![
+///     Row::new(vec![Value::from("Smith, J."), Value::Float(1.0)]),
+///     Row::new(vec![Value::from(""), Value::Null]),
+/// ];
+/// let csv = write(&columns, &rows, &CsvOptions::default());
+/// assert_eq!(csv, "name,score\n\"Smith, J.\",1.0\n\"\",\n");
+/// ```
+pub fn write(columns: &[String], rows: &[Row], options: &CsvOptions) -> String {
+    let delimiter = options.delimiter.to_string();
+    let mut out = String::new();
+    if options.header {
+        let names: Vec<String> = columns.iter().map(|name| field(name, options)).collect();
+        out += &names.join(&delimiter);
+        out += "\n";
+    }
+    for row in rows {
+        let fields: Vec<String> = (0..columns.len())
+            .map(|i| match row.values.get(i) {
+                None | Some(Value::Null) => options.null.clone(),
+                Some(Value::Float(f)) => field(&format!("{:?}", f), options),
+                Some(value) => field(&value.as_string().unwrap_or_default(), options),
+            })
+            .collect();
+        out += &fields.join(&delimiter);
+        out += "\n";
+    }
+    out
+}
@@ -0,0 +1,219 @@
+//! Logical SQL dumps of tables.
+//!
+//! A dump is a script of `CREATE TABLE`, batched `INSERT`, `CREATE UNIQUE INDEX` and
+//! `CREATE POLICY` statements in the dialect of [`sql`](crate::sql). Unlike `db.json`, it does not depend on
+//! the serde representation of tables and values, so it can be replayed by the SQL client or
+//! the server's `/restore` endpoint of another version to migrate the data.
+//!
+//! Tables are created after the tables their foreign keys refer to. Unique constraints are
+//! created as indexes after all rows are inserted, so they are checked once per table, and
+//! followed by the row-level security policies of the tables.
+use crate::row::Row;
+use crate::sql::ast::quote_ident;
+use crate::table::Table;
+use crate::value::{DataType, Value};
+
+/// The number of rows per `INSERT` statement if none is given.
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Writes an SQL script recreating the tables with their rows.
+///
+/// # Arguments
+///
+/// * `tables` - The tables to dump. Foreign keys to tables that are not dumped are kept, so
+///   those tables have to exist when the script is replayed.
+/// * `batch_size` - The maximum number of rows per `INSERT` statement.
+///
+/// # Examples
+///
+/// ```
+/// use core::column::Column;
+/// use core::dump::dump;
+/// use core::row::Row;
+/// use core::table::Table;
+/// use core::value::{DataType, Value};
+///
+/// let mut table = Table::new("users".to_string());
+/// table.add_column(Column {
+///     data_type: Some(DataType::Int),
+///     ..Column::new("id".to_string(), true, true, false, None)
+/// });
+/// table.add_column(Column {
+///     data_type: Some(DataType::Str),
+///     ..Column::new("email".to_string(), false, false, true, None)
+/// });
+/// table.add_row(Row::new(vec![Value::Int(1), Value::from("a@example.com")]));
+/// table.add_row(Row::new(vec![Value::Int(2), Value::Null]));
+///
+/// let script = dump(&[table], 100);
+/// assert!(script.contains("CREATE TABLE users (\n    id INT,\n    email STRING,\n    PRIMARY KEY (id)\n);"));
+/// assert!(script.contains("INSERT INTO users (id, email) VALUES\n    (1, 'a@example.com'),\n    (2, NULL);"));
+/// assert!(script.contains("CREATE UNIQUE INDEX users_email_key ON users (email);"));
+/// assert_eq!(core::sql::parse_statements(&script).unwrap().len(), 3);
+/// ```
+pub fn dump<'a>(tables: impl IntoIterator<Item = &'a Table>, batch_size: usize) -> String {
+    let tables = dependency_order(tables.into_iter().collect());
+    let names: Vec<&str> = tables.iter().map(|table| table.name.as_str()).collect();
+    let mut out = format!(
+        "-- Rust Database SQL dump, version {}\n-- Tables: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        names.join(", ")
+    );
+
+    for table in &tables {
+        out += "\n";
+        out += &create_table(table);
+        for rows in table.rows.chunks(batch_size.max(1)) {
+            out += "\n";
+            out += &insert(table, rows);
+        }
+    }
+
+    let definitions: Vec<String> = tables
+        .iter()
+        .flat_map(|table| {
+            table.unique_constraints.iter().map(|constraint| {
+                format!(
+                    "CREATE UNIQUE INDEX {} ON {} ({});\n",
+                    quote_ident(&constraint.name),
+                    quote_ident(&table.name),
+                    idents(&constraint.columns)
+                )
+            })
+        })
+        .chain(tables.iter().flat_map(|table| {
+            table
+                .policies
+                .iter()
+                .map(|policy| format!("{};\n", policy.to_sql(&table.name)))
+        }))
+        .collect();
+    if !definitions.is_empty() {
+        out += "\n";
+        out += &definitions.concat();
+    }
+    out
+}
+
+/// Orders the tables so that every table comes after the tables its foreign keys refer to.
+///
+/// Otherwise the original order is kept. References to tables that are not dumped and to the
+/// table itself are ignored.
+fn dependency_order(mut remaining: Vec<&Table>) -> Vec<&Table> {
+    let mut ordered: Vec<&Table> = Vec::new();
+    while !remaining.is_empty() {
+        let ready = remaining.iter().position(|table| {
+            table.columns.iter().all(|column| {
+                column.foreign_key.as_ref().is_none_or(|foreign_key| {
+                    foreign_key.table_name == table.name
+                        || ordered
+                            .iter()
+                            .any(|done| done.name == foreign_key.table_name)
+                        || !remaining
+                            .iter()
+                            .any(|other| other.name == foreign_key.table_name)
+                })
+            })
+        });
+        // A cycle cannot be created one table at a time, but is dumped as it is rather than lost
+        ordered.push(remaining.remove(ready.unwrap_or(0)));
+    }
+    ordered
+}
+
+/// Joins quoted identifiers with commas.
+fn idents(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|name| quote_ident(name))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Returns the type of the column at `index`.
+///
+/// Columns created without a type get the type of their values, or `STRING` if they are mixed.
+fn column_type(table: &Table, index: usize) -> DataType {
+    if let Some(data_type) = table.columns[index].data_type {
+        return data_type;
+    }
+    let mut types = table
+        .rows
+        .iter()
+        .filter_map(|row| match row.values.get(index) {
+            Some(Value::Int(_)) => Some(DataType::Int),
+            Some(Value::Float(_)) => Some(DataType::Float),
+            Some(Value::Bool(_)) => Some(DataType::Bool),
+            Some(Value::Str(_)) => Some(DataType::Str),
+            Some(Value::Null) | None => None,
+        });
+    match types.next() {
+        Some(first) if types.all(|data_type| data_type == first) => first,
+        _ => DataType::Str,
+    }
+}
+
+/// Writes the `CREATE TABLE` statement of a table, without its unique constraints.
+fn create_table(table: &Table) -> String {
+    let mut definitions: Vec<String> = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let mut definition =
+                format!("{} {}", quote_ident(&column.key), column_type(table, index));
+            if column.non_null && !table.primary_key.contains(&column.key) {
+                definition += " NOT NULL";
+            }
+            if let Some(default) = &column.default {
+                definition += &format!(" DEFAULT {}", default.to_sql());
+            }
+            if column.auto_increment {
+                definition += " AUTO_INCREMENT";
+            }
+            if let Some(check) = &column.check {
+                definition += &format!(" CHECK ({})", check);
+            }
+            if let Some(foreign_key) = &column.foreign_key {
+                definition += &format!(
+                    " REFERENCES {} ({})",
+                    quote_ident(&foreign_key.table_name),
+                    quote_ident(&foreign_key.column)
+                );
+            }
+            definition
+        })
+        .collect();
+    if !table.primary_key.is_empty() {
+        definitions.push(format!("PRIMARY KEY ({})", idents(&table.primary_key)));
+    }
+    format!(
+        "CREATE TABLE {} (\n    {}\n);\n",
+        quote_ident(&table.name),
+        definitions.join(",\n    ")
+    )
+}
+
+/// Writes one `INSERT` statement for the rows.
+fn insert(table: &Table, rows: &[Row]) -> String {
+    let columns: Vec<String> = table
+        .columns
+        .iter()
+        .map(|column| column.key.clone())
+        .collect();
+    let tuples: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let values: Vec<String> = (0..columns.len())
+                .map(|index| row.values.get(index).unwrap_or(&Value::Null).to_sql())
+                .collect();
+            format!("({})", values.join(", "))
+        })
+        .collect();
+    format!(
+        "INSERT INTO {} ({}) VALUES\n    {};\n",
+        quote_ident(&table.name),
+        idents(&columns),
+        tuples.join(",\n    ")
+    )
+}
@@ -0,0 +1,159 @@
+//! Query plans as shown by `EXPLAIN`.
+//!
+//! A plan is a tree of nodes, each an operator producing rows from the rows of its children,
+//! e.g. a projection above the scan of a table. Every node has an estimate of the number of rows
+//! it produces. `EXPLAIN ANALYZE` runs the query and adds the number of rows every node
+//! actually produced and the time it took, including the time of its children. The plans are
+//! built from the physical plans of [`plan`](crate::plan).
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A node of a query plan.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlanNode {
+    /// The operator, e.g. `Seq Scan` or `Project`.
+    pub operator: String,
+    /// What the operator works on, e.g. the scanned table or the projected columns.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// The condition of the rows an index lookup reads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_condition: Option<String>,
+    /// The condition the rows a scan reads have to satisfy to be produced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// The estimated number of rows the node produces.
+    pub estimated_rows: u64,
+    /// The number of rows the node produced, with `EXPLAIN ANALYZE`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_rows: Option<u64>,
+    /// The number of rows the filter removed, with `EXPLAIN ANALYZE`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows_removed_by_filter: Option<u64>,
+    /// The time the node and its children took in milliseconds, with `EXPLAIN ANALYZE`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_ms: Option<f64>,
+    /// The nodes whose rows this node reads.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    /// Creates a node without children.
+    ///
+    /// # Arguments
+    ///
+    /// * `operator` - The operator, e.g. `Seq Scan`.
+    /// * `detail` - What the operator works on, if anything.
+    /// * `estimated_rows` - The estimated number of rows the node produces.
+    pub fn new(operator: &str, detail: Option<String>, estimated_rows: u64) -> PlanNode {
+        PlanNode {
+            operator: operator.to_string(),
+            detail,
+            index_condition: None,
+            filter: None,
+            estimated_rows,
+            actual_rows: None,
+            rows_removed_by_filter: None,
+            time_ms: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns the node with `child` added to its children.
+    pub fn with_child(mut self, child: PlanNode) -> PlanNode {
+        self.children.push(child);
+        self
+    }
+
+    /// Records the number of rows the node produced and the time it took.
+    pub fn analyzed(&mut self, rows: usize, time: Duration) {
+        self.actual_rows = Some(rows as u64);
+        self.time_ms = Some(time.as_secs_f64() * 1000.0);
+    }
+
+    /// Returns the lines of the plan as printed by `EXPLAIN`, children indented below their
+    /// parent and conditions below their node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::explain::PlanNode;
+    /// use std::time::Duration;
+    ///
+    /// let mut scan = PlanNode::new("Seq Scan", Some("users".to_string()), 1);
+    /// scan.filter = Some("city = 'Oslo'".to_string());
+    /// let mut plan = PlanNode::new("Project", Some("name".to_string()), 1).with_child(scan);
+    /// assert_eq!(
+    ///     plan.lines(),
+    ///     [
+    ///         "Project: name  (rows=1)",
+    ///         "  ->  Seq Scan: users  (rows=1)",
+    ///         "        Filter: city = 'Oslo'",
+    ///     ]
+    /// );
+    ///
+    /// plan.analyzed(1, Duration::from_micros(250));
+    /// assert_eq!(
+    ///     plan.lines()[0],
+    ///     "Project: name  (rows=1) (actual rows=1 time=0.250 ms)"
+    /// );
+    /// ```
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        self.write_lines(0, &mut lines);
+        lines
+    }
+
+    fn write_lines(&self, depth: usize, lines: &mut Vec<String>) {
+        let mut line = match depth {
+            0 => String::new(),
+            _ => format!("{}->  ", " ".repeat(6 * depth - 4)),
+        };
+        line += &self.operator;
+        if let Some(detail) = &self.detail {
+            line += &format!(": {}", detail);
+        }
+        line += &format!("  (rows={})", self.estimated_rows);
+        if let (Some(rows), Some(time_ms)) = (self.actual_rows, self.time_ms) {
+            line += &format!(" (actual rows={} time={:.3} ms)", rows, time_ms);
+        }
+        lines.push(line);
+        let indent = match depth {
+            0 => "  ".to_string(),
+            _ => " ".repeat(6 * depth + 2),
+        };
+        if let Some(condition) = &self.index_condition {
+            lines.push(format!("{}Index Cond: {}", indent, condition));
+        }
+        if let Some(filter) = &self.filter {
+            lines.push(format!("{}Filter: {}", indent, filter));
+        }
+        if let Some(removed) = self.rows_removed_by_filter {
+            lines.push(format!("{}Rows Removed by Filter: {}", indent, removed));
+        }
+        for child in &self.children {
+            child.write_lines(depth + 1, lines);
+        }
+    }
+}
+
+/// The plan of a query, as returned by `/select?explain=plan` and `/select?explain=analyze`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Explanation {
+    pub plan: PlanNode,
+    /// The time it took to run the query in milliseconds, with `EXPLAIN ANALYZE`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_time_ms: Option<f64>,
+}
+
+impl Explanation {
+    /// Returns the lines of the plan followed by the execution time, if the query was run.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = self.plan.lines();
+        if let Some(execution_time_ms) = self.execution_time_ms {
+            lines.push(format!("Execution Time: {:.3} ms", execution_time_ms));
+        }
+        lines
+    }
+}
@@ -15,6 +15,20 @@
 //!
 //! These modules encapsulate related functionality and data structures essential for database operations.
 //!
+//! - [`catalog`](catalog): Builds the read-only `information_schema` views describing the tables.
+//! - [`audit`](audit): Entries of the audit log recording who changed the schema and data.
+//! - [`statistics`](statistics): Execution statistics of statements.
+//! - [`explain`](explain): Query plans as shown by `EXPLAIN`.
+//! - [`plan`](plan): Logical and physical query plans, the optimizer and the executor.
+//! - [`csv`](csv): Reads and writes CSV for importing and exporting tables.
+//! - [`dump`](dump): Writes logical SQL dumps that recreate tables with their rows.
+//! - [`privilege`](privilege): Privileges granted to users and roles, and checking them.
+//! - [`policy`](policy): Row-level security policies restricting the rows users may see and change.
+//!
+//! ## SQL
+//!
+//! - [`sql`](sql): Tokenizer, parser and syntax tree for the SQL dialect, usable by clients and the server.
+//!
 //! ## Client-Side Functionality
 //!
 //! The following module provides functions for building a client to interact with the server's API:
@@ -26,10 +40,20 @@
 //!
 //! For examples of using the client_functions, see the documentation of the client_functions module.
 
+pub mod audit;
+pub mod catalog;
 pub mod client_functions;
 pub mod column;
+pub mod csv;
+pub mod dump;
+pub mod explain;
+pub mod plan;
+pub mod policy;
+pub mod privilege;
 pub mod request_types;
 pub mod row;
+pub mod sql;
+pub mod statistics;
 pub mod table;
 pub mod value;
 
@@ -0,0 +1,1701 @@
+//! Query plans: the logical plans requests compile into, the optimizer and the executor.
+//!
+//! Select, update, delete and join requests are compiled into a [`LogicalPlan`], a tree of
+//! scans, filters, projections, joins, updates and deletes stating what the request computes.
+//! [`LogicalPlan::optimize`], or [`LogicalPlan::optimize_join`] for a join of two tables, turns
+//! it into a [`PhysicalPlan`] stating how:
+//!
+//! - Constant folding evaluates the parts of predicates that do not depend on the row once,
+//!   e.g. `current_setting('app.tenant')` in a row-level security policy.
+//! - Predicate pushdown moves filters below projections and joins and into the scan of the
+//!   table, so that rows are filtered while they are read, before any of their values are
+//!   copied or joined.
+//! - Projection pruning removes projections that keep every column in order.
+//! - Index selection reads the rows with a value in an indexed column through its [`Index`]
+//!   instead of reading every row, using the index that reads the fewest rows.
+//!
+//! Column names are resolved to positions once, when the plan is optimized. Joins hash the
+//! rows of the joined table by the value of their join column and look up the rows of the
+//! first table in it. [`PhysicalPlan::execute`] runs the plan over the rows of the table, or
+//! [`PhysicalPlan::execute_join`] over the rows of two tables, and records the rows and time of
+//! every node, from which [`PhysicalPlan::explain`] builds the plan shown by `EXPLAIN`.
+use crate::csv;
+use crate::explain::{Explanation, PlanNode};
+use crate::policy::{session_function, Session};
+use crate::request_types::{Condition, JoinCondition, UpdateColumnRequest};
+use crate::row::Row;
+use crate::sql::ast::{quote_ident, BinaryOp, Expr};
+use crate::sql::eval::{self, Scope};
+use crate::table::Table;
+use crate::value::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A condition rows have to satisfy.
+#[derive(Clone, Debug)]
+pub enum Predicate {
+    /// The value of a column, as text, equals a value, like the conditions of requests. `NULL`
+    /// equals the empty string.
+    Equals(Condition),
+    /// One of the expressions is `TRUE`, like the `USING` expressions of row-level security
+    /// policies. Expressions that cannot be evaluated are not `TRUE`, and without expressions
+    /// no row satisfies the predicate. The session provides the values of the
+    /// [`SESSION_FUNCTIONS`](crate::policy::SESSION_FUNCTIONS).
+    Any { exprs: Vec<Expr>, session: Session },
+}
+
+impl Predicate {
+    /// Lists the columns the predicate refers to.
+    fn columns(&self) -> Vec<&str> {
+        match self {
+            Predicate::Equals(condition) => vec![condition.column.as_str()],
+            Predicate::Any { exprs, .. } => exprs.iter().flat_map(eval::columns).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Predicate::Equals(condition) => write!(f, "{}", condition.to_sql()),
+            Predicate::Any { exprs, .. } => match exprs.as_slice() {
+                [] => write!(f, "FALSE"),
+                [expr] => write!(f, "{}", expr),
+                exprs => {
+                    let exprs: Vec<String> =
+                        exprs.iter().map(|expr| format!("({})", expr)).collect();
+                    write!(f, "{}", exprs.join(" OR "))
+                }
+            },
+        }
+    }
+}
+
+/// What a request computes, as a tree whose leaf reads a table.
+#[derive(Clone, Debug)]
+pub enum LogicalPlan {
+    /// Reads every row of a table.
+    Scan { table: String },
+    /// Keeps the rows of its input that satisfy a predicate.
+    Filter {
+        predicate: Predicate,
+        input: Box<LogicalPlan>,
+    },
+    /// Keeps the given columns of the rows of its input, in the given order.
+    Project {
+        columns: Vec<String>,
+        input: Box<LogicalPlan>,
+    },
+    /// Sets columns of the rows of its input to new values. Updates are always the root of
+    /// their plan.
+    Update {
+        assignments: Vec<(String, Value)>,
+        input: Box<LogicalPlan>,
+    },
+    /// Removes the rows of its input from the table. Deletes are always the root of their
+    /// plan.
+    Delete { input: Box<LogicalPlan> },
+    /// Pairs the rows of two inputs reading different tables whose values in two columns are
+    /// equal, like an inner join. Its rows have the columns of both tables, named by their
+    /// table, e.g. `users.id`, and so are the columns it is joined on.
+    Join {
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+        on: (String, String),
+    },
+}
+
+impl LogicalPlan {
+    /// Compiles a select from a table.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The table rows are selected from.
+    /// * `columns` - The selected columns, or `None` to select all columns.
+    /// * `condition` - The condition of the selected rows, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a column does not exist.
+    pub fn select(
+        table: &Table,
+        columns: Option<&[String]>,
+        condition: Option<&Condition>,
+    ) -> Result<LogicalPlan, String> {
+        let columns = match columns {
+            Some(columns) => columns.to_vec(),
+            None => table
+                .columns
+                .iter()
+                .map(|column| column.key.clone())
+                .collect(),
+        };
+        let plan = LogicalPlan::Project {
+            columns,
+            input: Box::new(LogicalPlan::scan(table, condition)),
+        };
+        plan.check_columns(table)?;
+        Ok(plan)
+    }
+
+    /// Compiles an update of the rows of a table.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The table whose rows are updated.
+    /// * `condition` - The condition of the updated rows, or `None` to update every row.
+    /// * `updates` - The new values of the updated columns. They are converted to the declared
+    ///   types of the columns, and stored as strings in columns without one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a column does not exist, a value is not valid for the type of its
+    /// column, or `NULL` is assigned to a non-null column.
+    pub fn update(
+        table: &Table,
+        condition: Option<&Condition>,
+        updates: &[UpdateColumnRequest],
+    ) -> Result<LogicalPlan, String> {
+        let assignments = updates
+            .iter()
+            .map(|update| {
+                let column = table
+                    .columns
+                    .iter()
+                    .find(|column| column.key == update.column)
+                    .ok_or_else(|| format!("Column '{}' not found", update.column))?;
+                let value = match (&update.value, column.data_type) {
+                    (None, _) if column.non_null || column.primary_key => {
+                        return Err(format!(
+                            "Column '{}' does not allow NULL values",
+                            column.key
+                        ))
+                    }
+                    (None, _) => Value::Null,
+                    (Some(text), Some(data_type)) => csv::parse_value(text, data_type)?,
+                    (Some(text), None) => Value::from(text.as_str()),
+                };
+                Ok((update.column.clone(), value))
+            })
+            .collect::<Result<_, String>>()?;
+        let plan = LogicalPlan::Update {
+            assignments,
+            input: Box::new(LogicalPlan::scan(table, condition)),
+        };
+        plan.check_columns(table)?;
+        Ok(plan)
+    }
+
+    /// Compiles a delete of the rows of a table.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The table whose rows are deleted.
+    /// * `condition` - The condition of the deleted rows, or `None` to delete every row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column of the condition does not exist.
+    pub fn delete(table: &Table, condition: Option<&Condition>) -> Result<LogicalPlan, String> {
+        let plan = LogicalPlan::Delete {
+            input: Box::new(LogicalPlan::scan(table, condition)),
+        };
+        plan.check_columns(table)?;
+        Ok(plan)
+    }
+
+    /// Compiles a select from the join of two tables.
+    ///
+    /// # Arguments
+    ///
+    /// * `left` - The first table.
+    /// * `right` - The joined table.
+    /// * `on` - The columns whose values have to be equal for two rows to be joined.
+    /// * `columns` - The selected columns, named by their table, or `None` to select all
+    ///   columns of both tables.
+    /// * `condition` - The condition of the selected rows, on a column named by its table, if
+    ///   there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a table is joined with itself or a column does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::column::Column;
+    /// use core::plan::{Index, LogicalPlan};
+    /// use core::request_types::{Condition, JoinCondition};
+    /// use core::row::Row;
+    /// use core::table::Table;
+    /// use core::value::Value;
+    ///
+    /// let mut users = Table::new("users".to_string());
+    /// users.add_column(Column::new("id".to_string(), true, true, false, None));
+    /// users.add_column(Column::new("name".to_string(), false, false, false, None));
+    /// users.add_row(Row::new(vec![Value::Int(1), "Ann".into()]));
+    /// users.add_row(Row::new(vec![Value::Int(2), "Bob".into()]));
+    /// let mut orders = Table::new("orders".to_string());
+    /// orders.add_column(Column::new("user_id".to_string(), false, false, false, None));
+    /// orders.add_column(Column::new("total".to_string(), false, false, false, None));
+    /// for (user_id, total) in [(2, 10), (1, 20), (2, 30)] {
+    ///     orders.add_row(Row::new(vec![Value::Int(user_id), Value::Int(total)]));
+    /// }
+    /// let on = JoinCondition {
+    ///     left_column: "id".to_string(),
+    ///     right_column: "user_id".to_string(),
+    /// };
+    /// let columns = ["users.name".to_string(), "orders.total".to_string()];
+    /// let condition = Condition {
+    ///     column: "users.name".to_string(),
+    ///     value: "Bob".to_string(),
+    /// };
+    ///
+    /// // The condition is moved into the scan of the table of its column
+    /// let plan = LogicalPlan::join(&users, &orders, &on, Some(&columns), Some(&condition));
+    /// let plan = plan.unwrap().optimize_join(&users, &[], &orders, &[]);
+    /// assert_eq!(
+    ///     plan.explain(None).lines(),
+    ///     [
+    ///         "Project: users.name, orders.total  (rows=2)",
+    ///         "  ->  Hash Join: users.id = orders.user_id  (rows=2)",
+    ///         "        ->  Seq Scan: users  (rows=1)",
+    ///         "              Filter: name = 'Bob'",
+    ///         "        ->  Seq Scan: orders  (rows=3)",
+    ///     ]
+    /// );
+    /// let output = plan.execute_join(&users.rows, &orders.rows);
+    /// let totals: Vec<&Value> = output.rows.iter().map(|row| &row.values[1]).collect();
+    /// assert_eq!(totals, [&Value::Int(10), &Value::Int(30)]);
+    /// ```
+    pub fn join(
+        left: &Table,
+        right: &Table,
+        on: &JoinCondition,
+        columns: Option<&[String]>,
+        condition: Option<&Condition>,
+    ) -> Result<LogicalPlan, String> {
+        if left.name == right.name {
+            return Err(format!(
+                "Table '{}' cannot be joined with itself",
+                left.name
+            ));
+        }
+        let joined = qualified_columns(left, right);
+        let columns = match columns {
+            Some(columns) => columns.to_vec(),
+            None => joined.clone(),
+        };
+        let join = LogicalPlan::Join {
+            left: Box::new(LogicalPlan::scan(left, None)),
+            right: Box::new(LogicalPlan::scan(right, None)),
+            on: (
+                format!("{}.{}", left.name, on.left_column),
+                format!("{}.{}", right.name, on.right_column),
+            ),
+        };
+        let plan = LogicalPlan::Project {
+            columns,
+            input: Box::new(match condition {
+                Some(condition) => LogicalPlan::Filter {
+                    predicate: Predicate::Equals(condition.clone()),
+                    input: Box::new(join),
+                },
+                None => join,
+            }),
+        };
+        match plan
+            .read_columns()
+            .into_iter()
+            .find(|name| !joined.iter().any(|column| column == name))
+        {
+            Some(name) => Err(format!("Column '{}' not found", name)),
+            None => Ok(plan),
+        }
+    }
+
+    /// Restricts the rows the plan reads from its table to those satisfying `predicate`, with
+    /// a filter directly above the scan, e.g. for row-level security.
+    pub fn restrict(self, predicate: Predicate) -> LogicalPlan {
+        match self {
+            LogicalPlan::Scan { .. } => LogicalPlan::Filter {
+                predicate,
+                input: Box::new(self),
+            },
+            plan => plan.map_input(|input| input.restrict(predicate.clone())),
+        }
+    }
+
+    /// Restricts the rows a join reads from one of its tables to those satisfying `predicate`,
+    /// with a filter directly above the scan of the table. The columns of the predicate are
+    /// not named by their table.
+    pub fn restrict_table(self, table: &str, predicate: Predicate) -> LogicalPlan {
+        match self {
+            LogicalPlan::Scan { table: ref name } if name == table => LogicalPlan::Filter {
+                predicate,
+                input: Box::new(self),
+            },
+            LogicalPlan::Scan { .. } => self,
+            plan => plan.map_input(|input| input.restrict_table(table, predicate.clone())),
+        }
+    }
+
+    /// Lists the columns the plan reads: the projected columns and the columns of the
+    /// predicates. The columns an update only writes are not listed. Of a join, the columns it
+    /// is joined on are listed, named by their table, but not those of the predicates of its
+    /// inputs.
+    pub fn read_columns(&self) -> Vec<&str> {
+        let mut columns = Vec::new();
+        let mut plan = self;
+        loop {
+            plan = match plan {
+                LogicalPlan::Scan { .. } => return columns,
+                LogicalPlan::Filter { predicate, input } => {
+                    columns.extend(predicate.columns());
+                    input
+                }
+                LogicalPlan::Project {
+                    columns: projected,
+                    input,
+                } => {
+                    columns.extend(projected.iter().map(String::as_str));
+                    input
+                }
+                LogicalPlan::Update { input, .. } | LogicalPlan::Delete { input } => input,
+                LogicalPlan::Join { on, .. } => {
+                    columns.extend([on.0.as_str(), on.1.as_str()]);
+                    return columns;
+                }
+            };
+        }
+    }
+
+    /// Optimizes the plan and returns how to execute it.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The table the plan reads, whose rows are used to estimate the number of
+    ///   rows every step produces.
+    /// * `indexes` - The indexes of the table that may be used, built from its current rows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::column::Column;
+    /// use core::plan::{Index, LogicalPlan};
+    /// use core::request_types::Condition;
+    /// use core::row::Row;
+    /// use core::table::Table;
+    /// use core::value::Value;
+    ///
+    /// let mut table = Table::new("users".to_string());
+    /// table.add_column(Column::new("id".to_string(), true, true, false, None));
+    /// table.add_column(Column::new("name".to_string(), false, false, false, None));
+    /// table.add_column(Column::new("city".to_string(), false, false, false, None));
+    /// for (id, name, city) in [(1, "Ann", "Oslo"), (2, "Bob", "Rome"), (3, "Cid", "Oslo")] {
+    ///     table.add_row(Row::new(vec![Value::Int(id), name.into(), city.into()]));
+    /// }
+    /// let condition = |column: &str, value: &str| Condition {
+    ///     column: column.to_string(),
+    ///     value: value.to_string(),
+    /// };
+    /// let names = ["name".to_string()];
+    ///
+    /// // The condition on the primary key is answered by its index
+    /// let plan = LogicalPlan::select(&table, Some(&names), Some(&condition("id", "2"))).unwrap();
+    /// let mut plan = plan.optimize(&table, &Index::build(&table));
+    /// assert_eq!(
+    ///     plan.explain(None).lines(),
+    ///     [
+    ///         "Project: name  (rows=1)",
+    ///         "  ->  Index Lookup: users using users_pkey  (rows=1)",
+    ///         "        Index Cond: id = '2'",
+    ///     ]
+    /// );
+    /// let output = plan.execute(&mut table.rows);
+    /// assert_eq!(output.rows[0].values, [Value::from("Bob")]);
+    /// assert_eq!(output.scanned, 1);
+    ///
+    /// // Other conditions filter the rows while the table is read
+    /// let plan = LogicalPlan::select(&table, None, Some(&condition("city", "Oslo"))).unwrap();
+    /// let plan = plan.optimize(&table, &Index::build(&table));
+    /// assert_eq!(
+    ///     plan.explain(None).lines(),
+    ///     ["Seq Scan: users  (rows=2)", "  Filter: city = 'Oslo'"]
+    /// );
+    /// assert_eq!(plan.execute(&mut table.rows).rows.len(), 2);
+    /// ```
+    pub fn optimize(self, table: &Table, indexes: &[Arc<Index>]) -> PhysicalPlan {
+        self.fold_constants()
+            .push_down_predicates()
+            .prune_projections(table)
+            .physical(table, indexes)
+    }
+
+    /// Optimizes a plan compiled by [`LogicalPlan::join`] and returns how to execute it.
+    ///
+    /// # Arguments
+    ///
+    /// * `left` - The first table of the join.
+    /// * `left_indexes` - The indexes of the first table that may be used.
+    /// * `right` - The joined table.
+    /// * `right_indexes` - The indexes of the joined table that may be used.
+    pub fn optimize_join(
+        self,
+        left: &Table,
+        left_indexes: &[Arc<Index>],
+        right: &Table,
+        right_indexes: &[Arc<Index>],
+    ) -> PhysicalPlan {
+        let tables = [(left, left_indexes), (right, right_indexes)];
+        self.fold_constants()
+            .push_down_predicates()
+            .physical_join(tables)
+    }
+
+    /// Helper function to build the scan of a table with an optional condition
+    fn scan(table: &Table, condition: Option<&Condition>) -> LogicalPlan {
+        let scan = LogicalPlan::Scan {
+            table: table.name.clone(),
+        };
+        match condition {
+            Some(condition) => LogicalPlan::Filter {
+                predicate: Predicate::Equals(condition.clone()),
+                input: Box::new(scan),
+            },
+            None => scan,
+        }
+    }
+
+    /// Checks that the columns the plan reads and writes exist in `table`.
+    fn check_columns(&self, table: &Table) -> Result<(), String> {
+        let mut columns = self.read_columns();
+        if let LogicalPlan::Update { assignments, .. } = self {
+            columns.extend(assignments.iter().map(|(column, _)| column.as_str()));
+        }
+        match columns
+            .into_iter()
+            .find(|name| !table.columns.iter().any(|column| column.key == *name))
+        {
+            Some(name) => Err(format!("Column '{}' not found", name)),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the plan with `f` applied to its inputs, if it has any.
+    fn map_input(self, mut f: impl FnMut(LogicalPlan) -> LogicalPlan) -> LogicalPlan {
+        match self {
+            LogicalPlan::Scan { .. } => self,
+            LogicalPlan::Filter { predicate, input } => LogicalPlan::Filter {
+                predicate,
+                input: Box::new(f(*input)),
+            },
+            LogicalPlan::Project { columns, input } => LogicalPlan::Project {
+                columns,
+                input: Box::new(f(*input)),
+            },
+            LogicalPlan::Update { assignments, input } => LogicalPlan::Update {
+                assignments,
+                input: Box::new(f(*input)),
+            },
+            LogicalPlan::Delete { input } => LogicalPlan::Delete {
+                input: Box::new(f(*input)),
+            },
+            LogicalPlan::Join { left, right, on } => LogicalPlan::Join {
+                left: Box::new(f(*left)),
+                right: Box::new(f(*right)),
+                on,
+            },
+        }
+    }
+
+    /// Returns the name of the table the plan reads, the first table of a join.
+    fn table(&self) -> &str {
+        match self {
+            LogicalPlan::Scan { table } => table,
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Project { input, .. }
+            | LogicalPlan::Update { input, .. }
+            | LogicalPlan::Delete { input }
+            | LogicalPlan::Join { left: input, .. } => input.table(),
+        }
+    }
+
+    /// Folds the constant parts of the expressions of predicates. Filters that always hold
+    /// are removed, as are expressions that are never `TRUE`.
+    fn fold_constants(self) -> LogicalPlan {
+        match self.map_input(LogicalPlan::fold_constants) {
+            LogicalPlan::Filter {
+                predicate: Predicate::Any { exprs, session },
+                input,
+            } => {
+                let mut folded = Vec::new();
+                for expr in &exprs {
+                    match fold_constants(expr, &Constants(&session)) {
+                        Expr::Literal(value) if eval::is_true(&value) => return *input,
+                        Expr::Literal(_) => {}
+                        expr => folded.push(expr),
+                    }
+                }
+                LogicalPlan::Filter {
+                    predicate: Predicate::Any {
+                        exprs: folded,
+                        session,
+                    },
+                    input,
+                }
+            }
+            plan => plan,
+        }
+    }
+
+    /// Moves filters below projections, which only drop columns, and conditions on a column
+    /// of one of the tables of a join into the input reading that table.
+    fn push_down_predicates(self) -> LogicalPlan {
+        match self.map_input(LogicalPlan::push_down_predicates) {
+            LogicalPlan::Filter { predicate, input } => match *input {
+                LogicalPlan::Project { columns, input } => LogicalPlan::Project {
+                    columns,
+                    input: Box::new(
+                        LogicalPlan::Filter { predicate, input }.push_down_predicates(),
+                    ),
+                },
+                LogicalPlan::Join { left, right, on } => {
+                    let Predicate::Equals(condition) = predicate else {
+                        return LogicalPlan::Filter {
+                            predicate,
+                            input: Box::new(LogicalPlan::Join { left, right, on }),
+                        };
+                    };
+                    // The column of the condition is named by its table, which its input is not
+                    let unqualified = |input: &LogicalPlan| {
+                        let column = condition
+                            .column
+                            .strip_prefix(input.table())?
+                            .strip_prefix('.')?;
+                        Some(Predicate::Equals(Condition {
+                            column: column.to_string(),
+                            value: condition.value.clone(),
+                        }))
+                    };
+                    let filter = |predicate, input| {
+                        Box::new(LogicalPlan::Filter { predicate, input }.push_down_predicates())
+                    };
+                    match (unqualified(&left), unqualified(&right)) {
+                        (Some(predicate), _) => LogicalPlan::Join {
+                            left: filter(predicate, left),
+                            right,
+                            on,
+                        },
+                        (None, Some(predicate)) => LogicalPlan::Join {
+                            left,
+                            right: filter(predicate, right),
+                            on,
+                        },
+                        (None, None) => LogicalPlan::Filter {
+                            predicate: Predicate::Equals(condition),
+                            input: Box::new(LogicalPlan::Join { left, right, on }),
+                        },
+                    }
+                }
+                input => LogicalPlan::Filter {
+                    predicate,
+                    input: Box::new(input),
+                },
+            },
+            plan => plan,
+        }
+    }
+
+    /// Merges projections of projections and removes projections of every column in order.
+    fn prune_projections(self, table: &Table) -> LogicalPlan {
+        match self.map_input(|input| input.prune_projections(table)) {
+            LogicalPlan::Project { columns, input } => {
+                let input = match *input {
+                    LogicalPlan::Project { input, .. } => input,
+                    input => Box::new(input),
+                };
+                match columns
+                    .iter()
+                    .eq(table.columns.iter().map(|column| &column.key))
+                {
+                    true => *input,
+                    false => LogicalPlan::Project { columns, input },
+                }
+            }
+            plan => plan,
+        }
+    }
+
+    /// Turns the optimized plan into a physical plan, resolving column names to positions.
+    fn physical(self, table: &Table, indexes: &[Arc<Index>]) -> PhysicalPlan {
+        let position = |name: &str| {
+            table
+                .columns
+                .iter()
+                .position(|column| column.key == name)
+                .unwrap_or(usize::MAX)
+        };
+        match self {
+            LogicalPlan::Project { columns, input } => PhysicalPlan::Project {
+                columns: columns
+                    .into_iter()
+                    .map(|name| {
+                        let position = position(&name);
+                        (name, position)
+                    })
+                    .collect(),
+                input: Box::new(input.physical(table, indexes)),
+            },
+            LogicalPlan::Update { assignments, input } => PhysicalPlan::Update {
+                assignments: assignments
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let position = position(&name);
+                        (name, position, value)
+                    })
+                    .collect(),
+                input: input.into_scan(table, indexes),
+            },
+            LogicalPlan::Delete { input } => PhysicalPlan::Delete {
+                input: input.into_scan(table, indexes),
+            },
+            plan => PhysicalPlan::Scan(plan.into_scan(table, indexes)),
+        }
+    }
+
+    /// Turns an optimized join into a physical plan, resolving column names to their positions
+    /// in the joined rows. Filters above the join are ignored, as the conditions of compiled
+    /// joins are always moved into their inputs, and so are projections of every column.
+    fn physical_join(self, tables: [(&Table, &[Arc<Index>]); 2]) -> PhysicalPlan {
+        let [(left, left_indexes), (right, right_indexes)] = tables;
+        let joined = qualified_columns(left, right);
+        let position = |name: &str| {
+            joined
+                .iter()
+                .position(|column| column == name)
+                .unwrap_or(usize::MAX)
+        };
+        match self {
+            LogicalPlan::Project { columns, input } if columns != joined => PhysicalPlan::Project {
+                columns: columns
+                    .into_iter()
+                    .map(|name| {
+                        let position = position(&name);
+                        (name, position)
+                    })
+                    .collect(),
+                input: Box::new(input.physical_join(tables)),
+            },
+            LogicalPlan::Project { input, .. } | LogicalPlan::Filter { input, .. } => {
+                input.physical_join(tables)
+            }
+            LogicalPlan::Join {
+                left: left_input,
+                right: right_input,
+                on: (left_column, right_column),
+            } => {
+                let left_scan = left_input.into_scan(left, left_indexes);
+                let right_scan = right_input.into_scan(right, right_indexes);
+                let left_position = position(&left_column);
+                let right_position = position(&right_column).wrapping_sub(left.columns.len());
+
+                // Every value of the column with fewer distinct values is assumed to have a
+                // match in the other, and rows to be spread evenly over the values
+                let distinct = distinct_values(left, left_indexes, left_position)
+                    .max(distinct_values(right, right_indexes, right_position));
+                let estimated_rows = match distinct {
+                    0 => 0,
+                    distinct => (left_scan.estimated_rows * right_scan.estimated_rows)
+                        .div_ceil(distinct as u64),
+                };
+                PhysicalPlan::Join {
+                    left: left_scan,
+                    right: right_scan,
+                    left_column: (left_column, left_position),
+                    right_column: (right_column, right_position),
+                    estimated_rows,
+                }
+            }
+            plan => PhysicalPlan::Scan(plan.into_scan(left, left_indexes)),
+        }
+    }
+
+    /// Turns the filters above the scan of a table into a [`Scan`]. Updates, deletes and
+    /// projections below the filters are ignored, as compiled plans never have them there.
+    fn into_scan(self, table: &Table, indexes: &[Arc<Index>]) -> Scan {
+        let mut predicates = Vec::new();
+        let mut plan = self;
+        loop {
+            plan = match plan {
+                LogicalPlan::Scan { .. } => break,
+                LogicalPlan::Filter { predicate, input } => {
+                    predicates.push(predicate);
+                    *input
+                }
+                LogicalPlan::Project { input, .. }
+                | LogicalPlan::Update { input, .. }
+                | LogicalPlan::Delete { input }
+                | LogicalPlan::Join { left: input, .. } => *input,
+            };
+        }
+        // The innermost filters, such as row-level security, are evaluated first
+        predicates.reverse();
+        Scan::new(table, predicates, indexes)
+    }
+}
+
+/// Returns the names of the columns of a join of two tables, named by their table.
+fn qualified_columns(left: &Table, right: &Table) -> Vec<String> {
+    [left, right]
+        .into_iter()
+        .flat_map(|table| {
+            table
+                .columns
+                .iter()
+                .map(|column| format!("{}.{}", table.name, column.key))
+        })
+        .collect()
+}
+
+/// Replaces the parts of an expression that do not refer to columns by their values.
+///
+/// Parts that cannot be evaluated are kept, so that their errors are raised when the
+/// expression is evaluated for a row. `FALSE AND x` and `TRUE OR x` are folded even if `x`
+/// refers to columns.
+///
+/// # Arguments
+///
+/// * `expr` - The expression.
+/// * `scope` - The functions the expression may call. Its columns are never looked up.
+///
+/// # Examples
+///
+/// ```
+/// use core::plan::fold_constants;
+/// use core::sql::eval::Scope;
+/// use core::sql::parse_expression;
+/// use core::value::Value;
+///
+/// struct NoColumns;
+///
+/// impl Scope for NoColumns {
+///     fn column(&self, name: &str) -> Result<Value, String> {
+///         Err(format!("Column '{}' not found", name))
+///     }
+/// }
+///
+/// let expr = parse_expression("tenant = 2 * 3 + 1 AND upper('a') = 'A'").unwrap();
+/// assert_eq!(fold_constants(&expr, &NoColumns).to_string(), "(tenant = 7) AND TRUE");
+/// let expr = parse_expression("1 > 2 AND tenant = 7").unwrap();
+/// assert_eq!(fold_constants(&expr, &NoColumns).to_string(), "FALSE");
+/// let expr = parse_expression("tenant = 1 / 0").unwrap();
+/// assert_eq!(fold_constants(&expr, &NoColumns), expr);
+/// ```
+pub fn fold_constants(expr: &Expr, scope: &impl Scope) -> Expr {
+    if eval::columns(expr).is_empty() {
+        return match eval::evaluate(expr, scope) {
+            Ok(value) => Expr::Literal(value),
+            Err(_) => expr.clone(),
+        };
+    }
+    match expr {
+        Expr::Literal(_) | Expr::Column(_) => expr.clone(),
+        Expr::Unary { op, expr } => Expr::Unary {
+            op: *op,
+            expr: Box::new(fold_constants(expr, scope)),
+        },
+        Expr::Binary { left, op, right } => {
+            let left = fold_constants(left, scope);
+            match (op, &left) {
+                (BinaryOp::And, Expr::Literal(Value::Bool(false))) => left,
+                (BinaryOp::Or, Expr::Literal(Value::Bool(true))) => left,
+                _ => Expr::Binary {
+                    left: Box::new(left),
+                    op: *op,
+                    right: Box::new(fold_constants(right, scope)),
+                },
+            }
+        }
+        Expr::IsNull { expr, negated } => Expr::IsNull {
+            expr: Box::new(fold_constants(expr, scope)),
+            negated: *negated,
+        },
+        Expr::Function { name, args } => Expr::Function {
+            name: name.clone(),
+            args: args.iter().map(|arg| fold_constants(arg, scope)).collect(),
+        },
+    }
+}
+
+/// The functions of a session, for folding the expressions of predicates.
+struct Constants<'a>(&'a Session);
+
+impl Scope for Constants<'_> {
+    fn column(&self, name: &str) -> Result<Value, String> {
+        Err(format!("Column '{}' is not a constant", name))
+    }
+
+    fn function(&self, name: &str, args: &[Value]) -> Result<Value, String> {
+        session_function(self.0, name, args)
+    }
+}
+
+/// An index of the rows of a table by the text of their value in a column, as compared by
+/// [`Predicate::Equals`].
+#[derive(Debug)]
+pub struct Index {
+    /// The name of the constraint the index belongs to, e.g. `users_pkey`.
+    pub name: String,
+    /// The position of the indexed column.
+    pub column: usize,
+    rows: HashMap<String, Vec<usize>>,
+}
+
+impl Index {
+    /// Builds the indexes of a table: one on the first column of its primary key and of each
+    /// of its unique constraints.
+    pub fn build(table: &Table) -> Vec<Arc<Index>> {
+        let mut indexes: Vec<Arc<Index>> = Vec::new();
+        for (name, columns) in table.key_constraints() {
+            let Some(column) = columns
+                .first()
+                .and_then(|first| table.columns.iter().position(|c| c.key == *first))
+            else {
+                continue;
+            };
+            if indexes.iter().any(|index| index.column == column) {
+                continue;
+            }
+            let mut rows: HashMap<String, Vec<usize>> = HashMap::new();
+            for (position, row) in table.rows.iter().enumerate() {
+                rows.entry(text(row, column)).or_default().push(position);
+            }
+            indexes.push(Arc::new(Index { name, column, rows }));
+        }
+        indexes
+    }
+
+    /// Returns the positions of the rows whose value in the indexed column has the text
+    /// `value`, in order.
+    pub fn lookup(&self, value: &str) -> &[usize] {
+        self.rows.get(value).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the number of distinct texts of the values in the indexed column.
+    pub fn distinct_values(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+/// Returns the number of distinct texts of the values of a table in a column, from its index if
+/// it has one.
+fn distinct_values(table: &Table, indexes: &[Arc<Index>], column: usize) -> usize {
+    match indexes.iter().find(|index| index.column == column) {
+        Some(index) => index.distinct_values(),
+        None => table
+            .rows
+            .iter()
+            .map(|row| text(row, column))
+            .collect::<HashSet<String>>()
+            .len(),
+    }
+}
+
+/// Returns the text of the value of a row in a column, the empty string for `NULL`.
+fn text(row: &Row, column: usize) -> String {
+    row.values
+        .get(column)
+        .and_then(Value::as_string)
+        .unwrap_or_default()
+}
+
+/// How a request is executed, as a tree whose leaf scans a table.
+#[derive(Clone, Debug)]
+pub enum PhysicalPlan {
+    /// Reads the rows of a table that satisfy a filter.
+    Scan(Scan),
+    /// Keeps the values at the given positions of the rows of its input, named by the columns.
+    Project {
+        columns: Vec<(String, usize)>,
+        input: Box<PhysicalPlan>,
+    },
+    /// Sets the values at the given positions of the rows a scan reads, named by the columns.
+    Update {
+        assignments: Vec<(String, usize, Value)>,
+        input: Scan,
+    },
+    /// Removes the rows a scan reads from the table.
+    Delete { input: Scan },
+    /// Pairs the rows two scans of different tables read whose values at the given positions
+    /// have the same text, named by the columns. The positions of the joined table follow
+    /// those of the first. `NULL` values are never equal.
+    Join {
+        left: Scan,
+        right: Scan,
+        left_column: (String, usize),
+        right_column: (String, usize),
+        estimated_rows: u64,
+    },
+}
+
+/// How a scan reads the rows of its table.
+#[derive(Clone, Debug)]
+pub enum Access {
+    /// Reads every row.
+    Sequential,
+    /// Reads the rows satisfying a condition on the column of an index.
+    Index {
+        index: Arc<Index>,
+        condition: Condition,
+    },
+}
+
+/// Reads the rows of a table that satisfy a filter.
+#[derive(Clone, Debug)]
+pub struct Scan {
+    /// The name of the table.
+    pub table: String,
+    /// How the rows are read.
+    pub access: Access,
+    /// The estimated number of rows the scan produces.
+    pub estimated_rows: u64,
+    filter: Vec<BoundPredicate>,
+}
+
+impl Scan {
+    /// Helper function to build the scan of a table, selecting the index to read the rows
+    /// with and estimating the rows the predicates keep
+    fn new(table: &Table, mut predicates: Vec<Predicate>, indexes: &[Arc<Index>]) -> Scan {
+        let position = |name: &str| table.columns.iter().position(|c| c.key == name);
+        let index_of = |name: &str| {
+            let column = position(name)?;
+            indexes.iter().find(|index| index.column == column)
+        };
+
+        let lookup = predicates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, predicate)| match predicate {
+                Predicate::Equals(condition) => {
+                    let index = index_of(&condition.column)?;
+                    Some((i, index, index.lookup(&condition.value).len()))
+                }
+                Predicate::Any { .. } => None,
+            })
+            .min_by_key(|(_, _, rows)| *rows);
+        let (access, mut estimate) = match lookup {
+            Some((i, index, rows)) => match predicates.remove(i) {
+                Predicate::Equals(condition) => (
+                    Access::Index {
+                        index: index.clone(),
+                        condition,
+                    },
+                    rows as u64,
+                ),
+                predicate => {
+                    predicates.insert(i, predicate);
+                    (Access::Sequential, table.rows.len() as u64)
+                }
+            },
+            None => (Access::Sequential, table.rows.len() as u64),
+        };
+
+        // Equal values are assumed to be spread evenly, other predicates to keep every row
+        for predicate in &predicates {
+            if let Predicate::Equals(condition) = predicate {
+                let distinct = position(&condition.column)
+                    .map_or(0, |column| distinct_values(table, indexes, column));
+                estimate = match distinct {
+                    0 => 0,
+                    distinct => estimate.div_ceil(distinct as u64),
+                };
+            }
+        }
+
+        Scan {
+            table: table.name.clone(),
+            access,
+            estimated_rows: estimate,
+            filter: predicates
+                .into_iter()
+                .map(|predicate| BoundPredicate::new(predicate, table))
+                .collect(),
+        }
+    }
+
+    /// Helper function to read the rows satisfying the filter and return their positions
+    fn run(&self, rows: &[Row], start: Instant, output: &mut Output) -> Vec<usize> {
+        let read = |position: &usize| {
+            rows.get(*position)
+                .is_some_and(|row| self.filter.iter().all(|predicate| predicate.holds(row)))
+        };
+        let (scanned, positions): (usize, Vec<usize>) = match &self.access {
+            Access::Sequential => (rows.len(), (0..rows.len()).filter(read).collect()),
+            Access::Index { index, condition } => {
+                let candidates = index.lookup(&condition.value);
+                let positions = candidates.iter().copied().filter(read).collect();
+                (candidates.len(), positions)
+            }
+        };
+        output.scanned += scanned;
+        output.nodes.push(NodeStats {
+            rows: positions.len(),
+            removed: (!self.filter.is_empty()).then(|| scanned - positions.len()),
+            time: start.elapsed(),
+        });
+        positions
+    }
+
+    /// Helper function to describe the scan as a node of `EXPLAIN`
+    fn node(&self, stats: Option<&NodeStats>) -> PlanNode {
+        let mut node = match &self.access {
+            Access::Sequential => PlanNode::new(
+                "Seq Scan",
+                Some(quote_ident(&self.table)),
+                self.estimated_rows,
+            ),
+            Access::Index { index, condition } => {
+                let detail = format!("{} using {}", quote_ident(&self.table), index.name);
+                let mut node = PlanNode::new("Index Lookup", Some(detail), self.estimated_rows);
+                node.index_condition = Some(condition.to_sql());
+                node
+            }
+        };
+        let filter: Vec<String> = self
+            .filter
+            .iter()
+            .map(|bound| match &bound.predicate {
+                Predicate::Any { exprs, .. } if exprs.len() > 1 && self.filter.len() > 1 => {
+                    format!("({})", bound.predicate)
+                }
+                predicate => predicate.to_string(),
+            })
+            .collect();
+        node.filter = (!filter.is_empty()).then(|| filter.join(" AND "));
+        if let Some(stats) = stats {
+            stats.annotate(&mut node);
+        }
+        node
+    }
+}
+
+/// A predicate with the columns it refers to resolved to their positions.
+#[derive(Clone, Debug)]
+struct BoundPredicate {
+    predicate: Predicate,
+    columns: Vec<(String, usize)>,
+}
+
+impl BoundPredicate {
+    fn new(predicate: Predicate, table: &Table) -> BoundPredicate {
+        let columns = predicate
+            .columns()
+            .into_iter()
+            .filter_map(|name| {
+                let position = table.columns.iter().position(|c| c.key == name)?;
+                Some((name.to_string(), position))
+            })
+            .collect();
+        BoundPredicate { predicate, columns }
+    }
+
+    /// Whether a row satisfies the predicate.
+    fn holds(&self, row: &Row) -> bool {
+        match &self.predicate {
+            Predicate::Equals(condition) => {
+                let value = self.columns.first().map(|(_, column)| text(row, *column));
+                value.unwrap_or_default() == condition.value
+            }
+            Predicate::Any { exprs, session } => {
+                let scope = RowValues {
+                    columns: &self.columns,
+                    row,
+                    session,
+                };
+                exprs.iter().any(|expr| {
+                    eval::evaluate(expr, &scope).is_ok_and(|value| eval::is_true(&value))
+                })
+            }
+        }
+    }
+}
+
+/// The values of a row and the session, as seen by the expressions of predicates.
+struct RowValues<'a> {
+    columns: &'a [(String, usize)],
+    row: &'a Row,
+    session: &'a Session,
+}
+
+impl Scope for RowValues<'_> {
+    fn column(&self, name: &str) -> Result<Value, String> {
+        let (_, position) = self
+            .columns
+            .iter()
+            .find(|(column, _)| column == name)
+            .ok_or_else(|| format!("Column '{}' not found", name))?;
+        Ok(self
+            .row
+            .values
+            .get(*position)
+            .cloned()
+            .unwrap_or(Value::Null))
+    }
+
+    fn function(&self, name: &str, args: &[Value]) -> Result<Value, String> {
+        session_function(self.session, name, args)
+    }
+}
+
+/// What executing a physical plan produced.
+#[derive(Debug)]
+pub struct Output {
+    /// The produced rows: the selected rows, the updated rows as they are after the update, or
+    /// the deleted rows.
+    pub rows: Vec<Row>,
+    /// The positions of the rows in `rows` in the table before the change, which are ascending
+    /// for deleted rows. Plans that do not change rows leave them empty.
+    pub positions: Vec<usize>,
+    /// The number of rows read from the table.
+    pub scanned: usize,
+    /// The time the execution took.
+    pub time: Duration,
+    /// The statistics of the nodes, in the order they finished.
+    nodes: Vec<NodeStats>,
+}
+
+/// The rows a node produced and the time until it finished.
+#[derive(Debug)]
+struct NodeStats {
+    rows: usize,
+    /// The rows the filter of a scan removed, if it has one.
+    removed: Option<usize>,
+    time: Duration,
+}
+
+impl NodeStats {
+    fn annotate(&self, node: &mut PlanNode) {
+        node.analyzed(self.rows, self.time);
+        node.rows_removed_by_filter = self.removed.map(|removed| removed as u64);
+    }
+}
+
+impl PhysicalPlan {
+    /// Runs the plan.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - The rows of the table the plan was optimized for, which its indexes were
+    ///   built from. Updates change them in place and deletes remove them.
+    pub fn execute(&self, rows: &mut Vec<Row>) -> Output {
+        let start = Instant::now();
+        let mut output = Output {
+            rows: Vec::new(),
+            positions: Vec::new(),
+            scanned: 0,
+            time: Duration::ZERO,
+            nodes: Vec::new(),
+        };
+        output.rows = self.run(rows, start, &mut output);
+        output.time = start.elapsed();
+        output
+    }
+
+    /// Runs a plan optimized by [`LogicalPlan::optimize_join`].
+    ///
+    /// # Arguments
+    ///
+    /// * `left` - The rows of the first table of the join, which its indexes were built from.
+    /// * `right` - The rows of the joined table, which its indexes were built from.
+    pub fn execute_join(&self, left: &[Row], right: &[Row]) -> Output {
+        let start = Instant::now();
+        let mut output = Output {
+            rows: Vec::new(),
+            positions: Vec::new(),
+            scanned: 0,
+            time: Duration::ZERO,
+            nodes: Vec::new(),
+        };
+        output.rows = self.read(left, right, start, &mut output);
+        output.time = start.elapsed();
+        output
+    }
+
+    /// Returns the estimated number of rows the plan produces.
+    pub fn estimated_rows(&self) -> u64 {
+        match self {
+            PhysicalPlan::Scan(scan)
+            | PhysicalPlan::Update { input: scan, .. }
+            | PhysicalPlan::Delete { input: scan } => scan.estimated_rows,
+            PhysicalPlan::Project { input, .. } => input.estimated_rows(),
+            PhysicalPlan::Join { estimated_rows, .. } => *estimated_rows,
+        }
+    }
+
+    /// Returns the plan as shown by `EXPLAIN`, or by `EXPLAIN ANALYZE` with the output of its
+    /// execution.
+    pub fn explain(&self, output: Option<&Output>) -> Explanation {
+        let mut stats = output
+            .map_or(&[][..], |output| output.nodes.as_slice())
+            .iter()
+            .rev();
+        Explanation {
+            plan: self.node(&mut stats),
+            execution_time_ms: output.map(|output| output.time.as_secs_f64() * 1000.0),
+        }
+    }
+
+    fn run(&self, rows: &mut Vec<Row>, start: Instant, output: &mut Output) -> Vec<Row> {
+        let produced: Vec<Row> = match self {
+            PhysicalPlan::Scan(_) | PhysicalPlan::Project { .. } | PhysicalPlan::Join { .. } => {
+                return self.read(rows, &[], start, output);
+            }
+            PhysicalPlan::Update { assignments, input } => {
+                let positions = input.run(rows, start, output);
+                for &i in &positions {
+                    for (_, column, value) in assignments {
+                        if let Some(slot) = rows[i].values.get_mut(*column) {
+                            *slot = value.clone();
+                        }
+                    }
+                }
+                let updated = positions.iter().map(|&i| rows[i].clone()).collect();
+                output.positions = positions;
+                updated
+            }
+            PhysicalPlan::Delete { input } => {
+                let positions = input.run(rows, start, output);
+                let mut deleted = vec![false; rows.len()];
+                for &i in &positions {
+                    deleted[i] = true;
+                }
+                // The remaining rows keep their order
+                let (removed, kept) = std::mem::take(rows)
+                    .into_iter()
+                    .enumerate()
+                    .partition::<Vec<(usize, Row)>, _>(|(i, _)| deleted[*i]);
+                *rows = kept.into_iter().map(|(_, row)| row).collect();
+                let (positions, removed) = removed.into_iter().unzip();
+                output.positions = positions;
+                removed
+            }
+        };
+        output.nodes.push(NodeStats {
+            rows: produced.len(),
+            removed: None,
+            time: start.elapsed(),
+        });
+        produced
+    }
+
+    /// Helper function to run a plan that does not change rows, over the rows of its table or
+    /// the rows of the two tables of a join
+    fn read(&self, left: &[Row], right: &[Row], start: Instant, output: &mut Output) -> Vec<Row> {
+        let produced: Vec<Row> = match self {
+            PhysicalPlan::Scan(scan) => {
+                let positions = scan.run(left, start, output);
+                return positions.into_iter().map(|i| left[i].clone()).collect();
+            }
+            PhysicalPlan::Project { columns, input } => {
+                let project = |row: &Row| {
+                    Row::new(
+                        columns
+                            .iter()
+                            .map(|(_, i)| row.values.get(*i).cloned().unwrap_or(Value::Null))
+                            .collect(),
+                    )
+                };
+                match &**input {
+                    // Only the projected values of the rows a scan reads are copied
+                    PhysicalPlan::Scan(scan) => {
+                        let positions = scan.run(left, start, output);
+                        positions.into_iter().map(|i| project(&left[i])).collect()
+                    }
+                    input => input
+                        .read(left, right, start, output)
+                        .iter()
+                        .map(project)
+                        .collect(),
+                }
+            }
+            PhysicalPlan::Join {
+                left: left_scan,
+                right: right_scan,
+                left_column: (_, left_column),
+                right_column: (_, right_column),
+                ..
+            } => {
+                let key = |row: &Row, column: usize| match row.values.get(column) {
+                    Some(Value::Null) | None => None,
+                    Some(_) => Some(text(row, column)),
+                };
+                let left_positions = left_scan.run(left, start, output);
+                let mut hashed: HashMap<String, Vec<usize>> = HashMap::new();
+                for j in right_scan.run(right, start, output) {
+                    if let Some(key) = key(&right[j], *right_column) {
+                        hashed.entry(key).or_default().push(j);
+                    }
+                }
+                // The rows are produced in the order of the first table, then the joined one
+                let mut joined = Vec::new();
+                for i in left_positions {
+                    let Some(matches) = key(&left[i], *left_column).and_then(|k| hashed.get(&k))
+                    else {
+                        continue;
+                    };
+                    for &j in matches {
+                        let values = left[i].values.iter().chain(&right[j].values);
+                        joined.push(Row::new(values.cloned().collect()));
+                    }
+                }
+                joined
+            }
+            PhysicalPlan::Update { .. } | PhysicalPlan::Delete { .. } => {
+                unreachable!("Updates and deletes are always run by PhysicalPlan::run")
+            }
+        };
+        output.nodes.push(NodeStats {
+            rows: produced.len(),
+            removed: None,
+            time: start.elapsed(),
+        });
+        produced
+    }
+
+    /// Helper function to describe the plan as a tree of nodes, taking the statistics of the
+    /// nodes from the root down
+    fn node<'a>(&self, stats: &mut impl Iterator<Item = &'a NodeStats>) -> PlanNode {
+        let (operator, detail) = match self {
+            PhysicalPlan::Scan(scan) => return scan.node(stats.next()),
+            PhysicalPlan::Project { columns, .. } => {
+                let columns: Vec<String> =
+                    columns.iter().map(|(name, _)| quote_ident(name)).collect();
+                ("Project", columns.join(", "))
+            }
+            PhysicalPlan::Update { assignments, .. } => {
+                let assignments: Vec<String> = assignments
+                    .iter()
+                    .map(|(name, _, value)| format!("{} = {}", quote_ident(name), value.to_sql()))
+                    .collect();
+                ("Update", assignments.join(", "))
+            }
+            PhysicalPlan::Delete { input } => ("Delete", quote_ident(&input.table)),
+            PhysicalPlan::Join {
+                left_column,
+                right_column,
+                ..
+            } => (
+                "Hash Join",
+                format!(
+                    "{} = {}",
+                    quote_ident(&left_column.0),
+                    quote_ident(&right_column.0)
+                ),
+            ),
+        };
+        let mut node = PlanNode::new(operator, Some(detail), self.estimated_rows());
+        if let Some(stats) = stats.next() {
+            stats.annotate(&mut node);
+        }
+        match self {
+            PhysicalPlan::Project { input, .. } => node.with_child(input.node(stats)),
+            PhysicalPlan::Update { input, .. } | PhysicalPlan::Delete { input } => {
+                node.with_child(input.node(stats.next()))
+            }
+            // The joined table is scanned last, so its statistics come first
+            PhysicalPlan::Join { left, right, .. } => {
+                let right = right.node(stats.next());
+                node.with_child(left.node(stats.next())).with_child(right)
+            }
+            PhysicalPlan::Scan(_) => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::Column;
+    use crate::sql::parser::parse_expression;
+    use crate::table::UniqueConstraint;
+    use crate::value::DataType;
+
+    /// Users with an indexed `id`, and a `city` indexed by the unique constraint on
+    /// `(city, name)`.
+    fn users() -> Table {
+        let mut table = Table::new("users".to_string());
+        let mut id = Column::new("id".to_string(), true, true, false, None);
+        id.data_type = Some(DataType::Int);
+        table.add_column(id);
+        table.add_column(Column::new("city".to_string(), false, false, false, None));
+        table.add_column(Column::new("name".to_string(), false, false, false, None));
+        table
+            .add_unique_constraint(UniqueConstraint {
+                name: "users_city_name_key".to_string(),
+                columns: vec!["city".to_string(), "name".to_string()],
+            })
+            .unwrap();
+        for (id, city, name) in [
+            (1, "Oslo", "Ann"),
+            (2, "Rome", "Bob"),
+            (3, "Oslo", "Cid"),
+            (4, "Oslo", "Dan"),
+        ] {
+            table.add_row(Row::new(vec![Value::Int(id), city.into(), name.into()]));
+        }
+        table
+    }
+
+    fn orders() -> Table {
+        let mut table = Table::new("orders".to_string());
+        table.add_column(Column::new(
+            "user_id".to_string(),
+            false,
+            false,
+            false,
+            None,
+        ));
+        table.add_column(Column::new("total".to_string(), false, false, false, None));
+        for (user_id, total) in [(2, 10), (1, 20), (2, 30)] {
+            table.add_row(Row::new(vec![Value::Int(user_id), Value::Int(total)]));
+        }
+        table
+    }
+
+    fn condition(column: &str, value: &str) -> Condition {
+        Condition {
+            column: column.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn any(exprs: &[&str]) -> Predicate {
+        Predicate::Any {
+            exprs: exprs
+                .iter()
+                .map(|expr| parse_expression(expr).unwrap())
+                .collect(),
+            session: Session::default(),
+        }
+    }
+
+    fn names(rows: &[Row]) -> Vec<String> {
+        rows.iter()
+            .map(|row| row.values[2].as_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn filters_move_below_projections() {
+        let plan = LogicalPlan::Filter {
+            predicate: Predicate::Equals(condition("city", "Rome")),
+            input: Box::new(LogicalPlan::Project {
+                columns: vec!["name".to_string()],
+                input: Box::new(LogicalPlan::Scan {
+                    table: "users".to_string(),
+                }),
+            }),
+        };
+        let LogicalPlan::Project { input, .. } = plan.push_down_predicates() else {
+            panic!("Expected the projection to be the root");
+        };
+        assert!(matches!(
+            *input,
+            LogicalPlan::Filter { input, .. } if matches!(*input, LogicalPlan::Scan { .. })
+        ));
+    }
+
+    #[test]
+    fn join_conditions_move_into_the_input_of_their_table() {
+        let (users, orders) = (users(), orders());
+        let on = JoinCondition {
+            left_column: "id".to_string(),
+            right_column: "user_id".to_string(),
+        };
+        let plan = LogicalPlan::join(
+            &users,
+            &orders,
+            &on,
+            None,
+            Some(&condition("orders.total", "30")),
+        );
+        let plan = plan.unwrap().push_down_predicates();
+        let LogicalPlan::Project { input, .. } = plan else {
+            panic!("Expected the projection to be the root");
+        };
+        let LogicalPlan::Join { left, right, .. } = *input else {
+            panic!("Expected the filter to be moved below the join");
+        };
+        assert!(matches!(*left, LogicalPlan::Scan { .. }));
+        assert!(matches!(
+            *right,
+            LogicalPlan::Filter { predicate: Predicate::Equals(ref condition), .. }
+                if condition.column == "total"
+        ));
+
+        // Once the condition is read by the scan, the join only pairs the remaining rows
+        let plan = LogicalPlan::join(
+            &users,
+            &orders,
+            &on,
+            None,
+            Some(&condition("users.city", "Rome")),
+        );
+        let plan = plan.unwrap().optimize_join(
+            &users,
+            &Index::build(&users),
+            &orders,
+            &Index::build(&orders),
+        );
+        assert_eq!(
+            plan.explain(None).lines(),
+            [
+                "Hash Join: users.id = orders.user_id  (rows=1)",
+                "  ->  Index Lookup: users using users_city_name_key  (rows=1)",
+                "        Index Cond: city = 'Rome'",
+                "  ->  Seq Scan: orders  (rows=3)",
+            ]
+        );
+        let output = plan.execute_join(&users.rows, &orders.rows);
+        assert_eq!(output.rows.len(), 2);
+        assert_eq!(output.scanned, 4);
+    }
+
+    #[test]
+    fn conditions_on_unknown_tables_stay_above_the_join() {
+        let plan = LogicalPlan::Filter {
+            predicate: Predicate::Equals(condition("items.id", "1")),
+            input: Box::new(LogicalPlan::Join {
+                left: Box::new(LogicalPlan::Scan {
+                    table: "users".to_string(),
+                }),
+                right: Box::new(LogicalPlan::Scan {
+                    table: "orders".to_string(),
+                }),
+                on: ("users.id".to_string(), "orders.user_id".to_string()),
+            }),
+        };
+        assert!(matches!(
+            plan.push_down_predicates(),
+            LogicalPlan::Filter { input, .. } if matches!(*input, LogicalPlan::Join { .. })
+        ));
+    }
+
+    #[test]
+    fn filters_that_always_hold_are_removed() {
+        let table = users();
+        let plan = LogicalPlan::select(&table, None, None).unwrap();
+        let plan = plan.restrict(any(&["name = 'Ann'", "1 + 1 = 2"]));
+        assert_eq!(
+            plan.optimize(&table, &[]).explain(None).lines(),
+            ["Seq Scan: users  (rows=4)"]
+        );
+
+        // Expressions that are never true are dropped, the others are kept
+        let plan = LogicalPlan::select(&table, None, None).unwrap();
+        let plan = plan.restrict(any(&["1 > 2", "city = 'Rome'"]));
+        let mut rows = table.rows.clone();
+        let plan = plan.optimize(&table, &[]);
+        assert_eq!(
+            plan.explain(None).lines(),
+            ["Seq Scan: users  (rows=4)", "  Filter: city = 'Rome'"]
+        );
+        assert_eq!(names(&plan.execute(&mut rows).rows), ["Bob"]);
+    }
+
+    #[test]
+    fn the_index_reading_the_fewest_rows_is_used() {
+        let table = users();
+        let indexes = Index::build(&table);
+        assert_eq!(indexes.len(), 2);
+
+        // The city matches three rows, the primary key one
+        let plan = LogicalPlan::select(&table, None, Some(&condition("city", "Oslo"))).unwrap();
+        let plan = plan.restrict(Predicate::Equals(condition("id", "3")));
+        let plan = plan.optimize(&table, &indexes);
+        assert_eq!(
+            plan.explain(None).lines(),
+            [
+                "Index Lookup: users using users_pkey  (rows=1)",
+                "  Index Cond: id = '3'",
+                "  Filter: city = 'Oslo'",
+            ]
+        );
+        let mut rows = table.rows.clone();
+        let output = plan.execute(&mut rows);
+        assert_eq!(names(&output.rows), ["Cid"]);
+        assert_eq!(output.scanned, 1);
+
+        // A value without rows reads nothing
+        let plan = LogicalPlan::select(&table, None, Some(&condition("city", "Bern"))).unwrap();
+        let plan = plan.restrict(Predicate::Equals(condition("id", "1")));
+        let plan = plan.optimize(&table, &indexes);
+        assert_eq!(plan.estimated_rows(), 0);
+        assert_eq!(plan.execute(&mut rows).scanned, 0);
+    }
+
+    #[test]
+    fn conditions_without_an_index_scan_every_row() {
+        let table = users();
+        let plan = LogicalPlan::select(&table, None, Some(&condition("name", "Dan"))).unwrap();
+        let plan = plan.optimize(&table, &Index::build(&table));
+        assert_eq!(
+            plan.explain(None).lines(),
+            ["Seq Scan: users  (rows=1)", "  Filter: name = 'Dan'"]
+        );
+        let mut rows = table.rows.clone();
+        let output = plan.execute(&mut rows);
+        assert_eq!(names(&output.rows), ["Dan"]);
+        assert_eq!(output.scanned, 4);
+
+        // Indexes that were not passed are not used
+        let plan = LogicalPlan::select(&table, None, Some(&condition("id", "2"))).unwrap();
+        assert!(matches!(
+            plan.optimize(&table, &[]),
+            PhysicalPlan::Scan(Scan {
+                access: Access::Sequential,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn deletes_keep_the_order_of_the_remaining_rows() {
+        let table = users();
+        let plan = LogicalPlan::delete(&table, Some(&condition("city", "Oslo"))).unwrap();
+        let plan = plan.optimize(&table, &Index::build(&table));
+        let mut rows = table.rows.clone();
+        let output = plan.execute(&mut rows);
+        assert_eq!(names(&output.rows), ["Ann", "Cid", "Dan"]);
+        assert_eq!(output.positions, [0, 2, 3]);
+        assert_eq!(names(&rows), ["Bob"]);
+    }
+
+    #[test]
+    fn update_values_are_converted_to_the_type_of_their_column() {
+        let table = users();
+        let update = |column: &str, value: Option<&str>| UpdateColumnRequest {
+            column: column.to_string(),
+            value: value.map(str::to_string),
+        };
+        let plan = LogicalPlan::update(&table, None, &[update("id", Some("7"))]).unwrap();
+        let LogicalPlan::Update { assignments, .. } = plan else {
+            panic!("Expected an update");
+        };
+        assert_eq!(assignments, [("id".to_string(), Value::Int(7))]);
+
+        assert!(LogicalPlan::update(&table, None, &[update("id", Some("x"))]).is_err());
+        assert_eq!(
+            LogicalPlan::update(&table, None, &[update("id", None)]).unwrap_err(),
+            "Column 'id' does not allow NULL values"
+        );
+        assert!(LogicalPlan::update(&table, None, &[update("name", None)]).is_ok());
+        assert_eq!(
+            LogicalPlan::update(&table, None, &[update("age", Some("3"))]).unwrap_err(),
+            "Column 'age' not found"
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! Row-level security policies restricting the rows of a table users may see and change.
+//!
+//! A table without policies is not restricted. Once a table has a policy, users who are not
+//! admins only see, and update, the rows for which the `USING` expression of a policy applying
+//! to them is true. The rows they insert, and the rows as they are after an update, have to
+//! satisfy the `WITH CHECK` expression of such a policy, or its `USING` expression if it has no
+//! check. A policy applies to the users and roles it names, or to everyone if it names none.
+//!
+//! Expressions may refer to the columns of the table, `current_user` and the settings of the
+//! session with `current_setting('name')`. Clients send settings with every request as
+//! headers named [`SETTING_HEADER_PREFIX`] followed by the name of the setting.
+use crate::column::Column;
+use crate::plan::Predicate;
+use crate::row::Row;
+use crate::sql::ast::{quote_ident, Expr};
+use crate::sql::eval::{self, Scope, FUNCTIONS};
+use crate::table::Table;
+use crate::value::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The prefix of the request headers carrying session settings, e.g. `x-setting-app.tenant`.
+pub const SETTING_HEADER_PREFIX: &str = "x-setting-";
+
+/// The functions a policy may call in addition to the built-in [`FUNCTIONS`].
+pub const SESSION_FUNCTIONS: [&str; 2] = ["current_setting", "current_user"];
+
+/// A row-level security policy of a table.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Policy {
+    /// The name of the policy, unique within its table.
+    pub name: String,
+    /// The users and roles the policy applies to. Empty if it applies to everyone.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    /// The condition for existing rows to be selected and updated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub using: Option<Expr>,
+    /// The condition for inserted and updated rows. Defaults to `using`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check: Option<Expr>,
+}
+
+impl Policy {
+    /// Checks that the policy has a condition that only refers to columns of the table and to
+    /// known functions.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - The columns of the table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::column::Column;
+    /// use core::policy::Policy;
+    /// use core::sql::parse_expression;
+    ///
+    /// let columns = [Column::new("tenant".to_string(), false, false, false, None)];
+    /// let mut policy = Policy {
+    ///     name: "tenant_isolation".to_string(),
+    ///     roles: Vec::new(),
+    ///     using: Some(parse_expression("tenant = current_setting('app.tenant')").unwrap()),
+    ///     check: None,
+    /// };
+    /// assert!(policy.validate(&columns).is_ok());
+    ///
+    /// policy.check = Some(parse_expression("owner = current_user").unwrap());
+    /// assert_eq!(
+    ///     policy.validate(&columns),
+    ///     Err("Column 'owner' not found".to_string())
+    /// );
+    /// ```
+    pub fn validate(&self, columns: &[Column]) -> Result<(), String> {
+        if self.using.is_none() && self.check.is_none() {
+            return Err(format!(
+                "Policy '{}' needs a USING or WITH CHECK expression",
+                self.name
+            ));
+        }
+        for expr in self.using.iter().chain(&self.check) {
+            for name in eval::columns(expr) {
+                if !columns.iter().any(|column| column.key == name) {
+                    return Err(format!("Column '{}' not found", name));
+                }
+            }
+            validate_functions(expr)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the `CREATE POLICY` statement creating the policy on `table_name`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::policy::Policy;
+    /// use core::sql::ast::Statement;
+    /// use core::sql::{parse_expression, parse_statement};
+    ///
+    /// let policy = Policy {
+    ///     name: "own_rows".to_string(),
+    ///     roles: vec!["service".to_string()],
+    ///     using: Some(parse_expression("owner = current_user").unwrap()),
+    ///     check: None,
+    /// };
+    /// let sql = policy.to_sql("orders");
+    /// assert_eq!(
+    ///     sql,
+    ///     "CREATE POLICY own_rows ON orders TO service USING (owner = current_user())"
+    /// );
+    /// assert_eq!(
+    ///     parse_statement(&sql).unwrap(),
+    ///     Statement::CreatePolicy {
+    ///         table_name: "orders".to_string(),
+    ///         policy,
+    ///     }
+    /// );
+    /// ```
+    pub fn to_sql(&self, table_name: &str) -> String {
+        let mut sql = format!(
+            "CREATE POLICY {} ON {}",
+            quote_ident(&self.name),
+            quote_ident(table_name)
+        );
+        if !self.roles.is_empty() {
+            let roles: Vec<String> = self.roles.iter().map(|role| quote_ident(role)).collect();
+            sql += &format!(" TO {}", roles.join(", "));
+        }
+        if let Some(using) = &self.using {
+            sql += &format!(" USING ({})", using);
+        }
+        if let Some(check) = &self.check {
+            sql += &format!(" WITH CHECK ({})", check);
+        }
+        sql
+    }
+
+    /// Whether the policy applies to a session.
+    fn applies_to(&self, session: &Session) -> bool {
+        self.roles.is_empty()
+            || self
+                .roles
+                .iter()
+                .any(|role| *role == session.user || session.roles.contains(role))
+    }
+}
+
+/// Checks that the functions an expression calls exist.
+fn validate_functions(expr: &Expr) -> Result<(), String> {
+    match expr {
+        Expr::Literal(_) | Expr::Column(_) => Ok(()),
+        Expr::Unary { expr, .. } | Expr::IsNull { expr, .. } => validate_functions(expr),
+        Expr::Binary { left, right, .. } => {
+            validate_functions(left)?;
+            validate_functions(right)
+        }
+        Expr::Function { name, args } => {
+            if !FUNCTIONS.contains(&name.as_str()) && !SESSION_FUNCTIONS.contains(&name.as_str()) {
+                return Err(format!("Unknown function '{}'", name));
+            }
+            args.iter().try_for_each(validate_functions)
+        }
+    }
+}
+
+/// The user a request is made by and the settings of their session.
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    pub user: String,
+    /// The roles of the user.
+    pub roles: Vec<String>,
+    /// The settings read by `current_setting`.
+    pub settings: BTreeMap<String, String>,
+}
+
+/// The policies of a table that apply to a session.
+#[derive(Clone, Debug)]
+pub struct RowSecurity {
+    table_name: String,
+    columns: Vec<String>,
+    policies: Vec<Policy>,
+    session: Session,
+}
+
+impl RowSecurity {
+    /// Returns the row security of a table for a session, or `None` if the table has no
+    /// policies. A table with policies of which none applies to the session hides all rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The table, whose rows are not needed.
+    /// * `session` - The session rows are selected or written by.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::column::Column;
+    /// use core::policy::{Policy, RowSecurity, Session};
+    /// use core::row::Row;
+    /// use core::sql::parse_expression;
+    /// use core::table::Table;
+    /// use core::value::Value;
+    ///
+    /// let mut table = Table::new("orders".to_string());
+    /// table.add_column(Column::new("tenant".to_string(), false, false, false, None));
+    /// table.policies.push(Policy {
+    ///     name: "tenant_isolation".to_string(),
+    ///     roles: Vec::new(),
+    ///     using: Some(parse_expression("tenant = current_setting('app.tenant')").unwrap()),
+    ///     check: None,
+    /// });
+    /// let mut session = Session {
+    ///     user: "service".to_string(),
+    ///     ..Session::default()
+    /// };
+    /// session.settings.insert("app.tenant".to_string(), "7".to_string());
+    ///
+    /// let security = RowSecurity::new(&table, &session).unwrap();
+    /// assert!(security.visible(&Row::new(vec![Value::Int(7)])));
+    /// assert!(!security.visible(&Row::new(vec![Value::Int(8)])));
+    /// assert_eq!(
+    ///     security.check(&Row::new(vec![Value::Int(8)])),
+    ///     Err("New row violates the row-level security policies of table 'orders'".to_string())
+    /// );
+    /// assert!(RowSecurity::new(&Table::new("t".to_string()), &session).is_none());
+    /// ```
+    pub fn new(table: &Table, session: &Session) -> Option<RowSecurity> {
+        if table.policies.is_empty() {
+            return None;
+        }
+        Some(RowSecurity {
+            table_name: table.name.clone(),
+            columns: table.columns.iter().map(|c| c.key.clone()).collect(),
+            policies: table
+                .policies
+                .iter()
+                .filter(|policy| policy.applies_to(session))
+                .cloned()
+                .collect(),
+            session: session.clone(),
+        })
+    }
+
+    /// Returns the condition of the rows the session may see, for the plans of queries.
+    pub fn predicate(&self) -> Predicate {
+        Predicate::Any {
+            exprs: self
+                .policies
+                .iter()
+                .filter_map(|policy| policy.using.clone())
+                .collect(),
+            session: self.session.clone(),
+        }
+    }
+
+    /// Whether the session may see, and update, a row of the table. Rows for which a
+    /// condition cannot be evaluated are hidden.
+    pub fn visible(&self, row: &Row) -> bool {
+        self.policies
+            .iter()
+            .filter_map(|policy| policy.using.as_ref())
+            .any(|using| self.holds(using, row).unwrap_or(false))
+    }
+
+    /// Checks that the session may write a row to the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no policy allows the row, or its condition cannot be evaluated.
+    pub fn check(&self, row: &Row) -> Result<(), String> {
+        let mut allowed = false;
+        for policy in &self.policies {
+            if let Some(check) = policy.check.as_ref().or(policy.using.as_ref()) {
+                allowed |= self.holds(check, row).map_err(|error| {
+                    format!(
+                        "Policy '{}' of table '{}': {}",
+                        policy.name, self.table_name, error
+                    )
+                })?;
+            }
+        }
+        match allowed {
+            true => Ok(()),
+            false => Err(format!(
+                "New row violates the row-level security policies of table '{}'",
+                self.table_name
+            )),
+        }
+    }
+
+    /// Whether a condition holds for a row.
+    fn holds(&self, expr: &Expr, row: &Row) -> Result<bool, String> {
+        let scope = RowScope {
+            security: self,
+            row,
+        };
+        eval::evaluate(expr, &scope).map(|value| eval::is_true(&value))
+    }
+}
+
+/// The values of a row and the session, as seen by the expressions of policies.
+struct RowScope<'a> {
+    security: &'a RowSecurity,
+    row: &'a Row,
+}
+
+impl Scope for RowScope<'_> {
+    fn column(&self, name: &str) -> Result<Value, String> {
+        let index = self
+            .security
+            .columns
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| format!("Column '{}' not found", name))?;
+        Ok(self.row.values.get(index).cloned().unwrap_or(Value::Null))
+    }
+
+    fn function(&self, name: &str, args: &[Value]) -> Result<Value, String> {
+        session_function(&self.security.session, name, args)
+    }
+}
+
+/// Calls one of the [`SESSION_FUNCTIONS`].
+pub(crate) fn session_function(
+    session: &Session,
+    name: &str,
+    args: &[Value],
+) -> Result<Value, String> {
+    match (name, args) {
+        ("current_user", []) => Ok(Value::from(session.user.as_str())),
+        ("current_setting", [Value::Str(setting)]) => Ok(session
+            .settings
+            .get(&setting.to_lowercase())
+            .map_or(Value::Null, |value| Value::from(value.as_str()))),
+        ("current_user" | "current_setting", _) => {
+            Err(format!("Wrong arguments for function '{}'", name))
+        }
+        _ => Err(format!("Unknown function '{}'", name)),
+    }
+}
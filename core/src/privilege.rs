@@ -0,0 +1,375 @@
+//! Privileges of users and roles on the database, its tables and their columns.
+//!
+//! A [`Grant`] gives a user or role one privilege on every table of the database, on one
+//! table, or on some columns of one table. Users have the privileges granted to them and to
+//! their roles. Admins have every privilege without any grants.
+use crate::sql::ast::quote_ident;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A privilege that can be granted.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Privilege {
+    /// Read rows.
+    Select,
+    /// Insert rows, including CSV imports.
+    Insert,
+    /// Update rows.
+    Update,
+    /// Delete rows.
+    Delete,
+    /// Create tables. Can only be granted on the database.
+    Create,
+    /// Drop tables.
+    Drop,
+    /// Rename tables and add columns and indexes to them.
+    Alter,
+}
+
+impl Privilege {
+    /// Every privilege, in the order they are listed in.
+    pub const ALL: [Privilege; 7] = [
+        Privilege::Select,
+        Privilege::Insert,
+        Privilege::Update,
+        Privilege::Delete,
+        Privilege::Create,
+        Privilege::Drop,
+        Privilege::Alter,
+    ];
+
+    /// Returns the privilege named `name`, ignoring case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::privilege::Privilege;
+    ///
+    /// assert_eq!(Privilege::from_name("select"), Some(Privilege::Select));
+    /// assert_eq!(Privilege::from_name("analyst"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Privilege> {
+        Privilege::ALL
+            .into_iter()
+            .find(|privilege| privilege.as_str().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the SQL keyword of the privilege.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Privilege::Select => "SELECT",
+            Privilege::Insert => "INSERT",
+            Privilege::Update => "UPDATE",
+            Privilege::Delete => "DELETE",
+            Privilege::Create => "CREATE",
+            Privilege::Drop => "DROP",
+            Privilege::Alter => "ALTER",
+        }
+    }
+
+    /// Whether the privilege can be limited to some columns of a table.
+    pub fn has_columns(self) -> bool {
+        matches!(
+            self,
+            Privilege::Select | Privilege::Insert | Privilege::Update
+        )
+    }
+
+    /// Whether the privilege can be granted on a single table.
+    pub fn on_table(self) -> bool {
+        self != Privilege::Create
+    }
+}
+
+impl fmt::Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A privilege of a `GRANT` or `REVOKE`, limited to some columns or applying to all of them.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PrivilegeSpec {
+    pub privilege: Privilege,
+    /// The columns, or `None` for every column of the table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<String>>,
+}
+
+/// A privilege granted to a user or role.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Grant {
+    /// The user or role.
+    pub grantee: String,
+    pub privilege: Privilege,
+    /// The table, or `None` for every table of the database.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    /// The columns, or `None` for every column of the table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<String>>,
+}
+
+impl Grant {
+    /// Checks that the privilege can be granted on the grant's table and columns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `CREATE` is granted on a table, columns are given without a table or
+    /// for a privilege that cannot be limited to columns, or the column list is empty.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.table.is_some() && !self.privilege.on_table() {
+            return Err(format!(
+                "{} can only be granted on the database",
+                self.privilege
+            ));
+        }
+        match &self.columns {
+            Some(_) if self.table.is_none() => Err(format!(
+                "{} can only be granted on columns of a table",
+                self.privilege
+            )),
+            Some(_) if !self.privilege.has_columns() => {
+                Err(format!("{} cannot be granted on columns", self.privilege))
+            }
+            Some(columns) if columns.is_empty() => Err("The column list is empty".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the grant gives `privilege` on `column` of `table`, or on all of `table` if
+    /// `column` is `None`.
+    fn covers(&self, privilege: Privilege, table: &str, column: Option<&str>) -> bool {
+        self.privilege == privilege
+            && match (&self.table, &self.columns) {
+                (None, _) => true,
+                (Some(name), None) => name == table,
+                (Some(name), Some(columns)) => {
+                    name == table
+                        && column.is_some_and(|column| columns.iter().any(|c| c == column))
+                }
+            }
+    }
+
+    /// Whether the grant is on the same privilege, grantee and table as `other`.
+    fn same_target(&self, other: &Grant) -> bool {
+        self.grantee == other.grantee
+            && self.privilege == other.privilege
+            && self.table == other.table
+    }
+}
+
+impl fmt::Display for Grant {
+    /// Writes the `GRANT` statement giving the privilege.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GRANT {}", self.privilege)?;
+        if let Some(columns) = &self.columns {
+            let columns: Vec<String> = columns.iter().map(|column| quote_ident(column)).collect();
+            write!(f, " ({})", columns.join(", "))?;
+        }
+        match &self.table {
+            Some(table) => write!(f, " ON {}", quote_ident(table))?,
+            None => write!(f, " ON *")?,
+        }
+        write!(f, " TO {}", quote_ident(&self.grantee))
+    }
+}
+
+/// Adds a grant, merging it with the grant of the same privilege on the same table.
+///
+/// Columns are added to the columns granted before. Granting the whole table replaces a grant
+/// on some of its columns.
+///
+/// # Examples
+///
+/// ```
+/// use core::privilege::{grant, Grant, Privilege};
+///
+/// let select = |columns: Option<&[&str]>| Grant {
+///     grantee: "analyst".to_string(),
+///     privilege: Privilege::Select,
+///     table: Some("users".to_string()),
+///     columns: columns.map(|columns| columns.iter().map(|c| c.to_string()).collect()),
+/// };
+///
+/// let mut grants = Vec::new();
+/// grant(&mut grants, select(Some(&["id"])));
+/// grant(&mut grants, select(Some(&["id", "name"])));
+/// assert_eq!(grants, vec![select(Some(&["id", "name"]))]);
+///
+/// grant(&mut grants, select(None));
+/// assert_eq!(grants, vec![select(None)]);
+/// assert_eq!(grants[0].to_string(), "GRANT SELECT ON users TO analyst");
+/// ```
+pub fn grant(grants: &mut Vec<Grant>, new: Grant) {
+    let Some(existing) = grants.iter_mut().find(|grant| grant.same_target(&new)) else {
+        grants.push(new);
+        return;
+    };
+    match (&mut existing.columns, new.columns) {
+        (None, _) => {}
+        (columns @ Some(_), None) => *columns = None,
+        (Some(columns), Some(new_columns)) => {
+            for column in new_columns {
+                if !columns.contains(&column) {
+                    columns.push(column);
+                }
+            }
+        }
+    }
+}
+
+/// Removes a grant, returning whether anything was revoked.
+///
+/// Revoking columns removes them from the grant on their table. Revoking a table removes the
+/// grant on the table and on any of its columns, and revoking the database only removes the
+/// grant on the database, not those on single tables.
+///
+/// # Errors
+///
+/// Returns an error if columns are revoked from a grant on the whole table, as the remaining
+/// columns are unknown.
+///
+/// # Examples
+///
+/// ```
+/// use core::privilege::{revoke, Grant, Privilege};
+///
+/// let update = |columns: Option<Vec<&str>>| Grant {
+///     grantee: "service".to_string(),
+///     privilege: Privilege::Update,
+///     table: Some("orders".to_string()),
+///     columns: columns.map(|columns| columns.into_iter().map(String::from).collect()),
+/// };
+///
+/// let mut grants = vec![update(Some(vec!["status", "total"]))];
+/// assert_eq!(revoke(&mut grants, &update(Some(vec!["total"]))), Ok(true));
+/// assert_eq!(grants, vec![update(Some(vec!["status"]))]);
+/// assert_eq!(revoke(&mut grants, &update(None)), Ok(true));
+/// assert!(grants.is_empty());
+/// assert_eq!(revoke(&mut grants, &update(None)), Ok(false));
+/// ```
+pub fn revoke(grants: &mut Vec<Grant>, old: &Grant) -> Result<bool, String> {
+    let count = grants.len();
+    let Some(old_columns) = &old.columns else {
+        grants.retain(|grant| !grant.same_target(old));
+        return Ok(grants.len() != count);
+    };
+    let Some(index) = grants.iter().position(|grant| grant.same_target(old)) else {
+        return Ok(false);
+    };
+    let Some(columns) = &mut grants[index].columns else {
+        return Err(format!(
+            "{} is granted on all columns of table '{}', revoke it from the table",
+            old.privilege,
+            old.table.as_deref().unwrap_or_default()
+        ));
+    };
+    let before = columns.len();
+    columns.retain(|column| !old_columns.contains(column));
+    let revoked = columns.len() != before;
+    if columns.is_empty() {
+        grants.remove(index);
+    }
+    Ok(revoked)
+}
+
+/// Checks that one of `grantees` has `privilege` on `columns` of `table`.
+///
+/// # Arguments
+///
+/// * `grants` - The grants of all users and roles.
+/// * `grantees` - The user and the roles granted to it.
+/// * `privilege` - The privilege that is needed.
+/// * `table` - The table, or `None` for privileges on the database such as `CREATE`.
+/// * `columns` - The columns that are used, or none if the privilege is needed on the whole table.
+///
+/// # Errors
+///
+/// Returns an error naming the missing privilege.
+///
+/// # Examples
+///
+/// ```
+/// use core::privilege::{check, Grant, Privilege};
+///
+/// let grants = vec![
+///     Grant {
+///         grantee: "analyst".to_string(),
+///         privilege: Privilege::Select,
+///         table: Some("users".to_string()),
+///         columns: Some(vec!["id".to_string(), "name".to_string()]),
+///     },
+///     Grant {
+///         grantee: "alice".to_string(),
+///         privilege: Privilege::Create,
+///         table: None,
+///         columns: None,
+///     },
+/// ];
+/// let alice = ["alice", "analyst"];
+///
+/// assert!(check(&grants, &alice, Privilege::Select, Some("users"), &["id", "name"]).is_ok());
+/// assert!(check(&grants, &alice, Privilege::Create, None, &[]).is_ok());
+/// assert_eq!(
+///     check(&grants, &alice, Privilege::Select, Some("users"), &["id", "email"]),
+///     Err("Permission denied: SELECT on column 'email' of table 'users'".to_string())
+/// );
+/// assert_eq!(
+///     check(&grants, &["bob"], Privilege::Drop, Some("users"), &[]),
+///     Err("Permission denied: DROP on table 'users'".to_string())
+/// );
+/// ```
+pub fn check(
+    grants: &[Grant],
+    grantees: &[&str],
+    privilege: Privilege,
+    table: Option<&str>,
+    columns: &[&str],
+) -> Result<(), String> {
+    let mut granted = grants
+        .iter()
+        .filter(|grant| grantees.contains(&grant.grantee.as_str()));
+    let Some(table) = table else {
+        return match granted.any(|grant| grant.privilege == privilege && grant.table.is_none()) {
+            true => Ok(()),
+            false => Err(format!("Permission denied: {} on the database", privilege)),
+        };
+    };
+    let granted: Vec<&Grant> = granted.collect();
+    if columns.is_empty() {
+        return match granted
+            .iter()
+            .any(|grant| grant.covers(privilege, table, None))
+        {
+            true => Ok(()),
+            false => Err(format!(
+                "Permission denied: {} on table '{}'",
+                privilege, table
+            )),
+        };
+    }
+    match columns.iter().find(|column| {
+        !granted
+            .iter()
+            .any(|grant| grant.covers(privilege, table, Some(column)))
+    }) {
+        Some(column) => Err(format!(
+            "Permission denied: {} on column '{}' of table '{}'",
+            privilege, column, table
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Whether one of `grantees` has any privilege on `table` or some of its columns, which lets
+/// them see its definition. `CREATE` on the database does not count, as it is no privilege on
+/// existing tables.
+pub fn can_see(grants: &[Grant], grantees: &[&str], table: &str) -> bool {
+    grants.iter().any(|grant| {
+        grantees.contains(&grant.grantee.as_str())
+            && grant.privilege.on_table()
+            && grant.table.as_deref().is_none_or(|name| name == table)
+    })
+}
@@ -1,5 +1,10 @@
-use crate::column::Column;
+use crate::column::{Column, ForeignKey};
+use crate::policy::Policy;
+use crate::privilege::PrivilegeSpec;
 use crate::row::Row;
+use crate::sql::ast::{quote_ident, CreateTable, Expr, Privileges, TableConstraintKind};
+use crate::table::UniqueConstraint;
+use crate::value::{DataType, Value};
 use serde::{Deserialize, Serialize};
 
 /// Represents a request to create a new table.
@@ -9,10 +14,17 @@ pub struct CreateRequests {
 }
 
 /// Represents a request to create a new table with columns.
+///
+/// `primary_key` and `unique_constraints` define table-level constraints over one or more
+/// of the inserted columns. Columns flagged as `primary_key` or `unique` are added to them as well.
 #[derive(Deserialize, Serialize)]
 pub struct CreateTableRequests {
     pub name: String,
     pub insert_column_requests: Vec<InsertColumnRequest>,
+    #[serde(default)]
+    pub primary_key: Vec<String>,
+    #[serde(default)]
+    pub unique_constraints: Vec<UniqueConstraint>,
 }
 
 impl CreateTableRequests {
@@ -25,10 +37,147 @@ impl CreateTableRequests {
         CreateTableRequests {
             name,
             insert_column_requests: Vec::new(),
+            primary_key: Vec::new(),
+            unique_constraints: Vec::new(),
         }
     }
 }
 
+impl TryFrom<CreateTable> for CreateTableRequests {
+    type Error = String;
+
+    /// Builds the request of a `CREATE TABLE` statement.
+    ///
+    /// `PRIMARY KEY (a, b)` and `UNIQUE (a, b)` become table-level constraints of the request;
+    /// unnamed unique constraints are named `<table>_<columns>_key`. A
+    /// `FOREIGN KEY (a) REFERENCES t (b)` becomes the foreign key of column `a`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a default is not a literal, the table has several primary keys, a
+    /// foreign key has more than one column or a constraint refers to an unknown column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::request_types::CreateTableRequests;
+    /// use core::sql::ast::Statement;
+    /// use core::sql::parse_statement;
+    ///
+    /// let statement = parse_statement("CREATE TABLE t (a INT, b INT, UNIQUE (a, b))").unwrap();
+    /// let Statement::CreateTable(create) = statement else { unreachable!() };
+    /// let request = CreateTableRequests::try_from(create).unwrap();
+    /// assert_eq!(request.unique_constraints[0].name, "t_a_b_key");
+    ///
+    /// // A foreign key over several columns is not several foreign keys
+    /// let statement =
+    ///     parse_statement("CREATE TABLE t (a INT, b INT, FOREIGN KEY (a, b) REFERENCES u (c, d))");
+    /// let Statement::CreateTable(create) = statement.unwrap() else { unreachable!() };
+    /// assert!(CreateTableRequests::try_from(create).is_err());
+    /// ```
+    fn try_from(create: CreateTable) -> Result<Self, Self::Error> {
+        let mut insert_column_requests = create
+            .columns
+            .into_iter()
+            .map(|column| {
+                let default = match column.default {
+                    Some(Expr::Literal(value)) => Some(value),
+                    Some(expr) => {
+                        return Err(format!("Expected a literal value, found '{}'", expr))
+                    }
+                    None => None,
+                };
+                Ok(InsertColumnRequest {
+                    table_name: create.name.clone(),
+                    key: column.name,
+                    primary_key: column.primary_key,
+                    non_null: column.not_null,
+                    unique: column.unique,
+                    foreign_key: column.references,
+                    default,
+                    check: column.check,
+                    auto_increment: column.auto_increment,
+                    data_type: Some(column.data_type),
+                })
+            })
+            .collect::<Result<Vec<InsertColumnRequest>, String>>()?;
+
+        let mut primary_key = Vec::new();
+        let mut unique_constraints = Vec::new();
+        for constraint in create.constraints {
+            match constraint.kind {
+                TableConstraintKind::PrimaryKey(columns) => {
+                    if !primary_key.is_empty() {
+                        return Err("Multiple primary keys are not allowed".into());
+                    }
+                    primary_key = columns;
+                }
+                TableConstraintKind::Unique(columns) => {
+                    let name = constraint
+                        .name
+                        .unwrap_or_else(|| format!("{}_{}_key", create.name, columns.join("_")));
+                    unique_constraints.push(UniqueConstraint { name, columns });
+                }
+                TableConstraintKind::ForeignKey {
+                    columns,
+                    table_name,
+                    referred_columns,
+                } => {
+                    let ([name], [referred]) = (columns.as_slice(), referred_columns.as_slice())
+                    else {
+                        return Err(format!(
+                            "Foreign keys over several columns are not supported, found ({}) REFERENCES {} ({})",
+                            columns.join(", "),
+                            table_name,
+                            referred_columns.join(", ")
+                        ));
+                    };
+                    let column = insert_column_requests
+                        .iter_mut()
+                        .find(|column| column.key == *name)
+                        .ok_or_else(|| format!("Constraint refers to unknown column '{}'", name))?;
+                    column.foreign_key = Some(ForeignKey {
+                        table_name,
+                        column: referred.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(CreateTableRequests {
+            name: create.name,
+            insert_column_requests,
+            primary_key,
+            unique_constraints,
+        })
+    }
+}
+
+/// Represents a request to add a unique index to a table.
+///
+/// The index is stored as a unique constraint named `name`, so the existing rows have to be
+/// unique over `columns`.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CreateIndexRequest {
+    pub table_name: String,
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+/// Represents a request to add a row-level security policy to a table.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CreatePolicyRequest {
+    pub table_name: String,
+    pub policy: Policy,
+}
+
+/// Represents a request to drop a row-level security policy of a table.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DropPolicyRequest {
+    pub table_name: String,
+    pub name: String,
+}
+
 /// Represents a request to drop a table.
 #[derive(Deserialize, Serialize)]
 pub struct DropTableRequest {
@@ -50,7 +199,34 @@ pub struct InsertColumnRequest {
     pub primary_key: bool,
     pub non_null: bool,
     pub unique: bool,
-    pub foreign_key: Option<Vec<Column>>,
+    pub foreign_key: Option<ForeignKey>,
+    #[serde(default)]
+    pub default: Option<Value>,
+    #[serde(default)]
+    pub check: Option<Expr>,
+    #[serde(default)]
+    pub auto_increment: bool,
+    #[serde(default)]
+    pub data_type: Option<DataType>,
+}
+
+impl From<InsertColumnRequest> for Column {
+    /// Builds the column described by the request. `table_name` is ignored.
+    fn from(request: InsertColumnRequest) -> Self {
+        Column {
+            default: request.default,
+            check: request.check,
+            auto_increment: request.auto_increment,
+            data_type: request.data_type,
+            ..Column::new(
+                request.key,
+                request.primary_key,
+                request.non_null,
+                request.unique,
+                request.foreign_key,
+            )
+        }
+    }
 }
 
 /// Represents a request to insert a new row into a table.
@@ -60,6 +236,32 @@ pub struct InsertRowRequest {
     pub row: Row,
 }
 
+impl InsertRowRequest {
+    /// Returns the `INSERT` statement the request executes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::request_types::InsertRowRequest;
+    /// use core::row::Row;
+    /// use core::value::Value;
+    ///
+    /// let request = InsertRowRequest {
+    ///     table_name: "users".to_string(),
+    ///     row: Row::new(vec![Value::Int(1), Value::from("Ada"), Value::Null]),
+    /// };
+    /// assert_eq!(request.to_sql(), "INSERT INTO users VALUES (1, 'Ada', NULL)");
+    /// ```
+    pub fn to_sql(&self) -> String {
+        let values: Vec<String> = self.row.values.iter().map(Value::to_sql).collect();
+        format!(
+            "INSERT INTO {} VALUES ({})",
+            quote_ident(&self.table_name),
+            values.join(", ")
+        )
+    }
+}
+
 /// Represents a request to select a new row off a table.
 #[derive(Deserialize, Serialize, Debug)]
 pub struct SelectRequest {
@@ -68,13 +270,426 @@ pub struct SelectRequest {
     pub condition: Option<Condition>,
 }
 
-/// Condition for Select statements to specify what Column should be selected
+impl SelectRequest {
+    /// Returns the `SELECT` statement the request executes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::request_types::{Condition, SelectRequest};
+    ///
+    /// let request = SelectRequest {
+    ///     columns: Some(vec!["id".to_string(), "name".to_string()]),
+    ///     table_name: "users".to_string(),
+    ///     condition: Some(Condition {
+    ///         column: "email".to_string(),
+    ///         value: "ada@example.com".to_string(),
+    ///     }),
+    /// };
+    /// assert_eq!(
+    ///     request.to_sql(),
+    ///     "SELECT id, name FROM users WHERE email = 'ada@example.com'"
+    /// );
+    /// ```
+    pub fn to_sql(&self) -> String {
+        let columns = match &self.columns {
+            Some(columns) => columns
+                .iter()
+                .map(|column| quote_ident(column))
+                .collect::<Vec<String>>()
+                .join(", "),
+            None => "*".to_string(),
+        };
+        format!(
+            "SELECT {} FROM {}{}",
+            columns,
+            quote_ident(&self.table_name),
+            where_clause(self.condition.as_ref())
+        )
+    }
+}
+
+/// Represents a request to select the pairs of rows of two tables whose values in two
+/// columns are equal, like an inner join.
+///
+/// Columns are qualified by their table, e.g. `users.id`.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct JoinRequest {
+    pub columns: Option<Vec<String>>, // None means all columns of both tables
+    pub table_name: String,
+    pub join_table: String,
+    pub on: JoinCondition,
+    pub condition: Option<Condition>,
+}
+
+/// The columns whose values have to be equal for two rows to be joined.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct JoinCondition {
+    /// The column of the first table, without the table name.
+    pub left_column: String,
+    /// The column of the joined table, without the table name.
+    pub right_column: String,
+}
+
+impl JoinRequest {
+    /// Returns the `SELECT` statement the request executes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::request_types::{Condition, JoinCondition, JoinRequest};
+    ///
+    /// let request = JoinRequest {
+    ///     columns: Some(vec!["users.name".to_string(), "orders.total".to_string()]),
+    ///     table_name: "users".to_string(),
+    ///     join_table: "orders".to_string(),
+    ///     on: JoinCondition {
+    ///         left_column: "id".to_string(),
+    ///         right_column: "user_id".to_string(),
+    ///     },
+    ///     condition: Some(Condition {
+    ///         column: "users.city".to_string(),
+    ///         value: "Oslo".to_string(),
+    ///     }),
+    /// };
+    /// assert_eq!(
+    ///     request.to_sql(),
+    ///     "SELECT users.name, orders.total FROM users JOIN orders ON users.id = orders.user_id \
+    ///      WHERE users.city = 'Oslo'"
+    /// );
+    /// ```
+    pub fn to_sql(&self) -> String {
+        let columns = match &self.columns {
+            Some(columns) => columns
+                .iter()
+                .map(|column| quote_ident(column))
+                .collect::<Vec<String>>()
+                .join(", "),
+            None => "*".to_string(),
+        };
+        format!(
+            "SELECT {} FROM {} JOIN {} ON {}.{} = {}.{}{}",
+            columns,
+            quote_ident(&self.table_name),
+            quote_ident(&self.join_table),
+            quote_ident(&self.table_name),
+            quote_ident(&self.on.left_column),
+            quote_ident(&self.join_table),
+            quote_ident(&self.on.right_column),
+            where_clause(self.condition.as_ref())
+        )
+    }
+}
+
+/// How `/select` streams its result instead of returning a single JSON array.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    /// Newline-delimited JSON with one row per line.
+    Ndjson,
+    /// Newline-delimited [`SelectFrame`]s: a header with the column metadata, the rows in
+    /// arrays of up to `chunk_size` rows and an end frame with the row count.
+    Chunked,
+}
+
+/// Whether `/select` returns the plan of the query instead of its rows.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExplainMode {
+    /// The plan with estimated row counts, like `EXPLAIN`. The query is not run.
+    Plan,
+    /// The plan annotated with the rows every node produced and the time it took, like
+    /// `EXPLAIN ANALYZE`. The query is run but its rows are discarded.
+    Analyze,
+}
+
+/// Query parameters of `/select`.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct SelectOptions {
+    /// Streams the result in the given format. Without it the rows are returned as one array.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<StreamFormat>,
+    /// The largest number of rows per frame of a chunked stream.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_size: Option<usize>,
+    /// Returns the plan of the query instead of its rows. Cannot be combined with `stream`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explain: Option<ExplainMode>,
+}
+
+/// The name and declared type of a column in a select result.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ColumnHeader {
+    pub name: String,
+    pub data_type: Option<DataType>,
+}
+
+/// A frame of a chunked select stream. Every frame is sent on a line of its own.
+#[derive(Deserialize, Serialize, Debug)]
+pub enum SelectFrame {
+    /// The first frame, describing the selected columns.
+    Header { columns: Vec<ColumnHeader> },
+    /// The next rows of the result.
+    Rows(Vec<Row>),
+    /// The last frame of a complete result.
+    End { row_count: usize },
+    /// The result could not be completed. No frames follow.
+    Error(String),
+}
+
+/// Query parameters of a CSV import, in addition to the [`CsvOptions`](crate::csv::CsvOptions)
+/// of the file.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(default)]
+pub struct CsvImportOptions {
+    /// Comma separated names of the columns the fields are assigned to, in order. Defaults to
+    /// the header if there is one, or to all columns of the table otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<String>,
+    /// Comma separated types of columns, e.g. `id:INT,price:FLOAT`. They take precedence over
+    /// the types declared by the table.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub types: Option<String>,
+    /// Whether the type of a column without a declared type is inferred from its values.
+    /// Otherwise its values are imported as strings.
+    pub infer: bool,
+    /// Whether a missing table is created, with the columns of the header and their declared
+    /// or inferred types.
+    pub create: bool,
+}
+
+impl Default for CsvImportOptions {
+    fn default() -> Self {
+        CsvImportOptions {
+            columns: None,
+            types: None,
+            infer: true,
+            create: false,
+        }
+    }
+}
+
+/// The result of a CSV import.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CsvImportResult {
+    pub table_name: String,
+    /// The number of imported rows.
+    pub row_count: usize,
+    /// Whether the table was created by the import.
+    pub created: bool,
+}
+
+/// Query parameters of a request for an SQL dump.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct DumpOptions {
+    /// A comma separated list of the tables to dump. Without it, all tables are dumped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tables: Option<String>,
+    /// The maximum number of rows per `INSERT` statement.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<usize>,
+}
+
+/// The result of restoring an SQL dump.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RestoreResult {
+    /// The names of the created tables, in the order they were created.
+    pub tables: Vec<String>,
+    /// The number of inserted rows.
+    pub row_count: usize,
+    /// The number of created indexes.
+    pub index_count: usize,
+}
+
+/// A snapshot written by the server's `/admin/snapshot` endpoint.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SnapshotInfo {
+    /// The path of the snapshot file on the server.
+    pub path: String,
+    /// The log sequence number of the last change included in the snapshot.
+    pub lsn: u64,
+    /// The time the snapshot was taken, in RFC 3339 format.
+    pub timestamp: String,
+    /// The number of tables in the snapshot.
+    pub table_count: usize,
+}
+
+/// The state of the server's write-ahead log.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WalStatus {
+    /// The log sequence number of the last logged change.
+    pub last_lsn: u64,
+    /// The path of the segment new changes are written to.
+    pub segment: String,
+    /// The directory completed segments are archived to, if any.
+    pub archive: Option<String>,
+}
+
+/// Whether the server can serve requests, as returned by its `/ready` endpoint.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Readiness {
+    pub ready: bool,
+    /// Why the server is not ready, if it is not.
+    pub reason: Option<String>,
+}
+
+/// Information about the server returned by its `/info` endpoint.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ServerInfo {
+    /// The version of the server.
+    pub version: String,
+    /// Whether the server is a `debug` or `release` build.
+    pub profile: String,
+    /// The time since the server started, in seconds.
+    pub uptime_seconds: f64,
+    pub data_dir: String,
+    /// `disk` or `memory`.
+    pub persistence: String,
+    pub table_count: usize,
+    /// The size of the files in the data directory, in bytes.
+    pub storage_bytes: u64,
+    /// The enabled optional features, e.g. `csv` or `tls`.
+    pub features: Vec<String>,
+}
+
+/// Represents a request to log in with a user name and password.
+#[derive(Deserialize, Serialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// A bearer token issued by the server's `/login` endpoint.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct LoginResponse {
+    /// The token to send as `Authorization: Bearer <token>`.
+    pub token: String,
+    pub username: String,
+    /// The time the token expires, in RFC 3339 format.
+    pub expires_at: String,
+}
+
+/// Represents a request to create a user.
+#[derive(Deserialize, Serialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub password: String,
+    /// Whether the user may manage users and use the `/admin` endpoints.
+    #[serde(default)]
+    pub admin: bool,
+}
+
+/// Represents a request to change the password of a user.
+#[derive(Deserialize, Serialize)]
+pub struct AlterUserRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// Represents a request to drop a user.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DropUserRequest {
+    pub username: String,
+}
+
+/// A user as listed by the server's `/users` endpoint, without the password hash.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct UserInfo {
+    pub username: String,
+    pub admin: bool,
+    /// The roles granted to the user.
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// Represents a request to grant privileges to a user or role, or to revoke them.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GrantRequest {
+    pub privileges: Vec<PrivilegeSpec>,
+    /// The table, or `None` for the database.
+    #[serde(default)]
+    pub table: Option<String>,
+    pub grantee: String,
+}
+
+impl From<Privileges> for GrantRequest {
+    fn from(privileges: Privileges) -> Self {
+        GrantRequest {
+            privileges: privileges.privileges,
+            table: privileges.table,
+            grantee: privileges.grantee,
+        }
+    }
+}
+
+/// Represents a request to create or drop a role.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RoleRequest {
+    pub name: String,
+}
+
+/// Represents a request to grant a role to a user, or to revoke it.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GrantRoleRequest {
+    pub role: String,
+    pub username: String,
+}
+
+/// A role as listed by the server's `/roles` endpoint.
 #[derive(Deserialize, Serialize, Debug)]
+pub struct RoleInfo {
+    pub name: String,
+    /// The users the role is granted to.
+    pub members: Vec<String>,
+}
+
+/// Query parameters of the server's `/grants` endpoint.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct GrantsQuery {
+    /// The user or role whose grants are listed. Without it, admins get all grants and other
+    /// users their own grants and those of their roles.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grantee: Option<String>,
+}
+
+/// Condition for Select statements to specify what Column should be selected
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Condition {
     pub column: String,
     pub value: String,
 }
 
+impl Condition {
+    /// Returns the condition as an SQL expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::request_types::Condition;
+    ///
+    /// let condition = Condition {
+    ///     column: "name".to_string(),
+    ///     value: "O'Brien".to_string(),
+    /// };
+    /// assert_eq!(condition.to_sql(), "name = 'O''Brien'");
+    /// ```
+    pub fn to_sql(&self) -> String {
+        format!(
+            "{} = {}",
+            quote_ident(&self.column),
+            Value::from(self.value.as_str()).to_sql()
+        )
+    }
+}
+
+/// Returns the `WHERE` clause of a condition, with a leading space, or nothing without one.
+fn where_clause(condition: Option<&Condition>) -> String {
+    match condition {
+        Some(condition) => format!(" WHERE {}", condition.to_sql()),
+        None => String::new(),
+    }
+}
+
 /// Represents an update to Row(s) of a table
 #[derive(Deserialize, Serialize, Debug)]
 pub struct UpdateRequest {
@@ -83,9 +698,125 @@ pub struct UpdateRequest {
     pub updates: Vec<UpdateColumnRequest>,
 }
 
+impl UpdateRequest {
+    /// Returns the `UPDATE` statement the request executes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::request_types::{Condition, UpdateColumnRequest, UpdateRequest};
+    ///
+    /// let request = UpdateRequest {
+    ///     table_name: "users".to_string(),
+    ///     condition: Some(Condition {
+    ///         column: "id".to_string(),
+    ///         value: "7".to_string(),
+    ///     }),
+    ///     updates: vec![
+    ///         UpdateColumnRequest {
+    ///             column: "name".to_string(),
+    ///             value: Some("Ada".to_string()),
+    ///         },
+    ///         UpdateColumnRequest {
+    ///             column: "email".to_string(),
+    ///             value: None,
+    ///         },
+    ///     ],
+    /// };
+    /// assert_eq!(request.to_sql(), "UPDATE users SET name = 'Ada', email = NULL WHERE id = '7'");
+    /// ```
+    pub fn to_sql(&self) -> String {
+        let assignments: Vec<String> = self
+            .updates
+            .iter()
+            .map(|update| {
+                format!(
+                    "{} = {}",
+                    quote_ident(&update.column),
+                    update
+                        .value
+                        .as_deref()
+                        .map_or(Value::Null, Value::from)
+                        .to_sql()
+                )
+            })
+            .collect();
+        format!(
+            "UPDATE {} SET {}{}",
+            quote_ident(&self.table_name),
+            assignments.join(", "),
+            where_clause(self.condition.as_ref())
+        )
+    }
+}
+
+/// Represents the deletion of Row(s) of a table
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DeleteRequest {
+    pub table_name: String,
+    pub condition: Option<Condition>,
+}
+
+impl DeleteRequest {
+    /// Returns the `DELETE` statement the request executes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::request_types::{Condition, DeleteRequest};
+    ///
+    /// let request = DeleteRequest {
+    ///     table_name: "users".to_string(),
+    ///     condition: Some(Condition {
+    ///         column: "id".to_string(),
+    ///         value: "7".to_string(),
+    ///     }),
+    /// };
+    /// assert_eq!(request.to_sql(), "DELETE FROM users WHERE id = '7'");
+    /// ```
+    pub fn to_sql(&self) -> String {
+        format!(
+            "DELETE FROM {}{}",
+            quote_ident(&self.table_name),
+            where_clause(self.condition.as_ref())
+        )
+    }
+}
+
 /// Specification what columns should be updated with what
 #[derive(Deserialize, Serialize, Debug)]
 pub struct UpdateColumnRequest {
     pub column: String,
-    pub value: String,
+    /// The new value, converted to the type of the column, or `None` for `NULL`.
+    pub value: Option<String>,
+}
+
+/// Query parameters of a request for a page of a table's rows.
+///
+/// `order_by` is a comma separated list of columns, each prefixed with `-` to sort it in
+/// descending order, e.g. `name,-age`. Without it, rows are ordered by the primary key, or in
+/// insertion order if the table has none. `cursor` continues after the last row of a previous
+/// page and cannot be combined with `offset`.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct RowsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+/// A page of a table's rows.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RowsPage {
+    /// The names of the table's columns, in the order of the row values.
+    pub columns: Vec<String>,
+    pub rows: Vec<Row>,
+    /// The number of rows in the whole table.
+    pub total_rows: usize,
+    /// The cursor for the next page, or `None` if this is the last page.
+    pub next_cursor: Option<String>,
 }
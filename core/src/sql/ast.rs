@@ -0,0 +1,460 @@
+//! Typed syntax tree produced by the SQL parser.
+use crate::column::ForeignKey;
+use crate::policy::Policy;
+use crate::privilege::PrivilegeSpec;
+use crate::sql::parser::is_reserved;
+use crate::value::{DataType, Value};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A single parsed SQL statement.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Statement {
+    /// `CREATE TABLE name (column_definitions, table_constraints)`
+    CreateTable(CreateTable),
+    /// `CREATE [UNIQUE] INDEX name ON table_name (columns)`
+    CreateIndex(CreateIndex),
+    /// `DROP TABLE name`
+    DropTable { name: String },
+    /// `RENAME TABLE current_name TO new_name`
+    RenameTable {
+        current_name: String,
+        new_name: String,
+    },
+    /// `INSERT INTO name (columns) VALUES (values), ...`
+    Insert(Insert),
+    /// `SELECT items FROM name [WHERE expr]`
+    Select(Select),
+    /// `UPDATE name SET column = expr, ... [WHERE expr]`
+    Update(Update),
+    /// `DELETE FROM name [WHERE expr]`
+    Delete(Delete),
+    /// `SHOW TABLES`
+    ShowTables,
+    /// `DESCRIBE name` or `DESC name`
+    Describe { table_name: String },
+    /// `EXPLAIN [ANALYZE] SELECT ...`
+    Explain { analyze: bool, select: Select },
+    /// `CREATE USER name IDENTIFIED BY 'password' [ADMIN]`
+    CreateUser(CreateUser),
+    /// `ALTER USER name IDENTIFIED BY 'password'`
+    AlterUser { name: String, password: String },
+    /// `DROP USER name`
+    DropUser { name: String },
+    /// `SHOW USERS`
+    ShowUsers,
+    /// `GRANT privileges ON {* | [TABLE] name} TO grantee`
+    Grant(Privileges),
+    /// `REVOKE privileges ON {* | [TABLE] name} FROM grantee`
+    Revoke(Privileges),
+    /// `GRANT role TO user`
+    GrantRole { role: String, user: String },
+    /// `REVOKE role FROM user`
+    RevokeRole { role: String, user: String },
+    /// `CREATE ROLE name`
+    CreateRole { name: String },
+    /// `DROP ROLE name`
+    DropRole { name: String },
+    /// `SHOW ROLES`
+    ShowRoles,
+    /// `SHOW GRANTS [FOR name]`
+    ShowGrants { grantee: Option<String> },
+    /// `CREATE POLICY name ON table_name [TO roles] [USING (expr)] [WITH CHECK (expr)]`
+    CreatePolicy { table_name: String, policy: Policy },
+    /// `DROP POLICY name ON table_name`
+    DropPolicy { name: String, table_name: String },
+    /// `SET name {= | TO} value`, a setting of the session. Names are lowercase.
+    Set { name: String, value: String },
+    /// `RESET name` or `RESET ALL`, which is `None`
+    Reset { name: Option<String> },
+}
+
+/// The body of a `CREATE TABLE` statement.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::ast::{Statement, TableConstraintKind};
+/// use core::sql::parse_statement;
+///
+/// let statement = parse_statement(
+///     "CREATE TABLE orders (
+///         id INT AUTO_INCREMENT,
+///         user_id INT NOT NULL REFERENCES users (id),
+///         total FLOAT DEFAULT 0 CHECK (total >= 0),
+///         PRIMARY KEY (id, user_id)
+///     )",
+/// )
+/// .unwrap();
+///
+/// let Statement::CreateTable(create) = statement else { unreachable!() };
+/// assert!(create.columns[0].auto_increment);
+/// assert_eq!(create.columns[1].references.as_ref().unwrap().table_name, "users");
+/// assert_eq!(create.columns[2].check.as_ref().unwrap().to_string(), "total >= 0");
+/// assert_eq!(
+///     create.constraints[0].kind,
+///     TableConstraintKind::PrimaryKey(vec!["id".to_string(), "user_id".to_string()])
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateTable {
+    pub name: String,
+    pub columns: Vec<ColumnDef>,
+    pub constraints: Vec<TableConstraint>,
+}
+
+/// The body of a `CREATE INDEX` statement.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::ast::Statement;
+/// use core::sql::parse_statement;
+///
+/// let statement = parse_statement("CREATE UNIQUE INDEX users_email_key ON users (email)").unwrap();
+/// let Statement::CreateIndex(index) = statement else { unreachable!() };
+/// assert!(index.unique);
+/// assert_eq!(index.columns, vec!["email".to_string()]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateIndex {
+    pub name: String,
+    pub table_name: String,
+    pub columns: Vec<String>,
+    /// `UNIQUE`
+    pub unique: bool,
+}
+
+/// The body of a `CREATE USER` statement.
+///
+/// The user name is an identifier or a string literal, as in MySQL.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::ast::Statement;
+/// use core::sql::parse_statement;
+///
+/// let statement = parse_statement("CREATE USER 'alice' IDENTIFIED BY 's3cret' ADMIN").unwrap();
+/// let Statement::CreateUser(user) = statement else { unreachable!() };
+/// assert_eq!(user.name, "alice");
+/// assert_eq!(user.password, "s3cret");
+/// assert!(user.admin);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateUser {
+    pub name: String,
+    pub password: String,
+    /// `ADMIN`
+    pub admin: bool,
+}
+
+/// The body of a `GRANT` or `REVOKE` statement on privileges.
+///
+/// Privileges are given as a comma separated list, each optionally limited to a list of
+/// columns, or as `ALL [PRIVILEGES]`, which stands for every privilege that can be granted on
+/// the target. The target is `*` for the database or a table.
+///
+/// # Examples
+///
+/// ```
+/// use core::privilege::Privilege;
+/// use core::sql::ast::Statement;
+/// use core::sql::parse_statement;
+///
+/// let statement =
+///     parse_statement("GRANT SELECT (id, name), INSERT ON TABLE users TO 'analyst'").unwrap();
+/// let Statement::Grant(grant) = statement else { unreachable!() };
+/// assert_eq!(grant.privileges[0].privilege, Privilege::Select);
+/// assert_eq!(grant.privileges[0].columns, Some(vec!["id".to_string(), "name".to_string()]));
+/// assert_eq!(grant.privileges[1].columns, None);
+/// assert_eq!(grant.table.as_deref(), Some("users"));
+/// assert_eq!(grant.grantee, "analyst");
+///
+/// let statement = parse_statement("REVOKE ALL PRIVILEGES ON * FROM service").unwrap();
+/// let Statement::Revoke(revoke) = statement else { unreachable!() };
+/// assert_eq!(revoke.privileges.len(), Privilege::ALL.len());
+/// assert_eq!(revoke.table, None);
+///
+/// let statement = parse_statement("GRANT analyst TO alice").unwrap();
+/// assert_eq!(
+///     statement,
+///     Statement::GrantRole { role: "analyst".to_string(), user: "alice".to_string() }
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Privileges {
+    pub privileges: Vec<PrivilegeSpec>,
+    /// The table, or `None` for the database.
+    pub table: Option<String>,
+    /// The user or role.
+    pub grantee: String,
+}
+
+/// A column definition inside `CREATE TABLE`, including its inline constraints.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnDef {
+    pub name: String,
+    pub data_type: DataType,
+    /// `PRIMARY KEY`
+    pub primary_key: bool,
+    /// `NOT NULL`
+    pub not_null: bool,
+    /// `UNIQUE`
+    pub unique: bool,
+    /// `DEFAULT expr`
+    pub default: Option<Expr>,
+    /// `CHECK (expr)`
+    pub check: Option<Expr>,
+    /// `REFERENCES table (column)`
+    pub references: Option<ForeignKey>,
+    /// `AUTO_INCREMENT`
+    pub auto_increment: bool,
+}
+
+impl ColumnDef {
+    /// Creates a column definition without any constraints.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the column.
+    /// * `data_type` - The declared type of the column.
+    pub fn new(name: String, data_type: DataType) -> Self {
+        ColumnDef {
+            name,
+            data_type,
+            primary_key: false,
+            not_null: false,
+            unique: false,
+            default: None,
+            check: None,
+            references: None,
+            auto_increment: false,
+        }
+    }
+}
+
+/// A table-level constraint inside `CREATE TABLE`, optionally named with `CONSTRAINT name`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableConstraint {
+    pub name: Option<String>,
+    pub kind: TableConstraintKind,
+}
+
+/// The kinds of table-level constraints.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TableConstraintKind {
+    /// `PRIMARY KEY (columns)`
+    PrimaryKey(Vec<String>),
+    /// `UNIQUE (columns)`
+    Unique(Vec<String>),
+    /// `FOREIGN KEY (columns) REFERENCES table_name (referred_columns)`
+    ForeignKey {
+        columns: Vec<String>,
+        table_name: String,
+        referred_columns: Vec<String>,
+    },
+}
+
+/// The body of an `INSERT INTO` statement.
+///
+/// `columns` is empty if the statement did not name any columns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Insert {
+    pub table_name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Expr>>,
+}
+
+/// The body of a `SELECT` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Select {
+    pub projection: Vec<SelectItem>,
+    pub table_name: String,
+    pub join: Option<Join>,
+    pub selection: Option<Expr>,
+}
+
+/// `JOIN table ON expr` after the table of a `SELECT`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Join {
+    pub table_name: String,
+    pub on: Expr,
+}
+
+/// One item of a `SELECT` list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SelectItem {
+    /// `*`
+    Wildcard,
+    /// An expression with an optional `AS alias`.
+    Expr { expr: Expr, alias: Option<String> },
+}
+
+/// The body of an `UPDATE` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Update {
+    pub table_name: String,
+    pub assignments: Vec<Assignment>,
+    pub selection: Option<Expr>,
+}
+
+/// The body of a `DELETE` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delete {
+    pub table_name: String,
+    pub selection: Option<Expr>,
+}
+
+/// A `column = expr` pair in the `SET` list of an `UPDATE`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Assignment {
+    pub column: String,
+    pub value: Expr,
+}
+
+/// A scalar expression.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// A constant such as `42`, `'text'`, `TRUE` or `NULL`.
+    Literal(Value),
+    /// A reference to a column by name.
+    Column(String),
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    Binary {
+        left: Box<Expr>,
+        op: BinaryOp,
+        right: Box<Expr>,
+    },
+    /// `expr IS NULL` or, if `negated`, `expr IS NOT NULL`.
+    IsNull {
+        expr: Box<Expr>,
+        negated: bool,
+    },
+    /// A function call such as `lower(name)`.
+    Function {
+        name: String,
+        args: Vec<Expr>,
+    },
+}
+
+/// Prefix operators.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnaryOp {
+    Not,
+    Minus,
+    Plus,
+}
+
+/// Infix operators, listed from lowest to highest precedence group.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnaryOp::Not => write!(f, "NOT "),
+            UnaryOp::Minus => write!(f, "-"),
+            UnaryOp::Plus => write!(f, "+"),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            BinaryOp::Or => "OR",
+            BinaryOp::And => "AND",
+            BinaryOp::Eq => "=",
+            BinaryOp::NotEq => "<>",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEq => ">=",
+            BinaryOp::Plus => "+",
+            BinaryOp::Minus => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Modulo => "%",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// Formats the expression back into SQL text that the parser accepts.
+///
+/// Nested binary expressions are always parenthesised, so the output does not
+/// depend on operator precedence.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Literal(value) => write!(f, "{}", value.to_sql()),
+            Expr::Column(name) => write!(f, "{}", quote_ident(name)),
+            Expr::Unary { op, expr } => write!(f, "{}{}", op, Parenthesised(expr)),
+            Expr::Binary { left, op, right } => {
+                write!(f, "{} {} {}", Parenthesised(left), op, Parenthesised(right))
+            }
+            Expr::IsNull { expr, negated } => {
+                let not = if *negated { " NOT" } else { "" };
+                write!(f, "{} IS{} NULL", Parenthesised(expr), not)
+            }
+            Expr::Function { name, args } => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+        }
+    }
+}
+
+/// Wraps compound sub-expressions in parentheses when displayed.
+struct Parenthesised<'a>(&'a Expr);
+
+impl fmt::Display for Parenthesised<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Expr::Binary { .. } | Expr::IsNull { .. } | Expr::Unary { .. } => {
+                write!(f, "({})", self.0)
+            }
+            _ => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// Quotes an identifier with backticks unless it is a plain, non-reserved word.
+///
+/// Dotted names such as `information_schema.tables` are quoted part by part.
+pub fn quote_ident(name: &str) -> String {
+    name.split('.')
+        .map(|part| {
+            let plain = part
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+                && !is_reserved(part);
+            if plain {
+                part.to_string()
+            } else {
+                format!("`{}`", part.replace('`', "``"))
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(".")
+}
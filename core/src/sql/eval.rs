@@ -0,0 +1,282 @@
+//! Evaluation of expressions against the values of a row.
+//!
+//! Expressions follow SQL's three-valued logic: comparisons and arithmetic with `NULL` are
+//! `NULL`, and a condition only holds if it is `TRUE`. When a string is compared or combined
+//! with a number or boolean, the string is converted to that type first, so conditions on
+//! columns without a declared type behave as expected.
+use crate::sql::ast::{BinaryOp, Expr, UnaryOp};
+use crate::value::Value;
+use std::cmp::Ordering;
+
+/// The functions every expression may call, in addition to those of its [`Scope`].
+pub const FUNCTIONS: [&str; 5] = ["abs", "coalesce", "length", "lower", "upper"];
+
+/// What the columns and functions of an expression refer to.
+pub trait Scope {
+    /// Returns the value of the column `name`.
+    fn column(&self, name: &str) -> Result<Value, String>;
+
+    /// Calls the function `name` if it is not one of the built-in [`FUNCTIONS`].
+    fn function(&self, name: &str, _args: &[Value]) -> Result<Value, String> {
+        Err(format!("Unknown function '{}'", name))
+    }
+}
+
+/// Evaluates an expression.
+///
+/// # Arguments
+///
+/// * `expr` - The expression.
+/// * `scope` - The values of the columns and the functions the expression refers to.
+///
+/// # Errors
+///
+/// Returns an error if a column or function does not exist, an operator is applied to values
+/// of the wrong type, an integer overflows or a number is divided by zero.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::eval::{evaluate, Scope};
+/// use core::sql::parse_expression;
+/// use core::value::Value;
+///
+/// struct Row;
+///
+/// impl Scope for Row {
+///     fn column(&self, name: &str) -> Result<Value, String> {
+///         match name {
+///             "tenant" => Ok(Value::Int(7)),
+///             "name" => Ok(Value::from("Ann")),
+///             _ => Err(format!("Column '{}' not found", name)),
+///         }
+///     }
+/// }
+///
+/// let expr = parse_expression("tenant = '7' AND lower(name) = 'ann'").unwrap();
+/// assert_eq!(evaluate(&expr, &Row), Ok(Value::Bool(true)));
+/// let expr = parse_expression("tenant * 2 + NULL").unwrap();
+/// assert_eq!(evaluate(&expr, &Row), Ok(Value::Null));
+/// assert!(evaluate(&parse_expression("tenant / 0").unwrap(), &Row).is_err());
+/// ```
+pub fn evaluate(expr: &Expr, scope: &impl Scope) -> Result<Value, String> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Column(name) => scope.column(name),
+        Expr::Unary { op, expr } => unary(*op, evaluate(expr, scope)?),
+        Expr::Binary { left, op, right } => {
+            let left = evaluate(left, scope)?;
+            // `FALSE AND x` and `TRUE OR x` do not depend on `x`
+            let decided = match op {
+                BinaryOp::And => truth(&left)? == Some(false),
+                BinaryOp::Or => truth(&left)? == Some(true),
+                _ => false,
+            };
+            if decided {
+                return Ok(Value::Bool(*op == BinaryOp::Or));
+            }
+            binary(left, *op, evaluate(right, scope)?)
+        }
+        Expr::IsNull { expr, negated } => {
+            let is_null = evaluate(expr, scope)? == Value::Null;
+            Ok(Value::Bool(is_null != *negated))
+        }
+        Expr::Function { name, args } => {
+            let args = args
+                .iter()
+                .map(|arg| evaluate(arg, scope))
+                .collect::<Result<Vec<Value>, String>>()?;
+            match FUNCTIONS.contains(&name.as_str()) {
+                true => builtin(name, &args),
+                false => scope.function(name, &args),
+            }
+        }
+    }
+}
+
+/// Whether a value makes a condition hold, which only `TRUE` does.
+pub fn is_true(value: &Value) -> bool {
+    matches!(value, Value::Bool(true))
+}
+
+/// Lists the columns an expression refers to, in order of appearance.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::eval::columns;
+/// use core::sql::parse_expression;
+///
+/// let expr = parse_expression("tenant = current_setting('app.tenant') OR owner IS NULL").unwrap();
+/// assert_eq!(columns(&expr), vec!["tenant", "owner"]);
+/// ```
+pub fn columns(expr: &Expr) -> Vec<&str> {
+    let mut names = Vec::new();
+    collect_columns(expr, &mut names);
+    names
+}
+
+fn collect_columns<'a>(expr: &'a Expr, names: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Column(name) => names.push(name),
+        Expr::Unary { expr, .. } | Expr::IsNull { expr, .. } => collect_columns(expr, names),
+        Expr::Binary { left, right, .. } => {
+            collect_columns(left, names);
+            collect_columns(right, names);
+        }
+        Expr::Function { args, .. } => {
+            for arg in args {
+                collect_columns(arg, names);
+            }
+        }
+    }
+}
+
+/// Returns the truth value of a boolean, `None` for `NULL`.
+fn truth(value: &Value) -> Result<Option<bool>, String> {
+    match coerce(value, &Value::Bool(true)) {
+        Value::Bool(b) => Ok(Some(b)),
+        Value::Null => Ok(None),
+        _ => Err(format!("Expected a boolean, found {}", value.to_sql())),
+    }
+}
+
+/// Converts a string to the type of `other` if `other` is a number or boolean and the string
+/// can be parsed as one.
+fn coerce(value: &Value, other: &Value) -> Value {
+    let Value::Str(s) = value else {
+        return value.clone();
+    };
+    let s = s.trim();
+    let parsed = match other {
+        Value::Int(_) => s
+            .parse()
+            .map(Value::Int)
+            .or_else(|_| s.parse().map(Value::Float))
+            .ok(),
+        Value::Float(_) => s.parse().map(Value::Float).ok(),
+        Value::Bool(_) => s.to_ascii_lowercase().parse().map(Value::Bool).ok(),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| value.clone())
+}
+
+fn unary(op: UnaryOp, value: Value) -> Result<Value, String> {
+    match (op, &value) {
+        (_, Value::Null) => Ok(Value::Null),
+        (UnaryOp::Not, _) => Ok(Value::Bool(!truth(&value)?.unwrap_or_default())),
+        (UnaryOp::Minus, Value::Int(i)) => i
+            .checked_neg()
+            .map(Value::Int)
+            .ok_or_else(|| "Integer overflow".to_string()),
+        (UnaryOp::Minus, Value::Float(f)) => Ok(Value::Float(-f)),
+        (UnaryOp::Plus, Value::Int(_) | Value::Float(_)) => Ok(value),
+        _ => Err(format!("Cannot apply '{}' to {}", op, value.to_sql())),
+    }
+}
+
+fn binary(left: Value, op: BinaryOp, right: Value) -> Result<Value, String> {
+    let (left, right) = (coerce(&left, &right), coerce(&right, &left));
+    match op {
+        BinaryOp::And | BinaryOp::Or => {
+            let (a, b) = (truth(&left)?, truth(&right)?);
+            Ok(match (op, a, b) {
+                (BinaryOp::And, Some(false), _) | (BinaryOp::And, _, Some(false)) => {
+                    Value::Bool(false)
+                }
+                (BinaryOp::Or, Some(true), _) | (BinaryOp::Or, _, Some(true)) => Value::Bool(true),
+                (_, Some(_), Some(_)) => Value::Bool(op == BinaryOp::And),
+                _ => Value::Null,
+            })
+        }
+        _ if left == Value::Null || right == Value::Null => Ok(Value::Null),
+        BinaryOp::Eq => Ok(Value::Bool(left.compare(&right) == Ordering::Equal)),
+        BinaryOp::NotEq => Ok(Value::Bool(left.compare(&right) != Ordering::Equal)),
+        BinaryOp::Lt => Ok(Value::Bool(left.compare(&right) == Ordering::Less)),
+        BinaryOp::LtEq => Ok(Value::Bool(left.compare(&right) != Ordering::Greater)),
+        BinaryOp::Gt => Ok(Value::Bool(left.compare(&right) == Ordering::Greater)),
+        BinaryOp::GtEq => Ok(Value::Bool(left.compare(&right) != Ordering::Less)),
+        _ => arithmetic(left, op, right),
+    }
+}
+
+fn arithmetic(left: Value, op: BinaryOp, right: Value) -> Result<Value, String> {
+    let overflow = || "Integer overflow".to_string();
+    match (&left, &right) {
+        (Value::Int(a), Value::Int(b)) => {
+            let (a, b) = (*a, *b);
+            if matches!(op, BinaryOp::Divide | BinaryOp::Modulo) && b == 0 {
+                return Err("Division by zero".to_string());
+            }
+            let result = match op {
+                BinaryOp::Plus => a.checked_add(b),
+                BinaryOp::Minus => a.checked_sub(b),
+                BinaryOp::Multiply => a.checked_mul(b),
+                BinaryOp::Divide => a.checked_div(b),
+                _ => a.checked_rem(b),
+            };
+            result.map(Value::Int).ok_or_else(overflow)
+        }
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            let (a, b) = (float(&left), float(&right));
+            if matches!(op, BinaryOp::Divide | BinaryOp::Modulo) && b == 0.0 {
+                return Err("Division by zero".to_string());
+            }
+            Ok(Value::Float(match op {
+                BinaryOp::Plus => a + b,
+                BinaryOp::Minus => a - b,
+                BinaryOp::Multiply => a * b,
+                BinaryOp::Divide => a / b,
+                _ => a % b,
+            }))
+        }
+        _ => Err(format!(
+            "Cannot apply '{}' to {} and {}",
+            op,
+            left.to_sql(),
+            right.to_sql()
+        )),
+    }
+}
+
+fn float(value: &Value) -> f64 {
+    match value {
+        Value::Int(i) => *i as f64,
+        Value::Float(f) => *f,
+        _ => 0.0,
+    }
+}
+
+fn builtin(name: &str, args: &[Value]) -> Result<Value, String> {
+    let single = || match args {
+        [arg] => Ok(arg),
+        _ => Err(format!("Function '{}' takes 1 argument", name)),
+    };
+    match name {
+        "coalesce" => Ok(args
+            .iter()
+            .find(|arg| **arg != Value::Null)
+            .cloned()
+            .unwrap_or(Value::Null)),
+        "abs" => match single()? {
+            Value::Int(i) => i
+                .checked_abs()
+                .map(Value::Int)
+                .ok_or_else(|| "Integer overflow".to_string()),
+            Value::Float(f) => Ok(Value::Float(f.abs())),
+            Value::Null => Ok(Value::Null),
+            other => Err(format!("Cannot apply 'abs' to {}", other.to_sql())),
+        },
+        _ => {
+            let Some(text) = single()?.as_string() else {
+                return Ok(Value::Null);
+            };
+            Ok(match name {
+                "length" => Value::Int(text.chars().count() as i64),
+                "lower" => Value::Str(text.to_lowercase()),
+                _ => Value::Str(text.to_uppercase()),
+            })
+        }
+    }
+}
@@ -0,0 +1,602 @@
+//! Tokenizer for the SQL dialect understood by the database.
+use crate::sql::ast::quote_ident;
+use crate::sql::parser::is_reserved;
+use crate::sql::ParseError;
+
+/// The kind of a lexical token.
+///
+/// Keywords are not distinguished from identifiers at this level; the parser
+/// compares unquoted identifiers case-insensitively against the keywords it expects.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    /// An unquoted identifier or keyword, e.g. `users` or `SELECT`.
+    Ident(String),
+    /// An identifier enclosed in backticks, e.g. `` `user name` ``. Never treated as a keyword.
+    QuotedIdent(String),
+    /// A string literal enclosed in single or double quotes.
+    Str(String),
+    /// An integer literal.
+    Int(i64),
+    /// A floating point literal.
+    Float(f64),
+    Comma,
+    Dot,
+    Semicolon,
+    LParen,
+    RParen,
+    Star,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    /// The end of the input.
+    Eof,
+}
+
+/// A token together with the position where it starts.
+///
+/// Lines and columns are 1-based; columns count characters, not bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Splits `input` into tokens.
+///
+/// Whitespace, `-- line comments` and `/* block comments */` are skipped.
+/// The returned vector always ends with a `TokenKind::Eof` token.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::lexer::{tokenize, TokenKind};
+///
+/// let tokens = tokenize("SELECT name FROM users WHERE note = 'it''s, (fine)'").unwrap();
+/// assert_eq!(tokens[0].kind, TokenKind::Ident("SELECT".to_string()));
+/// assert_eq!(tokens[7].kind, TokenKind::Str("it's, (fine)".to_string()));
+/// assert_eq!(tokens[8].kind, TokenKind::Eof);
+/// ```
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    Lexer::new(input).run()
+}
+
+/// Returns whether `input` is terminated by a `;` outside of literals and comments.
+///
+/// Input ending inside a string, quoted identifier or block comment is never complete.
+/// Any other lexical error counts as complete, so that it can be reported by the parser.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::lexer::is_complete;
+///
+/// assert!(is_complete("SELECT * FROM users; -- done"));
+/// assert!(!is_complete("SELECT * FROM users\nWHERE name = 'a;"));
+/// assert!(!is_complete("SELECT * FROM users"));
+/// ```
+pub fn is_complete(input: &str) -> bool {
+    match tokenize(input) {
+        Ok(tokens) => tokens.len() >= 2 && tokens[tokens.len() - 2].kind == TokenKind::Semicolon,
+        Err(e) => !e.message.starts_with("Unterminated"),
+    }
+}
+
+/// Returns the normalised form of a statement, under which the executions of statements that
+/// only differ in their constants are counted together.
+///
+/// Literals, including `TRUE` and `FALSE`, are replaced by `?`, reserved keywords are written
+/// in upper case and whitespace and comments are collapsed. Text that cannot be tokenized is
+/// returned trimmed.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::lexer::normalize;
+///
+/// assert_eq!(
+///     normalize("select name from users\n  where id = 42 and active = true; -- by id"),
+///     "SELECT name FROM users WHERE id = ? AND active = ?"
+/// );
+/// assert_eq!(
+///     normalize("INSERT INTO users (id, name) VALUES (-7, 'O''Brien')"),
+///     normalize("insert into users (id, name) values (8, 'Smith')")
+/// );
+/// ```
+pub fn normalize(input: &str) -> String {
+    let Ok(tokens) = tokenize(input) else {
+        return input.trim().to_string();
+    };
+    let mut text = String::new();
+    let mut previous: Option<&TokenKind> = None;
+    for (index, token) in tokens.iter().enumerate() {
+        let next = tokens.get(index + 1).map(|token| &token.kind);
+        let word = match &token.kind {
+            TokenKind::Eof | TokenKind::Semicolon => continue,
+            // A sign directly in front of a number belongs to the literal
+            TokenKind::Plus | TokenKind::Minus
+                if matches!(next, Some(TokenKind::Int(_) | TokenKind::Float(_)))
+                    && !previous.is_some_and(ends_operand) =>
+            {
+                continue
+            }
+            TokenKind::Str(_) | TokenKind::Int(_) | TokenKind::Float(_) => "?".to_string(),
+            TokenKind::Ident(word)
+                if word.eq_ignore_ascii_case("TRUE") || word.eq_ignore_ascii_case("FALSE") =>
+            {
+                "?".to_string()
+            }
+            TokenKind::Ident(word) if is_reserved(word) => word.to_uppercase(),
+            TokenKind::Ident(word) => word.clone(),
+            TokenKind::QuotedIdent(word) => quote_ident(word),
+            TokenKind::Comma => ",".to_string(),
+            TokenKind::Dot => ".".to_string(),
+            TokenKind::LParen => "(".to_string(),
+            TokenKind::RParen => ")".to_string(),
+            TokenKind::Star => "*".to_string(),
+            TokenKind::Plus => "+".to_string(),
+            TokenKind::Minus => "-".to_string(),
+            TokenKind::Slash => "/".to_string(),
+            TokenKind::Percent => "%".to_string(),
+            TokenKind::Eq => "=".to_string(),
+            TokenKind::NotEq => "<>".to_string(),
+            TokenKind::Lt => "<".to_string(),
+            TokenKind::LtEq => "<=".to_string(),
+            TokenKind::Gt => ">".to_string(),
+            TokenKind::GtEq => ">=".to_string(),
+        };
+        let joined = match (previous, &token.kind) {
+            (None, _) => true,
+            (_, TokenKind::Comma | TokenKind::RParen | TokenKind::Dot) => true,
+            (Some(TokenKind::LParen | TokenKind::Dot), _) => true,
+            // Function calls
+            (Some(TokenKind::Ident(name)), TokenKind::LParen) => !is_reserved(name),
+            _ => false,
+        };
+        if !joined {
+            text.push(' ');
+        }
+        text.push_str(&word);
+        previous = Some(&token.kind);
+    }
+    text
+}
+
+/// Whether a token can end an operand, so that a following sign is a binary operator.
+fn ends_operand(kind: &TokenKind) -> bool {
+    match kind {
+        TokenKind::Ident(word) => !is_reserved(word),
+        TokenKind::QuotedIdent(_)
+        | TokenKind::Str(_)
+        | TokenKind::Int(_)
+        | TokenKind::Float(_)
+        | TokenKind::RParen => true,
+        _ => false,
+    }
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Self {
+        Lexer {
+            chars: input.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn run(mut self) -> Result<Vec<Token>, ParseError> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments()?;
+            let (line, column) = (self.line, self.column);
+            let kind = match self.peek() {
+                None => {
+                    tokens.push(Token {
+                        kind: TokenKind::Eof,
+                        line,
+                        column,
+                    });
+                    return Ok(tokens);
+                }
+                Some(c) if c.is_ascii_digit() => match self.number(line, column, &tokens)? {
+                    // The smallest integer only fits with its sign, which becomes part of it
+                    TokenKind::Int(i64::MIN) => {
+                        let minus = tokens.pop().expect("A negated integer follows a minus");
+                        tokens.push(Token {
+                            kind: TokenKind::Int(i64::MIN),
+                            ..minus
+                        });
+                        continue;
+                    }
+                    kind => kind,
+                },
+                Some('.') if self.peek_second().is_some_and(|c| c.is_ascii_digit()) => {
+                    self.number(line, column, &tokens)?
+                }
+                Some(c) if c.is_alphabetic() || c == '_' => self.ident(),
+                Some(quote @ ('\'' | '"')) => self.string(quote, line, column)?,
+                Some('`') => self.quoted_ident(line, column)?,
+                Some(c) => self.symbol(c, line, column)?,
+            };
+            tokens.push(Token { kind, line, column });
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        let mut ahead = self.chars.clone();
+        ahead.next();
+        ahead.next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('-') if self.peek_second() == Some('-') => {
+                    while let Some(c) = self.bump() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                Some('/') if self.peek_second() == Some('*') => {
+                    let (line, column) = (self.line, self.column);
+                    self.bump();
+                    self.bump();
+                    loop {
+                        match self.bump() {
+                            Some('*') if self.peek() == Some('/') => {
+                                self.bump();
+                                break;
+                            }
+                            Some(_) => {}
+                            None => {
+                                return Err(ParseError::new(
+                                    "Unterminated block comment",
+                                    line,
+                                    column,
+                                ))
+                            }
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Reads a number. An integer one larger than `i64::MAX` following a unary minus is
+    /// returned as `i64::MIN`, for the caller to merge with the minus.
+    fn number(
+        &mut self,
+        line: usize,
+        column: usize,
+        previous: &[Token],
+    ) -> Result<TokenKind, ParseError> {
+        let mut text = String::new();
+        let mut is_float = false;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() {
+                text.push(c);
+            } else if c == '.' && !is_float {
+                is_float = true;
+                text.push(c);
+            } else if (c == 'e' || c == 'E')
+                && self
+                    .peek_second()
+                    .is_some_and(|n| n.is_ascii_digit() || n == '+' || n == '-')
+            {
+                is_float = true;
+                text.push(c);
+                self.bump();
+                if let Some(sign @ ('+' | '-')) = self.peek() {
+                    text.push(sign);
+                    self.bump();
+                }
+                continue;
+            } else {
+                break;
+            }
+            self.bump();
+        }
+
+        if is_float {
+            text.parse::<f64>()
+                .map(TokenKind::Float)
+                .map_err(|_| ParseError::new(format!("Invalid number '{}'", text), line, column))
+        } else {
+            let negated = match previous {
+                [.., before, minus] => {
+                    minus.kind == TokenKind::Minus && !ends_operand(&before.kind)
+                }
+                [minus] => minus.kind == TokenKind::Minus,
+                [] => false,
+            };
+            match text.parse::<i64>() {
+                Ok(i) => Ok(TokenKind::Int(i)),
+                Err(_) if negated && text.parse::<u64>() == Ok(i64::MIN.unsigned_abs()) => {
+                    Ok(TokenKind::Int(i64::MIN))
+                }
+                Err(_) => Err(ParseError::new(
+                    format!("Integer '{}' is out of range", text),
+                    line,
+                    column,
+                )),
+            }
+        }
+    }
+
+    fn ident(&mut self) -> TokenKind {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '_' {
+                text.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        TokenKind::Ident(text)
+    }
+
+    /// Reads a string literal. The quote character is escaped by doubling it (`'it''s'`)
+    /// or with a backslash, which also supports `\n`, `\t`, `\r`, `\0` and `\\`.
+    fn string(&mut self, quote: char, line: usize, column: usize) -> Result<TokenKind, ParseError> {
+        self.bump();
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                Some(c) if c == quote => {
+                    if self.peek() == Some(quote) {
+                        self.bump();
+                        text.push(quote);
+                    } else {
+                        return Ok(TokenKind::Str(text));
+                    }
+                }
+                Some('\\') => match self.bump() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('0') => text.push('\0'),
+                    Some(c) => text.push(c),
+                    None => break,
+                },
+                Some(c) => text.push(c),
+                None => break,
+            }
+        }
+        Err(ParseError::new("Unterminated string literal", line, column))
+    }
+
+    fn quoted_ident(&mut self, line: usize, column: usize) -> Result<TokenKind, ParseError> {
+        self.bump();
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                Some('`') => {
+                    if self.peek() == Some('`') {
+                        self.bump();
+                        text.push('`');
+                    } else if text.is_empty() {
+                        return Err(ParseError::new("Empty quoted identifier", line, column));
+                    } else {
+                        return Ok(TokenKind::QuotedIdent(text));
+                    }
+                }
+                Some(c) => text.push(c),
+                None => {
+                    return Err(ParseError::new(
+                        "Unterminated quoted identifier",
+                        line,
+                        column,
+                    ))
+                }
+            }
+        }
+    }
+
+    fn symbol(&mut self, c: char, line: usize, column: usize) -> Result<TokenKind, ParseError> {
+        self.bump();
+        let kind = match c {
+            ',' => TokenKind::Comma,
+            '.' => TokenKind::Dot,
+            ';' => TokenKind::Semicolon,
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            '*' => TokenKind::Star,
+            '+' => TokenKind::Plus,
+            '-' => TokenKind::Minus,
+            '/' => TokenKind::Slash,
+            '%' => TokenKind::Percent,
+            '=' => {
+                if self.peek() == Some('=') {
+                    self.bump();
+                }
+                TokenKind::Eq
+            }
+            '!' if self.peek() == Some('=') => {
+                self.bump();
+                TokenKind::NotEq
+            }
+            '<' => match self.peek() {
+                Some('=') => {
+                    self.bump();
+                    TokenKind::LtEq
+                }
+                Some('>') => {
+                    self.bump();
+                    TokenKind::NotEq
+                }
+                _ => TokenKind::Lt,
+            },
+            '>' => {
+                if self.peek() == Some('=') {
+                    self.bump();
+                    TokenKind::GtEq
+                } else {
+                    TokenKind::Gt
+                }
+            }
+            other => {
+                return Err(ParseError::new(
+                    format!("Unexpected character '{}'", other),
+                    line,
+                    column,
+                ))
+            }
+        };
+        Ok(kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    fn error(input: &str) -> (String, usize, usize) {
+        let error = tokenize(input).unwrap_err();
+        (error.message, error.line, error.column)
+    }
+
+    #[test]
+    fn unterminated_literals_report_their_start() {
+        assert_eq!(
+            error("SELECT 'abc"),
+            ("Unterminated string literal".to_string(), 1, 8)
+        );
+        assert_eq!(
+            error("SELECT 'it''s\\'"),
+            ("Unterminated string literal".to_string(), 1, 8)
+        );
+        assert_eq!(
+            error("SELECT\n  `name"),
+            ("Unterminated quoted identifier".to_string(), 2, 3)
+        );
+        assert_eq!(
+            error("SELECT ``"),
+            ("Empty quoted identifier".to_string(), 1, 8)
+        );
+    }
+
+    #[test]
+    fn block_comments_need_their_own_closing_star() {
+        assert_eq!(
+            error("SELECT /* note"),
+            ("Unterminated block comment".to_string(), 1, 8)
+        );
+        assert_eq!(
+            error("SELECT /*/ 1"),
+            ("Unterminated block comment".to_string(), 1, 8)
+        );
+        assert_eq!(kinds("/**/ 1"), [TokenKind::Int(1), TokenKind::Eof]);
+        assert_eq!(kinds("/*/ 2 */ 1"), [TokenKind::Int(1), TokenKind::Eof]);
+        assert_eq!(kinds("1 -- note"), [TokenKind::Int(1), TokenKind::Eof]);
+    }
+
+    #[test]
+    fn incomplete_input_is_not_complete() {
+        assert!(!is_complete("SELECT 1 /*/ ;"));
+        assert!(!is_complete("SELECT ';"));
+        assert!(!is_complete("SELECT `a;"));
+        assert!(is_complete("SELECT 1 /**/ ;"));
+        // Other errors are left to the parser
+        assert!(is_complete("SELECT 99999999999999999999;"));
+    }
+
+    #[test]
+    fn integers_cover_the_range_of_i64() {
+        assert_eq!(
+            kinds("9223372036854775807"),
+            [TokenKind::Int(i64::MAX), TokenKind::Eof]
+        );
+        assert_eq!(
+            error("9223372036854775808"),
+            (
+                "Integer '9223372036854775808' is out of range".to_string(),
+                1,
+                1
+            )
+        );
+        let tokens = tokenize("-9223372036854775808").unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::Int(i64::MIN));
+        assert_eq!((tokens[0].line, tokens[0].column), (1, 1));
+        assert_eq!(
+            kinds("(-9223372036854775808)"),
+            [
+                TokenKind::LParen,
+                TokenKind::Int(i64::MIN),
+                TokenKind::RParen,
+                TokenKind::Eof
+            ]
+        );
+        // After an operand the minus is a subtraction, so the integer is out of range
+        assert!(tokenize("1 -9223372036854775808").is_err());
+        assert!(tokenize("-9223372036854775809").is_err());
+        assert_eq!(
+            kinds("1e3 .5"),
+            [
+                TokenKind::Float(1000.0),
+                TokenKind::Float(0.5),
+                TokenKind::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn positions_count_characters() {
+        let tokens = tokenize("'é' x\n  y").unwrap();
+        let positions: Vec<(usize, usize)> = tokens
+            .iter()
+            .map(|token| (token.line, token.column))
+            .collect();
+        assert_eq!(positions, [(1, 1), (1, 5), (2, 3), (2, 4)]);
+    }
+
+    #[test]
+    fn normalize_keeps_text_it_cannot_tokenize() {
+        assert_eq!(normalize("  SELECT 'a  "), "SELECT 'a");
+        assert_eq!(
+            normalize("select -9223372036854775808, x - 1"),
+            "SELECT ?, x - ?"
+        );
+    }
+}
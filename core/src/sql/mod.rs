@@ -0,0 +1,63 @@
+//! SQL front end shared by the clients and the server.
+//!
+//! - [`lexer`](lexer): Splits SQL text into tokens with line and column positions.
+//! - [`parser`](parser): Builds a typed [`ast::Statement`] from the tokens.
+//! - [`ast`](ast): The syntax tree types.
+//! - [`eval`](eval): Evaluates expressions against the values of a row.
+//!
+//! # Examples
+//!
+//! ```
+//! use core::sql::ast::Statement;
+//! use core::sql::parse_statement;
+//!
+//! let statement = parse_statement("insert into users values (1, 'O''Brien', NULL)").unwrap();
+//! assert!(matches!(statement, Statement::Insert(_)));
+//! ```
+use std::fmt;
+
+pub mod ast;
+pub mod eval;
+pub mod lexer;
+pub mod parser;
+
+pub use parser::{parse_expression, parse_statement, parse_statements};
+
+/// An error produced while tokenizing or parsing SQL text.
+///
+/// `line` and `column` are 1-based and point at the offending character or token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl ParseError {
+    /// Creates a new `ParseError` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - A description of what went wrong.
+    /// * `line` - The 1-based line of the error.
+    /// * `column` - The 1-based column of the error.
+    pub fn new(message: impl Into<String>, line: usize, column: usize) -> Self {
+        ParseError {
+            message: message.into(),
+            line,
+            column,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Syntax error at line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,1222 @@
+//! Recursive-descent parser turning tokens into a [`Statement`](crate::sql::ast::Statement).
+use crate::column::ForeignKey;
+use crate::policy::Policy;
+use crate::privilege::{Privilege, PrivilegeSpec};
+use crate::sql::ast::{
+    Assignment, BinaryOp, ColumnDef, CreateIndex, CreateTable, CreateUser, Delete, Expr, Insert,
+    Join, Privileges, Select, SelectItem, Statement, TableConstraint, TableConstraintKind, UnaryOp,
+    Update,
+};
+use crate::sql::lexer::{tokenize, Token, TokenKind};
+use crate::sql::ParseError;
+use crate::value::{DataType, Value};
+
+/// Keywords that cannot be used as unquoted identifiers.
+pub const RESERVED_KEYWORDS: &[&str] = &[
+    "AND",
+    "AS",
+    "CHECK",
+    "CONSTRAINT",
+    "CREATE",
+    "DEFAULT",
+    "DROP",
+    "FALSE",
+    "FOREIGN",
+    "FROM",
+    "INSERT",
+    "INTO",
+    "IS",
+    "NOT",
+    "NULL",
+    "OR",
+    "PRIMARY",
+    "REFERENCES",
+    "RENAME",
+    "SELECT",
+    "SET",
+    "TABLE",
+    "TO",
+    "TRUE",
+    "UNIQUE",
+    "UPDATE",
+    "VALUES",
+    "WHERE",
+];
+
+/// Parses exactly one statement. A trailing semicolon is allowed.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::ast::{Expr, Statement};
+/// use core::sql::parse_statement;
+/// use core::value::Value;
+///
+/// let statement = parse_statement("select * from users where name = 'Alice, (Smith)';").unwrap();
+/// match statement {
+///     Statement::Select(select) => {
+///         assert_eq!(select.table_name, "users");
+///         assert_eq!(
+///             select.selection.unwrap().to_string(),
+///             "name = 'Alice, (Smith)'"
+///         );
+///     }
+///     _ => unreachable!(),
+/// }
+///
+/// let error = parse_statement("SELECT name\nFROM").unwrap_err();
+/// assert_eq!((error.line, error.column), (2, 5));
+/// ```
+pub fn parse_statement(input: &str) -> Result<Statement, ParseError> {
+    let mut parser = Parser::new(tokenize(input)?);
+    let statement = parser.statement()?;
+    parser.consume(&TokenKind::Semicolon);
+    parser.expect_eof()?;
+    Ok(statement)
+}
+
+/// Parses a script of statements separated by semicolons.
+///
+/// Empty statements (for example a lone `;`) are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::parse_statements;
+///
+/// let statements = parse_statements("
+///     CREATE TABLE users (id INT PRIMARY KEY AUTO_INCREMENT, name STRING NOT NULL DEFAULT '');
+///     -- two rows in one statement
+///     INSERT INTO users (id, name) VALUES (1, 'Alice'), (2, 'Bob');
+/// ").unwrap();
+/// assert_eq!(statements.len(), 2);
+/// ```
+pub fn parse_statements(input: &str) -> Result<Vec<Statement>, ParseError> {
+    let mut parser = Parser::new(tokenize(input)?);
+    let mut statements = Vec::new();
+    loop {
+        while parser.consume(&TokenKind::Semicolon) {}
+        if parser.peek_kind() == &TokenKind::Eof {
+            return Ok(statements);
+        }
+        statements.push(parser.statement()?);
+        if parser.peek_kind() != &TokenKind::Eof {
+            parser.expect(&TokenKind::Semicolon)?;
+        }
+    }
+}
+
+/// Parses a standalone expression, e.g. the body of a `WHERE` clause.
+///
+/// # Examples
+///
+/// ```
+/// use core::sql::parse_expression;
+///
+/// let expr = parse_expression("age >= 18 AND NOT banned OR name IS NULL").unwrap();
+/// assert_eq!(expr.to_string(), "((age >= 18) AND (NOT banned)) OR (name IS NULL)");
+/// ```
+pub fn parse_expression(input: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser::new(tokenize(input)?);
+    let expr = parser.expr()?;
+    parser.expect_eof()?;
+    Ok(expr)
+}
+
+/// The deepest nesting of parentheses, function calls, `NOT` and signs in an expression, so
+/// that input from a request cannot overflow the stack. Each level takes several kilobytes of
+/// stack in debug builds, and threads of the server have 2 MiB.
+pub const MAX_EXPRESSION_DEPTH: usize = 128;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// The number of expressions being parsed that contain the next token.
+    depth: usize,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn peek_kind(&self) -> &TokenKind {
+        &self.peek().kind
+    }
+
+    fn peek_kind_at(&self, offset: usize) -> &TokenKind {
+        &self.tokens[(self.pos + offset).min(self.tokens.len() - 1)].kind
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error_at(&self, token: &Token, message: impl Into<String>) -> ParseError {
+        ParseError::new(message, token.line, token.column)
+    }
+
+    /// Parses an expression nested in the one being parsed with `parse`, failing past
+    /// [`MAX_EXPRESSION_DEPTH`].
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<Expr, ParseError>,
+    ) -> Result<Expr, ParseError> {
+        if self.depth == MAX_EXPRESSION_DEPTH {
+            let message = format!(
+                "Expressions cannot be nested more than {} levels deep",
+                MAX_EXPRESSION_DEPTH
+            );
+            return Err(self.error_at(self.peek(), message));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        let token = self.peek();
+        self.error_at(
+            token,
+            format!("Expected {}, found {}", expected, describe(&token.kind)),
+        )
+    }
+
+    /// Consumes the next token if it has the given kind.
+    fn consume(&mut self, kind: &TokenKind) -> bool {
+        if self.peek_kind() == kind {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: &TokenKind) -> Result<(), ParseError> {
+        if self.consume(kind) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&describe(kind)))
+        }
+    }
+
+    fn expect_eof(&self) -> Result<(), ParseError> {
+        match self.peek_kind() {
+            TokenKind::Eof => Ok(()),
+            _ => Err(self.unexpected("end of statement")),
+        }
+    }
+
+    /// Returns true if the next token is the given keyword (case-insensitive).
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek_kind(), TokenKind::Ident(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_keyword(keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.consume_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(keyword))
+        }
+    }
+
+    /// Parses a single identifier, rejecting reserved keywords unless quoted.
+    fn ident(&mut self) -> Result<String, ParseError> {
+        match self.peek_kind().clone() {
+            TokenKind::Ident(word) if !is_reserved(&word) => {
+                self.advance();
+                Ok(word)
+            }
+            TokenKind::QuotedIdent(word) => {
+                self.advance();
+                Ok(word)
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    /// Parses a possibly qualified name such as `information_schema.tables`.
+    fn object_name(&mut self) -> Result<String, ParseError> {
+        let mut name = self.ident()?;
+        while self.peek_kind() == &TokenKind::Dot {
+            self.advance();
+            name.push('.');
+            name.push_str(&self.ident()?);
+        }
+        Ok(name)
+    }
+
+    fn ident_list(&mut self) -> Result<Vec<String>, ParseError> {
+        self.expect(&TokenKind::LParen)?;
+        let mut names = vec![self.ident()?];
+        while self.consume(&TokenKind::Comma) {
+            names.push(self.ident()?);
+        }
+        self.expect(&TokenKind::RParen)?;
+        Ok(names)
+    }
+
+    fn statement(&mut self) -> Result<Statement, ParseError> {
+        if self.consume_keyword("CREATE") {
+            self.create()
+        } else if self.consume_keyword("DROP") {
+            self.drop()
+        } else if self.consume_keyword("RENAME") {
+            self.rename()
+        } else if self.consume_keyword("INSERT") {
+            self.insert()
+        } else if self.consume_keyword("SELECT") {
+            self.select().map(Statement::Select)
+        } else if self.consume_keyword("EXPLAIN") {
+            let analyze = self.consume_keyword("ANALYZE");
+            self.expect_keyword("SELECT")?;
+            let select = self.select()?;
+            Ok(Statement::Explain { analyze, select })
+        } else if self.consume_keyword("UPDATE") {
+            self.update()
+        } else if self.consume_keyword("DELETE") {
+            self.delete()
+        } else if self.consume_keyword("ALTER") {
+            self.expect_keyword("USER")?;
+            let name = self.user_name()?;
+            let password = self.identified_by()?;
+            Ok(Statement::AlterUser { name, password })
+        } else if self.consume_keyword("SET") {
+            self.set()
+        } else if self.consume_keyword("RESET") {
+            let name = match self.consume_keyword("ALL") {
+                true => None,
+                false => Some(self.setting_name()?),
+            };
+            Ok(Statement::Reset { name })
+        } else if self.consume_keyword("GRANT") {
+            self.grant(false)
+        } else if self.consume_keyword("REVOKE") {
+            self.grant(true)
+        } else if self.consume_keyword("SHOW") {
+            if self.consume_keyword("USERS") {
+                return Ok(Statement::ShowUsers);
+            }
+            if self.consume_keyword("ROLES") {
+                return Ok(Statement::ShowRoles);
+            }
+            if self.consume_keyword("GRANTS") {
+                let grantee = match self.consume_keyword("FOR") {
+                    true => Some(self.user_name()?),
+                    false => None,
+                };
+                return Ok(Statement::ShowGrants { grantee });
+            }
+            self.expect_keyword("TABLES")?;
+            Ok(Statement::ShowTables)
+        } else if self.consume_keyword("DESCRIBE") || self.consume_keyword("DESC") {
+            let table_name = self.object_name()?;
+            Ok(Statement::Describe { table_name })
+        } else {
+            Err(self.unexpected("a statement"))
+        }
+    }
+
+    fn create(&mut self) -> Result<Statement, ParseError> {
+        let unique = self.consume_keyword("UNIQUE");
+        if unique || self.peek_keyword("INDEX") {
+            self.expect_keyword("INDEX")?;
+            return self.create_index(unique);
+        }
+        if self.consume_keyword("ROLE") {
+            let name = self.user_name()?;
+            return Ok(Statement::CreateRole { name });
+        }
+        if self.consume_keyword("POLICY") {
+            return self.create_policy();
+        }
+        if self.consume_keyword("USER") {
+            let name = self.user_name()?;
+            let password = self.identified_by()?;
+            let admin = self.consume_keyword("ADMIN");
+            return Ok(Statement::CreateUser(CreateUser {
+                name,
+                password,
+                admin,
+            }));
+        }
+        self.expect_keyword("TABLE")?;
+        let name = self.object_name()?;
+        self.expect(&TokenKind::LParen)?;
+        let mut columns = Vec::new();
+        let mut constraints = Vec::new();
+        loop {
+            if ["CONSTRAINT", "PRIMARY", "UNIQUE", "FOREIGN"]
+                .iter()
+                .any(|keyword| self.peek_keyword(keyword))
+            {
+                constraints.push(self.table_constraint()?);
+            } else {
+                columns.push(self.column_def()?);
+            }
+            if !self.consume(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(&TokenKind::RParen)?;
+        Ok(Statement::CreateTable(CreateTable {
+            name,
+            columns,
+            constraints,
+        }))
+    }
+
+    fn create_index(&mut self, unique: bool) -> Result<Statement, ParseError> {
+        let name = self.ident()?;
+        self.expect_keyword("ON")?;
+        let table_name = self.object_name()?;
+        let columns = self.ident_list()?;
+        Ok(Statement::CreateIndex(CreateIndex {
+            name,
+            table_name,
+            columns,
+            unique,
+        }))
+    }
+
+    fn column_def(&mut self) -> Result<ColumnDef, ParseError> {
+        let name = self.ident()?;
+        let data_type = self.data_type()?;
+        let mut column = ColumnDef::new(name, data_type);
+
+        loop {
+            if self.consume_keyword("PRIMARY") {
+                self.expect_keyword("KEY")?;
+                column.primary_key = true;
+                column.not_null = true;
+            } else if self.consume_keyword("NOT") {
+                self.expect_keyword("NULL")?;
+                column.not_null = true;
+            } else if self.consume_keyword("NULL") {
+                column.not_null = false;
+            } else if self.consume_keyword("UNIQUE") {
+                column.unique = true;
+            } else if self.consume_keyword("DEFAULT") {
+                column.default = Some(self.unary()?);
+            } else if self.consume_keyword("CHECK") {
+                self.expect(&TokenKind::LParen)?;
+                column.check = Some(self.expr()?);
+                self.expect(&TokenKind::RParen)?;
+            } else if self.consume_keyword("REFERENCES") {
+                let table_name = self.object_name()?;
+                let referred = self.ident_list()?;
+                if referred.len() != 1 {
+                    return Err(self.unexpected("a single referenced column"));
+                }
+                column.references = Some(ForeignKey {
+                    table_name,
+                    column: referred[0].clone(),
+                });
+            } else if self.consume_keyword("AUTO_INCREMENT")
+                || self.consume_keyword("AUTOINCREMENT")
+            {
+                column.auto_increment = true;
+            } else {
+                return Ok(column);
+            }
+        }
+    }
+
+    fn table_constraint(&mut self) -> Result<TableConstraint, ParseError> {
+        let name = if self.consume_keyword("CONSTRAINT") {
+            Some(self.ident()?)
+        } else {
+            None
+        };
+
+        let kind = if self.consume_keyword("PRIMARY") {
+            self.expect_keyword("KEY")?;
+            TableConstraintKind::PrimaryKey(self.ident_list()?)
+        } else if self.consume_keyword("UNIQUE") {
+            TableConstraintKind::Unique(self.ident_list()?)
+        } else if self.consume_keyword("FOREIGN") {
+            self.expect_keyword("KEY")?;
+            let columns = self.ident_list()?;
+            self.expect_keyword("REFERENCES")?;
+            let table_name = self.object_name()?;
+            let referred_columns = self.ident_list()?;
+            if referred_columns.len() != columns.len() {
+                return Err(self.unexpected(&format!(
+                    "{} referenced column(s) after REFERENCES {}",
+                    columns.len(),
+                    table_name
+                )));
+            }
+            TableConstraintKind::ForeignKey {
+                columns,
+                table_name,
+                referred_columns,
+            }
+        } else {
+            return Err(self.unexpected("PRIMARY KEY, UNIQUE or FOREIGN KEY"));
+        };
+
+        Ok(TableConstraint { name, kind })
+    }
+
+    fn data_type(&mut self) -> Result<DataType, ParseError> {
+        let token = self.peek().clone();
+        let data_type = match &token.kind {
+            TokenKind::Ident(word) => match word.parse::<DataType>() {
+                Ok(data_type) => data_type,
+                Err(message) => return Err(self.error_at(&token, message)),
+            },
+            _ => return Err(self.unexpected("column type")),
+        };
+        self.advance();
+
+        // Accept and ignore a length or precision such as VARCHAR(255) or DECIMAL(10, 2)
+        if self.peek_kind() == &TokenKind::LParen {
+            self.advance();
+            loop {
+                match self.advance().kind {
+                    TokenKind::Int(_) | TokenKind::Comma => {}
+                    TokenKind::RParen => break,
+                    _ => return Err(self.error_at(&token, "Invalid type arguments")),
+                }
+            }
+        }
+        Ok(data_type)
+    }
+
+    fn drop(&mut self) -> Result<Statement, ParseError> {
+        if self.consume_keyword("USER") {
+            let name = self.user_name()?;
+            return Ok(Statement::DropUser { name });
+        }
+        if self.consume_keyword("ROLE") {
+            let name = self.user_name()?;
+            return Ok(Statement::DropRole { name });
+        }
+        if self.consume_keyword("POLICY") {
+            let name = self.ident()?;
+            self.expect_keyword("ON")?;
+            let table_name = self.object_name()?;
+            return Ok(Statement::DropPolicy { name, table_name });
+        }
+        self.expect_keyword("TABLE")?;
+        let name = self.object_name()?;
+        Ok(Statement::DropTable { name })
+    }
+
+    /// Parses a user name, which is an identifier or a string literal.
+    fn user_name(&mut self) -> Result<String, ParseError> {
+        if let TokenKind::Str(name) = self.peek_kind().clone() {
+            self.advance();
+            return Ok(name);
+        }
+        self.ident()
+    }
+
+    /// Parses the rest of a `GRANT` or `REVOKE`, which is about privileges if it starts with
+    /// `ALL` or a privilege, and about a role otherwise.
+    fn grant(&mut self, revoke: bool) -> Result<Statement, ParseError> {
+        let preposition = if revoke { "FROM" } else { "TO" };
+        let on_privileges = self.peek_keyword("ALL")
+            || Privilege::ALL
+                .iter()
+                .any(|privilege| self.peek_keyword(privilege.as_str()));
+        if !on_privileges {
+            let role = self.user_name()?;
+            self.expect_keyword(preposition)?;
+            let user = self.user_name()?;
+            return Ok(match revoke {
+                true => Statement::RevokeRole { role, user },
+                false => Statement::GrantRole { role, user },
+            });
+        }
+
+        let privileges = if self.consume_keyword("ALL") {
+            self.consume_keyword("PRIVILEGES");
+            None
+        } else {
+            let mut privileges = vec![self.privilege_spec()?];
+            while self.consume(&TokenKind::Comma) {
+                privileges.push(self.privilege_spec()?);
+            }
+            Some(privileges)
+        };
+        self.expect_keyword("ON")?;
+        let table = match self.consume(&TokenKind::Star) {
+            true => None,
+            false => {
+                self.consume_keyword("TABLE");
+                Some(self.object_name()?)
+            }
+        };
+        self.expect_keyword(preposition)?;
+        let grantee = self.user_name()?;
+
+        // `ALL` stands for the privileges that can be granted on the target
+        let privileges = privileges.unwrap_or_else(|| {
+            Privilege::ALL
+                .into_iter()
+                .filter(|privilege| table.is_none() || privilege.on_table())
+                .map(|privilege| PrivilegeSpec {
+                    privilege,
+                    columns: None,
+                })
+                .collect()
+        });
+        let privileges = Privileges {
+            privileges,
+            table,
+            grantee,
+        };
+        Ok(match revoke {
+            true => Statement::Revoke(privileges),
+            false => Statement::Grant(privileges),
+        })
+    }
+
+    /// Parses a privilege with an optional list of columns, e.g. `SELECT (id, name)`.
+    fn privilege_spec(&mut self) -> Result<PrivilegeSpec, ParseError> {
+        let token = self.advance();
+        let privilege = match &token.kind {
+            TokenKind::Ident(word) => Privilege::from_name(word),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            self.error_at(
+                &token,
+                format!("Expected a privilege, found {}", describe(&token.kind)),
+            )
+        })?;
+        let columns = match self.peek_kind() {
+            TokenKind::LParen => Some(self.ident_list()?),
+            _ => None,
+        };
+        Ok(PrivilegeSpec { privilege, columns })
+    }
+
+    /// Parses the rest of `CREATE POLICY`, e.g.
+    /// `tenant_isolation ON orders TO service USING (tenant = current_setting('app.tenant'))`.
+    fn create_policy(&mut self) -> Result<Statement, ParseError> {
+        let name = self.ident()?;
+        self.expect_keyword("ON")?;
+        let table_name = self.object_name()?;
+        let mut roles = Vec::new();
+        if self.consume_keyword("TO") {
+            roles.push(self.user_name()?);
+            while self.consume(&TokenKind::Comma) {
+                roles.push(self.user_name()?);
+            }
+        }
+        let using = match self.consume_keyword("USING") {
+            true => Some(self.parenthesised_expr()?),
+            false => None,
+        };
+        let check = match self.consume_keyword("WITH") {
+            true => {
+                self.expect_keyword("CHECK")?;
+                Some(self.parenthesised_expr()?)
+            }
+            false => None,
+        };
+        if using.is_none() && check.is_none() {
+            return Err(self.unexpected("USING or WITH CHECK"));
+        }
+        let policy = Policy {
+            name,
+            roles,
+            using,
+            check,
+        };
+        Ok(Statement::CreatePolicy { table_name, policy })
+    }
+
+    fn parenthesised_expr(&mut self) -> Result<Expr, ParseError> {
+        self.expect(&TokenKind::LParen)?;
+        let expr = self.expr()?;
+        self.expect(&TokenKind::RParen)?;
+        Ok(expr)
+    }
+
+    /// Parses the rest of `SET name = value`. The value is a literal or a bare word.
+    fn set(&mut self) -> Result<Statement, ParseError> {
+        let name = self.setting_name()?;
+        if !self.consume(&TokenKind::Eq) {
+            self.expect_keyword("TO")?;
+        }
+        let token = self.peek().clone();
+        let value = match self.unary()? {
+            Expr::Literal(Value::Null) => None,
+            Expr::Literal(value) => value.as_string(),
+            Expr::Column(word) => Some(word),
+            _ => None,
+        };
+        let value = value.ok_or_else(|| {
+            self.error_at(
+                &token,
+                format!("Expected a setting value, found {}", describe(&token.kind)),
+            )
+        })?;
+        Ok(Statement::Set { name, value })
+    }
+
+    /// Parses the name of a setting, such as `app.tenant`, in lowercase.
+    fn setting_name(&mut self) -> Result<String, ParseError> {
+        Ok(self.object_name()?.to_lowercase())
+    }
+
+    /// Parses `IDENTIFIED BY 'password'`.
+    fn identified_by(&mut self) -> Result<String, ParseError> {
+        self.expect_keyword("IDENTIFIED")?;
+        self.expect_keyword("BY")?;
+        match self.peek_kind().clone() {
+            TokenKind::Str(password) => {
+                self.advance();
+                Ok(password)
+            }
+            _ => Err(self.unexpected("password string")),
+        }
+    }
+
+    fn rename(&mut self) -> Result<Statement, ParseError> {
+        self.expect_keyword("TABLE")?;
+        let current_name = self.object_name()?;
+        self.expect_keyword("TO")?;
+        let new_name = self.object_name()?;
+        Ok(Statement::RenameTable {
+            current_name,
+            new_name,
+        })
+    }
+
+    fn insert(&mut self) -> Result<Statement, ParseError> {
+        self.expect_keyword("INTO")?;
+        let table_name = self.object_name()?;
+        let columns = if self.peek_kind() == &TokenKind::LParen {
+            self.ident_list()?
+        } else {
+            Vec::new()
+        };
+        self.expect_keyword("VALUES")?;
+
+        let mut rows = vec![self.value_tuple()?];
+        while self.consume(&TokenKind::Comma) {
+            rows.push(self.value_tuple()?);
+        }
+        Ok(Statement::Insert(Insert {
+            table_name,
+            columns,
+            rows,
+        }))
+    }
+
+    fn value_tuple(&mut self) -> Result<Vec<Expr>, ParseError> {
+        self.expect(&TokenKind::LParen)?;
+        let mut values = vec![self.expr()?];
+        while self.consume(&TokenKind::Comma) {
+            values.push(self.expr()?);
+        }
+        self.expect(&TokenKind::RParen)?;
+        Ok(values)
+    }
+
+    fn select(&mut self) -> Result<Select, ParseError> {
+        let mut projection = vec![self.select_item()?];
+        while self.consume(&TokenKind::Comma) {
+            projection.push(self.select_item()?);
+        }
+        self.expect_keyword("FROM")?;
+        let table_name = self.object_name()?;
+        let join = if self.consume_keyword("JOIN") {
+            let table_name = self.object_name()?;
+            self.expect_keyword("ON")?;
+            Some(Join {
+                table_name,
+                on: self.expr()?,
+            })
+        } else {
+            None
+        };
+        let selection = self.where_clause()?;
+        Ok(Select {
+            projection,
+            table_name,
+            join,
+            selection,
+        })
+    }
+
+    fn select_item(&mut self) -> Result<SelectItem, ParseError> {
+        if self.consume(&TokenKind::Star) {
+            return Ok(SelectItem::Wildcard);
+        }
+        let expr = self.expr()?;
+        let alias = if self.consume_keyword("AS") {
+            Some(self.ident()?)
+        } else {
+            None
+        };
+        Ok(SelectItem::Expr { expr, alias })
+    }
+
+    fn update(&mut self) -> Result<Statement, ParseError> {
+        let table_name = self.object_name()?;
+        self.expect_keyword("SET")?;
+        let mut assignments = vec![self.assignment()?];
+        while self.consume(&TokenKind::Comma) {
+            assignments.push(self.assignment()?);
+        }
+        let selection = self.where_clause()?;
+        Ok(Statement::Update(Update {
+            table_name,
+            assignments,
+            selection,
+        }))
+    }
+
+    fn delete(&mut self) -> Result<Statement, ParseError> {
+        self.expect_keyword("FROM")?;
+        let table_name = self.object_name()?;
+        let selection = self.where_clause()?;
+        Ok(Statement::Delete(Delete {
+            table_name,
+            selection,
+        }))
+    }
+
+    fn assignment(&mut self) -> Result<Assignment, ParseError> {
+        let column = self.ident()?;
+        self.expect(&TokenKind::Eq)?;
+        let value = self.expr()?;
+        Ok(Assignment { column, value })
+    }
+
+    fn where_clause(&mut self) -> Result<Option<Expr>, ParseError> {
+        if self.consume_keyword("WHERE") {
+            Ok(Some(self.expr()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        self.nested(Parser::or_expr)
+    }
+
+    fn or_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.and_expr()?;
+        while self.consume_keyword("OR") {
+            let right = self.and_expr()?;
+            left = binary(left, BinaryOp::Or, right);
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.not_expr()?;
+        while self.consume_keyword("AND") {
+            let right = self.not_expr()?;
+            left = binary(left, BinaryOp::And, right);
+        }
+        Ok(left)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr, ParseError> {
+        if self.consume_keyword("NOT") {
+            let expr = self.nested(Parser::not_expr)?;
+            return Ok(Expr::Unary {
+                op: UnaryOp::Not,
+                expr: Box::new(expr),
+            });
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let left = self.additive()?;
+
+        if self.consume_keyword("IS") {
+            let negated = self.consume_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expr::IsNull {
+                expr: Box::new(left),
+                negated,
+            });
+        }
+
+        let op = match self.peek_kind() {
+            TokenKind::Eq => BinaryOp::Eq,
+            TokenKind::NotEq => BinaryOp::NotEq,
+            TokenKind::Lt => BinaryOp::Lt,
+            TokenKind::LtEq => BinaryOp::LtEq,
+            TokenKind::Gt => BinaryOp::Gt,
+            TokenKind::GtEq => BinaryOp::GtEq,
+            _ => return Ok(left),
+        };
+        self.advance();
+        let right = self.additive()?;
+        Ok(binary(left, op, right))
+    }
+
+    fn additive(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.multiplicative()?;
+        loop {
+            let op = match self.peek_kind() {
+                TokenKind::Plus => BinaryOp::Plus,
+                TokenKind::Minus => BinaryOp::Minus,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.multiplicative()?;
+            left = binary(left, op, right);
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek_kind() {
+                TokenKind::Star => BinaryOp::Multiply,
+                TokenKind::Slash => BinaryOp::Divide,
+                TokenKind::Percent => BinaryOp::Modulo,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.unary()?;
+            left = binary(left, op, right);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        let op = match self.peek_kind() {
+            TokenKind::Minus => UnaryOp::Minus,
+            TokenKind::Plus => UnaryOp::Plus,
+            _ => return self.primary(),
+        };
+        self.advance();
+
+        // Fold signs directly into numeric literals so `-5` is a plain constant
+        match (op, self.peek_kind().clone()) {
+            (UnaryOp::Minus, TokenKind::Int(i)) if i != i64::MIN => {
+                self.advance();
+                Ok(Expr::Literal(Value::Int(-i)))
+            }
+            (UnaryOp::Minus, TokenKind::Float(f)) => {
+                self.advance();
+                Ok(Expr::Literal(Value::Float(-f)))
+            }
+            _ => {
+                let expr = self.nested(Parser::unary)?;
+                Ok(Expr::Unary {
+                    op,
+                    expr: Box::new(expr),
+                })
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let token = self.peek().clone();
+        match token.kind {
+            TokenKind::Int(i) => {
+                self.advance();
+                Ok(Expr::Literal(Value::Int(i)))
+            }
+            TokenKind::Float(f) => {
+                self.advance();
+                Ok(Expr::Literal(Value::Float(f)))
+            }
+            TokenKind::Str(s) => {
+                self.advance();
+                Ok(Expr::Literal(Value::Str(s)))
+            }
+            TokenKind::LParen => {
+                self.advance();
+                let expr = self.expr()?;
+                self.expect(&TokenKind::RParen)?;
+                Ok(expr)
+            }
+            TokenKind::Ident(ref word) if word.eq_ignore_ascii_case("NULL") => {
+                self.advance();
+                Ok(Expr::Literal(Value::Null))
+            }
+            TokenKind::Ident(ref word) if word.eq_ignore_ascii_case("TRUE") => {
+                self.advance();
+                Ok(Expr::Literal(Value::Bool(true)))
+            }
+            TokenKind::Ident(ref word) if word.eq_ignore_ascii_case("FALSE") => {
+                self.advance();
+                Ok(Expr::Literal(Value::Bool(false)))
+            }
+            // `current_user` is a function called without parentheses, as in standard SQL
+            TokenKind::Ident(ref word)
+                if word.eq_ignore_ascii_case("CURRENT_USER")
+                    && self.peek_kind_at(1) != &TokenKind::LParen =>
+            {
+                self.advance();
+                Ok(Expr::Function {
+                    name: "current_user".to_string(),
+                    args: Vec::new(),
+                })
+            }
+            TokenKind::Ident(_) | TokenKind::QuotedIdent(_) => {
+                if matches!(token.kind, TokenKind::Ident(_))
+                    && self.peek_kind_at(1) == &TokenKind::LParen
+                {
+                    return self.function_call();
+                }
+                Ok(Expr::Column(self.object_name()?))
+            }
+            _ => Err(self.unexpected("an expression")),
+        }
+    }
+
+    fn function_call(&mut self) -> Result<Expr, ParseError> {
+        let name = match self.advance().kind {
+            TokenKind::Ident(name) => name.to_lowercase(),
+            _ => unreachable!("function_call is only called on identifiers"),
+        };
+        self.expect(&TokenKind::LParen)?;
+        let mut args = Vec::new();
+        if !self.consume(&TokenKind::RParen) {
+            args.push(self.expr()?);
+            while self.consume(&TokenKind::Comma) {
+                args.push(self.expr()?);
+            }
+            self.expect(&TokenKind::RParen)?;
+        }
+        Ok(Expr::Function { name, args })
+    }
+}
+
+fn binary(left: Expr, op: BinaryOp, right: Expr) -> Expr {
+    Expr::Binary {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    }
+}
+
+/// Returns true if `word` is a reserved keyword (case-insensitive).
+pub fn is_reserved(word: &str) -> bool {
+    RESERVED_KEYWORDS
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+/// Describes a token for use in error messages.
+fn describe(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::Ident(word) => format!("'{}'", word),
+        TokenKind::QuotedIdent(word) => format!("`{}`", word),
+        TokenKind::Str(s) => format!("string '{}'", s),
+        TokenKind::Int(i) => format!("number {}", i),
+        TokenKind::Float(f) => format!("number {}", f),
+        TokenKind::Comma => "','".to_string(),
+        TokenKind::Dot => "'.'".to_string(),
+        TokenKind::Semicolon => "';'".to_string(),
+        TokenKind::LParen => "'('".to_string(),
+        TokenKind::RParen => "')'".to_string(),
+        TokenKind::Star => "'*'".to_string(),
+        TokenKind::Plus => "'+'".to_string(),
+        TokenKind::Minus => "'-'".to_string(),
+        TokenKind::Slash => "'/'".to_string(),
+        TokenKind::Percent => "'%'".to_string(),
+        TokenKind::Eq => "'='".to_string(),
+        TokenKind::NotEq => "'<>'".to_string(),
+        TokenKind::Lt => "'<'".to_string(),
+        TokenKind::LtEq => "'<='".to_string(),
+        TokenKind::Gt => "'>'".to_string(),
+        TokenKind::GtEq => "'>='".to_string(),
+        TokenKind::Eof => "end of input".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::ast::{SelectItem, Statement, UnaryOp};
+
+    fn select(input: &str) -> Select {
+        match parse_statement(input).unwrap() {
+            Statement::Select(select) => select,
+            other => panic!("Expected a SELECT, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reserved_keywords_need_quotes_to_be_identifiers() {
+        let error = parse_statement("CREATE TABLE select (id INT)").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 14));
+        assert!(error.message.contains("identifier"), "{}", error.message);
+        assert!(parse_statement("SELECT from FROM users").is_err());
+        assert!(parse_statement("SELECT * FROM users WHERE table = 1").is_err());
+
+        let quoted = select("SELECT `from` FROM `select` WHERE `table` = 1");
+        assert_eq!(quoted.table_name, "select");
+        assert_eq!(
+            quoted.projection,
+            [SelectItem::Expr {
+                expr: Expr::Column("from".to_string()),
+                alias: None
+            }]
+        );
+        assert_eq!(quoted.selection.unwrap().to_string(), "`table` = 1");
+    }
+
+    #[test]
+    fn keywords_that_are_not_reserved_are_identifiers() {
+        let select = select("SELECT index, key, user FROM tables WHERE analyze = 1");
+        assert_eq!(select.table_name, "tables");
+        assert_eq!(select.projection.len(), 3);
+        assert_eq!(
+            parse_expression("Name").unwrap(),
+            Expr::Column("Name".into())
+        );
+    }
+
+    #[test]
+    fn integer_literals_cover_the_range_of_i64() {
+        assert_eq!(
+            parse_expression("-9223372036854775808").unwrap(),
+            Expr::Literal(Value::Int(i64::MIN))
+        );
+        assert_eq!(
+            parse_expression("-9223372036854775807").unwrap(),
+            Expr::Literal(Value::Int(-i64::MAX))
+        );
+        assert_eq!(
+            parse_expression("9223372036854775807").unwrap(),
+            Expr::Literal(Value::Int(i64::MAX))
+        );
+        // Negating the smallest integer again overflows when it is evaluated
+        assert_eq!(
+            parse_expression("- -9223372036854775808").unwrap(),
+            Expr::Unary {
+                op: UnaryOp::Minus,
+                expr: Box::new(Expr::Literal(Value::Int(i64::MIN)))
+            }
+        );
+        let error = parse_expression("9223372036854775808").unwrap_err();
+        assert_eq!(
+            error.message,
+            "Integer '9223372036854775808' is out of range"
+        );
+    }
+
+    #[test]
+    fn scripts_hold_several_statements() {
+        let statements = parse_statements(
+            ";; SELECT a FROM t; -- first\n\
+             INSERT INTO t (a) VALUES (1), (2);\n\
+             /* last, without a semicolon */ SELECT 'x;y' FROM u",
+        )
+        .unwrap();
+        assert_eq!(statements.len(), 3);
+        assert!(matches!(&statements[1], Statement::Insert(insert) if insert.rows.len() == 2));
+        assert!(parse_statements("").unwrap().is_empty());
+        assert!(parse_statements(" ; -- nothing").unwrap().is_empty());
+
+        let error = parse_statements("SELECT a FROM t SELECT b FROM u").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 17));
+        let error = parse_statements("SELECT a FROM t;\nSELECT FROM u;").unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn deletes_have_an_optional_condition() {
+        assert_eq!(
+            parse_statement("DELETE FROM users WHERE id = 7").unwrap(),
+            Statement::Delete(Delete {
+                table_name: "users".to_string(),
+                selection: Some(parse_expression("id = 7").unwrap()),
+            })
+        );
+        assert_eq!(
+            parse_statement("delete from `delete`;").unwrap(),
+            Statement::Delete(Delete {
+                table_name: "delete".to_string(),
+                selection: None,
+            })
+        );
+        assert!(parse_statement("DELETE users WHERE id = 7").is_err());
+        assert!(parse_statement("DELETE FROM users WHERE").is_err());
+    }
+
+    #[test]
+    fn selects_may_join_a_second_table() {
+        let joined = select(
+            "SELECT users.name, orders.total FROM users JOIN orders \
+             ON users.id = orders.user_id WHERE users.city = 'Oslo'",
+        );
+        assert_eq!(joined.table_name, "users");
+        assert_eq!(
+            joined.join,
+            Some(Join {
+                table_name: "orders".to_string(),
+                on: parse_expression("users.id = orders.user_id").unwrap(),
+            })
+        );
+        assert_eq!(joined.selection.unwrap().to_string(), "users.city = 'Oslo'");
+        assert_eq!(select("SELECT * FROM users").join, None);
+        assert!(parse_statement("SELECT * FROM users JOIN orders").is_err());
+        assert!(parse_statement("SELECT * FROM users JOIN ON id = user_id").is_err());
+    }
+
+    #[test]
+    fn expressions_nested_too_deeply_are_rejected() {
+        // The outermost expression is the first level
+        let levels = MAX_EXPRESSION_DEPTH - 1;
+        let nested = format!("{}1{}", "(".repeat(levels), ")".repeat(levels));
+        assert_eq!(parse_expression(&nested), Ok(Expr::Literal(Value::Int(1))));
+        assert!(parse_expression(&format!("{}TRUE", "NOT ".repeat(levels))).is_ok());
+        assert!(parse_expression(&format!("({})", nested)).is_err());
+
+        // Without the limit these would overflow the stack
+        for input in [
+            format!("{}1", "(".repeat(100_000)),
+            format!("{}TRUE", "NOT ".repeat(100_000)),
+            format!("{}x", "- ".repeat(100_000)),
+            format!("{}1", "abs(".repeat(100_000)),
+        ] {
+            let error = parse_expression(&input).unwrap_err();
+            assert!(error.message.contains("nested"), "{}", error.message);
+        }
+        // The error points at the first parenthesis past the limit
+        let script = format!(
+            "SELECT a FROM t;\nDELETE FROM t WHERE {}1",
+            "(".repeat(100_000)
+        );
+        let error = parse_statements(&script).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 21 + MAX_EXPRESSION_DEPTH));
+    }
+
+    #[test]
+    fn a_single_statement_allows_one_trailing_semicolon() {
+        assert!(parse_statement("SELECT a FROM t;").is_ok());
+        assert!(parse_statement("SELECT a FROM t;;").is_err());
+        assert!(parse_statement("SELECT a FROM t; SELECT b FROM u").is_err());
+        assert!(parse_statement("").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Execution statistics of statements, like PostgreSQL's `pg_stat_statements`.
+//!
+//! The server counts the requests of every user under their normalised statement, see
+//! [`normalize`](crate::sql::lexer::normalize), so that statements that only differ in their
+//! constants are counted together. The statistics can be selected like a table from the
+//! read-only view `system.statement_stats`, see [`catalog`](crate::catalog).
+use crate::row::Row;
+use crate::value::Value;
+use std::time::Duration;
+
+/// The statistics of a statement executed by a user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatementStats {
+    /// The user who executed the statement.
+    pub principal: String,
+    /// The normalised statement, or the method and route of requests that are not
+    /// statements, e.g. `POST /create_table`.
+    pub statement: String,
+    /// The number of executions.
+    pub calls: u64,
+    /// The time spent executing the statement, in milliseconds.
+    pub total_time_ms: f64,
+    /// The time of the fastest execution, in milliseconds.
+    pub min_time_ms: f64,
+    /// The time of the slowest execution, in milliseconds.
+    pub max_time_ms: f64,
+    /// The number of rows read from tables.
+    pub rows_scanned: u64,
+    /// The number of rows returned, or changed by statements that change rows.
+    pub rows_returned: u64,
+}
+
+impl StatementStats {
+    /// Creates statistics without executions.
+    ///
+    /// # Arguments
+    ///
+    /// * `principal` - The user who executes the statement.
+    /// * `statement` - The normalised statement.
+    pub fn new(principal: &str, statement: &str) -> StatementStats {
+        StatementStats {
+            principal: principal.to_string(),
+            statement: statement.to_string(),
+            calls: 0,
+            total_time_ms: 0.0,
+            min_time_ms: 0.0,
+            max_time_ms: 0.0,
+            rows_scanned: 0,
+            rows_returned: 0,
+        }
+    }
+
+    /// Adds an execution.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - The time the execution took.
+    /// * `rows_scanned` - The number of rows it read from tables.
+    /// * `rows_returned` - The number of rows it returned or changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::statistics::StatementStats;
+    /// use std::time::Duration;
+    ///
+    /// let mut stats = StatementStats::new("alice", "SELECT * FROM users WHERE id = ?");
+    /// stats.record(Duration::from_millis(4), 100, 1);
+    /// stats.record(Duration::from_millis(2), 100, 0);
+    /// assert_eq!(stats.calls, 2);
+    /// assert_eq!(stats.mean_time_ms(), 3.0);
+    /// assert_eq!((stats.min_time_ms, stats.max_time_ms), (2.0, 4.0));
+    /// assert_eq!((stats.rows_scanned, stats.rows_returned), (200, 1));
+    /// ```
+    pub fn record(&mut self, duration: Duration, rows_scanned: u64, rows_returned: u64) {
+        let time_ms = duration.as_secs_f64() * 1000.0;
+        if self.calls == 0 || time_ms < self.min_time_ms {
+            self.min_time_ms = time_ms;
+        }
+        self.max_time_ms = self.max_time_ms.max(time_ms);
+        self.calls += 1;
+        self.total_time_ms += time_ms;
+        self.rows_scanned += rows_scanned;
+        self.rows_returned += rows_returned;
+    }
+
+    /// Returns the mean time of an execution in milliseconds, or 0 without executions.
+    pub fn mean_time_ms(&self) -> f64 {
+        match self.calls {
+            0 => 0.0,
+            calls => self.total_time_ms / calls as f64,
+        }
+    }
+
+    /// Returns the statistics as a row of `system.statement_stats`.
+    pub fn to_row(&self) -> Row {
+        Row::new(vec![
+            Value::from(self.principal.as_str()),
+            Value::from(self.statement.as_str()),
+            Value::Int(self.calls as i64),
+            Value::Float(self.total_time_ms),
+            Value::Float(self.mean_time_ms()),
+            Value::Float(self.min_time_ms),
+            Value::Float(self.max_time_ms),
+            Value::Int(self.rows_scanned as i64),
+            Value::Int(self.rows_returned as i64),
+        ])
+    }
+}
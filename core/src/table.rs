@@ -1,6 +1,10 @@
 use crate::column::Column;
+use crate::policy::Policy;
 use crate::row::Row;
+use crate::sql::eval::{self, Scope};
+use crate::value::Value;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 /// Represents a database table.
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -8,6 +12,38 @@ pub struct Table {
     pub name: String,
     pub columns: Vec<Column>,
     pub rows: Vec<Row>,
+    /// Ordered names of the columns forming the primary key. Empty if the table has none.
+    #[serde(default)]
+    pub primary_key: Vec<String>,
+    #[serde(default)]
+    pub unique_constraints: Vec<UniqueConstraint>,
+    /// The row-level security policies of the table, see [`policy`](crate::policy).
+    #[serde(default)]
+    pub policies: Vec<Policy>,
+}
+
+/// The definition of a table without its rows.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TableSchema {
+    pub name: String,
+    pub columns: Vec<Column>,
+    #[serde(default)]
+    pub primary_key: Vec<String>,
+    #[serde(default)]
+    pub unique_constraints: Vec<UniqueConstraint>,
+    #[serde(default)]
+    pub policies: Vec<Policy>,
+    /// The number of rows in the table.
+    pub row_count: usize,
+}
+
+/// A named constraint requiring the combination of values in `columns` to be unique.
+///
+/// Rows with a NULL in any of the columns are not considered duplicates.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UniqueConstraint {
+    pub name: String,
+    pub columns: Vec<String>,
 }
 
 impl Table {
@@ -21,15 +57,43 @@ impl Table {
             name,
             columns: Vec::new(),
             rows: Vec::new(),
+            primary_key: Vec::new(),
+            unique_constraints: Vec::new(),
+            policies: Vec::new(),
+        }
+    }
+
+    /// Returns the definition of the table, without copying its rows.
+    pub fn schema(&self) -> TableSchema {
+        TableSchema {
+            name: self.name.clone(),
+            columns: self.columns.clone(),
+            primary_key: self.primary_key.clone(),
+            unique_constraints: self.unique_constraints.clone(),
+            policies: self.policies.clone(),
+            row_count: self.rows.len(),
         }
     }
 
     /// Adds a column to the table.
     ///
+    /// A column flagged as `primary_key` is appended to the table's primary key, and a
+    /// column flagged as `unique` gets a single-column unique constraint named `<table>_<column>_key`.
+    ///
     /// # Arguments
     ///
     /// * `column` - The column to add.
-    pub fn add_column(&mut self, column: Column) {
+    pub fn add_column(&mut self, mut column: Column) {
+        if column.primary_key {
+            column.non_null = true;
+            self.primary_key.push(column.key.clone());
+        }
+        if column.unique {
+            self.unique_constraints.push(UniqueConstraint {
+                name: format!("{}_{}_key", self.name, column.key),
+                columns: vec![column.key.clone()],
+            });
+        }
         self.columns.push(column);
     }
 
@@ -41,4 +105,320 @@ impl Table {
     pub fn add_row(&mut self, row: Row) {
         self.rows.push(row);
     }
+
+    /// Sets the primary key of the table.
+    ///
+    /// The columns are marked as primary key and non-null.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - The ordered names of the key columns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the table already has a primary key or a column does not exist.
+    pub fn set_primary_key(&mut self, columns: Vec<String>) -> Result<(), String> {
+        if !self.primary_key.is_empty() {
+            return Err(format!("Table '{}' already has a primary key", self.name));
+        }
+        self.column_indices(&columns)?;
+        for column in &mut self.columns {
+            if columns.contains(&column.key) {
+                column.primary_key = true;
+                column.non_null = true;
+            }
+        }
+        self.primary_key = columns;
+        Ok(())
+    }
+
+    /// Adds a unique constraint to the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `constraint` - The constraint to add.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a constraint with the same name exists or a column does not exist.
+    pub fn add_unique_constraint(&mut self, constraint: UniqueConstraint) -> Result<(), String> {
+        if self
+            .unique_constraints
+            .iter()
+            .any(|existing| existing.name == constraint.name)
+        {
+            return Err(format!(
+                "Constraint '{}' already exists on table '{}'",
+                constraint.name, self.name
+            ));
+        }
+        self.column_indices(&constraint.columns)?;
+        self.unique_constraints.push(constraint);
+        Ok(())
+    }
+
+    /// Adds a row-level security policy to the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy to add.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a policy with the same name exists or the policy is invalid, see
+    /// [`Policy::validate`].
+    pub fn add_policy(&mut self, policy: Policy) -> Result<(), String> {
+        if self
+            .policies
+            .iter()
+            .any(|existing| existing.name == policy.name)
+        {
+            return Err(format!(
+                "Policy '{}' already exists on table '{}'",
+                policy.name, self.name
+            ));
+        }
+        policy.validate(&self.columns)?;
+        self.policies.push(policy);
+        Ok(())
+    }
+
+    /// Checks that `row` can be added without violating the primary key, a unique constraint or
+    /// the `CHECK` expression of a column.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The row to check, with one value per column.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first violated constraint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::column::Column;
+    /// use core::row::Row;
+    /// use core::table::Table;
+    /// use core::value::Value;
+    ///
+    /// let mut table = Table::new("memberships".to_string());
+    /// table.add_column(Column::new("user_id".to_string(), false, false, false, None));
+    /// table.add_column(Column::new("group_id".to_string(), false, false, false, None));
+    /// table.set_primary_key(vec!["user_id".to_string(), "group_id".to_string()]).unwrap();
+    /// table.add_row(Row::new(vec![Value::Int(1), Value::Int(1)]));
+    ///
+    /// assert!(table.check_row(&Row::new(vec![Value::Int(1), Value::Int(2)])).is_ok());
+    /// assert!(table.check_row(&Row::new(vec![Value::Int(1), Value::Int(1)])).is_err());
+    /// ```
+    pub fn check_row(&self, row: &Row) -> Result<(), String> {
+        self.check_values(row)?;
+        for (name, columns) in self.key_constraints() {
+            let indices = self.column_indices(columns)?;
+            let Some(key) = key_of(row, &indices) else {
+                continue;
+            };
+            if self
+                .rows
+                .iter()
+                .any(|existing| key_of(existing, &indices).as_ref() == Some(&key))
+            {
+                return Err(duplicate_error(&name, columns, &key));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that no two rows of the table violate the primary key or a unique constraint, and
+    /// that every row satisfies the `CHECK` expressions of the columns.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first violated constraint.
+    pub fn check_constraints(&self) -> Result<(), String> {
+        for row in &self.rows {
+            self.check_values(row)?;
+        }
+        for (name, columns) in self.key_constraints() {
+            let indices = self.column_indices(columns)?;
+            let mut seen = HashSet::new();
+            for row in &self.rows {
+                let Some(key) = key_of(row, &indices) else {
+                    continue;
+                };
+                // Values are compared by their serialized form, as `Value` cannot be hashed
+                let serialized = serde_json::to_string(&key).map_err(|e| e.to_string())?;
+                if !seen.insert(serialized) {
+                    return Err(duplicate_error(&name, columns, &key));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `row` satisfies the `CHECK` expressions of the columns.
+    ///
+    /// Like in SQL, an expression is only violated if it is `FALSE`, so a `NULL` result
+    /// satisfies it.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The row to check, with one value per column.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an expression is `FALSE`, is not a boolean or cannot be evaluated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::column::Column;
+    /// use core::row::Row;
+    /// use core::sql::parse_expression;
+    /// use core::table::Table;
+    /// use core::value::Value;
+    ///
+    /// let mut table = Table::new("products".to_string());
+    /// let mut price = Column::new("price".to_string(), false, false, false, None);
+    /// price.check = Some(parse_expression("price >= 0").unwrap());
+    /// table.add_column(price);
+    ///
+    /// assert!(table.check_values(&Row::new(vec![Value::Int(5)])).is_ok());
+    /// assert!(table.check_values(&Row::new(vec![Value::Null])).is_ok());
+    /// assert!(table.check_values(&Row::new(vec![Value::Int(-1)])).is_err());
+    /// ```
+    pub fn check_values(&self, row: &Row) -> Result<(), String> {
+        let scope = RowColumns { table: self, row };
+        for column in &self.columns {
+            let Some(check) = &column.check else {
+                continue;
+            };
+            match eval::evaluate(check, &scope) {
+                Ok(Value::Bool(true) | Value::Null) => {}
+                Ok(Value::Bool(false)) => {
+                    return Err(format!(
+                        "Value of column '{}' violates its CHECK constraint ({})",
+                        column.key, check
+                    ))
+                }
+                Ok(value) => {
+                    return Err(format!(
+                        "CHECK constraint ({}) of column '{}' is {}, not a boolean",
+                        check,
+                        column.key,
+                        value.to_sql()
+                    ))
+                }
+                Err(error) => {
+                    return Err(format!(
+                        "CHECK constraint ({}) of column '{}' failed: {}",
+                        check, column.key, error
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the `CHECK` expressions of the columns only refer to columns of the table
+    /// and to known functions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first invalid expression.
+    pub fn validate_checks(&self) -> Result<(), String> {
+        // Operators and functions applied to NULL are NULL, so a row of NULLs evaluates every
+        // part of an expression and only fails on unknown names and invalid literals
+        let row = Row::new(vec![Value::Null; self.columns.len()]);
+        let scope = RowColumns {
+            table: self,
+            row: &row,
+        };
+        for column in &self.columns {
+            if let Some(check) = &column.check {
+                eval::evaluate(check, &scope).map_err(|error| {
+                    format!(
+                        "Invalid CHECK constraint ({}) of column '{}': {}",
+                        check, column.key, error
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the primary key and all unique constraints as `(name, columns)` pairs.
+    pub(crate) fn key_constraints(&self) -> Vec<(String, &Vec<String>)> {
+        let mut constraints = Vec::new();
+        if !self.primary_key.is_empty() {
+            constraints.push((format!("{}_pkey", self.name), &self.primary_key));
+        }
+        for constraint in &self.unique_constraints {
+            constraints.push((constraint.name.clone(), &constraint.columns));
+        }
+        constraints
+    }
+
+    /// Resolves column names to their positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - The column names.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `columns` is empty or a column does not exist.
+    pub fn column_indices(&self, columns: &[String]) -> Result<Vec<usize>, String> {
+        if columns.is_empty() {
+            return Err("A constraint needs at least one column".to_string());
+        }
+        columns
+            .iter()
+            .map(|name| {
+                self.columns
+                    .iter()
+                    .position(|col| col.key == *name)
+                    .ok_or_else(|| format!("Column '{}' not found", name))
+            })
+            .collect()
+    }
+}
+
+/// The values of a row, as seen by the `CHECK` expressions of its table.
+struct RowColumns<'a> {
+    table: &'a Table,
+    row: &'a Row,
+}
+
+impl Scope for RowColumns<'_> {
+    fn column(&self, name: &str) -> Result<Value, String> {
+        let index = self
+            .table
+            .columns
+            .iter()
+            .position(|column| column.key == name)
+            .ok_or_else(|| format!("Column '{}' not found", name))?;
+        Ok(self.row.values.get(index).cloned().unwrap_or(Value::Null))
+    }
+}
+
+/// Returns the values of `row` at `indices`, or `None` if any of them is NULL.
+fn key_of(row: &Row, indices: &[usize]) -> Option<Vec<Value>> {
+    indices
+        .iter()
+        .map(|&index| match row.values.get(index) {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(value.clone()),
+        })
+        .collect()
+}
+
+fn duplicate_error(name: &str, columns: &[String], key: &[Value]) -> String {
+    let values: Vec<String> = key.iter().map(Value::to_sql).collect();
+    format!(
+        "Duplicate key violates constraint '{}': ({}) = ({})",
+        name,
+        columns.join(", "),
+        values.join(", ")
+    )
 }
@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 
 /// Represents a value in a database table.
 ///
 /// This enum can represent a string, boolean, integer, or float value.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum Value {
     Str(String),
     Bool(bool),
@@ -84,4 +85,102 @@ impl Value {
             Value::Null => None,
         }
     }
+
+    /// Returns the value formatted as an SQL literal.
+    ///
+    /// Strings are single-quoted with embedded quotes doubled and backslashes escaped,
+    /// so the result can be parsed back.
+    pub fn to_sql(&self) -> String {
+        match self {
+            Value::Str(s) => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "''")),
+            Value::Bool(true) => "TRUE".to_string(),
+            Value::Bool(false) => "FALSE".to_string(),
+            Value::Int(i) => i.to_string(),
+            Value::Float(f) => format!("{:?}", f),
+            Value::Null => "NULL".to_string(),
+        }
+    }
+
+    /// Compares two values for sorting.
+    ///
+    /// NULL sorts first, followed by booleans, numbers and strings. Integers and floats are
+    /// compared by their numeric value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::value::Value;
+    /// use std::cmp::Ordering;
+    ///
+    /// assert_eq!(Value::Int(2).compare(&Value::Float(1.5)), Ordering::Greater);
+    /// assert_eq!(Value::Null.compare(&Value::from("a")), Ordering::Less);
+    /// ```
+    pub fn compare(&self, other: &Value) -> Ordering {
+        fn rank(value: &Value) -> u8 {
+            match value {
+                Value::Null => 0,
+                Value::Bool(_) => 1,
+                Value::Int(_) | Value::Float(_) => 2,
+                Value::Str(_) => 3,
+            }
+        }
+
+        match (self, other) {
+            (Value::Str(a), Value::Str(b)) => a.cmp(b),
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::Int(a), Value::Float(b)) => (*a as f64).total_cmp(b),
+            (Value::Float(a), Value::Int(b)) => a.total_cmp(&(*b as f64)),
+            (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+
+/// Represents the declared type of a column.
+///
+/// Each variant corresponds to one of the non-null `Value` variants.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataType {
+    Str,
+    Bool,
+    Int,
+    Float,
+}
+
+/// Formats the type as its SQL name, e.g. `INT`.
+impl std::fmt::Display for DataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DataType::Str => "STRING",
+            DataType::Bool => "BOOL",
+            DataType::Int => "INT",
+            DataType::Float => "FLOAT",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Parses an SQL type name such as `INT`, `VARCHAR` or `boolean`.
+///
+/// # Examples
+///
+/// ```
+/// use core::value::DataType;
+///
+/// assert_eq!("integer".parse::<DataType>(), Ok(DataType::Int));
+/// assert!("BLOB".parse::<DataType>().is_err());
+/// ```
+impl std::str::FromStr for DataType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "INT" | "INTEGER" | "BIGINT" | "SMALLINT" => Ok(DataType::Int),
+            "FLOAT" | "DOUBLE" | "REAL" | "DECIMAL" | "NUMERIC" => Ok(DataType::Float),
+            "STRING" | "TEXT" | "VARCHAR" | "CHAR" => Ok(DataType::Str),
+            "BOOL" | "BOOLEAN" => Ok(DataType::Bool),
+            _ => Err(format!("Unsupported column type '{}'", s)),
+        }
+    }
 }
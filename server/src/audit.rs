@@ -0,0 +1,279 @@
+//! The audit log of requests that may change the database.
+//!
+//! Every request that is not read-only is recorded once it is answered, with the user who sent
+//! it, the address it came from, its route, the table it targets, the number of rows it changed
+//! and its outcome. Handlers report the table and row count through the [`Audit`] extension of
+//! their request. Entries are appended to a file as lines of JSON. When the file would grow
+//! beyond its size limit, it is renamed to `<file>.1`, older files move up by one and the oldest
+//! is removed. Without a file, the latest entries are kept in memory.
+use crate::auth::Principal;
+use crate::config::{AuditConfig, FsyncPolicy, Persistence, StorageConfig};
+use axum::extract::{ConnectInfo, MatchedPath, Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::{SecondsFormat, Utc};
+use core::audit::{AuditEntry, Outcome};
+use log::{error, warn};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// The name of the audit log in the data directory, unless another file is configured.
+pub const AUDIT_FILE: &str = "audit.log";
+
+/// The number of entries kept when the audit log has no file.
+const MEMORY_ENTRIES: usize = 10_000;
+
+/// Routes that only read, which are not recorded even though they are `POST` requests.
+const READ_ONLY_ROUTES: [&str; 2] = ["/select", "/select/csv"];
+
+/// The target table and changed rows of a request, reported by its handler.
+#[derive(Clone, Default)]
+pub struct Audit(Arc<Mutex<Details>>);
+
+#[derive(Default)]
+struct Details {
+    table_name: Option<String>,
+    row_count: Option<u64>,
+}
+
+impl Audit {
+    /// Records the table the request targets.
+    pub fn table(&self, table_name: &str) {
+        self.details().table_name = Some(table_name.to_string());
+    }
+
+    /// Records the number of rows the request inserted, updated or imported.
+    pub fn rows(&self, row_count: usize) {
+        self.details().row_count = Some(row_count as u64);
+    }
+
+    fn details(&self) -> MutexGuard<'_, Details> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The file of the audit log, or the latest entries if it has none.
+pub struct AuditLog {
+    enabled: bool,
+    path: Option<PathBuf>,
+    max_file_bytes: u64,
+    max_files: usize,
+    fsync: FsyncPolicy,
+    sink: Mutex<Sink>,
+}
+
+#[derive(Default)]
+struct Sink {
+    file: Option<File>,
+    /// The size of the current file
+    size: u64,
+    memory: VecDeque<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Opens the audit log, creating its file if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The settings of the audit log.
+    /// * `storage` - The storage of the tables. The file defaults to [`AUDIT_FILE`] in the data
+    ///   directory, or to keeping entries in memory if the tables are kept in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened.
+    pub fn open(config: &AuditConfig, storage: &StorageConfig) -> Result<AuditLog, Error> {
+        let path = match (&config.path, storage.persistence) {
+            (Some(path), _) => Some(path.clone()),
+            (None, Persistence::Disk) => Some(storage.path(AUDIT_FILE)),
+            (None, Persistence::Memory) => None,
+        };
+        let mut sink = Sink::default();
+        if let (true, Some(path)) = (config.enabled, &path) {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            sink.size = file.metadata()?.len();
+            sink.file = Some(file);
+        }
+        Ok(AuditLog {
+            enabled: config.enabled,
+            path,
+            max_file_bytes: config.max_file_bytes,
+            max_files: config.max_files,
+            fsync: storage.fsync,
+            sink: Mutex::new(sink),
+        })
+    }
+
+    /// Appends an entry, logging an error if it cannot be written.
+    pub fn record(&self, entry: AuditEntry) {
+        if !self.enabled {
+            return;
+        }
+        let mut sink = self.sink();
+        let Some(path) = &self.path else {
+            if sink.memory.len() == MEMORY_ENTRIES {
+                sink.memory.pop_front();
+            }
+            sink.memory.push_back(entry);
+            return;
+        };
+        let mut line = serde_json::to_string(&entry).expect("Failed to serialize an audit entry");
+        line.push('\n');
+        if let Err(err) = self.append(&mut sink, path, line.as_bytes()) {
+            error!("Failed to write the audit log {}: {}", path.display(), err);
+        }
+    }
+
+    /// Helper function to append a line to the file, rotating it first if it would get too large
+    fn append(&self, sink: &mut Sink, path: &Path, line: &[u8]) -> Result<(), Error> {
+        if sink.size > 0 && sink.size + line.len() as u64 > self.max_file_bytes {
+            sink.file = None;
+            rotate(path, self.max_files)?;
+            sink.file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+            sink.size = 0;
+        }
+        let file = match &mut sink.file {
+            Some(file) => file,
+            None => sink
+                .file
+                .insert(OpenOptions::new().create(true).append(true).open(path)?),
+        };
+        file.write_all(line)?;
+        if self.fsync == FsyncPolicy::Always {
+            file.sync_data()?;
+        }
+        sink.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Reads all entries, oldest first, including those of rotated files. Lines that are not
+    /// valid entries are skipped with a warning.
+    pub fn entries(&self) -> Result<Vec<AuditEntry>, Error> {
+        // Hold the lock so that the files are not rotated while they are read
+        let sink = self.sink();
+        let Some(path) = &self.path else {
+            return Ok(sink.memory.iter().cloned().collect());
+        };
+        let mut entries = Vec::new();
+        for index in (0..=self.max_files).rev() {
+            let file_path = rotated_path(path, index);
+            let file = match File::open(&file_path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            for (number, line) in BufReader::new(file).lines().enumerate() {
+                match serde_json::from_str(&line?) {
+                    Ok(entry) => entries.push(entry),
+                    Err(err) => warn!(
+                        "Skipping line {} of the audit log {}: {}",
+                        number + 1,
+                        file_path.display(),
+                        err
+                    ),
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Syncs the current file to disk, whatever the fsync policy.
+    pub fn sync(&self) -> Result<(), Error> {
+        match &self.sink().file {
+            Some(file) => file.sync_data(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the size of the file and the rotated files in bytes.
+    pub fn size(&self) -> u64 {
+        let _sink = self.sink();
+        let Some(path) = &self.path else {
+            return 0;
+        };
+        (0..=self.max_files)
+            .filter_map(|index| rotated_path(path, index).metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
+    fn sink(&self) -> MutexGuard<'_, Sink> {
+        self.sink.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns the path of the `index`th rotated file, or of the current file for 0.
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => path.to_path_buf(),
+        _ => {
+            let mut name = path.as_os_str().to_owned();
+            name.push(format!(".{}", index));
+            PathBuf::from(name)
+        }
+    }
+}
+
+/// Moves every file up by one, removing the oldest, so that the current file becomes `<file>.1`.
+fn rotate(path: &Path, max_files: usize) -> Result<(), Error> {
+    if max_files == 0 {
+        return fs::remove_file(path);
+    }
+    for index in (0..max_files).rev() {
+        let from = rotated_path(path, index);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, index + 1))?;
+        }
+    }
+    Ok(())
+}
+
+/// Middleware to record every request that may change the database in the audit log
+///
+/// It has to run after authentication, which provides the principal, and gives every request
+/// an [`Audit`] extension for its handler to report the table and rows it changed.
+pub async fn record(
+    State(log): State<Arc<AuditLog>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let audit = Audit::default();
+    request.extensions_mut().insert(audit.clone());
+    let route = request.extensions().get::<MatchedPath>().map_or_else(
+        || request.uri().path().to_string(),
+        |path| path.as_str().to_string(),
+    );
+    let recorded = ![Method::GET, Method::HEAD].contains(request.method())
+        && !READ_ONLY_ROUTES.contains(&route.as_str());
+    let request_type = format!("{} {}", request.method(), route);
+    let principal = request
+        .extensions()
+        .get::<Principal>()
+        .map(|principal| principal.username.clone());
+    let client_address = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.to_string());
+
+    let response = next.run(request).await;
+    if recorded {
+        let details = std::mem::take(&mut *audit.details());
+        let status = response.status().as_u16();
+        log.record(AuditEntry {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            principal: principal.unwrap_or_default(),
+            client_address,
+            request: request_type,
+            table_name: details.table_name,
+            row_count: details.row_count,
+            outcome: Outcome::from_status(status),
+            status,
+        });
+    }
+    response
+}
@@ -0,0 +1,859 @@
+//! Users, roles, privileges and the authentication of requests.
+//!
+//! Users are stored in `users.json` in the data directory, together with the roles and the
+//! privileges granted to both. Passwords are hashed with Argon2id, each with its own random
+//! salt, and never stored or logged in plain text. Requests authenticate with HTTP Basic
+//! authentication or with a bearer token issued by `/login`. Tokens are kept in memory only, so
+//! they end when the server restarts, the user's password changes or the user is dropped.
+use crate::config::AuthConfig;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::extract::{Extension, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use chrono::{DateTime, TimeDelta, Utc};
+use core::policy::{Session, SETTING_HEADER_PREFIX};
+use core::privilege::{self, Grant, Privilege};
+use core::request_types::{GrantRequest, RoleInfo, UserInfo};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+
+/// The file users are saved to, relative to the data directory.
+pub const USERS_FILE: &str = "users.json";
+
+/// A user with the hash of its password.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct User {
+    pub username: String,
+    /// The Argon2 hash of the password in PHC string format, which includes its salt.
+    pub password_hash: String,
+    /// Whether the user may manage users and use the `/admin` endpoints, and has every
+    /// privilege.
+    pub admin: bool,
+    /// The roles granted to the user.
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// The contents of `users.json`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Accounts {
+    pub users: Vec<User>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub grants: Vec<Grant>,
+}
+
+impl Accounts {
+    /// Returns the user named `username`.
+    fn user(&mut self, username: &str) -> Result<&mut User, AuthError> {
+        self.users
+            .iter_mut()
+            .find(|user| user.username == username)
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    format!("User '{}' does not exist", username),
+                )
+            })
+    }
+
+    /// Checks that the role named `role` exists.
+    fn role(&self, role: &str) -> Result<(), AuthError> {
+        match self.roles.iter().any(|name| name == role) {
+            true => Ok(()),
+            false => Err((
+                StatusCode::NOT_FOUND,
+                format!("Role '{}' does not exist", role),
+            )),
+        }
+    }
+
+    /// Checks that `name` is not taken by a user or role.
+    fn check_name_free(&self, name: &str) -> Result<(), AuthError> {
+        if self.users.iter().any(|user| user.username == name) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("User '{}' already exists", name),
+            ));
+        }
+        if self.roles.iter().any(|role| role == name) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Role '{}' already exists", name),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The user a request is authenticated as.
+#[derive(Clone, Debug)]
+pub struct Principal {
+    pub username: String,
+    pub admin: bool,
+    /// The roles granted to the user when the request was authenticated.
+    pub roles: Vec<String>,
+}
+
+impl Principal {
+    /// The principal of all requests while authentication is disabled.
+    fn anonymous() -> Self {
+        Principal {
+            username: "anonymous".to_string(),
+            admin: true,
+            roles: Vec::new(),
+        }
+    }
+
+    /// The names privileges of the principal can be granted to: the user and its roles.
+    pub fn grantees(&self) -> Vec<&str> {
+        std::iter::once(self.username.as_str())
+            .chain(self.roles.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+impl From<&User> for Principal {
+    fn from(user: &User) -> Self {
+        Principal {
+            username: user.username.clone(),
+            admin: user.admin,
+            roles: user.roles.clone(),
+        }
+    }
+}
+
+/// A token issued by `/login`.
+struct Token {
+    username: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// The error of a user management request, with the status code of its response.
+pub type AuthError = (StatusCode, String);
+
+/// The users of the server and the tokens issued to them.
+pub struct Auth {
+    enabled: bool,
+    token_ttl: TimeDelta,
+    /// The file users are saved to, or `None` if they are kept in memory only.
+    path: Option<PathBuf>,
+    accounts: RwLock<Accounts>,
+    tokens: Mutex<HashMap<String, Token>>,
+    /// Verified instead of a missing user's hash, so unknown names take as long as wrong
+    /// passwords.
+    dummy_hash: String,
+}
+
+/// Hashes a password with Argon2id and a random salt.
+fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("Failed to hash a password")
+        .to_string()
+}
+
+/// Checks a password against a hash written by [`hash_password`].
+fn verify_password(password_hash: &str, password: &str) -> bool {
+    PasswordHash::new(password_hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Hashes a password on a blocking thread, as hashing takes tens of milliseconds.
+async fn hash_password_blocking(password: &str) -> String {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || hash_password(&password))
+        .await
+        .expect("Failed to hash a password")
+}
+
+/// Returns a random string of `bytes` random bytes, for tokens and generated passwords.
+fn random_string(bytes: usize) -> String {
+    let mut random = vec![0; bytes];
+    OsRng.fill_bytes(&mut random);
+    URL_SAFE_NO_PAD.encode(random)
+}
+
+impl Auth {
+    /// Loads the users and creates the admin user if there are none.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The authentication settings, including the admin user to create.
+    /// * `path` - The file users are saved to, or `None` to keep them in memory only.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, parsed or written.
+    pub async fn load(config: &AuthConfig, path: Option<PathBuf>) -> Result<Auth, String> {
+        let accounts: Accounts = match &path {
+            Some(path) => match tokio::fs::read_to_string(path).await {
+                Ok(contents) => serde_json::from_str(&contents)
+                    .map_err(|e| format!("Invalid users in {}: {}", path.display(), e))?,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Accounts::default(),
+                Err(err) => return Err(format!("Failed to read {}: {}", path.display(), err)),
+            },
+            None => Accounts::default(),
+        };
+        let auth = Auth {
+            enabled: config.enabled,
+            token_ttl: TimeDelta::from_std(config.token_ttl).unwrap_or(TimeDelta::MAX),
+            path,
+            accounts: RwLock::new(Accounts::default()),
+            tokens: Mutex::new(HashMap::new()),
+            dummy_hash: hash_password_blocking(&random_string(16)).await,
+        };
+
+        if !accounts.users.is_empty() {
+            *auth.accounts.write().await = accounts;
+            return Ok(auth);
+        }
+        let password = match &config.admin_password {
+            Some(password) => password.clone(),
+            None => {
+                let password = random_string(18);
+                warn!(
+                    "Generated the password '{}' for the admin user '{}'. Change it with ALTER USER.",
+                    password, config.admin_user
+                );
+                password
+            }
+        };
+        auth.create_user(&config.admin_user, &password, true)
+            .await
+            .map_err(|(_, error)| error)?;
+        info!("Created the admin user '{}'", config.admin_user);
+        Ok(auth)
+    }
+
+    /// Authenticates a request by its `Authorization` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is missing or uses another scheme than `Basic` or
+    /// `Bearer`, the password is wrong, or the token is unknown or expired.
+    pub async fn authenticate(
+        &self,
+        authorization: Option<&HeaderValue>,
+    ) -> Result<Principal, String> {
+        let authorization = authorization
+            .ok_or("Authentication required")?
+            .to_str()
+            .map_err(|_| "Invalid Authorization header")?;
+        let (scheme, credentials) = authorization
+            .split_once(' ')
+            .ok_or("Invalid Authorization header")?;
+        if scheme.eq_ignore_ascii_case("Basic") {
+            let decoded = STANDARD
+                .decode(credentials.trim())
+                .ok()
+                .and_then(|decoded| String::from_utf8(decoded).ok())
+                .ok_or("Invalid Basic credentials")?;
+            let (username, password) =
+                decoded.split_once(':').ok_or("Invalid Basic credentials")?;
+            self.verify(username, password).await
+        } else if scheme.eq_ignore_ascii_case("Bearer") {
+            let username = {
+                let tokens = self.tokens.lock().unwrap_or_else(PoisonError::into_inner);
+                match tokens.get(credentials.trim()) {
+                    Some(token) if token.expires_at > Utc::now() => token.username.clone(),
+                    Some(_) => return Err("The token has expired".to_string()),
+                    None => return Err("Invalid token".to_string()),
+                }
+            };
+            let accounts = self.accounts.read().await;
+            accounts
+                .users
+                .iter()
+                .find(|user| user.username == username)
+                .map(Principal::from)
+                .ok_or_else(|| "Invalid token".to_string())
+        } else {
+            Err(format!("Unsupported authorization scheme '{}'", scheme))
+        }
+    }
+
+    /// Checks a user name and password.
+    async fn verify(&self, username: &str, password: &str) -> Result<Principal, String> {
+        let user = {
+            let accounts = self.accounts.read().await;
+            accounts
+                .users
+                .iter()
+                .find(|user| user.username == username)
+                .cloned()
+        };
+        let password_hash = user
+            .as_ref()
+            .map_or(self.dummy_hash.clone(), |user| user.password_hash.clone());
+        let password = password.to_string();
+        let verified =
+            tokio::task::spawn_blocking(move || verify_password(&password_hash, &password))
+                .await
+                .unwrap_or(false);
+        match user {
+            Some(user) if verified => Ok(Principal::from(&user)),
+            _ => Err("Invalid user name or password".to_string()),
+        }
+    }
+
+    /// Checks a user name and password and issues a bearer token.
+    ///
+    /// # Returns
+    ///
+    /// Returns the token and the time it expires.
+    pub async fn login(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<(String, DateTime<Utc>), String> {
+        let principal = self.verify(username, password).await?;
+        let token = random_string(32);
+        let expires_at = Utc::now()
+            .checked_add_signed(self.token_ttl)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let mut tokens = self.tokens.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Utc::now();
+        tokens.retain(|_, token| token.expires_at > now);
+        tokens.insert(
+            token.clone(),
+            Token {
+                username: principal.username,
+                expires_at,
+            },
+        );
+        Ok((token, expires_at))
+    }
+
+    /// Revokes a bearer token, returning false if it was not issued.
+    pub fn logout(&self, token: &str) -> bool {
+        let mut tokens = self.tokens.lock().unwrap_or_else(PoisonError::into_inner);
+        tokens.remove(token).is_some()
+    }
+
+    /// Revokes all tokens of a user.
+    fn revoke_tokens(&self, username: &str) {
+        let mut tokens = self.tokens.lock().unwrap_or_else(PoisonError::into_inner);
+        tokens.retain(|_, token| token.username != username);
+    }
+
+    /// Lists the users without their password hashes.
+    pub async fn users(&self) -> Vec<UserInfo> {
+        let accounts = self.accounts.read().await;
+        accounts
+            .users
+            .iter()
+            .map(|user| UserInfo {
+                username: user.username.clone(),
+                admin: user.admin,
+                roles: user.roles.clone(),
+            })
+            .collect()
+    }
+
+    /// Creates a user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name or password is empty, the user or a role of the same name
+    /// exists, or the users cannot be saved.
+    pub async fn create_user(
+        &self,
+        username: &str,
+        password: &str,
+        admin: bool,
+    ) -> Result<(), AuthError> {
+        if username.is_empty() || username.contains(':') {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "User names must not be empty or contain ':'".to_string(),
+            ));
+        }
+        if password.is_empty() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "The password must not be empty".to_string(),
+            ));
+        }
+        let password_hash = hash_password_blocking(password).await;
+
+        self.update(|accounts| {
+            accounts.check_name_free(username)?;
+            accounts.users.push(User {
+                username: username.to_string(),
+                password_hash,
+                admin,
+                roles: Vec::new(),
+            });
+            Ok(())
+        })
+        .await
+    }
+
+    /// Changes the password of a user and revokes the user's tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the password is empty, the user does not exist, or the users cannot
+    /// be saved.
+    pub async fn alter_user(&self, username: &str, password: &str) -> Result<(), AuthError> {
+        if password.is_empty() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "The password must not be empty".to_string(),
+            ));
+        }
+        let password_hash = hash_password_blocking(password).await;
+
+        self.update(|accounts| {
+            accounts.user(username)?.password_hash = password_hash;
+            Ok(())
+        })
+        .await?;
+        self.revoke_tokens(username);
+        Ok(())
+    }
+
+    /// Drops a user with its privileges and revokes the user's tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user does not exist, is the last admin, or the users cannot be
+    /// saved.
+    pub async fn drop_user(&self, username: &str) -> Result<(), AuthError> {
+        self.update(|accounts| {
+            let admin = accounts.user(username)?.admin;
+            if admin && accounts.users.iter().filter(|user| user.admin).count() == 1 {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("User '{}' is the last admin", username),
+                ));
+            }
+            accounts.users.retain(|user| user.username != username);
+            accounts.grants.retain(|grant| grant.grantee != username);
+            Ok(())
+        })
+        .await?;
+        self.revoke_tokens(username);
+        Ok(())
+    }
+
+    /// Lists the roles with the users they are granted to.
+    pub async fn roles(&self) -> Vec<RoleInfo> {
+        let accounts = self.accounts.read().await;
+        accounts
+            .roles
+            .iter()
+            .map(|role| RoleInfo {
+                name: role.clone(),
+                members: accounts
+                    .users
+                    .iter()
+                    .filter(|user| user.roles.contains(role))
+                    .map(|user| user.username.clone())
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Creates a role.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty or a privilege, a user or role of the same name
+    /// exists, or the roles cannot be saved.
+    pub async fn create_role(&self, name: &str) -> Result<(), AuthError> {
+        if name.is_empty()
+            || name.eq_ignore_ascii_case("ALL")
+            || Privilege::from_name(name).is_some()
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("'{}' cannot be the name of a role", name),
+            ));
+        }
+        self.update(|accounts| {
+            accounts.check_name_free(name)?;
+            accounts.roles.push(name.to_string());
+            Ok(())
+        })
+        .await
+    }
+
+    /// Drops a role with its privileges, revoking it from its users.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the role does not exist or the roles cannot be saved.
+    pub async fn drop_role(&self, name: &str) -> Result<(), AuthError> {
+        self.update(|accounts| {
+            accounts.role(name)?;
+            accounts.roles.retain(|role| role != name);
+            for user in &mut accounts.users {
+                user.roles.retain(|role| role != name);
+            }
+            accounts.grants.retain(|grant| grant.grantee != name);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Grants a role to a user. The user's requests get the role's privileges once they are
+    /// authenticated again, which includes the next request with a token.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the role or user does not exist, or the roles cannot be saved.
+    pub async fn grant_role(&self, role: &str, username: &str) -> Result<(), AuthError> {
+        self.update(|accounts| {
+            accounts.role(role)?;
+            let user = accounts.user(username)?;
+            if !user.roles.iter().any(|name| name == role) {
+                user.roles.push(role.to_string());
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Revokes a role from a user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the role or user does not exist, the role is not granted to the
+    /// user, or the roles cannot be saved.
+    pub async fn revoke_role(&self, role: &str, username: &str) -> Result<(), AuthError> {
+        self.update(|accounts| {
+            accounts.role(role)?;
+            let user = accounts.user(username)?;
+            if !user.roles.iter().any(|name| name == role) {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("Role '{}' is not granted to user '{}'", role, username),
+                ));
+            }
+            user.roles.retain(|name| name != role);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Lists the grants of `grantee`, or all grants.
+    pub async fn grants(&self, grantee: Option<&str>) -> Vec<Grant> {
+        let accounts = self.accounts.read().await;
+        accounts
+            .grants
+            .iter()
+            .filter(|grant| grantee.is_none_or(|grantee| grant.grantee == grantee))
+            .cloned()
+            .collect()
+    }
+
+    /// Lists the grants of a principal and its roles.
+    pub async fn grants_of(&self, principal: &Principal) -> Vec<Grant> {
+        let grantees = principal.grantees();
+        let accounts = self.accounts.read().await;
+        accounts
+            .grants
+            .iter()
+            .filter(|grant| grantees.contains(&grant.grantee.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Grants privileges to a user or role.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the grantee does not exist, a privilege cannot be granted on the
+    /// table or columns, or the grants cannot be saved.
+    pub async fn grant(&self, request: &GrantRequest) -> Result<(), AuthError> {
+        let grants = grants_of_request(request)?;
+        self.update(|accounts| {
+            check_grantee(accounts, &request.grantee)?;
+            for grant in grants {
+                privilege::grant(&mut accounts.grants, grant);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Revokes privileges of a user or role.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the grantee does not exist, columns are revoked from a grant on a
+    /// whole table, or the grants cannot be saved.
+    pub async fn revoke(&self, request: &GrantRequest) -> Result<(), AuthError> {
+        let grants = grants_of_request(request)?;
+        self.update(|accounts| {
+            check_grantee(accounts, &request.grantee)?;
+            for grant in &grants {
+                privilege::revoke(&mut accounts.grants, grant)
+                    .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Checks that a principal has a privilege on columns of a table, see [`privilege::check`].
+    /// Admins have every privilege.
+    pub async fn check(
+        &self,
+        principal: &Principal,
+        privilege: Privilege,
+        table: Option<&str>,
+        columns: &[&str],
+    ) -> Result<(), String> {
+        if principal.admin {
+            return Ok(());
+        }
+        let accounts = self.accounts.read().await;
+        privilege::check(
+            &accounts.grants,
+            &principal.grantees(),
+            privilege,
+            table,
+            columns,
+        )
+    }
+
+    /// Returns a function telling whether a principal has any privilege on a table, which
+    /// lets it see the table's definition.
+    pub async fn visible_tables(&self, principal: &Principal) -> impl Fn(&str) -> bool {
+        let grants = match principal.admin {
+            true => None,
+            false => Some(self.grants_of(principal).await),
+        };
+        let principal = principal.clone();
+        move |table: &str| {
+            grants
+                .as_ref()
+                .is_none_or(|grants| privilege::can_see(grants, &principal.grantees(), table))
+        }
+    }
+
+    /// Grants every privilege on a table to the user who created it. Admins are skipped, as
+    /// they have every privilege anyway.
+    pub async fn table_created(&self, principal: &Principal, table: &str) -> Result<(), AuthError> {
+        if principal.admin {
+            return Ok(());
+        }
+        self.update(|accounts| {
+            for privilege in Privilege::ALL.into_iter().filter(|p| p.on_table()) {
+                let grant = Grant {
+                    grantee: principal.username.clone(),
+                    privilege,
+                    table: Some(table.to_string()),
+                    columns: None,
+                };
+                privilege::grant(&mut accounts.grants, grant);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Moves the grants on a renamed table to its new name.
+    pub async fn table_renamed(&self, current_name: &str, new_name: &str) -> Result<(), AuthError> {
+        self.update(|accounts| {
+            for grant in &mut accounts.grants {
+                if grant.table.as_deref() == Some(current_name) {
+                    grant.table = Some(new_name.to_string());
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Removes the grants on a dropped table, so they do not apply to a new table of the same
+    /// name.
+    pub async fn table_dropped(&self, table: &str) -> Result<(), AuthError> {
+        self.update(|accounts| {
+            accounts
+                .grants
+                .retain(|grant| grant.table.as_deref() != Some(table));
+            Ok(())
+        })
+        .await
+    }
+
+    /// Checks that the users and roles named by a row-level security policy exist.
+    pub async fn check_grantees(&self, grantees: &[String]) -> Result<(), AuthError> {
+        let accounts = self.accounts.read().await;
+        grantees
+            .iter()
+            .try_for_each(|grantee| check_grantee(&accounts, grantee))
+    }
+
+    /// Changes a copy of the accounts, saves it and only then makes it current, so a change
+    /// that fails to be saved is not applied.
+    async fn update<T>(
+        &self,
+        change: impl FnOnce(&mut Accounts) -> Result<T, AuthError>,
+    ) -> Result<T, AuthError> {
+        let mut accounts = self.accounts.write().await;
+        let mut updated = accounts.clone();
+        let result = change(&mut updated)?;
+        self.save(&updated).await?;
+        *accounts = updated;
+        Ok(result)
+    }
+
+    /// Writes the accounts to their file, replacing it in one step.
+    async fn save(&self, accounts: &Accounts) -> Result<(), AuthError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let write = async {
+            let contents = serde_json::to_string_pretty(accounts)?;
+            let partial = path.with_extension("json.partial");
+            let mut options = tokio::fs::OpenOptions::new();
+            options.create(true).write(true).truncate(true);
+            // The hashes are only readable by the server's user
+            #[cfg(unix)]
+            options.mode(0o600);
+            let mut file = options.open(&partial).await?;
+            file.write_all(contents.as_bytes()).await?;
+            file.sync_all().await?;
+            tokio::fs::rename(&partial, path).await
+        };
+        write.await.map_err(|err: std::io::Error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to save users: {}", err),
+            )
+        })
+    }
+}
+
+/// Returns the grants of a grant or revoke request, checking that they can be granted.
+fn grants_of_request(request: &GrantRequest) -> Result<Vec<Grant>, AuthError> {
+    if request.privileges.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "No privileges given".to_string()));
+    }
+    request
+        .privileges
+        .iter()
+        .map(|spec| {
+            let grant = Grant {
+                grantee: request.grantee.clone(),
+                privilege: spec.privilege,
+                table: request.table.clone(),
+                columns: spec.columns.clone(),
+            };
+            grant.validate().map(|_| grant)
+        })
+        .collect::<Result<Vec<Grant>, String>>()
+        .map_err(|error| (StatusCode::BAD_REQUEST, error))
+}
+
+/// Checks that a user or role named `grantee` exists.
+fn check_grantee(accounts: &Accounts, grantee: &str) -> Result<(), AuthError> {
+    let exists = accounts.users.iter().any(|user| user.username == grantee)
+        || accounts.roles.iter().any(|role| role == grantee);
+    match exists {
+        true => Ok(()),
+        false => Err((
+            StatusCode::NOT_FOUND,
+            format!("No user or role named '{}'", grantee),
+        )),
+    }
+}
+
+/// Returns the bearer token of a request, if it has one.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let authorization = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = authorization.split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("Bearer")
+        .then_some(token.trim())
+}
+
+/// Middleware to authenticate requests, rejecting those without valid credentials
+///
+/// The authenticated [`Principal`] is added to the request's extensions, together with the
+/// [`Session`] row-level security policies are evaluated in. While authentication is disabled,
+/// every request is authenticated as an anonymous admin.
+pub async fn authenticate(
+    State(auth): State<Arc<Auth>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let principal = if auth.enabled {
+        match auth
+            .authenticate(request.headers().get(header::AUTHORIZATION))
+            .await
+        {
+            Ok(principal) => principal,
+            Err(error) => {
+                warn!("Rejected {} {}: {}", request.method(), request.uri(), error);
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    [(
+                        header::WWW_AUTHENTICATE,
+                        "Basic realm=\"Rust Database\", charset=\"UTF-8\"",
+                    )],
+                    Json(error),
+                )
+                    .into_response();
+            }
+        }
+    } else {
+        Principal::anonymous()
+    };
+    let session = session(&principal, request.headers());
+    request.extensions_mut().insert(principal);
+    request.extensions_mut().insert(session);
+    next.run(request).await
+}
+
+/// Helper function to build the session of a request from its principal and the settings sent
+/// as headers named [`SETTING_HEADER_PREFIX`] followed by the name of the setting.
+fn session(principal: &Principal, headers: &HeaderMap) -> Session {
+    let settings = headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let setting = name.as_str().strip_prefix(SETTING_HEADER_PREFIX)?;
+            Some((setting.to_lowercase(), value.to_str().ok()?.to_string()))
+        })
+        .collect();
+    Session {
+        user: principal.username.clone(),
+        roles: principal.roles.clone(),
+        settings,
+    }
+}
+
+/// Middleware to reject requests of users who are not admins
+pub async fn require_admin(
+    Extension(principal): Extension<Principal>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !principal.admin {
+        let error = format!("User '{}' is not an admin", principal.username);
+        warn!("Rejected {} {}: {}", request.method(), request.uri(), error);
+        return (StatusCode::FORBIDDEN, Json(error)).into_response();
+    }
+    next.run(request).await
+}
@@ -0,0 +1,664 @@
+//! Write-ahead log, snapshots and point-in-time restore.
+//!
+//! Every change of the tables is logged as a [`WalRecord`] with an increasing log sequence
+//! number (LSN) and written to disk before it is applied. Records are appended as JSON lines
+//! to segment files in `wal/`, named after the hexadecimal LSN of their first record. A
+//! segment is completed once it grows beyond [`SEGMENT_BYTES`], when a snapshot is taken or on
+//! request; completed segments are moved to the archive directory if one is configured, and
+//! otherwise removed once `db.json` contains their changes.
+//!
+//! A [`Snapshot`] holds all tables as of one LSN, and `db.json` is one too. A server starting
+//! after a crash replays the records after the LSN of `db.json`, and replaying the archived
+//! records after the LSN of a snapshot restores the database to any later LSN or point in
+//! time, see [`restore`].
+use crate::config::FsyncPolicy;
+use chrono::{DateTime, Utc};
+use core::row::Row;
+use core::table::Table;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// The file the tables are saved to, relative to the data directory.
+pub const DATABASE_FILE: &str = "db.json";
+
+/// The directory of the write-ahead log, relative to the data directory.
+pub const WAL_DIR: &str = "wal";
+
+/// The directory snapshots are written to, relative to the data directory.
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
+/// The size after which a segment is completed and a new one is started.
+pub const SEGMENT_BYTES: u64 = 16 * 1024 * 1024;
+
+/// A change of the tables.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum WalOperation {
+    /// Stores a table, replacing the table called `name` if there is one.
+    PutTable { name: String, table: Table },
+    /// Removes a table.
+    DropTable { name: String },
+    /// Appends a row to a table.
+    InsertRow { table_name: String, row: Row },
+    /// Replaces rows of a table, given with their positions.
+    UpdateRows {
+        table_name: String,
+        rows: Vec<(usize, Row)>,
+    },
+    /// Removes the rows of a table at the positions, in ascending order. The remaining rows
+    /// keep their order.
+    DeleteRows {
+        table_name: String,
+        positions: Vec<usize>,
+    },
+}
+
+impl WalOperation {
+    /// Applies the change to the tables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a dropped table or the table of changed rows does not exist, or a
+    /// position is not one of a row of the table.
+    pub fn apply(self, tables: &mut Vec<Table>) -> Result<(), String> {
+        let position =
+            |tables: &[Table], name: &str| tables.iter().position(|table| table.name == name);
+        match self {
+            WalOperation::PutTable { name, table } => match position(tables, &name) {
+                Some(index) => tables[index] = table,
+                None => tables.push(table),
+            },
+            WalOperation::DropTable { name } => match position(tables, &name) {
+                Some(index) => {
+                    tables.remove(index);
+                }
+                None => return Err(format!("Table '{}' does not exist", name)),
+            },
+            WalOperation::InsertRow { table_name, row } => match position(tables, &table_name) {
+                Some(index) => tables[index].add_row(row),
+                None => return Err(format!("Table '{}' does not exist", table_name)),
+            },
+            WalOperation::UpdateRows { table_name, rows } => {
+                let index = position(tables, &table_name)
+                    .ok_or_else(|| format!("Table '{}' does not exist", table_name))?;
+                let table = &mut tables[index];
+                if let Some((row, _)) = rows.iter().find(|(row, _)| *row >= table.rows.len()) {
+                    return Err(format!("Table '{}' has no row {}", table_name, row));
+                }
+                for (row, values) in rows {
+                    table.rows[row] = values;
+                }
+            }
+            WalOperation::DeleteRows {
+                table_name,
+                positions,
+            } => {
+                let index = position(tables, &table_name)
+                    .ok_or_else(|| format!("Table '{}' does not exist", table_name))?;
+                let table = &mut tables[index];
+                if let Some(row) = positions.iter().find(|row| **row >= table.rows.len()) {
+                    return Err(format!("Table '{}' has no row {}", table_name, row));
+                }
+                let mut deleted = positions.into_iter().peekable();
+                let mut row = 0;
+                table.rows.retain(|_| {
+                    let keep = deleted.next_if_eq(&row).is_none();
+                    row += 1;
+                    keep
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An entry of the write-ahead log.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WalRecord {
+    pub lsn: u64,
+    pub timestamp: DateTime<Utc>,
+    pub operation: WalOperation,
+}
+
+/// All tables as of one LSN, as written to `db.json` and by `/admin/snapshot`.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The LSN of the last change included in the snapshot.
+    pub lsn: u64,
+    pub timestamp: DateTime<Utc>,
+    pub tables: Vec<Table>,
+}
+
+/// The write-ahead log of a running server.
+pub struct Wal {
+    dir: PathBuf,
+    archive: Option<PathBuf>,
+    last_lsn: u64,
+    segment: PathBuf,
+    file: File,
+    size: u64,
+    fsync: FsyncPolicy,
+    /// The LSN of the last change `db.json` contains.
+    saved_lsn: u64,
+    /// Completed segments with the LSN of their last record, kept without an archive until
+    /// `db.json` contains their changes.
+    unsaved: Vec<(u64, PathBuf)>,
+}
+
+/// Returns the file name of the segment starting at `lsn`.
+fn segment_name(lsn: u64) -> String {
+    format!("{:016X}.wal", lsn)
+}
+
+/// Lists the segments in `dir` by the LSN they start at.
+fn segments(dir: &Path) -> io::Result<BTreeMap<u64, PathBuf>> {
+    let mut segments = BTreeMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "wal") {
+            let first_lsn = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| u64::from_str_radix(stem, 16).ok());
+            if let Some(first_lsn) = first_lsn {
+                segments.insert(first_lsn, path);
+            }
+        }
+    }
+    Ok(segments)
+}
+
+/// Reads the records of a segment.
+///
+/// A last line that cannot be parsed was cut off by a crash and is ignored.
+///
+/// # Returns
+///
+/// Returns the records and the length of the file up to the end of the last record.
+fn read_segment(path: &Path) -> io::Result<(Vec<WalRecord>, u64)> {
+    let mut records = Vec::new();
+    let mut valid_length = 0;
+    let mut lines = BufReader::new(File::open(path)?).lines().peekable();
+    while let Some(line) = lines.next() {
+        let line = line?;
+        match serde_json::from_str::<WalRecord>(&line) {
+            Ok(record) => {
+                records.push(record);
+                valid_length += line.len() as u64 + 1;
+            }
+            Err(_) if lines.peek().is_none() => {
+                warn!(
+                    "Ignoring an incomplete record at the end of {}",
+                    path.display()
+                );
+            }
+            Err(err) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid record in {}: {}", path.display(), err),
+                ))
+            }
+        }
+    }
+    Ok((records, valid_length))
+}
+
+impl Wal {
+    /// Opens the write-ahead log in `dir`, continuing its newest segment.
+    ///
+    /// Older segments left behind by an interrupted switch are completed first. Without an
+    /// archive they are kept until [`saved`](Wal::saved) reports that `db.json` contains
+    /// their changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory of the segments, created if it does not exist.
+    /// * `archive` - The directory completed segments are moved to.
+    /// * `fsync` - Whether written records are synced to disk.
+    pub fn open(dir: &Path, archive: Option<PathBuf>, fsync: FsyncPolicy) -> io::Result<Wal> {
+        fs::create_dir_all(dir)?;
+        let mut segments = segments(dir)?;
+        let (first_lsn, segment) = segments
+            .pop_last()
+            .unwrap_or_else(|| (1, dir.join(segment_name(1))));
+        let mut wal = Wal {
+            dir: dir.to_path_buf(),
+            archive,
+            last_lsn: first_lsn - 1,
+            file: OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&segment)?,
+            segment: segment.clone(),
+            size: 0,
+            fsync,
+            saved_lsn: 0,
+            unsaved: Vec::new(),
+        };
+        // A segment ends right before the next one starts
+        let next_lsns: Vec<u64> = segments
+            .keys()
+            .skip(1)
+            .copied()
+            .chain([first_lsn])
+            .collect();
+        for (path, next_lsn) in segments.into_values().zip(next_lsns) {
+            wal.complete_segment(&path, next_lsn - 1)?;
+        }
+
+        let (records, valid_length) = read_segment(&segment)?;
+        if let Some(record) = records.last() {
+            wal.last_lsn = record.lsn;
+        }
+        // Drop an incomplete last record, so that new records start on a line of their own
+        wal.file.set_len(valid_length)?;
+        wal.size = valid_length;
+        info!(
+            "Opened the write-ahead log at LSN {} in {}",
+            wal.last_lsn,
+            segment.display()
+        );
+        Ok(wal)
+    }
+
+    /// Returns the LSN of the last logged change.
+    pub fn last_lsn(&self) -> u64 {
+        self.last_lsn
+    }
+
+    /// Returns the path of the segment records are written to.
+    pub fn segment(&self) -> &Path {
+        &self.segment
+    }
+
+    /// Returns the directory completed segments are moved to.
+    pub fn archive(&self) -> Option<&Path> {
+        self.archive.as_deref()
+    }
+
+    /// Writes a change to the segment and syncs it to disk if the fsync policy is `always`.
+    ///
+    /// The segment is completed if it grew beyond [`SEGMENT_BYTES`].
+    ///
+    /// # Returns
+    ///
+    /// Returns the LSN of the change and the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record cannot be written. The change is not logged then and
+    /// must not be applied.
+    pub fn log(&mut self, operation: &WalOperation) -> io::Result<(u64, usize)> {
+        #[derive(Serialize)]
+        struct RecordRef<'a> {
+            lsn: u64,
+            timestamp: DateTime<Utc>,
+            operation: &'a WalOperation,
+        }
+
+        let lsn = self.last_lsn + 1;
+        let record = RecordRef {
+            lsn,
+            timestamp: Utc::now(),
+            operation,
+        };
+        // Tables, rows and values always serialize to JSON
+        let mut line = serde_json::to_string(&record).expect("Failed to serialize a WAL record");
+        line.push('\n');
+        let written = self.file.write_all(line.as_bytes()).and_then(|_| {
+            if self.fsync == FsyncPolicy::Always {
+                self.file.sync_data()
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(err) = written {
+            // Cut off what was written of the record, so that it is not replayed
+            let _ = self.file.set_len(self.size);
+            return Err(err);
+        }
+        self.last_lsn = lsn;
+        self.size += line.len() as u64;
+        if self.size >= SEGMENT_BYTES {
+            // The record is safely written, a failed switch is retried by the next record
+            if let Err(err) = self.switch() {
+                warn!(
+                    "Failed to complete WAL segment {}: {}",
+                    self.segment.display(),
+                    err
+                );
+            }
+        }
+        Ok((lsn, line.len()))
+    }
+
+    /// Syncs the written records of the current segment to disk, whatever the fsync policy.
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+
+    /// Records that `db.json` contains the changes up to `lsn`, removing the completed
+    /// segments it covers if there is no archive.
+    pub fn saved(&mut self, lsn: u64) -> io::Result<()> {
+        self.saved_lsn = self.saved_lsn.max(lsn);
+        while let Some((last_lsn, path)) = self.unsaved.first() {
+            if *last_lsn > self.saved_lsn {
+                break;
+            }
+            fs::remove_file(path)?;
+            self.unsaved.remove(0);
+        }
+        Ok(())
+    }
+
+    /// Continues the log after `lsn` if it ends before, which happens if `db.json` is newer
+    /// than the segments, so that new records are not taken to be contained in it.
+    pub fn continue_after(&mut self, lsn: u64) -> io::Result<()> {
+        if lsn <= self.last_lsn {
+            return Ok(());
+        }
+        warn!(
+            "The write-ahead log ends at LSN {}, continuing it after LSN {}",
+            self.last_lsn, lsn
+        );
+        self.switch()?;
+        let empty = self.segment.clone();
+        self.last_lsn = lsn;
+        self.segment = self.dir.join(segment_name(lsn + 1));
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.segment)?;
+        fs::remove_file(empty)
+    }
+
+    /// Reads the logged changes after `lsn` from the segments and the archive.
+    ///
+    /// # Returns
+    ///
+    /// Returns the records ordered by LSN.
+    pub fn records_after(&self, lsn: u64) -> Result<Vec<WalRecord>, String> {
+        let mut dirs = vec![self.dir.clone()];
+        dirs.extend(self.archive.iter().filter(|dir| dir.exists()).cloned());
+        Ok(read_records(&dirs, lsn)?.into_values().collect())
+    }
+
+    /// Completes the current segment and starts a new one.
+    ///
+    /// Nothing happens if the current segment has no records.
+    ///
+    /// # Returns
+    ///
+    /// Returns the path of the archived segment, or `None` if there was nothing to archive or
+    /// no archive is configured.
+    pub fn switch(&mut self) -> io::Result<Option<PathBuf>> {
+        if self.size == 0 {
+            return Ok(None);
+        }
+
+        let completed = self.segment.clone();
+        let segment = self.dir.join(segment_name(self.last_lsn + 1));
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&segment)?;
+        self.segment = segment;
+        self.size = 0;
+        self.complete_segment(&completed, self.last_lsn)
+    }
+
+    /// Moves a completed segment to the archive. Without one, the segment is removed if
+    /// `db.json` contains its changes up to `last_lsn`, and kept until it does otherwise.
+    fn complete_segment(&mut self, path: &Path, last_lsn: u64) -> io::Result<Option<PathBuf>> {
+        let Some(archive) = &self.archive else {
+            if last_lsn <= self.saved_lsn {
+                fs::remove_file(path)?;
+            } else {
+                self.unsaved.push((last_lsn, path.to_path_buf()));
+            }
+            return Ok(None);
+        };
+        fs::create_dir_all(archive)?;
+        let file_name = path.file_name().unwrap_or_default();
+        let target = archive.join(file_name);
+        // Copied under a temporary name first, so the archive never holds a partial segment
+        let partial = archive.join(format!("{}.partial", file_name.to_string_lossy()));
+        fs::copy(path, &partial)?;
+        File::open(&partial)?.sync_all()?;
+        fs::rename(&partial, &target)?;
+        fs::remove_file(path)?;
+        info!("Archived WAL segment {}", target.display());
+        Ok(Some(target))
+    }
+}
+
+/// Reads the records after `lsn` from the segments in `dirs`. Segments found in several
+/// directories are read once, and segments ending at or before `lsn` are skipped.
+fn read_records(dirs: &[PathBuf], lsn: u64) -> Result<BTreeMap<u64, WalRecord>, String> {
+    let mut all_segments = BTreeMap::new();
+    for dir in dirs {
+        let segments =
+            segments(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        for (first_lsn, path) in segments {
+            all_segments.entry(first_lsn).or_insert(path);
+        }
+    }
+
+    let mut records = BTreeMap::new();
+    let mut segments = all_segments.into_iter().peekable();
+    while let Some((_, path)) = segments.next() {
+        if segments
+            .peek()
+            .is_some_and(|(next_lsn, _)| *next_lsn <= lsn + 1)
+        {
+            continue;
+        }
+        let (segment_records, _) = read_segment(&path).map_err(|e| e.to_string())?;
+        for record in segment_records {
+            if record.lsn > lsn {
+                records.insert(record.lsn, record);
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Applies records in LSN order to tables as of `lsn`, up to `target`.
+///
+/// Replaying stops with a warning at a gap in the LSNs.
+///
+/// # Returns
+///
+/// Returns the LSN of the last replayed record, or `lsn` if none were replayed, and the
+/// number of replayed records.
+///
+/// # Errors
+///
+/// Returns an error if a record cannot be applied.
+pub fn replay(
+    tables: &mut Vec<Table>,
+    mut lsn: u64,
+    records: impl IntoIterator<Item = WalRecord>,
+    target: RestoreTarget,
+) -> Result<(u64, usize), String> {
+    let mut replayed = 0;
+    for record in records {
+        let reached = match target {
+            RestoreTarget::Latest => false,
+            RestoreTarget::Lsn(target) => record.lsn > target,
+            RestoreTarget::Time(time) => record.timestamp > time,
+        };
+        if reached {
+            break;
+        }
+        if record.lsn != lsn + 1 {
+            warn!(
+                "The write-ahead log is missing the records from LSN {} to {}",
+                lsn + 1,
+                record.lsn - 1
+            );
+            break;
+        }
+        record
+            .operation
+            .apply(tables)
+            .map_err(|e| format!("Failed to replay LSN {}: {}", record.lsn, e))?;
+        lsn = record.lsn;
+        replayed += 1;
+    }
+    Ok((lsn, replayed))
+}
+
+/// Reads `db.json`.
+///
+/// Files written before `db.json` became a [`Snapshot`] hold only the tables. They contain all
+/// changes logged before, so they are taken to be as of `last_lsn`.
+///
+/// # Errors
+///
+/// Returns an error if the contents are neither a snapshot nor a list of tables.
+pub fn parse_database(contents: &str, last_lsn: u64) -> serde_json::Result<Snapshot> {
+    if contents.trim_start().starts_with('[') {
+        return Ok(Snapshot {
+            lsn: last_lsn,
+            timestamp: Utc::now(),
+            tables: serde_json::from_str(contents)?,
+        });
+    }
+    serde_json::from_str(contents)
+}
+
+/// Writes a snapshot to a file in `dir`, named after its LSN.
+///
+/// # Returns
+///
+/// Returns the path of the snapshot.
+pub fn write_snapshot(dir: &Path, snapshot: &Snapshot) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("snapshot-{:016X}.json", snapshot.lsn));
+    let partial = path.with_extension("json.partial");
+    let mut file = File::create(&partial)?;
+    serde_json::to_writer(io::BufWriter::new(&mut file), snapshot)?;
+    file.sync_all()?;
+    fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+/// Where a point-in-time restore stops.
+#[derive(Clone, Copy, Debug)]
+pub enum RestoreTarget {
+    /// Replays all available records.
+    Latest,
+    /// Replays the records up to and including this LSN.
+    Lsn(u64),
+    /// Replays the records logged at or before this time.
+    Time(DateTime<Utc>),
+}
+
+/// The outcome of a restore.
+pub struct RestoreSummary {
+    /// The LSN of the snapshot.
+    pub snapshot_lsn: u64,
+    /// The LSN of the last replayed record, or of the snapshot if none were replayed.
+    pub lsn: u64,
+    /// The number of replayed records.
+    pub replayed: usize,
+    pub table_count: usize,
+}
+
+/// Rebuilds a data directory from a snapshot and archived WAL segments.
+///
+/// The records after the snapshot's LSN are replayed in order up to `target`. The data
+/// directory gets a `db.json` with the restored tables and a write-ahead log continuing after
+/// the last replayed LSN, so a server started in it logs the next change with the next LSN.
+///
+/// # Arguments
+///
+/// * `snapshot` - The path of a snapshot written by `/admin/snapshot`.
+/// * `wal_dirs` - The directories of the segments, e.g. the archive and the `wal/` directory
+///   of the old data directory. Segments found in several directories are read once.
+/// * `target` - Where to stop replaying.
+/// * `data_dir` - The data directory to create. It must not contain a `db.json` yet.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written, the target is before the snapshot,
+/// the records have a gap, or the target LSN is not reached.
+pub fn restore(
+    snapshot: &Path,
+    wal_dirs: &[PathBuf],
+    target: RestoreTarget,
+    data_dir: &Path,
+) -> Result<RestoreSummary, String> {
+    let database = data_dir.join(DATABASE_FILE);
+    if database.exists() {
+        return Err(format!("{} already exists", database.display()));
+    }
+    let snapshot: Snapshot = File::open(snapshot)
+        .map_err(|e| format!("Failed to open {}: {}", snapshot.display(), e))
+        .and_then(|file| {
+            serde_json::from_reader(BufReader::new(file))
+                .map_err(|e| format!("Invalid snapshot {}: {}", snapshot.display(), e))
+        })?;
+    match target {
+        RestoreTarget::Lsn(lsn) if lsn < snapshot.lsn => {
+            return Err(format!(
+                "The snapshot was taken at LSN {}, after the target LSN {}",
+                snapshot.lsn, lsn
+            ))
+        }
+        RestoreTarget::Time(time) if time < snapshot.timestamp => {
+            return Err(format!(
+                "The snapshot was taken at {}, after the target time {}",
+                snapshot.timestamp.to_rfc3339(),
+                time.to_rfc3339()
+            ))
+        }
+        _ => {}
+    }
+
+    let records = read_records(wal_dirs, snapshot.lsn)?;
+    let mut tables = snapshot.tables;
+    let (lsn, replayed) = replay(&mut tables, snapshot.lsn, records.into_values(), target)?;
+    if let RestoreTarget::Lsn(target) = target {
+        if lsn < target {
+            return Err(format!(
+                "The write-ahead log ends at LSN {} before the target LSN {}",
+                lsn, target
+            ));
+        }
+    }
+
+    let wal_dir = data_dir.join(WAL_DIR);
+    let write = || -> io::Result<usize> {
+        fs::create_dir_all(&wal_dir)?;
+        if segments(&wal_dir)?.values().next().is_some() {
+            return Err(io::Error::other(format!(
+                "{} already contains WAL segments",
+                wal_dir.display()
+            )));
+        }
+        File::create(wal_dir.join(segment_name(lsn + 1)))?;
+        let restored = Snapshot {
+            lsn,
+            timestamp: Utc::now(),
+            tables,
+        };
+        let mut file = File::create(&database)?;
+        serde_json::to_writer(io::BufWriter::new(&mut file), &restored)?;
+        file.sync_all()?;
+        Ok(restored.tables.len())
+    };
+    let table_count =
+        write().map_err(|e| format!("Failed to write {}: {}", data_dir.display(), e))?;
+
+    Ok(RestoreSummary {
+        snapshot_lsn: snapshot.lsn,
+        lsn,
+        replayed,
+        table_count,
+    })
+}
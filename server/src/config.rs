@@ -0,0 +1,575 @@
+//! Server configuration.
+//!
+//! Settings are read from a TOML file and overridden by environment variables and command line
+//! flags, in that order. Every setting has a default, so the file and all of its sections are
+//! optional:
+//!
+//! ```toml
+//! [server]
+//! bind = ["0.0.0.0:3000"]
+//! request_timeout = "60s"
+//! shutdown_timeout = "30s"
+//!
+//! [tls]
+//! cert = "/etc/rust-db/server.crt"
+//! key = "/etc/rust-db/server.key"
+//! client_ca = "/etc/rust-db/client-ca.crt"
+//! reload_interval = "1m"
+//!
+//! [storage]
+//! data_dir = "."
+//! persistence = "disk"
+//! fsync = "always"
+//! wal_archive = "/backups/wal"
+//!
+//! [log]
+//! level = "info"
+//! format = "text"
+//! slow_query_threshold = "500ms"
+//!
+//! [audit]
+//! enabled = true
+//! path = "/var/log/rust-db/audit.log"
+//! max_file_bytes = 10485760
+//! max_files = 5
+//!
+//! [limits]
+//! max_body_bytes = 2097152
+//! max_csv_import_bytes = 268435456
+//! max_restore_bytes = 1073741824
+//!
+//! [features]
+//! web_ui = true
+//! admin_api = true
+//! csv = true
+//! sql_dump = true
+//! metrics = true
+//!
+//! [auth]
+//! enabled = true
+//! admin_user = "admin"
+//! admin_password = "change me"
+//! token_ttl = "24h"
+//! ```
+use clap::ValueEnum;
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The effective configuration of the server.
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub tls: TlsConfig,
+    pub storage: StorageConfig,
+    pub log: LogConfig,
+    pub audit: AuditConfig,
+    pub limits: LimitsConfig,
+    pub features: FeaturesConfig,
+    pub auth: AuthConfig,
+}
+
+/// How the server accepts requests.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// The addresses the server listens on.
+    pub bind: Vec<String>,
+    /// The time a request may take until its response starts, or `None` for no limit. `0s`
+    /// disables the limit.
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Option<Duration>,
+    /// The time requests in flight may take to finish once the server is shutting down. `0s`
+    /// cancels them right away.
+    #[serde(with = "humantime_serde")]
+    pub shutdown_timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: vec!["0.0.0.0:3000".to_string()],
+            request_timeout: Some(Duration::from_secs(60)),
+            shutdown_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Whether the server accepts HTTPS instead of HTTP, see [`tls`](crate::tls).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// The PEM file of the server's certificate chain. HTTPS is enabled if it is given.
+    pub cert: Option<PathBuf>,
+    /// The PEM file of the certificate's private key.
+    pub key: Option<PathBuf>,
+    /// The PEM file of the CA certificates client certificates are verified against. Without
+    /// it, clients are not asked for a certificate.
+    pub client_ca: Option<PathBuf>,
+    /// How often the files are checked for changes, which are loaded without a restart, or
+    /// `None` to only reload them on `SIGHUP`. `0s` disables the checks.
+    #[serde(with = "humantime_serde")]
+    pub reload_interval: Option<Duration>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig {
+            cert: None,
+            key: None,
+            client_ca: None,
+            reload_interval: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl TlsConfig {
+    /// Whether the server accepts HTTPS.
+    pub fn enabled(&self) -> bool {
+        self.cert.is_some()
+    }
+}
+
+/// Whether changes are written to disk.
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Persistence {
+    /// Keeps `db.json` and the write-ahead log in the data directory.
+    Disk,
+    /// Keeps the tables in memory only. They are lost when the server stops.
+    Memory,
+}
+
+/// When written files are synced to disk.
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FsyncPolicy {
+    /// Syncs the write-ahead log and `db.json` after every change, so no acknowledged change
+    /// is lost in a crash.
+    Always,
+    /// Leaves syncing to the operating system, which is faster but may lose the latest changes
+    /// when the machine crashes.
+    Never,
+}
+
+/// Where and how the tables are stored.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// The directory of `db.json`, the write-ahead log and snapshots.
+    pub data_dir: PathBuf,
+    pub persistence: Persistence,
+    pub fsync: FsyncPolicy,
+    /// The directory completed write-ahead log segments are archived to. Without it, segments
+    /// are removed once they are completed.
+    pub wal_archive: Option<PathBuf>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            data_dir: PathBuf::from("."),
+            persistence: Persistence::Disk,
+            fsync: FsyncPolicy::Always,
+            wal_archive: None,
+        }
+    }
+}
+
+impl StorageConfig {
+    /// Returns the path of a file or directory in the data directory.
+    pub fn path(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.data_dir.join(name)
+    }
+}
+
+/// The format of log lines.
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines with a timestamp, level and module.
+    Text,
+    /// One JSON object per line with the fields `timestamp`, `level`, `target` and `message`.
+    Json,
+}
+
+/// What is logged and how.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// The most verbose level that is logged: `off`, `error`, `warn`, `info`, `debug` or
+    /// `trace`.
+    #[serde(with = "level_filter")]
+    pub level: LevelFilter,
+    pub format: LogFormat,
+    /// The duration from which requests are logged as slow queries, or `None` to log none.
+    /// `0s` logs every request.
+    #[serde(with = "humantime_serde")]
+    pub slow_query_threshold: Option<Duration>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: LevelFilter::Info,
+            format: LogFormat::Text,
+            slow_query_threshold: None,
+        }
+    }
+}
+
+/// (De)serializes a level filter by its lowercase name, as it is written on the command line.
+mod level_filter {
+    use log::LevelFilter;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(level: &LevelFilter, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&level.as_str().to_lowercase())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<LevelFilter, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| D::Error::custom(format!("unknown log level `{}`", name)))
+    }
+}
+
+/// The audit log of requests that may change the database, see [`audit`](crate::audit).
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    /// The file entries are appended to. Defaults to `audit.log` in the data directory, or to
+    /// keeping the latest entries in memory if the tables are kept in memory.
+    pub path: Option<PathBuf>,
+    /// The size in bytes at which the file is rotated.
+    pub max_file_bytes: u64,
+    /// The number of rotated files that are kept, from `<path>.1` (the newest) to
+    /// `<path>.<max_files>`.
+    pub max_files: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        AuditConfig {
+            enabled: true,
+            path: None,
+            max_file_bytes: 10 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+/// Limits of request bodies, in bytes.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// The limit of JSON requests.
+    pub max_body_bytes: usize,
+    /// The limit of CSV files imported with `POST /tables/{name}/csv`.
+    pub max_csv_import_bytes: usize,
+    /// The limit of scripts restored with `POST /restore`.
+    pub max_restore_bytes: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            max_body_bytes: 2 * 1024 * 1024,
+            max_csv_import_bytes: 256 * 1024 * 1024,
+            max_restore_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// Optional groups of endpoints.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeaturesConfig {
+    /// The HTML overview of all tables at `/`.
+    pub web_ui: bool,
+    /// The snapshot and write-ahead log endpoints under `/admin`.
+    pub admin_api: bool,
+    /// The CSV import and export endpoints.
+    pub csv: bool,
+    /// `GET /dump` and `POST /restore`.
+    pub sql_dump: bool,
+    /// The Prometheus metrics at `/metrics`.
+    pub metrics: bool,
+}
+
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        FeaturesConfig {
+            web_ui: true,
+            admin_api: true,
+            csv: true,
+            sql_dump: true,
+            metrics: true,
+        }
+    }
+}
+
+/// Who may send requests.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Whether requests need HTTP Basic or bearer authentication. Without it, every request is
+    /// treated as coming from an admin.
+    pub enabled: bool,
+    /// The name of the admin user created when the server starts without users.
+    pub admin_user: String,
+    /// The password of the admin user created at the first start. A random password is
+    /// generated and logged if none is given. Never printed with `--print-config`.
+    #[serde(skip_serializing)]
+    pub admin_password: Option<String>,
+    /// How long a token issued by `/login` is valid.
+    #[serde(with = "humantime_serde")]
+    pub token_ttl: Duration,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            enabled: true,
+            admin_user: "admin".to_string(),
+            admin_password: None,
+            token_ttl: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// Settings given on the command line or in environment variables, overriding the file.
+#[derive(clap::Args, Default, Debug)]
+pub struct Overrides {
+    /// Addresses to listen on, separated by commas.
+    #[arg(long, env = "RUST_DB_BIND", value_name = "ADDR", value_delimiter = ',')]
+    pub bind: Vec<String>,
+
+    /// Time a request may take until its response starts, e.g. `30s`; `0` disables the limit.
+    #[arg(long, env = "RUST_DB_REQUEST_TIMEOUT", value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub request_timeout: Option<Duration>,
+
+    /// Time requests in flight may take to finish when the server shuts down, e.g. `10s`.
+    #[arg(long, env = "RUST_DB_SHUTDOWN_TIMEOUT", value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub shutdown_timeout: Option<Duration>,
+
+    /// PEM file of the server's certificate chain, which enables HTTPS.
+    #[arg(long, env = "RUST_DB_TLS_CERT", value_name = "PATH")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM file of the private key of the server's certificate.
+    #[arg(long, env = "RUST_DB_TLS_KEY", value_name = "PATH")]
+    pub tls_key: Option<PathBuf>,
+
+    /// PEM file of the CA certificates that client certificates are verified against.
+    #[arg(long, env = "RUST_DB_TLS_CLIENT_CA", value_name = "PATH")]
+    pub tls_client_ca: Option<PathBuf>,
+
+    /// Directory of `db.json`, the write-ahead log and snapshots.
+    #[arg(long, env = "RUST_DB_DATA_DIR", value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Whether tables are kept on disk or in memory only.
+    #[arg(long, env = "RUST_DB_PERSISTENCE")]
+    pub persistence: Option<Persistence>,
+
+    /// Whether written files are synced to disk after every change.
+    #[arg(long, env = "RUST_DB_FSYNC")]
+    pub fsync: Option<FsyncPolicy>,
+
+    /// Directory completed write-ahead log segments are archived to.
+    #[arg(long, env = "RUST_DB_WAL_ARCHIVE", value_name = "DIR")]
+    pub wal_archive: Option<PathBuf>,
+
+    /// Most verbose level that is logged: off, error, warn, info, debug or trace.
+    #[arg(long, env = "RUST_DB_LOG_LEVEL", value_name = "LEVEL")]
+    pub log_level: Option<LevelFilter>,
+
+    /// Format of log lines.
+    #[arg(long, env = "RUST_DB_LOG_FORMAT")]
+    pub log_format: Option<LogFormat>,
+
+    /// Duration from which requests are logged as slow queries, e.g. `500ms`.
+    #[arg(long, env = "RUST_DB_SLOW_QUERY_THRESHOLD", value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub slow_query_threshold: Option<Duration>,
+
+    /// Enables or disables the audit log.
+    #[arg(long, env = "RUST_DB_AUDIT", value_name = "BOOL")]
+    pub audit: Option<bool>,
+
+    /// File the audit log is appended to.
+    #[arg(long, env = "RUST_DB_AUDIT_PATH", value_name = "PATH")]
+    pub audit_path: Option<PathBuf>,
+
+    /// Limit of JSON request bodies in bytes.
+    #[arg(long, env = "RUST_DB_MAX_BODY_BYTES", value_name = "BYTES")]
+    pub max_body_bytes: Option<usize>,
+
+    /// Enables or disables the HTML overview at `/`.
+    #[arg(long, env = "RUST_DB_WEB_UI", value_name = "BOOL")]
+    pub web_ui: Option<bool>,
+
+    /// Enables or disables the endpoints under `/admin`.
+    #[arg(long, env = "RUST_DB_ADMIN_API", value_name = "BOOL")]
+    pub admin_api: Option<bool>,
+
+    /// Enables or disables authentication.
+    #[arg(long, env = "RUST_DB_AUTH", value_name = "BOOL")]
+    pub auth: Option<bool>,
+
+    /// Name of the admin user created when the server starts without users.
+    #[arg(long, env = "RUST_DB_ADMIN_USER", value_name = "NAME")]
+    pub admin_user: Option<String>,
+
+    /// Password of the admin user created at the first start. Prefer the environment variable,
+    /// as command lines are visible to other users of the machine.
+    #[arg(
+        long,
+        env = "RUST_DB_ADMIN_PASSWORD",
+        value_name = "PASSWORD",
+        hide_env_values = true
+    )]
+    pub admin_password: Option<String>,
+}
+
+impl Config {
+    /// Reads the configuration from a TOML file and applies the overrides.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The configuration file, or `None` to start from the defaults.
+    /// * `overrides` - The settings given on the command line or in environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not valid TOML or contains unknown
+    /// settings, or if the resulting configuration is invalid.
+    pub fn load(path: Option<&Path>, overrides: Overrides) -> Result<Config, String> {
+        let mut config = match path {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                toml::from_str(&contents)
+                    .map_err(|e| format!("Invalid configuration in {}: {}", path.display(), e))?
+            }
+            None => Config::default(),
+        };
+        config.apply(overrides);
+        // A zero timeout disables the limit, as a setting cannot be removed by an override
+        config.server.request_timeout = config
+            .server
+            .request_timeout
+            .filter(|timeout| !timeout.is_zero());
+        config.tls.reload_interval = config
+            .tls
+            .reload_interval
+            .filter(|interval| !interval.is_zero());
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Replaces the settings that are given in `overrides`.
+    fn apply(&mut self, overrides: Overrides) {
+        if !overrides.bind.is_empty() {
+            self.server.bind = overrides.bind;
+        }
+        if overrides.request_timeout.is_some() {
+            self.server.request_timeout = overrides.request_timeout;
+        }
+        if let Some(shutdown_timeout) = overrides.shutdown_timeout {
+            self.server.shutdown_timeout = shutdown_timeout;
+        }
+        if overrides.tls_cert.is_some() {
+            self.tls.cert = overrides.tls_cert;
+        }
+        if overrides.tls_key.is_some() {
+            self.tls.key = overrides.tls_key;
+        }
+        if overrides.tls_client_ca.is_some() {
+            self.tls.client_ca = overrides.tls_client_ca;
+        }
+        if let Some(data_dir) = overrides.data_dir {
+            self.storage.data_dir = data_dir;
+        }
+        if let Some(persistence) = overrides.persistence {
+            self.storage.persistence = persistence;
+        }
+        if let Some(fsync) = overrides.fsync {
+            self.storage.fsync = fsync;
+        }
+        if overrides.wal_archive.is_some() {
+            self.storage.wal_archive = overrides.wal_archive;
+        }
+        if let Some(level) = overrides.log_level {
+            self.log.level = level;
+        }
+        if let Some(format) = overrides.log_format {
+            self.log.format = format;
+        }
+        if overrides.slow_query_threshold.is_some() {
+            self.log.slow_query_threshold = overrides.slow_query_threshold;
+        }
+        if let Some(audit) = overrides.audit {
+            self.audit.enabled = audit;
+        }
+        if overrides.audit_path.is_some() {
+            self.audit.path = overrides.audit_path;
+        }
+        if let Some(max_body_bytes) = overrides.max_body_bytes {
+            self.limits.max_body_bytes = max_body_bytes;
+        }
+        if let Some(web_ui) = overrides.web_ui {
+            self.features.web_ui = web_ui;
+        }
+        if let Some(admin_api) = overrides.admin_api {
+            self.features.admin_api = admin_api;
+        }
+        if let Some(auth) = overrides.auth {
+            self.auth.enabled = auth;
+        }
+        if let Some(admin_user) = overrides.admin_user {
+            self.auth.admin_user = admin_user;
+        }
+        if overrides.admin_password.is_some() {
+            self.auth.admin_password = overrides.admin_password;
+        }
+    }
+
+    /// Checks settings that cannot be checked while parsing.
+    fn validate(&self) -> Result<(), String> {
+        if self.server.bind.is_empty() {
+            return Err("At least one bind address is required".to_string());
+        }
+        // Host names are resolved when binding, but the port has to be given
+        if let Some(address) = self.server.bind.iter().find(|address| {
+            address
+                .rsplit_once(':')
+                .is_none_or(|(_, port)| port.parse::<u16>().is_err())
+        }) {
+            return Err(format!("Invalid bind address '{}'", address));
+        }
+        if self.tls.cert.is_some() != self.tls.key.is_some() {
+            return Err("TLS needs both a certificate and a key".to_string());
+        }
+        if self.tls.client_ca.is_some() && !self.tls.enabled() {
+            return Err("Verifying client certificates needs a server certificate".to_string());
+        }
+        if self.audit.max_file_bytes == 0 {
+            return Err("The audit log's max_file_bytes must be positive".to_string());
+        }
+        if self.auth.admin_user.is_empty() {
+            return Err("The admin user name must not be empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// Writes the configuration as TOML, in the format of the configuration file.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("Failed to serialize the configuration")
+    }
+}
@@ -1,96 +1,621 @@
-use axum::http::StatusCode;
+use audit::{Audit, AuditLog};
+use auth::{Auth, AuthError, Principal, USERS_FILE};
+use axum::body::Body;
+use axum::http::HeaderMap;
+use axum::http::{header, StatusCode};
 use axum::response::Response;
 use axum::response::{Html, IntoResponse};
 use axum::{
-    extract::State,
+    extract::{DefaultBodyLimit, Extension, Path, Query, Request, State},
+    middleware::{self, Next},
     routing::{get, post},
     Json, Router,
 };
+use backup::{RestoreTarget, Snapshot, Wal, WalOperation, DATABASE_FILE, SNAPSHOT_DIR, WAL_DIR};
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::{Parser, Subcommand};
+use config::{Config, FsyncPolicy, LogConfig, LogFormat, Overrides, Persistence, StorageConfig};
 use core::{
-    column::Column,
+    catalog,
+    client_functions::{self, TlsOptions},
+    column::{Column, ForeignKey},
+    csv::{self, CsvOptions},
+    dump::{self, DEFAULT_BATCH_SIZE},
+    plan::{Index, LogicalPlan, PhysicalPlan},
+    policy::{RowSecurity, Session},
+    privilege::Privilege,
     request_types::{
-        Condition, CreateRequests, CreateTableRequests, DropTableRequest, InsertColumnRequest,
-        InsertRowRequest, RenameTableRequest, SelectRequest, UpdateRequest,
+        AlterUserRequest, ColumnHeader, Condition, CreateIndexRequest, CreatePolicyRequest,
+        CreateRequests, CreateTableRequests, CreateUserRequest, CsvImportOptions, CsvImportResult,
+        DeleteRequest, DropPolicyRequest, DropTableRequest, DropUserRequest, DumpOptions,
+        ExplainMode, GrantRequest, GrantRoleRequest, GrantsQuery, InsertColumnRequest,
+        InsertRowRequest, JoinRequest, LoginRequest, LoginResponse, Readiness, RenameTableRequest,
+        RestoreResult, RoleRequest, RowsPage, RowsQuery, SelectFrame, SelectOptions, SelectRequest,
+        ServerInfo, SnapshotInfo, StreamFormat, UpdateRequest, WalStatus,
     },
     row::Row,
-    table::Table,
-    value::Value,
+    sql::{
+        ast::{CreateIndex, Expr, Insert, Statement},
+        parse_statements,
+    },
+    table::{Table, TableSchema, UniqueConstraint},
+    value::{DataType, Value},
 };
-use log::{error, info, LevelFilter};
+use log::{debug, error, info, warn};
+use metrics::Metrics;
+use serde::{Deserialize, Serialize};
+use shutdown::Shutdown;
+use statistics::{Execution, Statistics};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::io::Error;
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 use tokio::fs::{File, OpenOptions};
 use tokio::io::{self, AsyncReadExt, AsyncWriteExt, BufReader};
-use tokio::sync::Mutex;
-use tokio::{signal::ctrl_c, spawn};
+use tokio::spawn;
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::wrappers::ReceiverStream;
+
+mod audit;
+mod auth;
+mod backup;
+mod config;
+mod metrics;
+mod shutdown;
+mod statistics;
+mod tls;
+
+/// The Rust Database server.
+///
+/// Serves the tables in `db.json` of the data directory, logging every change to the
+/// write-ahead log in `wal/`. Settings are read from the configuration file and overridden by
+/// environment variables and flags.
+#[derive(Parser)]
+#[command(name = "server")]
+struct Args {
+    /// TOML configuration file.
+    #[arg(short, long, env = "RUST_DB_CONFIG", value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Prints the effective configuration as TOML and exits.
+    #[arg(long)]
+    print_config: bool,
+
+    #[command(flatten)]
+    overrides: Overrides,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Rebuilds a data directory from a snapshot and archived write-ahead log segments.
+    Restore(RestoreArgs),
+    /// Checks that a running server is up, exiting with 1 if it is not. Meant for container
+    /// health checks.
+    Healthcheck(HealthcheckArgs),
+}
+
+#[derive(clap::Args)]
+struct HealthcheckArgs {
+    /// URL of the server. Defaults to the first bind address on this machine, with HTTPS if
+    /// TLS is configured.
+    #[arg(long, value_name = "URL")]
+    url: Option<String>,
+
+    /// PEM file of a CA certificate to trust in addition to the system's.
+    #[arg(long, value_name = "PATH")]
+    ca_cert: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct RestoreArgs {
+    /// Snapshot written by `POST /admin/snapshot`.
+    #[arg(long, value_name = "PATH")]
+    snapshot: PathBuf,
+
+    /// Directory of write-ahead log segments to replay. Can be repeated.
+    #[arg(long = "wal", value_name = "DIR")]
+    wal_dirs: Vec<PathBuf>,
+
+    /// Stops after the change with this log sequence number.
+    #[arg(long, value_name = "LSN", conflicts_with = "until_time")]
+    until_lsn: Option<u64>,
+
+    /// Stops after the last change logged at or before this time, e.g. 2024-05-01T12:00:00Z.
+    #[arg(long, value_name = "TIME")]
+    until_time: Option<DateTime<Utc>>,
+
+    /// Data directory to create. Start the server in it to serve the restored tables.
+    #[arg(long, value_name = "DIR")]
+    data_dir: PathBuf,
+}
 
 #[tokio::main]
 async fn main() {
+    let args = Args::parse();
+    let config = match Config::load(args.config.as_deref(), args.overrides) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        }
+    };
+    if args.print_config {
+        print!("{}", config.to_toml());
+        return;
+    }
+
     // Initialize logger
-    env_logger::builder()
-        .filter_level(LevelFilter::Info)
-        .format_timestamp_millis()
-        .init();
+    init_logger(&config.log);
+
+    match args.command {
+        Some(Command::Restore(restore_args)) => {
+            run_restore(restore_args);
+            return;
+        }
+        Some(Command::Healthcheck(healthcheck_args)) => {
+            run_healthcheck(&config, healthcheck_args).await;
+            return;
+        }
+        None => {}
+    }
 
-    // Load application state from file or create new state
+    // Load application state from file, logging changes to the write-ahead log
+    let storage = &config.storage;
+    let wal = match storage.persistence {
+        Persistence::Disk => match Wal::open(
+            &storage.path(WAL_DIR),
+            storage.wal_archive.clone(),
+            storage.fsync,
+        ) {
+            Ok(wal) => Some(wal),
+            Err(err) => {
+                error!("Failed to open the write-ahead log: {}", err);
+                return;
+            }
+        },
+        Persistence::Memory => {
+            info!("Keeping tables in memory only");
+            None
+        }
+    };
+    let users_path = (storage.persistence == Persistence::Disk).then(|| storage.path(USERS_FILE));
+    let auth = match Auth::load(&config.auth, users_path).await {
+        Ok(auth) => auth,
+        Err(error) => {
+            error!("{}", error);
+            return;
+        }
+    };
+    if !config.auth.enabled {
+        info!("Authentication is disabled, every request is served");
+    }
+    let audit = match AuditLog::open(&config.audit, &config.storage) {
+        Ok(audit) => audit,
+        Err(err) => {
+            error!("Failed to open the audit log: {}", err);
+            return;
+        }
+    };
+    let statistics = Statistics::new(config.log.slow_query_threshold);
     let app_state: Arc<AppState> =
-        Arc::new(AppState::load().await.unwrap_or_else(|_| AppState::new()));
+        match AppState::load(&config.storage, wal, auth, audit, statistics).await {
+            Ok(state) => Arc::new(state),
+            Err(err) => {
+                error!("Failed to load {}: {}", DATABASE_FILE, err);
+                return;
+            }
+        };
+
+    let app = router(&config, Arc::clone(&app_state));
+
+    // Load the certificates once, so all listeners serve the reloaded ones
+    let rustls = match config.tls.enabled() {
+        true => match tls::load(&config.tls) {
+            Ok(rustls) => {
+                tls::spawn_reloader(config.tls.clone(), rustls.clone());
+                Some(rustls)
+            }
+            Err(error) => {
+                error!("Failed to load the TLS certificates: {}", error);
+                return;
+            }
+        },
+        false => None,
+    };
+    let scheme = if rustls.is_some() { "https" } else { "http" };
+
+    // Start an HTTP server on every bind address, which stops accepting connections and drains
+    // the requests in flight once the server is shutting down
+    let shutdown = app_state.shutdown.clone();
+    let mut server_tasks = Vec::new();
+    for address in &config.server.bind {
+        let listener = match tokio::net::TcpListener::bind(address).await {
+            Ok(listener) => {
+                info!("Http service started running on {}://{}", scheme, address);
+                listener
+            }
+            Err(err) => {
+                error!("Failed to bind {}: {}", address, err);
+                return;
+            }
+        };
+        let app = app.clone();
+        let rustls = rustls.clone();
+        let shutdown = shutdown.clone();
+        server_tasks.push(spawn(async move {
+            let result = match rustls {
+                Some(rustls) => match listener.into_std() {
+                    Ok(listener) => {
+                        let handle = axum_server::Handle::new();
+                        spawn({
+                            let handle = handle.clone();
+                            async move {
+                                shutdown.triggered().await;
+                                handle.graceful_shutdown(None);
+                            }
+                        });
+                        axum_server::from_tcp_rustls(listener, rustls)
+                            .handle(handle)
+                            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                            .await
+                    }
+                    Err(err) => Err(err),
+                },
+                None => {
+                    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+                    axum::serve(listener, app)
+                        .with_graceful_shutdown(async move { shutdown.triggered().await })
+                        .await
+                }
+            };
+            if let Err(err) = result {
+                error!("Server error: {}", err);
+            }
+        }));
+    }
+
+    // Shut down on SIGINT or SIGTERM, cancelling the requests that take too long to drain
+    shutdown::signal_received().await;
+    shutdown.trigger();
+    let shutdown_timeout = config.server.shutdown_timeout;
+    let drained = tokio::time::timeout(shutdown_timeout, async {
+        for server_task in &mut server_tasks {
+            if let Err(err) = server_task.await {
+                error!("Server task error: {}", err);
+            }
+        }
+    })
+    .await;
+    if drained.is_err() {
+        warn!(
+            "Cancelling the requests still running after {}",
+            humantime::format_duration(shutdown_timeout)
+        );
+        for server_task in &server_tasks {
+            server_task.abort();
+        }
+    }
+
+    // Persist the state before exiting
+    match app_state.close().await {
+        Ok(()) => info!("Saved the tables, exiting"),
+        Err(err) => {
+            error!("Failed to save state: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
 
-    // Define routes and handlers
-    let app = Router::new()
-        .route("/", get(root))
+/// Initialize the logger with the configured level and format
+fn init_logger(config: &LogConfig) {
+    use std::io::Write;
+
+    let mut builder = env_logger::builder();
+    builder.filter_level(config.level).format_timestamp_millis();
+    if config.format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let line = serde_json::json!({
+                "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{}", line)
+        });
+    }
+    builder.init();
+}
+
+/// Define the routes and handlers of the enabled features
+fn router(config: &Config, state: Arc<AppState>) -> Router {
+    let features = &config.features;
+    let limits = &config.limits;
+    let mut app = Router::new()
         .route("/tables", get(get_tables))
+        .route("/tables/:name", get(get_table))
+        .route("/tables/:name/rows", get(get_rows))
         .route("/create", post(create))
         .route("/create_table", post(create_table))
         .route("/drop_table", post(drop_table))
         .route("/rename_table", post(rename_table))
         .route("/update_table", post(update_table))
+        .route("/delete", post(delete_rows))
         .route("/insert_column", post(insert_column))
         .route("/insert_row", post(insert_row))
         .route("/select", post(select))
-        .with_state(Arc::clone(&app_state));
+        .route("/join", post(join_tables))
+        .route("/create_index", post(create_index))
+        .route("/create_policy", post(create_policy))
+        .route("/drop_policy", post(drop_policy));
+    if features.web_ui {
+        app = app.route("/", get(root));
+    }
+    if features.csv {
+        app = app
+            .route(
+                "/tables/:name/csv",
+                get(export_csv)
+                    .post(import_csv)
+                    .layer(DefaultBodyLimit::max(limits.max_csv_import_bytes)),
+            )
+            .route("/select/csv", post(select_csv));
+    }
+    if features.sql_dump {
+        app = app.route("/dump", get(dump)).route(
+            "/restore",
+            post(restore).layer(DefaultBodyLimit::max(limits.max_restore_bytes)),
+        );
+    }
+    app = app
+        .route(
+            "/info",
+            get(info).layer(Extension(enabled_features(config))),
+        )
+        .route("/alter_user", post(alter_user))
+        .route("/grants", get(get_grants))
+        .route("/logout", post(logout));
+
+    // User, role and privilege management and the admin API are restricted to admins
+    let mut admin = Router::new()
+        .route("/users", get(get_users))
+        .route("/create_user", post(create_user))
+        .route("/drop_user", post(drop_user))
+        .route("/roles", get(get_roles))
+        .route("/create_role", post(create_role))
+        .route("/drop_role", post(drop_role))
+        .route("/grant_role", post(grant_role))
+        .route("/revoke_role", post(revoke_role))
+        .route("/grant", post(grant))
+        .route("/revoke", post(revoke));
+    if features.metrics {
+        admin = admin.route("/metrics", get(get_metrics));
+    }
+    if features.admin_api {
+        admin = admin
+            .route("/admin/snapshot", post(create_snapshot))
+            .route("/admin/wal", get(wal_status))
+            .route("/admin/wal/switch", post(switch_wal))
+            .route("/admin/statements/reset", post(reset_statements));
+    }
+    let auth = Arc::clone(&state.auth);
+    let audit = Arc::clone(&state.audit);
+    let statistics = Arc::clone(&state.statistics);
+    let metrics = Arc::clone(&state.metrics);
+    let mut app = app
+        .merge(admin.route_layer(middleware::from_fn(auth::require_admin)))
+        .route_layer(middleware::from_fn_with_state(
+            statistics,
+            statistics::record,
+        ))
+        .route_layer(middleware::from_fn_with_state(audit, audit::record))
+        .route_layer(middleware::from_fn_with_state(auth, auth::authenticate))
+        // Logging in and the probes of the server's state need no credentials
+        .route("/login", post(login))
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+        .layer(DefaultBodyLimit::max(limits.max_body_bytes))
+        .with_state(state);
+    if let Some(request_timeout) = config.server.request_timeout {
+        app = app.layer(middleware::from_fn_with_state(request_timeout, timeout));
+    }
+    // Outermost, so that timed out and unauthenticated requests are counted as well
+    app.layer(middleware::from_fn_with_state(metrics, metrics::track))
+}
+
+/// Helper function to list the enabled optional features, as reported by `/info`
+fn enabled_features(config: &Config) -> Vec<&'static str> {
+    let features = &config.features;
+    [
+        ("web_ui", features.web_ui),
+        ("admin_api", features.admin_api),
+        ("csv", features.csv),
+        ("sql_dump", features.sql_dump),
+        ("metrics", features.metrics),
+        ("auth", config.auth.enabled),
+        ("audit", config.audit.enabled),
+        ("tls", config.tls.enabled()),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect()
+}
 
-    // Start HTTP server
-    let address = "0.0.0.0:3000";
-    let listener = match tokio::net::TcpListener::bind(address).await {
-        Ok(listener) => {
-            info!("Http service started running on http://{}", address);
-            listener
+/// Middleware to answer requests whose response does not start within the request timeout
+async fn timeout(State(limit): State<Duration>, request: Request, next: Next) -> Response {
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            let error = format!(
+                "The request timed out after {}",
+                humantime::format_duration(limit)
+            );
+            error!("{}", error);
+            (StatusCode::REQUEST_TIMEOUT, Json(error)).into_response()
         }
+    }
+}
+
+/// Check that the server is up as requested by `server healthcheck`
+async fn run_healthcheck(config: &Config, args: HealthcheckArgs) {
+    let url = args.url.unwrap_or_else(|| {
+        let scheme = if config.tls.enabled() {
+            "https"
+        } else {
+            "http"
+        };
+        // Addresses listening on all interfaces are reached through the loopback interface
+        let address = config.server.bind[0]
+            .replace("0.0.0.0", "127.0.0.1")
+            .replace("[::]", "[::1]");
+        format!("{}://{}", scheme, address)
+    });
+    client_functions::set_server_url(&url);
+    let tls = TlsOptions {
+        ca_cert: args.ca_cert,
+        ..TlsOptions::default()
+    };
+    let result = match client_functions::build_client(&tls) {
+        Ok(client) => client_functions::ping(&client).await,
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(round_trip) => info!("The server at {} answered in {:?}", url, round_trip),
         Err(err) => {
-            error!("Error: {}", err);
-            return;
+            error!("The server at {} is not healthy: {}", url, err);
+            std::process::exit(1);
         }
-    };
+    }
+}
 
-    let server_task = spawn(async move {
-        if let Err(err) = axum::serve(listener, app).await {
-            error!("Server error: {}", err);
+/// Rebuild a data directory as requested by `server restore`
+fn run_restore(args: RestoreArgs) {
+    let target = match (args.until_lsn, args.until_time) {
+        (Some(lsn), _) => RestoreTarget::Lsn(lsn),
+        (None, Some(time)) => RestoreTarget::Time(time),
+        (None, None) => RestoreTarget::Latest,
+    };
+    match backup::restore(&args.snapshot, &args.wal_dirs, target, &args.data_dir) {
+        Ok(summary) => info!(
+            "Restored {} tables into {} at LSN {}, replaying {} changes after the snapshot at LSN {}",
+            summary.table_count,
+            args.data_dir.display(),
+            summary.lsn,
+            summary.replayed,
+            summary.snapshot_lsn
+        ),
+        Err(error) => {
+            error!("{}", error);
+            std::process::exit(1);
         }
-    });
+    }
+}
 
-    // Handle Ctrl+C (SIGINT) to gracefully shut down the server
-    let _ = spawn({
-        let app_state = Arc::clone(&app_state);
-        async move {
-            ctrl_c().await.expect("Failed to listen for Ctrl+C");
-            if let Err(err) = app_state.save().await {
-                error!("Failed to save state: {}", err);
+/// Handler for root endpoint
+///
+/// Users who are not admins only see the tables and columns they may select, and the rows
+/// row-level security lets them see.
+async fn root(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+) -> impl IntoResponse {
+    let mut tables = state.get_all().await;
+    if !principal.admin {
+        let mut selectable = Vec::new();
+        for mut table in tables {
+            if let Some(security) = row_security(&principal, &session, &table) {
+                table.rows.retain(|row| security.visible(row));
+            }
+            if let Some(table) = selectable_columns(&state, &principal, table).await {
+                selectable.push(table);
             }
         }
-    })
-    .await;
+        tables = selectable;
+    }
+    Html(format_tables_html(tables))
+}
+
+/// Helper function to remove the columns of a table the user may not select, or the whole
+/// table if there are none left
+async fn selectable_columns(
+    state: &AppState,
+    principal: &Principal,
+    mut table: Table,
+) -> Option<Table> {
+    let mut selectable = Vec::new();
+    for column in &table.columns {
+        let allowed = state
+            .auth
+            .check(
+                principal,
+                Privilege::Select,
+                Some(&table.name),
+                &[&column.key],
+            )
+            .await
+            .is_ok();
+        selectable.push(allowed);
+    }
+    if !selectable.contains(&true) {
+        return None;
+    }
+
+    let is_selectable = |key: &String| {
+        table
+            .columns
+            .iter()
+            .zip(&selectable)
+            .any(|(column, allowed)| *allowed && &column.key == key)
+    };
+    if !table.primary_key.iter().all(is_selectable) {
+        table.primary_key.clear();
+    }
+    let constraints = std::mem::take(&mut table.unique_constraints);
+    table.unique_constraints = constraints
+        .into_iter()
+        .filter(|constraint| constraint.columns.iter().all(is_selectable))
+        .collect();
 
-    // Wait for server task to finish (though it should run indefinitely until SIGINT)
-    if let Err(err) = server_task.await {
-        error!("Server task error: {}", err);
+    let keep = |values: Vec<Value>| -> Vec<Value> {
+        values
+            .into_iter()
+            .zip(&selectable)
+            .filter_map(|(value, allowed)| allowed.then_some(value))
+            .collect()
+    };
+    for row in &mut table.rows {
+        row.values = keep(std::mem::take(&mut row.values));
     }
+    table.columns = table
+        .columns
+        .into_iter()
+        .zip(&selectable)
+        .filter_map(|(column, allowed)| allowed.then_some(column))
+        .collect();
+    Some(table)
 }
 
-/// Handler for root endpoint
-async fn root(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let tables = state.get_all().await;
-    Html(format_tables_html(tables))
+/// Helper function to escape text for HTML, so names and values stored by users cannot add markup
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
 }
 
 /// Format tables data into HTML
@@ -120,30 +645,73 @@ fn format_tables_html(tables: Vec<Table>) -> String {
         html.push_str(&format!(
             r#"
             <h2>{}</h2>
+        "#,
+            escape_html(&table.name)
+        ));
+
+        let mut constraints = Vec::new();
+        if !table.primary_key.is_empty() {
+            constraints.push(format!("Primary Key ({})", table.primary_key.join(", ")));
+        }
+        for constraint in &table.unique_constraints {
+            constraints.push(format!(
+                "Unique {} ({})",
+                constraint.name,
+                constraint.columns.join(", ")
+            ));
+        }
+        for constraint in constraints {
+            html.push_str(&format!(
+                r#"
+            <p class="label">{}</p>
+        "#,
+                escape_html(&constraint)
+            ));
+        }
+
+        html.push_str(
+            r#"
             <table>
                 <tr>
         "#,
-            table.name
-        ));
+        );
 
         for column in &table.columns {
             let mut labels = Vec::new();
 
+            if let Some(data_type) = column.data_type {
+                labels.push(data_type.to_string());
+            }
             if column.primary_key {
-                labels.push("Primary");
+                labels.push("Primary".to_string());
             }
             if column.unique {
-                labels.push("Unique");
+                labels.push("Unique".to_string());
             }
             if column.non_null {
-                labels.push("Non-Null");
+                labels.push("Non-Null".to_string());
+            }
+            if column.auto_increment {
+                labels.push("Auto-Increment".to_string());
+            }
+            if let Some(default) = &column.default {
+                labels.push(format!("Default {}", default.to_sql()));
+            }
+            if let Some(check) = &column.check {
+                labels.push(format!("Check ({})", check));
+            }
+            if let Some(foreign_key) = &column.foreign_key {
+                labels.push(format!(
+                    "References {}({})",
+                    foreign_key.table_name, foreign_key.column
+                ));
             }
 
             let labels_str = labels.join(", ");
 
             html.push_str(&format!(r#"
                     <th style="border-right: 1px solid #ddd;"><span style="float: left;">{}</span><span class="label" style="float: right;">{}</span></th>
-            "#, column.key, labels_str));
+            "#, escape_html(&column.key), escape_html(&labels_str)));
         }
 
         html.push_str(
@@ -164,7 +732,7 @@ fn format_tables_html(tables: Vec<Table>) -> String {
                     r#"
                     <td style="border-right: 1px solid #ddd;">{}</td>
                 "#,
-                    value.as_string().unwrap_or_default()
+                    escape_html(&value.as_string().unwrap_or_default())
                 ));
             }
 
@@ -192,156 +760,547 @@ fn format_tables_html(tables: Vec<Table>) -> String {
     html
 }
 
-/// Handler to get all tables
-async fn get_tables(State(state): State<Arc<AppState>>) -> Json<Vec<Table>> {
-    let tables = state.get_all().await;
-    let json = Json(tables);
-    info!("Tables: {:?}", json);
-    json
-}
+/// The number of rows returned by `/tables/{name}/rows` when no limit is given.
+const DEFAULT_PAGE_SIZE: usize = 100;
 
-/// Handler to create a new table
+/// The largest number of rows returned by `/tables/{name}/rows` at once.
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Handler to get the schemas of all tables
 ///
 /// # Example
 ///
 /// ```
-/// curl -X POST http://localhost:3000/create -H "Content-Type: application/json" -d '{"name":"test_table"}'
+/// curl http://localhost:3000/tables
 /// ```
 ///
-/// Creates a new table in the database.
+/// ## Returns
+///
+/// Returns a JSON array with the name, columns, primary key, unique constraints and row count of
+/// every table. The rows themselves are not included, use `/tables/{name}/rows` to fetch them.
+///
+/// ## Notes
+///
+/// - Users who are not admins only see the tables they have a privilege on.
+/// - Of a table with row-level security policies, only the rows the user may see are counted.
+async fn get_tables(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+) -> Json<Vec<TableSchema>> {
+    let visible = state.auth.visible_tables(&principal).await;
+    let mut schemas = state.visible_schemas(&principal, &session).await;
+    schemas.retain(|schema| visible(&schema.name));
+    debug!("Listed {} tables", schemas.len());
+    Json(schemas)
+}
+
+/// Handler to get the schema of a table
+///
+/// # Example
+///
+/// ```
+/// curl http://localhost:3000/tables/test_table
+/// ```
 ///
 /// ## Parameters
 ///
-/// - `name`: The name of the table to be created.
+/// - `name`: The name of the table.
 ///
 /// ## Returns
 ///
-/// Returns a JSON object representing the newly created table.
+/// Returns a JSON object with the name, columns, primary key, unique constraints and row count of
+/// the table.
 ///
 /// ## Errors
 ///
-/// - Returns an error if a table with the same name already exists.
-async fn create(
+/// - Returns an error if the table does not exist.
+/// - Returns an error if the user has no privilege on the table.
+///
+/// ## Notes
+///
+/// - Of a table with row-level security policies, only the rows the user may see are counted.
+async fn get_table(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<CreateRequests>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Path(name): Path<String>,
 ) -> Response {
-    let table_name = payload.name;
-
-    if state.get(&table_name).await.is_some() {
-        let error = format!("Table '{}' already exists", table_name);
-        error!("{}", error);
-        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
-    }
-
-    let new_table = Table {
-        name: table_name,
-        columns: Vec::new(),
-        rows: Vec::new(),
-    };
-
-    state.create(new_table.clone()).await;
-    match state.save().await {
-        Ok(_) => {
-            info!("Created table: {:?}", &new_table);
-            (StatusCode::OK, Json(new_table)).into_response()
+    let visible = state.auth.visible_tables(&principal).await;
+    match state.visible_schema(&name, &principal, &session).await {
+        Some(_) if !visible(&name) => {
+            let error = format!("Permission denied: no privilege on table '{}'", name);
+            error!("{}", error);
+            (StatusCode::FORBIDDEN, Json(error)).into_response()
         }
-        Err(err) => {
-            let error = format!("Failed to save state: {}", err);
+        Some(schema) => (StatusCode::OK, Json(schema)).into_response(),
+        None => {
+            let error = format!("Table '{}' does not exist", name);
             error!("{}", error);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
         }
     }
 }
 
-/// Handler to drop a table
+/// Handler to get a page of a table's rows
 ///
 /// # Example
 ///
 /// ```
-/// curl -X POST http://localhost:3000/drop_table -H "Content-Type: application/json" -d '{"name":"test_table"}'
+/// curl "http://localhost:3000/tables/test_table/rows?limit=2&order_by=-test_key"
+/// curl "http://localhost:3000/tables/test_table/rows?limit=2&order_by=-test_key&cursor=<next_cursor>"
 /// ```
 ///
-/// Drops a table from the database.
-///
 /// ## Parameters
 ///
-/// - `name`: The name of the table to be dropped.
+/// - `name`: The name of the table.
+/// - `limit`: Optional. The maximum number of rows to return, 100 by default and at most 1000.
+/// - `offset`: Optional. The number of rows to skip.
+/// - `order_by`: Optional. Comma separated columns to sort by, each prefixed with `-` for
+///   descending order. Defaults to the primary key, or insertion order without one.
+/// - `cursor`: Optional. The `next_cursor` of the previous page, to continue after its last row.
 ///
 /// ## Returns
 ///
-/// Returns a success message if the table is dropped successfully.
+/// Returns a JSON object with the column names, the rows of the page, the total number of rows
+/// and the cursor of the next page, which is `null` on the last page.
 ///
 /// ## Errors
 ///
 /// - Returns an error if the table does not exist.
-async fn drop_table(
+/// - Returns an error if `order_by` names a column that does not exist.
+/// - Returns an error if the cursor is malformed, was created with a different `order_by`, or
+///   is combined with `offset`.
+/// - Returns an error if the user may not select every column of the table.
+///
+/// ## Notes
+///
+/// - A cursor stores the sort key of the last row rather than its position, so pages do not
+///   skip or repeat rows when rows are inserted between requests. Rows with equal sort keys
+///   are ordered by their primary key, so in tables with one deletes do not either. Tables
+///   without one order them by insertion.
+/// - Rows hidden by row-level security are not returned or counted.
+async fn get_rows(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<DropTableRequest>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Path(name): Path<String>,
+    Query(query): Query<RowsQuery>,
 ) -> Response {
-    let table_name = payload.name;
-
-    if state.drop_table(&table_name).await {
-        match state.save().await {
-            Ok(_) => {
-                info!("Dropped table: {}", table_name);
-                (
-                    StatusCode::OK,
-                    Json(format!("Dropped table '{}'", table_name)),
-                )
-                    .into_response()
-            }
-            Err(err) => {
-                let error = format!("Failed to save state: {}", err);
-                error!("{}", error);
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
-            }
+    let mut security = None;
+    if let Some(table) = state.get_without_rows(&name).await {
+        let columns = column_keys(&table.columns);
+        if let Err(response) =
+            check_privilege(&state, &principal, Privilege::Select, Some(&name), &columns).await
+        {
+            return response;
+        }
+        security = row_security(&principal, &session, &table);
+    }
+    match state.rows_page(&name, &query, security.as_ref()).await {
+        Some(Ok(page)) => {
+            debug!("Returned {} rows of table '{}'", page.rows.len(), name);
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Some(Err(error)) => {
+            error!("{}", error);
+            (StatusCode::BAD_REQUEST, Json(error)).into_response()
+        }
+        None => {
+            let error = format!("Table '{}' does not exist", name);
+            error!("{}", error);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
         }
-    } else {
-        let error = format!("Table '{}' does not exist", table_name);
-        error!("{}", error);
-        (StatusCode::NOT_FOUND, Json(error)).into_response()
     }
 }
 
-/// Handler to rename a table's name
-///
-/// # Example
-///
-/// ```
-/// curl -X POST http://localhost:3000/rename_table -H "Content-Type: application/json" -d '{"current_name":"test_table2","new_name":"test_drop_table"}'
-/// ```
-///
-/// Renames a table in the database.
+/// The position of a page in a table, as encoded in the `cursor` of `/tables/{name}/rows`.
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    /// The `order_by` the cursor was created with.
+    order_by: Option<String>,
+    /// The sort key of the last row of the previous page, ending with its primary key.
+    key: Vec<Value>,
+    /// The insertion position of that row, which breaks ties between equal keys in tables
+    /// without a primary key.
+    position: usize,
+}
+
+impl Cursor {
+    /// Encodes the cursor as a hex string, so it can be passed in a URL without escaping.
+    fn encode(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        json.bytes().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn decode(cursor: &str) -> Option<Cursor> {
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// Helper function to select a page of rows from a table
 ///
 /// ## Parameters
 ///
-/// - `current_name`: The current name of the table to be renamed.
-/// - `new_name`: The new name of the table.
+/// - `table`: The table from which rows are selected.
+/// - `query`: The limit, offset, order and cursor of the page.
+/// - `security`: Optional. The row-level security hiding rows of the table.
 ///
 /// ## Returns
 ///
-/// Returns a success message if the table is renamed successfully.
+/// Returns the page, with a cursor pointing after its last row if more rows follow.
 ///
 /// ## Errors
 ///
-/// - Returns an error if the table does not exist.
-async fn rename_table(
-    State(state): State<Arc<AppState>>,
-    Json(payload): Json<RenameTableRequest>,
-) -> Response {
-    let current_name = payload.current_name;
+/// - Returns an error if `query.order_by` names a column that does not exist in the table.
+/// - Returns an error if `query.cursor` is malformed, does not match `query.order_by` or is
+///   combined with `query.offset`.
+fn rows_page(
+    table: &Table,
+    query: &RowsQuery,
+    security: Option<&RowSecurity>,
+) -> Result<RowsPage, String> {
+    // The sort columns with whether they are sorted in descending order
+    let mut order: Vec<(usize, bool)> = match &query.order_by {
+        Some(order_by) => order_by
+            .split(',')
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .map(|column| {
+                let (name, descending) = match column.strip_prefix('-') {
+                    Some(name) => (name, true),
+                    None => (column, false),
+                };
+                match table.columns.iter().position(|c| c.key == name) {
+                    Some(index) => Ok((index, descending)),
+                    None => Err(format!("Column '{}' not found", name)),
+                }
+            })
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+    // Ties are broken by the primary key, which unlike the position of a row does not change
+    // when rows before it are deleted between pages
+    for index in table
+        .primary_key
+        .iter()
+        .filter_map(|key| table.columns.iter().position(|c| c.key == *key))
+    {
+        if !order.iter().any(|(sorted, _)| *sorted == index) {
+            order.push((index, false));
+        }
+    }
+    let by_position = table.primary_key.is_empty();
+
+    let sort_key = |position: usize| -> Vec<Value> {
+        order
+            .iter()
+            .map(|(index, _)| {
+                table.rows[position]
+                    .values
+                    .get(*index)
+                    .cloned()
+                    .unwrap_or(Value::Null)
+            })
+            .collect()
+    };
+    let compare = |a: (&[Value], usize), b: (&[Value], usize)| -> Ordering {
+        a.0.iter()
+            .zip(b.0)
+            .zip(&order)
+            .map(|((a, b), (_, descending))| match descending {
+                true => b.compare(a),
+                false => a.compare(b),
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| match by_position {
+                true => a.1.cmp(&b.1),
+                false => Ordering::Equal,
+            })
+    };
+
+    let keys: Vec<Vec<Value>> = (0..table.rows.len()).map(sort_key).collect();
+    let mut positions: Vec<usize> = (0..table.rows.len())
+        .filter(|position| security.is_none_or(|security| security.visible(&table.rows[*position])))
+        .collect();
+    positions.sort_by(|a, b| compare((&keys[*a], *a), (&keys[*b], *b)));
+
+    let mut start = query.offset.unwrap_or(0);
+    if let Some(cursor) = &query.cursor {
+        if query.offset.is_some() {
+            return Err("A cursor cannot be combined with an offset".to_string());
+        }
+        let cursor = Cursor::decode(cursor).ok_or("Invalid cursor")?;
+        if cursor.order_by != query.order_by || cursor.key.len() != order.len() {
+            return Err("The cursor was created with a different order_by".to_string());
+        }
+        start = positions.partition_point(|position| {
+            compare(
+                (&keys[*position], *position),
+                (&cursor.key, cursor.position),
+            )
+            .is_le()
+        });
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+    let page: Vec<usize> = positions.iter().skip(start).take(limit).copied().collect();
+    let next_cursor = match page.last() {
+        Some(last) if start + page.len() < positions.len() => Some(
+            Cursor {
+                order_by: query.order_by.clone(),
+                key: keys[*last].clone(),
+                position: *last,
+            }
+            .encode(),
+        ),
+        _ => None,
+    };
+
+    Ok(RowsPage {
+        columns: table.columns.iter().map(|c| c.key.clone()).collect(),
+        rows: page
+            .into_iter()
+            .map(|position| table.rows[position].clone())
+            .collect(),
+        total_rows: positions.len(),
+        next_cursor,
+    })
+}
+
+/// Helper function to copy the definition of a table without its rows
+fn without_rows(table: &Table) -> Table {
+    Table {
+        name: table.name.clone(),
+        columns: table.columns.clone(),
+        rows: Vec::new(),
+        primary_key: table.primary_key.clone(),
+        unique_constraints: table.unique_constraints.clone(),
+        policies: table.policies.clone(),
+    }
+}
+
+/// Handler to create a new table
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/create -H "Content-Type: application/json" -d '{"name":"test_table"}'
+/// ```
+///
+/// Creates a new table in the database.
+///
+/// ## Parameters
+///
+/// - `name`: The name of the table to be created.
+///
+/// ## Returns
+///
+/// Returns a JSON object representing the newly created table.
+///
+/// ## Errors
+///
+/// - Returns an error if a table with the same name already exists.
+/// - Returns an error if the user does not have the `CREATE` privilege.
+///
+/// ## Notes
+///
+/// - A user who is not an admin is granted every privilege on the tables they create.
+async fn create(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(audit): Extension<Audit>,
+    Json(payload): Json<CreateRequests>,
+) -> Response {
+    let table_name = payload.name;
+    audit.table(&table_name);
+
+    if let Err(response) = check_privilege(&state, &principal, Privilege::Create, None, &[]).await {
+        return response;
+    }
+    if catalog::is_catalog_name(&table_name) {
+        return read_only_schema_error(&table_name);
+    }
+    let new_table = match state
+        .create_from(CreateTableRequests::new(table_name))
+        .await
+    {
+        Ok(table) => table,
+        Err((status, error)) => {
+            error!("{}", error);
+            return (status, Json(error)).into_response();
+        }
+    };
+
+    match state.save().await {
+        Ok(_) => {
+            info!("Created table: {:?}", &new_table);
+            log_grants_error(state.auth.table_created(&principal, &new_table.name).await);
+            (StatusCode::OK, Json(new_table)).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to save state: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Returns the error response for an attempt to create a table in the read-only
+/// `information_schema` or `system` schema.
+fn read_only_schema_error(table_name: &str) -> Response {
+    let schema = table_name
+        .split_once('.')
+        .map_or(table_name, |(schema, _)| schema);
+    let error = format!(
+        "Cannot create table '{}': schema '{}' is read-only",
+        table_name, schema
+    );
+    error!("{}", error);
+    (StatusCode::BAD_REQUEST, Json(error)).into_response()
+}
+
+/// Handler to drop a table
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/drop_table -H "Content-Type: application/json" -d '{"name":"test_table"}'
+/// ```
+///
+/// Drops a table from the database.
+///
+/// ## Parameters
+///
+/// - `name`: The name of the table to be dropped.
+///
+/// ## Returns
+///
+/// Returns a success message if the table is dropped successfully.
+///
+/// ## Errors
+///
+/// - Returns an error if the table does not exist.
+/// - Returns an error if the user does not have the `DROP` privilege on the table.
+///
+/// ## Notes
+///
+/// - The privileges granted on the table are revoked.
+async fn drop_table(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(audit): Extension<Audit>,
+    Json(payload): Json<DropTableRequest>,
+) -> Response {
+    let table_name = payload.name;
+    audit.table(&table_name);
+
+    if let Err(response) =
+        check_privilege(&state, &principal, Privilege::Drop, Some(&table_name), &[]).await
+    {
+        return response;
+    }
+    let dropped = match state.drop_table(&table_name).await {
+        Ok(dropped) => dropped,
+        Err(error) => {
+            let (status, error) = error.into();
+            error!("{}", error);
+            return (status, Json(error)).into_response();
+        }
+    };
+    if dropped {
+        match state.save().await {
+            Ok(_) => {
+                info!("Dropped table: {}", table_name);
+                log_grants_error(state.auth.table_dropped(&table_name).await);
+                (
+                    StatusCode::OK,
+                    Json(format!("Dropped table '{}'", table_name)),
+                )
+                    .into_response()
+            }
+            Err(err) => {
+                let error = format!("Failed to save state: {}", err);
+                error!("{}", error);
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+            }
+        }
+    } else {
+        let error = format!("Table '{}' does not exist", table_name);
+        error!("{}", error);
+        (StatusCode::NOT_FOUND, Json(error)).into_response()
+    }
+}
+
+/// Handler to rename a table's name
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/rename_table -H "Content-Type: application/json" -d '{"current_name":"test_table2","new_name":"test_drop_table"}'
+/// ```
+///
+/// Renames a table in the database.
+///
+/// ## Parameters
+///
+/// - `current_name`: The current name of the table to be renamed.
+/// - `new_name`: The new name of the table.
+///
+/// ## Returns
+///
+/// Returns a success message if the table is renamed successfully.
+///
+/// ## Errors
+///
+/// - Returns an error if the table does not exist.
+/// - Returns an error if the user does not have the `ALTER` privilege on the table.
+///
+/// ## Notes
+///
+/// - The privileges granted on the table move to its new name.
+async fn rename_table(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(audit): Extension<Audit>,
+    Json(payload): Json<RenameTableRequest>,
+) -> Response {
+    let current_name = payload.current_name;
     let new_name = payload.new_name;
+    audit.table(&current_name);
 
+    if let Err(response) = check_privilege(
+        &state,
+        &principal,
+        Privilege::Alter,
+        Some(&current_name),
+        &[],
+    )
+    .await
+    {
+        return response;
+    }
+    if catalog::is_catalog_name(&new_name) {
+        return read_only_schema_error(&new_name);
+    }
     if let Some(mut table) = state.get(&current_name).await {
         table.name = new_name;
-        state.drop_table(&current_name).await;
-        state.create(table.clone()).await;
+        if let Err(error) = state.replace(&current_name, table.clone()).await {
+            let (status, error) = error.into();
+            error!("{}", error);
+            return (status, Json(error)).into_response();
+        }
         match state.save().await {
             Ok(_) => {
                 info!(
                     "Rename table name from '{}' to '{}'",
                     current_name, table.name
                 );
+                log_grants_error(state.auth.table_renamed(&current_name, &table.name).await);
                 (
                     StatusCode::OK,
                     Json(format!(
@@ -381,7 +1340,11 @@ async fn rename_table(
 /// - `primary_key`: Whether the column is a primary key.
 /// - `non_null`: Whether the column is non-null.
 /// - `unique`: Whether the column is unique.
-/// - `foreign_key`: The foreign key constraint for the column.
+/// - `foreign_key`: The foreign key constraint for the column, as `{"table_name": ..., "column": ...}`.
+/// - `default`: Optional. The value used when an inserted row omits the column. Existing rows are filled with it.
+/// - `check`: Optional. A boolean expression the column's values have to satisfy. It is checked for the existing rows.
+/// - `auto_increment`: Optional. Whether missing or NULL values are replaced by the next integer.
+/// - `data_type`: Optional. The declared type of the column: `"Str"`, `"Bool"`, `"Int"` or `"Float"`.
 ///
 /// ## Returns
 ///
@@ -390,40 +1353,117 @@ async fn rename_table(
 /// ## Errors
 ///
 /// - Returns an error if the table does not exist.
+/// - Returns an error if the foreign key references a table or column that does not exist.
+/// - Returns an error if the user does not have the `ALTER` privilege on the table.
 async fn insert_column(
     State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(audit): Extension<Audit>,
     Json(payload): Json<InsertColumnRequest>,
 ) -> Response {
-    let table_name = payload.table_name;
+    audit.table(&payload.table_name);
+    if let Err(response) = check_privilege(
+        &state,
+        &principal,
+        Privilege::Alter,
+        Some(&payload.table_name),
+        &[],
+    )
+    .await
+    {
+        return response;
+    }
+    add_column(&state, payload).await
+}
 
-    if let Some(mut table) = state.get(&table_name).await {
-        let column = Column::new(
-            payload.key,
-            payload.primary_key,
-            payload.non_null,
-            payload.unique,
-            payload
-                .foreign_key
-                .map(|fk| fk.into_iter().map(Box::new).collect()),
-        );
-        table.add_column(column.clone());
-        state.drop_table(&table_name).await;
-        state.create(table).await;
-        match state.save().await {
-            Ok(_) => {
-                info!("Inserted column into table '{}': {:?}", table_name, column);
-                (StatusCode::OK, Json(column)).into_response()
+/// Helper function to add a column to a table and fill it in the existing rows
+async fn add_column(state: &AppState, payload: InsertColumnRequest) -> Response {
+    let table_name = payload.table_name.clone();
+    // Another table a foreign key references is only read, so it is looked up first
+    let referenced = match &payload.foreign_key {
+        Some(foreign_key) if foreign_key.table_name != table_name => {
+            Some(state.schema(&foreign_key.table_name).await)
+        }
+        _ => None,
+    };
+    let column = Column::from(payload);
+
+    // The column is added under the lock the table is replaced in, so rows written
+    // meanwhile are kept and filled
+    let added = state
+        .update_with(&table_name, |table, _| {
+            if let Some(foreign_key) = &column.foreign_key {
+                let columns = match &referenced {
+                    Some(schema) => schema.as_ref().map(|schema| schema.columns.as_slice()),
+                    None => Some(table.columns.as_slice()),
+                };
+                check_foreign_key(foreign_key, columns)
+                    .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
             }
-            Err(err) => {
-                let error = format!("Failed to save state: {}", err);
-                error!("{}", error);
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+
+            // Existing rows get the column's default value (or NULL) for the new column
+            let filler = column.default.clone().unwrap_or(Value::Null);
+            if (column.non_null || column.primary_key)
+                && matches!(filler, Value::Null)
+                && !table.rows.is_empty()
+            {
+                return Err((StatusCode::BAD_REQUEST, format!(
+                    "Column '{}' does not allow NULL values, but table '{}' has rows and the column has no default",
+                    column.key, table_name
+                )));
             }
+            for row in &mut table.rows {
+                row.values.resize(table.columns.len(), Value::Null);
+                row.add_value(filler.clone());
+            }
+            table.add_column(column.clone());
+            table
+                .validate_checks()
+                .and_then(|()| table.check_constraints())
+                .map_err(|error| (StatusCode::BAD_REQUEST, error))
+        })
+        .await;
+    match added {
+        Some(Ok(())) => {}
+        Some(Err((status, error))) => {
+            error!("{}", error);
+            return (status, Json(error)).into_response();
         }
-    } else {
-        let error = format!("Table '{}' does not exist", table_name);
-        error!("{}", error);
-        (StatusCode::NOT_FOUND, Json(error)).into_response()
+        None => {
+            let error = format!("Table '{}' does not exist", table_name);
+            error!("{}", error);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    }
+
+    match state.save().await {
+        Ok(_) => {
+            info!("Inserted column into table '{}': {:?}", table_name, column);
+            (StatusCode::OK, Json(column)).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to save state: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Helper function to check that the column a foreign key references exists, given the
+/// columns of the referenced table or `None` if it does not exist
+fn check_foreign_key(foreign_key: &ForeignKey, columns: Option<&[Column]>) -> Result<(), String> {
+    let Some(columns) = columns else {
+        return Err(format!(
+            "Referenced table '{}' does not exist",
+            foreign_key.table_name
+        ));
+    };
+    match columns.iter().any(|col| col.key == foreign_key.column) {
+        true => Ok(()),
+        false => Err(format!(
+            "Referenced column '{}' does not exist in table '{}'",
+            foreign_key.column, foreign_key.table_name
+        )),
     }
 }
 
@@ -449,44 +1489,40 @@ async fn insert_column(
 /// ## Errors
 ///
 /// - Returns an error if a table with the same name already exists.
+/// - Returns an error if the user does not have the `CREATE` privilege.
+///
+/// ## Notes
+///
+/// - A user who is not an admin is granted every privilege on the tables they create.
 async fn create_table(
     State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(audit): Extension<Audit>,
     Json(payload): Json<CreateTableRequests>,
 ) -> impl IntoResponse {
-    let table_name = payload.name;
+    let table_name = payload.name.clone();
+    audit.table(&table_name);
 
-    if state.get(&table_name).await.is_some() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(format!("Table '{}' already exists", table_name)),
-        )
-            .into_response();
+    if let Err(response) = check_privilege(&state, &principal, Privilege::Create, None, &[]).await {
+        return response;
+    }
+    if catalog::is_catalog_name(&table_name) {
+        return read_only_schema_error(&table_name);
     }
 
-    let new_table = Table {
-        name: table_name.clone(),
-        columns: Vec::new(),
-        rows: Vec::new(),
-    };
-
-    state.create(new_table.clone()).await;
-
-    for insert_column_request in payload.insert_column_requests {
-        let mut request = insert_column_request;
-        request.table_name = table_name.clone();
-        let json_payload = Json(request);
-
-        let response = insert_column(State(state.clone()), json_payload).await;
-
-        // Return immediately if there's an error
-        if response.status() != StatusCode::OK {
-            return response;
+    // The table is built completely before it is added, so a failed request leaves no trace
+    let new_table = match state.create_from(payload).await {
+        Ok(table) => table,
+        Err((status, error)) => {
+            error!("{}", error);
+            return (status, Json(error)).into_response();
         }
-    }
+    };
 
     match state.save().await {
         Ok(_) => {
             info!("Created table: {:?}", new_table);
+            log_grants_error(state.auth.table_created(&principal, &table_name).await);
             (StatusCode::OK, Json(new_table)).into_response()
         }
         Err(err) => {
@@ -497,6 +1533,31 @@ async fn create_table(
     }
 }
 
+/// Helper function to add table-level constraints to a newly created table
+///
+/// ## Parameters
+///
+/// - `table`: The table to modify.
+/// - `primary_key`: The ordered primary key columns. Empty if the key is defined by column flags or absent.
+/// - `unique_constraints`: The unique constraints to add.
+///
+/// ## Errors
+///
+/// - Returns an error if a primary key is defined twice, a constraint name is reused or a column does not exist.
+fn apply_table_constraints(
+    table: &mut Table,
+    primary_key: Vec<String>,
+    unique_constraints: Vec<UniqueConstraint>,
+) -> Result<(), String> {
+    if !primary_key.is_empty() {
+        table.set_primary_key(primary_key)?;
+    }
+    for constraint in unique_constraints {
+        table.add_unique_constraint(constraint)?;
+    }
+    Ok(())
+}
+
 /// Handler to insert a new row into a table
 ///
 /// # Example
@@ -519,64 +1580,84 @@ async fn create_table(
 /// ## Errors
 ///
 /// - Returns an error if the table does not exist.
+/// - Returns an error if the user does not have the `INSERT` privilege on the columns given
+///   values.
 async fn insert_row(
     State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Extension(audit): Extension<Audit>,
+    Extension(execution): Extension<Execution>,
     Json(payload): Json<InsertRowRequest>,
 ) -> Response {
+    execution.statement(payload.to_sql());
     let table_name = payload.table_name;
+    audit.table(&table_name);
     info!("Received insert request for table '{}'", table_name);
 
-    if let Some(mut table) = state.get(&table_name).await {
-        let mut row = payload.row;
-        info!("Inserting row: {:?}", row);
-
-        let columns_len = table.columns.len();
-        info!("Table '{}' expects {} columns", table_name, columns_len);
-
-        if row.values.len() > columns_len {
-            let error = format!(
-                "Row has {} values, but table expects {} values consider adding more columns",
-                row.values.len(),
-                columns_len
-            );
-            error!("{}", error);
-            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+    if let Some(table) = state.get(&table_name).await {
+        let columns = column_keys(&table.columns);
+        let given = payload.row.values.len().min(columns.len());
+        if let Err(response) = check_privilege(
+            &state,
+            &principal,
+            Privilege::Insert,
+            Some(&table_name),
+            &columns[..given],
+        )
+        .await
+        {
+            return response;
         }
+        info!("Inserting row: {:?}", payload.row);
+        info!(
+            "Table '{}' expects {} columns",
+            table_name,
+            table.columns.len()
+        );
 
-        if row.values.len() < columns_len {
-            // Check if column allows Non-Null
-            let additional_rows = columns_len - row.values.len();
-            // if any additional columns are non_null return with an error
-            if table
-                .columns
-                .iter()
-                .rev()
-                .take(additional_rows)
-                .any(|col| col.non_null)
-            {
-                let error = format!("Row has {} values, but table expects {} values. This fails out because at least one additional column is Non-Null", row.values.len(), columns_len);
-                error!("{}", error);
-                return (StatusCode::BAD_REQUEST, Json(error)).into_response();
-            } else {
-                for _ in 0..additional_rows {
-                    row.add_value(None)
+        // Auto-increment values are assigned and the keys are checked against the rows of the
+        // table under the lock the row is appended in, so concurrent inserts cannot both add
+        // the same key
+        let inserted = state
+            .insert_checked(&table_name, |table| {
+                let row = complete_row(table, payload.row)
+                    .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+                table
+                    .check_row(&row)
+                    .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+                if let Some(security) = row_security(&principal, &session, table) {
+                    security
+                        .check(&row)
+                        .map_err(|error| (StatusCode::FORBIDDEN, error))?;
                 }
+                Ok(row)
+            })
+            .await;
+        let row = match inserted {
+            Some(Ok(row)) => row,
+            Some(Err((status, error))) => {
+                error!("{}", error);
+                return (status, Json(error)).into_response();
             }
-        }
-
+            None => {
+                let error = format!("Table '{}' does not exist", table_name);
+                error!("{}", error);
+                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+            }
+        };
         let row_values = row
             .values
             .iter()
             .map(|value| value.as_string().unwrap_or_default())
             .collect::<Vec<String>>();
-        table.add_row(row.clone());
-        state.drop_table(&table_name).await;
-        state.create(table).await;
 
         // Handle the Result from state.save() manually
         match state.save().await {
             Ok(_) => {
                 info!("Inserted row into table '{}': {:?}", table_name, row);
+                audit.rows(1);
+                execution.rows(0, 1);
                 (StatusCode::OK, Json(row_values)).into_response()
             }
             Err(err) => {
@@ -588,298 +1669,3564 @@ async fn insert_row(
     } else {
         let error = format!("Table '{}' does not exist", table_name);
         error!("{}", error);
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        (StatusCode::NOT_FOUND, Json(error)).into_response()
     }
 }
 
-/// Handler to select rows from a table based on specified conditions or retrieve all rows if no conditions are provided.
+/// Helper function to complete a row before it is inserted into a table
 ///
-/// # Example
+/// ## Parameters
+///
+/// - `table`: The table the row is inserted into.
+/// - `row`: The row, with at most one value per column.
+///
+/// ## Returns
+///
+/// Returns the row with missing trailing values set to the columns' defaults, and NULL values of
+/// auto-increment columns replaced by the next value.
+///
+/// ## Errors
+///
+/// - Returns an error if the row has more values than the table has columns.
+/// - Returns an error if a non-null column would be NULL.
+fn complete_row(table: &Table, mut row: Row) -> Result<Row, String> {
+    let columns_len = table.columns.len();
+
+    if row.values.len() > columns_len {
+        return Err(format!(
+            "Row has {} values, but table expects {} values consider adding more columns",
+            row.values.len(),
+            columns_len
+        ));
+    }
+
+    if row.values.len() < columns_len {
+        // Missing trailing values fall back to the column's default or auto-increment value
+        let missing_columns = &table.columns[row.values.len()..];
+        // if any additional columns are non_null without a fallback return with an error
+        if missing_columns
+            .iter()
+            .any(|col| col.non_null && col.default.is_none() && !col.auto_increment)
+        {
+            return Err(format!("Row has {} values, but table expects {} values. This fails out because at least one additional column is Non-Null", row.values.len(), columns_len));
+        }
+        for column in missing_columns {
+            row.add_value(column.default.clone().unwrap_or(Value::Null));
+        }
+    }
+
+    for (index, column) in table.columns.iter().enumerate() {
+        if column.auto_increment && matches!(row.values[index], Value::Null) {
+            row.values[index] = Value::Int(next_auto_increment(table, index));
+        }
+        if column.non_null && matches!(row.values[index], Value::Null) {
+            return Err(format!(
+                "Column '{}' does not allow NULL values",
+                column.key
+            ));
+        }
+    }
+    Ok(row)
+}
+
+/// Helper function to compute the next value of an auto-increment column
+///
+/// ## Parameters
+///
+/// - `table`: The table containing the column.
+/// - `col_index`: The position of the auto-increment column.
+///
+/// ## Returns
+///
+/// Returns one more than the largest integer stored in the column, or 1 if there is none.
+fn next_auto_increment(table: &Table, col_index: usize) -> i64 {
+    table
+        .rows
+        .iter()
+        .filter_map(|row| match row.values.get(col_index) {
+            Some(Value::Int(i)) => Some(*i),
+            _ => None,
+        })
+        .max()
+        .map_or(1, |max| max + 1)
+}
+
+/// Handler to select rows from a table based on specified conditions or retrieve all rows if no conditions are provided.
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/select -H "Content-Type: application/json" -d '{"table_name":"test_table","columns":["test_key","test_key3"],"condition":{"column":"test_key","value":"true"}}'
+/// curl -X POST "http://localhost:3000/select?stream=chunked&chunk_size=500" -H "Content-Type: application/json" -d '{"table_name":"test_table","columns":null,"condition":null}'
+/// curl -X POST "http://localhost:3000/select?explain=analyze" -H "Content-Type: application/json" -d '{"table_name":"test_table","columns":null,"condition":{"column":"test_key","value":"true"}}'
+/// ```
+///
+/// Retrieves rows from the specified table (`table_name`) optionally filtered by columns (`columns`) and a conditional (`condition`).
+/// If `columns` is not provided, all columns are selected (`SELECT *`).
+///
+/// ## Parameters
+///
+/// - `table_name`: Name of the table from which rows are selected.
+/// - `columns`: Optional. List of columns to select. If not provided, all columns are selected.
+/// - `condition`: Optional. Specifies a condition to filter rows. Only rows matching this condition are returned.
+/// - `stream` (query): Optional. `ndjson` or `chunked` to stream the result, see below.
+/// - `chunk_size` (query): Optional. The largest number of rows per frame of a chunked stream, 1000 by default.
+/// - `explain` (query): Optional. `plan` or `analyze` to return the plan of the query instead of its rows, see below.
+///
+/// ## Returns
+///
+/// Returns a JSON array of rows, where each row is represented as an array of strings (values of selected columns).
+/// With `explain`, returns the plan as `{"plan":{...},"execution_time_ms":...}`.
+///
+/// ## Errors
+///
+/// - Returns an error if the specified `table_name` does not exist in the application state.
+/// - Returns an error if the specified `condition.column` does not exist in the table.
+/// - Returns an error if the user does not have the `SELECT` privilege on the selected columns
+///   and the condition's column.
+/// - Returns an error if `explain` is combined with `stream`.
+///
+/// ## Notes
+///
+/// - This handler supports flexible column selection and row filtering based on conditions.
+/// - The read-only `information_schema.tables`, `information_schema.columns`,
+///   `information_schema.table_constraints` and `information_schema.indexes` views can be selected
+///   like tables, e.g. `{"table_name":"information_schema.columns","columns":null,"condition":{"column":"table_name","value":"test_table"}}`.
+///   They only list the tables the user has a privilege on.
+/// - The read-only `system.audit_log` view lists the entries of the audit log. Users who are not
+///   admins only see their own requests.
+/// - With `stream=ndjson` the rows are sent as newline-delimited JSON, one row per line. With
+///   `stream=chunked` every line is a frame: `{"Header":{"columns":[...]}}` with the name and type of
+///   the selected columns, `{"Rows":[...]}` frames and a final `{"End":{"row_count":n}}`. Errors
+///   are reported before the first line, except for failures while streaming, which end a chunked
+///   stream with an `{"Error":"..."}` frame and abort an NDJSON stream.
+/// - Streamed rows are read from the table in chunks, so a stream does not hold the table
+///   locked, and it may include rows inserted while it is being sent.
+/// - Rows hidden by row-level security are not selected.
+/// - The read-only `system.statement_stats` view lists the execution statistics of statements.
+///   Users who are not admins only see their own statements.
+/// - Streamed rows are not counted in the statistics and the slow query log, whose duration
+///   ends when the stream starts.
+/// - The request is compiled into a query plan and optimized, see `core::plan`. A condition on
+///   the first column of the primary key or of a unique constraint reads the matching rows
+///   through an index instead of scanning the table. Indexes are built when a select first
+///   needs them after the table changed. Streamed selects always scan the table.
+/// - With `explain=plan` the plan is returned without running the query, like `EXPLAIN`. Every
+///   node has an operator (`Seq Scan`, `Index Lookup` or `Project`), an optional detail and
+///   `estimated_rows`, and its input in `children`. Scans also have the `index_condition` of an
+///   index lookup and the `filter` the rows they read have to satisfy, which includes the
+///   policies of row-level security. The rows matching a condition are estimated from the
+///   number of distinct values of its column. With `explain=analyze` the query is run, like
+///   `EXPLAIN ANALYZE`, and every node also has the `actual_rows` it produced and the `time_ms`
+///   it took, including the time of its children, and scans the `rows_removed_by_filter`.
+async fn select(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Extension(execution): Extension<Execution>,
+    Query(options): Query<SelectOptions>,
+    Json(payload): Json<SelectRequest>,
+) -> Response {
+    execution.statement(match options.explain {
+        Some(ExplainMode::Plan) => format!("EXPLAIN {}", payload.to_sql()),
+        Some(ExplainMode::Analyze) => format!("EXPLAIN ANALYZE {}", payload.to_sql()),
+        None => payload.to_sql(),
+    });
+    if options.explain.is_some() && options.stream.is_some() {
+        let error = "A streamed select cannot be explained".to_string();
+        error!("{}", error);
+        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    let table = match state
+        .catalog_view(&payload.table_name, &principal, &session)
+        .await
+    {
+        Ok(Some(view)) => Some((view, Vec::new())),
+        Ok(None) => match options.stream {
+            // Only the definition is needed up front, rows are read while streaming
+            Some(_) => state
+                .get_without_rows(payload.table_name.as_str())
+                .await
+                .map(|table| (table, Vec::new())),
+            None => state.get_indexed(payload.table_name.as_str()).await,
+        },
+        Err(err) => return audit_log_error(err),
+    };
+    let Some((mut table, indexes)) = table else {
+        let error = format!("Table '{}' does not exist", payload.table_name);
+        error!("{}", error);
+        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+    };
+
+    let selection = match Selection::new(
+        &table,
+        payload.columns.as_deref(),
+        payload.condition.as_ref(),
+    ) {
+        Ok(selection) => selection,
+        Err(error) => {
+            error!("{}", error);
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+    if let Err(response) = check_selection(&state, &principal, &table, &selection).await {
+        return response;
+    }
+    let security = row_security(&principal, &session, &table);
+    let plan = selection.optimize(&table, security.as_ref(), &indexes);
+
+    if let Some(format) = options.stream {
+        let chunk_size = options.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE).max(1);
+        return stream_select(state, table, selection.headers, plan, format, chunk_size);
+    }
+    if options.explain == Some(ExplainMode::Plan) {
+        return (StatusCode::OK, Json(plan.explain(None))).into_response();
+    }
+    let output = plan.execute(&mut table.rows);
+    execution.rows(output.scanned, output.rows.len());
+    if options.explain == Some(ExplainMode::Analyze) {
+        return (StatusCode::OK, Json(plan.explain(Some(&output)))).into_response();
+    }
+    debug!(
+        "Selected {} rows from table '{}'",
+        output.rows.len(),
+        payload.table_name
+    );
+    (StatusCode::OK, Json(output.rows)).into_response()
+}
+
+/// Handler to select the joined rows of two tables
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/join -H "Content-Type: application/json" -d '{"table_name":"users","join_table":"orders","on":{"left_column":"id","right_column":"user_id"},"columns":["users.name","orders.total"],"condition":{"column":"users.city","value":"Oslo"}}'
+/// ```
+///
+/// Selects the pairs of rows of two tables (`table_name` and `join_table`) whose values in two
+/// columns (`on`) are equal, like an inner join, optionally filtered by a condition
+/// (`condition`).
+///
+/// ## Parameters
+///
+/// - `table_name`: Name of the first table.
+/// - `join_table`: Name of the joined table.
+/// - `on`: The column of the first table (`left_column`) and of the joined table
+///   (`right_column`) whose values have to be equal.
+/// - `columns`: Optional. List of columns to select, named by their table, e.g. `users.name`.
+///   If not provided, all columns of the first table are selected, followed by those of the
+///   joined table.
+/// - `condition`: Optional. Specifies a condition on a column named by its table. Only pairs
+///   of rows matching this condition are selected.
+/// - `explain`: Optional query parameter, like for `/select`.
+///
+/// ## Returns
+///
+/// Returns the joined rows, with the values in the order of the requested columns.
+///
+/// ## Errors
+///
+/// - Returns an error if one of the tables does not exist, or both are the same table.
+/// - Returns an error if a column does not exist in its table.
+/// - Returns an error if the user does not have the `SELECT` privilege on the columns read of
+///   either table.
+/// - Returns an error if `stream` is set.
+///
+/// ## Notes
+///
+/// - The values are compared as text, like the conditions of `/select`. `NULL` values are
+///   never joined.
+/// - The rows are produced in the order of the first table. Rows of the joined table with the
+///   same value keep their order.
+/// - Rows hidden by row-level security of either table are not joined.
+/// - The condition is moved into the scan of the table of its column, so it may read the
+///   rows through an index. With `explain` the plan shows a `Hash Join` node with the scans of
+///   both tables as children.
+async fn join_tables(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Extension(execution): Extension<Execution>,
+    Query(options): Query<SelectOptions>,
+    Json(payload): Json<JoinRequest>,
+) -> Response {
+    execution.statement(match options.explain {
+        Some(ExplainMode::Plan) => format!("EXPLAIN {}", payload.to_sql()),
+        Some(ExplainMode::Analyze) => format!("EXPLAIN ANALYZE {}", payload.to_sql()),
+        None => payload.to_sql(),
+    });
+    if options.stream.is_some() {
+        let error = "A join cannot be streamed".to_string();
+        error!("{}", error);
+        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    let left = state.get_indexed(&payload.table_name).await;
+    let right = state.get_indexed(&payload.join_table).await;
+    let ((left, left_indexes), (right, right_indexes)) = match (left, right) {
+        (Some(left), Some(right)) => (left, right),
+        (left, _) => {
+            let name = match left {
+                None => &payload.table_name,
+                Some(_) => &payload.join_table,
+            };
+            let error = format!("Table '{}' does not exist", name);
+            error!("{}", error);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    };
+
+    let plan = match LogicalPlan::join(
+        &left,
+        &right,
+        &payload.on,
+        payload.columns.as_deref(),
+        payload.condition.as_ref(),
+    ) {
+        Ok(plan) => plan,
+        Err(error) => {
+            error!("{}", error);
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+    let mut restricted = plan.clone();
+    for table in [&left, &right] {
+        // The columns of the plan are named by their table, those of privileges are not
+        let prefix = format!("{}.", table.name);
+        let read_columns = plan.read_columns();
+        let columns: Vec<&str> = read_columns
+            .iter()
+            .filter_map(|column| column.strip_prefix(&prefix))
+            .collect();
+        let table_name = Some(table.name.as_str());
+        let check = check_privilege(&state, &principal, Privilege::Select, table_name, &columns);
+        if let Err(response) = check.await {
+            return response;
+        }
+        if let Some(security) = row_security(&principal, &session, table) {
+            restricted = restricted.restrict_table(&table.name, security.predicate());
+        }
+    }
+    let plan = restricted.optimize_join(&left, &left_indexes, &right, &right_indexes);
+
+    if options.explain == Some(ExplainMode::Plan) {
+        return (StatusCode::OK, Json(plan.explain(None))).into_response();
+    }
+    let output = plan.execute_join(&left.rows, &right.rows);
+    execution.rows(output.scanned, output.rows.len());
+    if options.explain == Some(ExplainMode::Analyze) {
+        return (StatusCode::OK, Json(plan.explain(Some(&output)))).into_response();
+    }
+    debug!(
+        "Selected {} rows from the join of tables '{}' and '{}'",
+        output.rows.len(),
+        payload.table_name,
+        payload.join_table
+    );
+    (StatusCode::OK, Json(output.rows)).into_response()
+}
+
+/// Helper function to build the error response for an audit log that cannot be read
+fn audit_log_error(err: Error) -> Response {
+    let error = format!("Failed to read the audit log: {}", err);
+    error!("{}", error);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+}
+
+/// Helper function to check that the user may select the columns a selection reads. The rows of
+/// `information_schema` and `system` views are filtered instead.
+async fn check_selection(
+    state: &AppState,
+    principal: &Principal,
+    table: &Table,
+    selection: &Selection,
+) -> Result<(), Response> {
+    if catalog::is_catalog_name(&table.name) {
+        return Ok(());
+    }
+    let columns = selection.read_columns();
+    check_privilege(
+        state,
+        principal,
+        Privilege::Select,
+        Some(&table.name),
+        &columns,
+    )
+    .await
+}
+
+/// The number of rows per frame of a chunked select stream when no chunk size is given.
+const DEFAULT_CHUNK_SIZE: usize = 1000;
+
+/// The number of frames a select stream may run ahead of the client.
+const STREAM_BUFFER: usize = 4;
+
+/// The plan of a select request from a table, with the names and types of its columns.
+struct Selection {
+    /// The logical plan of the request, without row-level security.
+    plan: LogicalPlan,
+    /// The names and types of the selected columns.
+    headers: Vec<ColumnHeader>,
+}
+
+impl Selection {
+    /// Helper function to compile the columns and condition of a select request
+    ///
+    /// ## Parameters
+    ///
+    /// - `table`: The table from which rows are selected.
+    /// - `columns`: Optional. List of columns to select. If not provided, all columns are selected.
+    /// - `condition`: Optional. Specifies a condition to filter rows.
+    ///
+    /// ## Errors
+    ///
+    /// - Returns an error if the specified `condition.column` does not exist in the table.
+    /// - Returns an error if any of the `columns` does not exist in the table.
+    fn new(
+        table: &Table,
+        columns: Option<&[String]>,
+        condition: Option<&Condition>,
+    ) -> Result<Self, String> {
+        let plan = LogicalPlan::select(table, columns, condition)?;
+        let headers = match columns {
+            Some(columns) => columns
+                .iter()
+                .filter_map(|name| table.columns.iter().find(|column| column.key == *name))
+                .collect(),
+            None => table.columns.iter().collect::<Vec<&Column>>(),
+        }
+        .into_iter()
+        .map(|column| ColumnHeader {
+            name: column.key.clone(),
+            data_type: column.data_type,
+        })
+        .collect();
+        Ok(Selection { plan, headers })
+    }
+
+    /// Returns the names of the columns the selection reads: the selected columns and the
+    /// condition's column.
+    fn read_columns(&self) -> Vec<&str> {
+        self.plan.read_columns()
+    }
+
+    /// Helper function to optimize the plan for the rows of a table
+    ///
+    /// ## Parameters
+    ///
+    /// - `table`: The table the rows are selected from.
+    /// - `security`: Optional. The row-level security hiding rows of the table.
+    /// - `indexes`: The indexes of the rows of `table` the plan may read them through.
+    fn optimize(
+        &self,
+        table: &Table,
+        security: Option<&RowSecurity>,
+        indexes: &[Arc<Index>],
+    ) -> PhysicalPlan {
+        let plan = self.plan.clone();
+        match security {
+            Some(security) => plan.restrict(security.predicate()),
+            None => plan,
+        }
+        .optimize(table, indexes)
+    }
+}
+
+/// Helper function to stream the selected rows of a table
+///
+/// ## Parameters
+///
+/// - `state`: The application state, from which rows of base tables are read in chunks.
+/// - `table`: The table to select from. Its rows are streamed if it is a catalog view;
+///   otherwise only its name is used.
+/// - `headers`: The names and types of the selected columns.
+/// - `plan`: The plan of the select, which is run on every chunk. It must not read the rows
+///   through indexes, whose positions are those of the whole table.
+/// - `format`: The format of the stream.
+/// - `chunk_size`: The number of rows read from the table at once.
+///
+/// ## Returns
+///
+/// Returns a response whose body is produced by a background task while it is sent.
+fn stream_select(
+    state: Arc<AppState>,
+    table: Table,
+    headers: Vec<ColumnHeader>,
+    plan: PhysicalPlan,
+    format: StreamFormat,
+    chunk_size: usize,
+) -> Response {
+    let (sender, receiver) = mpsc::channel::<Result<String, Error>>(STREAM_BUFFER);
+
+    spawn(async move {
+        let is_view = catalog::is_catalog_name(&table.name);
+        let mut position = 0;
+        let mut row_count = 0;
+
+        if format == StreamFormat::Chunked {
+            let header = SelectFrame::Header { columns: headers };
+            if sender.send(Ok(frame_line(&header))).await.is_err() {
+                return;
+            }
+        }
+
+        loop {
+            let chunk = match is_view {
+                true => Some(
+                    table
+                        .rows
+                        .iter()
+                        .skip(position)
+                        .take(chunk_size)
+                        .cloned()
+                        .collect(),
+                ),
+                false => state.rows_range(&table.name, position, chunk_size).await,
+            };
+            let Some(mut chunk) = chunk else {
+                let error = format!("Table '{}' was dropped while streaming", table.name);
+                error!("{}", error);
+                let line = match format {
+                    StreamFormat::Chunked => Ok(frame_line(&SelectFrame::Error(error))),
+                    StreamFormat::Ndjson => Err(Error::other(error)),
+                };
+                let _ = sender.send(line).await;
+                return;
+            };
+            if chunk.is_empty() {
+                break;
+            }
+            position += chunk.len();
+
+            let rows = plan.execute(&mut chunk).rows;
+            if rows.is_empty() {
+                continue;
+            }
+            row_count += rows.len();
+            let text = match format {
+                StreamFormat::Ndjson => rows.iter().map(frame_line).collect(),
+                StreamFormat::Chunked => frame_line(&SelectFrame::Rows(rows)),
+            };
+            // The client disconnected
+            if sender.send(Ok(text)).await.is_err() {
+                return;
+            }
+        }
+
+        if format == StreamFormat::Chunked {
+            let _ = sender
+                .send(Ok(frame_line(&SelectFrame::End { row_count })))
+                .await;
+        }
+        debug!("Streamed {} rows from table '{}'", row_count, table.name);
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(ReceiverStream::new(receiver)))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Serializes a row or frame of a select stream as a line of JSON.
+fn frame_line<T: Serialize>(frame: &T) -> String {
+    serde_json::to_string(frame).unwrap_or_default() + "\n"
+}
+
+/// Handler to import rows from a CSV file into a table
+///
+/// # Example
+///
+/// ```
+/// curl -X POST "http://localhost:3000/tables/test_table/csv?create=true" -H "Content-Type: text/csv" --data-binary @test_table.csv
+/// curl -X POST "http://localhost:3000/tables/test_table/csv?header=false&delimiter=%09&null=%5CN&columns=test_key,test_key2" --data-binary @test_table.tsv
+/// ```
+///
+/// Imports the records of the CSV file in the request body as rows of the table. Either all
+/// rows are imported or none.
+///
+/// ## Parameters
+///
+/// - `name`: The name of the table.
+/// - `header`: Optional. Whether the first record holds the column names, `true` by default.
+/// - `delimiter`: Optional. The field delimiter, `,` by default.
+/// - `quote`: Optional. The quote character, `"` by default.
+/// - `null`: Optional. The text of an unquoted NULL field, empty by default.
+/// - `columns`: Optional. Comma separated columns the fields are assigned to. Defaults to the
+///   header, or to all columns of the table without a header.
+/// - `types`: Optional. Comma separated column types such as `id:INT,price:FLOAT`, taking
+///   precedence over the types declared by the table.
+/// - `infer`: Optional. Whether the types of other columns without a declared type are
+///   inferred from their values, `true` by default. Otherwise they are imported as strings.
+/// - `create`: Optional. Whether a missing table is created from the header, `false` by default.
+///
+/// ## Returns
+///
+/// Returns a JSON object with the table name, the number of imported rows and whether the
+/// table was created.
+///
+/// ## Errors
+///
+/// - Returns an error if the table does not exist and `create` is not set.
+/// - Returns an error if `create` is set and another request creates the table first.
+/// - Returns an error if the file is malformed, or a record has the wrong number of fields.
+/// - Returns an error if a column does not exist, or a value does not match its column's type.
+/// - Returns an error if a row violates a non-null, primary key or unique constraint.
+/// - Returns an error if the user does not have the `INSERT` privilege on every column of the
+///   table, or the `CREATE` privilege if the import creates it.
+///
+/// ## Notes
+///
+/// - Omitted columns get their default value or NULL, and auto-increment columns their
+///   next value.
+#[allow(clippy::too_many_arguments)]
+async fn import_csv(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Extension(audit): Extension<Audit>,
+    Path(name): Path<String>,
+    Query(options): Query<CsvOptions>,
+    Query(import): Query<CsvImportOptions>,
+    body: String,
+) -> Response {
+    audit.table(&name);
+    if catalog::is_catalog_name(&name) {
+        return read_only_schema_error(&name);
+    }
+    let (schema, created) = match state.schema(&name).await {
+        Some(schema) => (Some(schema), false),
+        None if import.create => (None, true),
+        None => {
+            let error = format!("Table '{}' does not exist", name);
+            error!("{}", error);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    };
+    let check = match schema {
+        None => check_privilege(&state, &principal, Privilege::Create, None, &[]).await,
+        Some(schema) => {
+            let columns = column_keys(&schema.columns);
+            check_privilege(&state, &principal, Privilege::Insert, Some(&name), &columns).await
+        }
+    };
+    if let Err(response) = check {
+        return response;
+    }
+
+    // The rows are added to the table under the lock it is replaced or created in, so
+    // concurrent changes are not lost and concurrent imports cannot both create it
+    let add_rows = |table: &mut Table| {
+        let row_count = import_rows(table, created, &body, &options, &import)
+            .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+        if let Some(security) = row_security(&principal, &session, table) {
+            table.rows[table.rows.len() - row_count..]
+                .iter()
+                .try_for_each(|row| security.check(row))
+                .map_err(|error| (StatusCode::FORBIDDEN, error))?;
+        }
+        Ok(row_count)
+    };
+    let imported = match created {
+        true => state.create_with(&name, add_rows).await.unwrap_or_else(|| {
+            Err((
+                StatusCode::BAD_REQUEST,
+                format!("Table '{}' already exists", name),
+            ))
+        }),
+        false => state
+            .update_with(&name, |table, _| add_rows(table))
+            .await
+            .unwrap_or_else(|| {
+                Err((
+                    StatusCode::NOT_FOUND,
+                    format!("Table '{}' does not exist", name),
+                ))
+            }),
+    };
+    let row_count = match imported {
+        Ok(row_count) => row_count,
+        Err((status, error)) => {
+            error!("{}", error);
+            return (status, Json(error)).into_response();
+        }
+    };
+
+    match state.save().await {
+        Ok(_) => {
+            info!("Imported {} rows into table '{}'", row_count, name);
+            audit.rows(row_count);
+            if created {
+                log_grants_error(state.auth.table_created(&principal, &name).await);
+            }
+            let result = CsvImportResult {
+                table_name: name,
+                row_count,
+                created,
+            };
+            (StatusCode::OK, Json(result)).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to save state: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Helper function to add the records of a CSV file to a table
+///
+/// ## Parameters
+///
+/// - `table`: The table, without columns if it is created by the import.
+/// - `create`: Whether the table is created by the import, adding a column for every field.
+/// - `text`: The CSV file.
+/// - `options`: The dialect of the file.
+/// - `import`: The columns and types of the fields.
+///
+/// ## Returns
+///
+/// Returns the number of rows added to the table.
+///
+/// ## Errors
+///
+/// - Returns an error if the file is malformed, a column or type does not exist, a value does
+///   not match its type, or a row violates a constraint. Errors in records name their line.
+///   The table may be partially changed then.
+fn import_rows(
+    table: &mut Table,
+    create: bool,
+    text: &str,
+    options: &CsvOptions,
+    import: &CsvImportOptions,
+) -> Result<usize, String> {
+    let mut records = csv::parse(text, options)?.into_iter().peekable();
+    let header = match options.header {
+        true => match records.next() {
+            Some(record) => Some(
+                record
+                    .fields
+                    .into_iter()
+                    .map(|name| name.ok_or("The header contains a NULL column name"))
+                    .collect::<Result<Vec<String>, _>>()?,
+            ),
+            None => None,
+        },
+        false => None,
+    };
+    let records: Vec<csv::CsvRecord> = records.collect();
+
+    // The column each field is assigned to
+    let columns: Vec<String> = match (&import.columns, header) {
+        (Some(columns), _) => columns
+            .split(',')
+            .map(|column| column.trim().to_string())
+            .filter(|column| !column.is_empty())
+            .collect(),
+        (None, Some(header)) => header,
+        (None, None) if !create => table.columns.iter().map(|c| c.key.clone()).collect(),
+        (None, None) => {
+            return Err(format!(
+                "Table '{}' cannot be created without a header or a column list",
+                table.name
+            ))
+        }
+    };
+
+    let mut declared = Vec::new();
+    for declaration in import.types.iter().flat_map(|types| types.split(',')) {
+        let (column, data_type) = declaration
+            .split_once(':')
+            .ok_or_else(|| format!("Expected 'column:TYPE', found '{}'", declaration))?;
+        if !columns.iter().any(|name| name == column.trim()) {
+            return Err(format!("Column '{}' not found", column.trim()));
+        }
+        declared.push((
+            column.trim().to_string(),
+            data_type.trim().parse::<DataType>()?,
+        ));
+    }
+
+    if create {
+        for column in &columns {
+            table.add_column(Column::new(column.clone(), false, false, false, None));
+        }
+    }
+    let positions = columns
+        .iter()
+        .map(|name| {
+            table
+                .columns
+                .iter()
+                .position(|column| column.key == *name)
+                .ok_or_else(|| format!("Column '{}' not found", name))
+        })
+        .collect::<Result<Vec<usize>, String>>()?;
+
+    // The type of each field, or `None` to keep the text as a string
+    let types: Vec<Option<DataType>> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let declared = declared
+                .iter()
+                .find(|(column, _)| column == name)
+                .map(|(_, data_type)| *data_type);
+            declared
+                .or(table.columns[positions[i]].data_type)
+                .or_else(|| {
+                    let values = records
+                        .iter()
+                        .filter_map(|record| record.fields.get(i).cloned().flatten());
+                    let values: Vec<String> = values.collect();
+                    import
+                        .infer
+                        .then(|| csv::infer_type(values.iter().map(String::as_str)))
+                })
+        })
+        .collect();
+    if create {
+        for (position, data_type) in positions.iter().zip(&types) {
+            table.columns[*position].data_type = Some(data_type.unwrap_or(DataType::Str));
+        }
+    }
+
+    // Auto-increment values are counted here instead of searching the table for every row
+    let mut next_ids: Vec<Option<i64>> = vec![None; table.columns.len()];
+    let row_count = records.len();
+    for record in records {
+        if record.fields.len() != columns.len() {
+            return Err(format!(
+                "Line {}: expected {} fields but found {}",
+                record.line,
+                columns.len(),
+                record.fields.len()
+            ));
+        }
+        let mut values: Vec<Value> = table
+            .columns
+            .iter()
+            .map(|column| column.default.clone().unwrap_or(Value::Null))
+            .collect();
+        for (i, field) in record.fields.into_iter().enumerate() {
+            values[positions[i]] = match (field, types[i]) {
+                (None, _) => Value::Null,
+                (Some(text), None) => Value::Str(text),
+                (Some(text), Some(data_type)) => {
+                    csv::parse_value(&text, data_type).map_err(|e| {
+                        format!("Line {}: {} for column '{}'", record.line, e, columns[i])
+                    })?
+                }
+            };
+        }
+        for (index, column) in table.columns.iter().enumerate() {
+            if column.auto_increment && values[index] == Value::Null {
+                let next = next_ids[index].unwrap_or_else(|| next_auto_increment(table, index));
+                values[index] = Value::Int(next);
+                next_ids[index] = Some(next + 1);
+            }
+        }
+        let row = complete_row(table, Row::new(values))
+            .map_err(|e| format!("Line {}: {}", record.line, e))?;
+        table.add_row(row);
+    }
+
+    table.check_constraints()?;
+    Ok(row_count)
+}
+
+/// Handler to export a table as CSV
+///
+/// # Example
+///
+/// ```
+/// curl "http://localhost:3000/tables/test_table/csv?delimiter=;&null=NULL"
+/// ```
+///
+/// ## Parameters
+///
+/// - `name`: The name of the table or `information_schema` view.
+/// - `header`, `delimiter`, `quote`, `null`: Optional. The dialect, as for the import.
+///
+/// ## Returns
+///
+/// Returns all rows of the table as CSV, preceded by the column names unless `header=false`.
+///
+/// ## Errors
+///
+/// - Returns an error if the table does not exist.
+/// - Returns an error if the user may not select every column of the table.
+async fn export_csv(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Path(name): Path<String>,
+    Query(options): Query<CsvOptions>,
+) -> Response {
+    let table = match state.catalog_view(&name, &principal, &session).await {
+        Ok(Some(view)) => Some(view),
+        Ok(None) => state.get(&name).await,
+        Err(err) => return audit_log_error(err),
+    };
+    match table {
+        Some(mut table) => {
+            let selection = match Selection::new(&table, None, None) {
+                Ok(selection) => selection,
+                Err(error) => {
+                    error!("{}", error);
+                    return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+                }
+            };
+            if let Err(response) = check_selection(&state, &principal, &table, &selection).await {
+                return response;
+            }
+            let security = row_security(&principal, &session, &table);
+            let plan = selection.optimize(&table, security.as_ref(), &[]);
+            let rows = plan.execute(&mut table.rows).rows;
+            let columns: Vec<String> = table.columns.iter().map(|c| c.key.clone()).collect();
+            csv_response(csv::write(&columns, &rows, &options))
+        }
+        None => {
+            let error = format!("Table '{}' does not exist", name);
+            error!("{}", error);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to export the result of a select request as CSV
+///
+/// # Example
+///
+/// ```
+/// curl -X POST "http://localhost:3000/select/csv?header=false" -H "Content-Type: application/json" -d '{"table_name":"test_table","columns":["test_key"],"condition":null}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `table_name`, `columns`, `condition`: The select request, as for `/select`.
+/// - `header`, `delimiter`, `quote`, `null`: Optional. The dialect, as for the import.
+///
+/// ## Returns
+///
+/// Returns the selected rows as CSV, preceded by the selected column names unless `header=false`.
+///
+/// ## Errors
+///
+/// - Returns an error if the table or a column does not exist.
+/// - Returns an error if the user may not select the columns, as for `/select`.
+async fn select_csv(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Extension(execution): Extension<Execution>,
+    Query(options): Query<CsvOptions>,
+    Json(payload): Json<SelectRequest>,
+) -> Response {
+    execution.statement(payload.to_sql());
+    let table = match state
+        .catalog_view(&payload.table_name, &principal, &session)
+        .await
+    {
+        Ok(Some(view)) => Some((view, Vec::new())),
+        Ok(None) => state.get_indexed(&payload.table_name).await,
+        Err(err) => return audit_log_error(err),
+    };
+    let Some((mut table, indexes)) = table else {
+        let error = format!("Table '{}' does not exist", payload.table_name);
+        error!("{}", error);
+        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+    };
+
+    match Selection::new(
+        &table,
+        payload.columns.as_deref(),
+        payload.condition.as_ref(),
+    ) {
+        Ok(selection) => {
+            if let Err(response) = check_selection(&state, &principal, &table, &selection).await {
+                return response;
+            }
+            let security = row_security(&principal, &session, &table);
+            let plan = selection.optimize(&table, security.as_ref(), &indexes);
+            let output = plan.execute(&mut table.rows);
+            execution.rows(output.scanned, output.rows.len());
+            let columns: Vec<String> = selection.headers.into_iter().map(|c| c.name).collect();
+            csv_response(csv::write(&columns, &output.rows, &options))
+        }
+        Err(error) => {
+            error!("{}", error);
+            (StatusCode::BAD_REQUEST, Json(error)).into_response()
+        }
+    }
+}
+
+/// Helper function to build a response with a CSV body
+fn csv_response(text: String) -> Response {
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+        text,
+    )
+        .into_response()
+}
+
+/// Handler to add a unique index to a table
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/create_index -H "Content-Type: application/json" -d '{"table_name":"test_table","name":"test_table_test_key_key","columns":["test_key"]}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `table_name`: The name of the table.
+/// - `name`: The name of the index, unique within the table.
+/// - `columns`: The columns whose combined values have to be unique.
+///
+/// ## Returns
+///
+/// Returns the table's unique constraints, including the new one.
+///
+/// ## Errors
+///
+/// - Returns an error if the table or a column does not exist, or the name is already used.
+/// - Returns an error if the existing rows are not unique over the columns.
+/// - Returns an error if the user does not have the `ALTER` privilege on the table.
+///
+/// ## Notes
+///
+/// - The index is stored as a unique constraint, so it is listed by `information_schema.indexes`.
+async fn create_index(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(audit): Extension<Audit>,
+    Json(payload): Json<CreateIndexRequest>,
+) -> Response {
+    audit.table(&payload.table_name);
+    if let Err(response) = check_privilege(
+        &state,
+        &principal,
+        Privilege::Alter,
+        Some(&payload.table_name),
+        &[],
+    )
+    .await
+    {
+        return response;
+    }
+    // The rows are checked under the lock the table is replaced in, so rows inserted
+    // meanwhile cannot bypass the index
+    let name = payload.name.clone();
+    let created = state
+        .update_with(&payload.table_name, |table, _| {
+            add_index(table, payload.name, payload.columns)
+                .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+            Ok::<_, (StatusCode, String)>(table.unique_constraints.clone())
+        })
+        .await;
+    let constraints = match created {
+        Some(Ok(constraints)) => constraints,
+        Some(Err((status, error))) => {
+            error!("{}", error);
+            return (status, Json(error)).into_response();
+        }
+        None => {
+            let error = format!("Table '{}' does not exist", payload.table_name);
+            error!("{}", error);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    };
+
+    match state.save().await {
+        Ok(_) => {
+            info!("Created index '{}' on table '{}'", name, payload.table_name);
+            (StatusCode::OK, Json(constraints)).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to save state: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Helper function to add a unique index to a table
+///
+/// ## Parameters
+///
+/// - `table`: The table.
+/// - `name`: The name of the index.
+/// - `columns`: The indexed columns.
+///
+/// ## Errors
+///
+/// - Returns an error if a column does not exist, the name is already used or the rows are not
+///   unique over the columns. The table is left unchanged.
+fn add_index(table: &mut Table, name: String, columns: Vec<String>) -> Result<(), String> {
+    table.add_unique_constraint(UniqueConstraint { name, columns })?;
+    if let Err(error) = table.check_constraints() {
+        table.unique_constraints.pop();
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Handler to add a row-level security policy to a table
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/create_policy -H "Content-Type: application/json" -d '{"table_name":"orders","policy":{"name":"tenant_isolation","using":{"Binary":{"left":{"Column":"tenant"},"op":"Eq","right":{"Function":{"name":"current_setting","args":[{"Literal":{"Str":"app.tenant"}}]}}}}}}'
+/// ```
+///
+/// Once a table has a policy, users who are not admins only see and update the rows for which
+/// the `using` expression of a policy applying to them is true, and the rows they insert or
+/// update have to satisfy its `check` expression, or `using` without one.
+///
+/// ## Parameters
+///
+/// - `table_name`: The name of the table.
+/// - `policy.name`: The name of the policy, unique within the table.
+/// - `policy.roles`: Optional. The users and roles the policy applies to, everyone by default.
+/// - `policy.using`: Optional. The condition for existing rows, as an expression.
+/// - `policy.check`: Optional. The condition for inserted and updated rows.
+///
+/// ## Returns
+///
+/// Returns the table's policies, including the new one.
+///
+/// ## Errors
+///
+/// - Returns an error if the table does not exist or already has a policy of the same name.
+/// - Returns an error if the policy has neither condition, or a condition refers to a column
+///   or function that does not exist.
+/// - Returns an error if a user or role does not exist.
+/// - Returns an error if the user does not have the `ALTER` privilege on the table.
+///
+/// ## Notes
+///
+/// - The SQL client creates policies with `CREATE POLICY`, whose expressions can call
+///   `current_user` and `current_setting('name')`. Settings are sent by clients as
+///   `x-setting-<name>` headers.
+async fn create_policy(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(audit): Extension<Audit>,
+    Json(payload): Json<CreatePolicyRequest>,
+) -> Response {
+    audit.table(&payload.table_name);
+    if let Err(response) = check_privilege(
+        &state,
+        &principal,
+        Privilege::Alter,
+        Some(&payload.table_name),
+        &[],
+    )
+    .await
+    {
+        return response;
+    }
+    if let Err((status, error)) = state.auth.check_grantees(&payload.policy.roles).await {
+        error!("{}", error);
+        return (status, Json(error)).into_response();
+    }
+
+    // The policy is added under the lock the table is replaced in, so concurrent writes and
+    // policies are kept
+    let name = payload.policy.name.clone();
+    let created = state
+        .update_with(&payload.table_name, |table, _| {
+            table
+                .add_policy(payload.policy)
+                .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+            Ok::<_, (StatusCode, String)>(table.policies.clone())
+        })
+        .await;
+    let policies = match created {
+        Some(Ok(policies)) => policies,
+        Some(Err((status, error))) => {
+            error!("{}", error);
+            return (status, Json(error)).into_response();
+        }
+        None => {
+            let error = format!("Table '{}' does not exist", payload.table_name);
+            error!("{}", error);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    };
+
+    match state.save().await {
+        Ok(_) => {
+            info!(
+                "Created policy '{}' on table '{}'",
+                name, payload.table_name
+            );
+            (StatusCode::OK, Json(policies)).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to save state: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to drop a row-level security policy of a table
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/drop_policy -H "Content-Type: application/json" -d '{"table_name":"orders","name":"tenant_isolation"}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `table_name`: The name of the table.
+/// - `name`: The name of the policy.
+///
+/// ## Returns
+///
+/// Returns the table's remaining policies.
+///
+/// ## Errors
+///
+/// - Returns an error if the table or policy does not exist.
+/// - Returns an error if the user does not have the `ALTER` privilege on the table.
+///
+/// ## Notes
+///
+/// - Dropping the last policy of a table lifts its row-level security.
+async fn drop_policy(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(audit): Extension<Audit>,
+    Json(payload): Json<DropPolicyRequest>,
+) -> Response {
+    audit.table(&payload.table_name);
+    if let Err(response) = check_privilege(
+        &state,
+        &principal,
+        Privilege::Alter,
+        Some(&payload.table_name),
+        &[],
+    )
+    .await
+    {
+        return response;
+    }
+    let dropped = state
+        .update_with(&payload.table_name, |table, _| {
+            let index = table
+                .policies
+                .iter()
+                .position(|policy| policy.name == payload.name)
+                .ok_or_else(|| {
+                    let error = format!(
+                        "Policy '{}' does not exist on table '{}'",
+                        payload.name, payload.table_name
+                    );
+                    (StatusCode::NOT_FOUND, error)
+                })?;
+            table.policies.remove(index);
+            Ok::<_, (StatusCode, String)>(table.policies.clone())
+        })
+        .await
+        .unwrap_or_else(|| {
+            let error = format!("Table '{}' does not exist", payload.table_name);
+            Err((StatusCode::NOT_FOUND, error))
+        });
+    let policies = match dropped {
+        Ok(policies) => policies,
+        Err((status, error)) => {
+            error!("{}", error);
+            return (status, Json(error)).into_response();
+        }
+    };
+
+    match state.save().await {
+        Ok(_) => {
+            info!(
+                "Dropped policy '{}' of table '{}'",
+                payload.name, payload.table_name
+            );
+            (StatusCode::OK, Json(policies)).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to save state: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to write an SQL dump of the database
+///
+/// # Example
+///
+/// ```
+/// curl http://localhost:3000/dump > dump.sql
+/// curl "http://localhost:3000/dump?tables=users,orders&batch_size=500" > dump.sql
+/// ```
+///
+/// Writes a script of `CREATE TABLE`, batched `INSERT` and `CREATE UNIQUE INDEX` statements
+/// that recreates the tables with their rows. It can be replayed with `/restore` or the SQL
+/// client, also by other versions of the database.
+///
+/// ## Parameters
+///
+/// - `tables`: Optional. Comma separated names of the tables to dump. All tables by default, or
+///   all tables the user has a privilege on if they are not an admin.
+/// - `batch_size`: Optional. The maximum number of rows per `INSERT`, 100 by default.
+///
+/// ## Returns
+///
+/// Returns the script as `application/sql`.
+///
+/// ## Errors
+///
+/// - Returns an error if a table does not exist.
+/// - Returns an error if the user may not select every column of a dumped table.
+///
+/// ## Notes
+///
+/// - All tables are read at the same time, so the dump is consistent.
+/// - Tables are created after the tables their foreign keys refer to.
+/// - Rows hidden by row-level security are left out, and the policies are dumped after the
+///   indexes.
+async fn dump(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Query(options): Query<DumpOptions>,
+) -> Response {
+    let mut names: Option<Vec<String>> = options.tables.map(|tables| {
+        tables
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect()
+    });
+    let batch_size = options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+
+    let schemas = state.schemas().await;
+    if names.is_none() && !principal.admin {
+        let visible = state.auth.visible_tables(&principal).await;
+        names = Some(
+            schemas
+                .iter()
+                .filter(|schema| visible(&schema.name))
+                .map(|schema| schema.name.clone())
+                .collect(),
+        );
+    }
+    let dumped = schemas.iter().filter(|schema| {
+        names
+            .as_ref()
+            .is_none_or(|names| names.contains(&schema.name))
+    });
+    for schema in dumped {
+        let columns = column_keys(&schema.columns);
+        if let Err(response) = check_privilege(
+            &state,
+            &principal,
+            Privilege::Select,
+            Some(&schema.name),
+            &columns,
+        )
+        .await
+        {
+            return response;
+        }
+    }
+
+    let security = |table: &Table| row_security(&principal, &session, table);
+    match state.dump(names.as_deref(), batch_size, security).await {
+        Ok(script) => {
+            debug!("Wrote a dump of {} bytes", script.len());
+            (
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, "application/sql; charset=utf-8")],
+                script,
+            )
+                .into_response()
+        }
+        Err(error) => {
+            error!("{}", error);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to restore an SQL dump
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/restore -H "Content-Type: application/sql" --data-binary @dump.sql
+/// ```
+///
+/// Executes the `CREATE TABLE`, `INSERT`, `CREATE UNIQUE INDEX` and `CREATE POLICY` statements
+/// of a script, as written by `/dump`. Either all statements are executed or none.
+///
+/// ## Parameters
+///
+/// - The request body is the script.
+///
+/// ## Returns
+///
+/// Returns a JSON object with the created tables and the number of inserted rows and created
+/// indexes.
+///
+/// ## Errors
+///
+/// - Returns an error if the script cannot be parsed, or contains other statements.
+/// - Returns an error if a created table already exists, or an `INSERT`, `CREATE INDEX` or
+///   `CREATE POLICY` refers to a table the script did not create.
+/// - Returns an error if a value is not a literal, or a row violates a constraint.
+/// - Returns an error if the user does not have the `CREATE` privilege.
+///
+/// ## Notes
+///
+/// - Constraints are checked once per table after all statements, and the database is saved once.
+/// - A user who is not an admin is granted every privilege on the restored tables.
+async fn restore(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(audit): Extension<Audit>,
+    body: String,
+) -> Response {
+    if let Err(response) = check_privilege(&state, &principal, Privilege::Create, None, &[]).await {
+        return response;
+    }
+    let statements = match parse_statements(&body) {
+        Ok(statements) => statements,
+        Err(error) => {
+            let error = error.to_string();
+            error!("{}", error);
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    let result = match state.restore(statements).await {
+        Ok(result) => result,
+        Err((status, error)) => {
+            error!("{}", error);
+            return (status, Json(error)).into_response();
+        }
+    };
+    match state.save().await {
+        Ok(_) => {
+            info!(
+                "Restored {} tables with {} rows and {} indexes",
+                result.tables.len(),
+                result.row_count,
+                result.index_count
+            );
+            for table in &result.tables {
+                log_grants_error(state.auth.table_created(&principal, table).await);
+            }
+            audit.rows(result.row_count);
+            (StatusCode::OK, Json(result)).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to save state: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Helper function to execute the statements of a restored script
+///
+/// ## Parameters
+///
+/// - `existing`: The tables of the database, which foreign keys may refer to.
+/// - `statements`: The statements of the script.
+///
+/// ## Returns
+///
+/// Returns the created tables, in the order they were created, and a summary of the restore.
+///
+/// ## Errors
+///
+/// - Returns an error naming the 1-based number of the first statement that fails.
+fn restore_tables(
+    existing: &[Table],
+    statements: Vec<Statement>,
+) -> Result<(Vec<Table>, RestoreResult), String> {
+    let mut created: Vec<Table> = Vec::new();
+    let mut row_count = 0;
+    let mut index_count = 0;
+
+    for (number, statement) in statements.into_iter().enumerate() {
+        let failed = |error: String| format!("Statement {}: {}", number + 1, error);
+        // The table a statement changes has to be created by the script
+        let created_table = |created: &[Table], name: &str| {
+            let exists = existing.iter().any(|table| table.name == name);
+            match created.iter().position(|table| table.name == name) {
+                Some(index) => Ok(index),
+                None if exists => Err(failed(format!(
+                    "Table '{}' was not created by the script",
+                    name
+                ))),
+                None => Err(failed(format!("Table '{}' does not exist", name))),
+            }
+        };
+        match statement {
+            Statement::CreateTable(create) => {
+                let request = CreateTableRequests::try_from(create).map_err(failed)?;
+                let table = build_table(existing, &created, request).map_err(failed)?;
+                created.push(table);
+            }
+            Statement::Insert(insert) => {
+                let index = created_table(&created, &insert.table_name)?;
+                row_count += insert_rows(&mut created[index], insert).map_err(failed)?;
+            }
+            Statement::CreateIndex(CreateIndex {
+                name,
+                table_name,
+                columns,
+                unique,
+            }) => {
+                if !unique {
+                    return Err(failed("Only unique indexes are supported".to_string()));
+                }
+                let index = created_table(&created, &table_name)?;
+                created[index]
+                    .add_unique_constraint(UniqueConstraint { name, columns })
+                    .map_err(failed)?;
+                index_count += 1;
+            }
+            Statement::CreatePolicy { table_name, policy } => {
+                let index = created_table(&created, &table_name)?;
+                created[index].add_policy(policy).map_err(failed)?;
+            }
+            _ => {
+                return Err(failed(
+                    "Only CREATE TABLE, INSERT, CREATE INDEX and CREATE POLICY statements can be restored"
+                        .to_string(),
+                ))
+            }
+        }
+    }
+
+    for table in &created {
+        table.check_constraints()?;
+    }
+    let result = RestoreResult {
+        tables: created.iter().map(|table| table.name.clone()).collect(),
+        row_count,
+        index_count,
+    };
+    Ok((created, result))
+}
+
+/// Helper function to build a new table from a create table request
+///
+/// ## Parameters
+///
+/// - `existing`: The tables of the database.
+/// - `created`: The tables created before by the same script.
+/// - `request`: The definition of the table.
+///
+/// ## Errors
+///
+/// - Returns an error if the table exists or is in the `information_schema` schema.
+/// - Returns an error if a foreign key refers to a table or column that does not exist.
+/// - Returns an error if a constraint is invalid.
+fn build_table(
+    existing: &[Table],
+    created: &[Table],
+    request: CreateTableRequests,
+) -> Result<Table, String> {
+    if catalog::is_catalog_name(&request.name) {
+        return Err(format!(
+            "Cannot create table '{}': schema '{}' is read-only",
+            request.name,
+            catalog::SCHEMA_NAME
+        ));
+    }
+    let find = |name: &str| {
+        created
+            .iter()
+            .chain(existing)
+            .find(|table| table.name == name)
+    };
+    if find(&request.name).is_some() {
+        return Err(format!("Table '{}' already exists", request.name));
+    }
+
+    let mut table = Table::new(request.name);
+    for column_request in request.insert_column_requests {
+        if let Some(foreign_key) = &column_request.foreign_key {
+            let referenced = match foreign_key.table_name == table.name {
+                true => Some(&table),
+                false => find(&foreign_key.table_name),
+            };
+            let Some(referenced) = referenced else {
+                return Err(format!(
+                    "Referenced table '{}' does not exist",
+                    foreign_key.table_name
+                ));
+            };
+            if !referenced
+                .columns
+                .iter()
+                .any(|col| col.key == foreign_key.column)
+            {
+                return Err(format!(
+                    "Referenced column '{}' does not exist in table '{}'",
+                    foreign_key.column, foreign_key.table_name
+                ));
+            }
+        }
+        table.add_column(Column::from(column_request));
+    }
+    table.validate_checks()?;
+    apply_table_constraints(&mut table, request.primary_key, request.unique_constraints)?;
+    Ok(table)
+}
+
+/// Helper function to add the rows of an insert statement to a table
+///
+/// ## Parameters
+///
+/// - `table`: The table named by the statement.
+/// - `insert`: The statement. Columns it does not list get their default value or NULL.
+///
+/// ## Returns
+///
+/// Returns the number of added rows.
+///
+/// ## Errors
+///
+/// - Returns an error if a column does not exist, a value is not a literal, the number of
+///   values does not match the columns, or a column does not allow a NULL value.
+fn insert_rows(table: &mut Table, insert: Insert) -> Result<usize, String> {
+    let positions = match insert.columns.is_empty() {
+        true => (0..table.columns.len()).collect(),
+        false => table.column_indices(&insert.columns)?,
+    };
+    let row_count = insert.rows.len();
+    for values in insert.rows {
+        if values.len() != positions.len() {
+            return Err(format!(
+                "Expected {} values, found {}",
+                positions.len(),
+                values.len()
+            ));
+        }
+        let mut row = Row::new(
+            table
+                .columns
+                .iter()
+                .map(|column| column.default.clone().unwrap_or(Value::Null))
+                .collect(),
+        );
+        for (position, value) in positions.iter().zip(values) {
+            row.values[*position] = match value {
+                Expr::Literal(value) => value,
+                expr => return Err(format!("Expected a literal value, found '{}'", expr)),
+            };
+        }
+        let row = complete_row(table, row)?;
+        table.add_row(row);
+    }
+    Ok(row_count)
+}
+
+/// Handler to update rows in a table based on specified conditions
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/update_table -H "Content-Type: application/json" -d '{"table_name":"test_table","condition":{"column":"test_key","value":"true"},"updates":[{"column":"test_key3","value":"updated_value"},{"column":"test_key2","value":"17.78"}]}'
+/// ```
+///
+/// Updates rows in the specified table (`table_name`) optionally filtered by a condition (`condition`).
+///
+/// ## Parameters
+///
+/// - `table_name`: Name of the table from which rows are updated.
+/// - `condition`: Optional. Specifies a condition to filter rows. Only rows matching this condition are updated.
+/// - `updates`: List of updates to apply to the filtered rows. Each update specifies a column and a new value.
+///
+/// ## Returns
+///
+/// Returns a success message if the update is successful.
+///
+/// ## Errors
+///
+/// - Returns an error if the specified `table_name` does not exist in the application state.
+/// - Returns an error if the specified `condition.column` does not exist in the table.
+/// - Returns an error if any of the `updates` specify a column that does not exist in the table.
+/// - Returns an error if a value of the `updates` is not valid for the declared type of its
+///   column, or is `null` for a non-null column.
+/// - Returns an error if the user does not have the `UPDATE` privilege on the updated columns,
+///   or the `SELECT` privilege on the condition's column.
+/// - Returns an error if an updated row violates the row-level security of the table.
+///
+/// ## Notes
+///
+/// - This handler supports flexible row filtering based on conditions and updates multiple columns at once.
+/// - Without a condition, every row is updated.
+/// - Values are converted to the declared types of their columns, and a `null` value sets the
+///   column to `NULL`.
+/// - Rows hidden by row-level security are not updated.
+/// - The update is planned like a select, so a condition on an indexed column reads the rows
+///   to update through the index.
+async fn update_table(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Extension(audit): Extension<Audit>,
+    Extension(execution): Extension<Execution>,
+    Json(payload): Json<UpdateRequest>,
+) -> Response {
+    audit.table(&payload.table_name);
+    execution.statement(payload.to_sql());
+    let table_name = Some(payload.table_name.as_str());
+    let updated: Vec<&str> = payload.updates.iter().map(|u| u.column.as_str()).collect();
+    let mut check =
+        check_privilege(&state, &principal, Privilege::Update, table_name, &updated).await;
+    if let (Ok(()), Some(condition)) = (&check, &payload.condition) {
+        let columns = [condition.column.as_str()];
+        check = check_privilege(&state, &principal, Privilege::Select, table_name, &columns).await;
+    }
+    if let Err(response) = check {
+        return response;
+    }
+    // The rows are planned, updated and checked under the lock they are changed in, so
+    // concurrent changes are neither lost nor missed by the constraint checks
+    let updated = state
+        .change_rows(&payload.table_name, |table, indexes| {
+            let plan = LogicalPlan::update(table, payload.condition.as_ref(), &payload.updates)
+                .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+            let security = row_security(&principal, &session, table);
+            let plan = match &security {
+                Some(security) => plan.restrict(security.predicate()),
+                None => plan,
+            }
+            .optimize(table, indexes);
+            let output = plan.execute(&mut table.rows);
+            if let Some(security) = &security {
+                output
+                    .rows
+                    .iter()
+                    .try_for_each(|row| security.check(row))
+                    .map_err(|error| (StatusCode::FORBIDDEN, error))?;
+            }
+
+            // Reject updates that create duplicate keys
+            table
+                .check_constraints()
+                .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+            let operation = WalOperation::UpdateRows {
+                table_name: payload.table_name.clone(),
+                rows: output
+                    .positions
+                    .iter()
+                    .copied()
+                    .zip(output.rows.clone())
+                    .collect(),
+            };
+            Ok((output, operation))
+        })
+        .await;
+    let output = match updated {
+        Some(Ok(output)) => output,
+        Some(Err((status, error))) => {
+            error!("{}", error);
+            return (status, Json(error)).into_response();
+        }
+        None => {
+            let error = format!("Table '{}' does not exist", payload.table_name);
+            error!("{}", error);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    };
+    execution.rows(output.scanned, output.rows.len());
+
+    match state.save().await {
+        Ok(_) => {
+            info!("Updated Rows: {:?}", output.rows);
+            audit.rows(output.rows.len());
+            (StatusCode::OK, Json("Rows updated successfully")).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to save state: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to delete rows from a table based on specified conditions
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/delete -H "Content-Type: application/json" -d '{"table_name":"test_table","condition":{"column":"test_key","value":"true"}}'
+/// ```
+///
+/// Deletes the rows of the specified table (`table_name`) matching a condition (`condition`).
+///
+/// ## Parameters
+///
+/// - `table_name`: Name of the table from which rows are deleted.
+/// - `condition`: Optional. Specifies a condition to filter rows. Only rows matching this condition are deleted.
+///
+/// ## Returns
+///
+/// Returns the number of deleted rows.
+///
+/// ## Errors
+///
+/// - Returns an error if the specified `table_name` does not exist in the application state.
+/// - Returns an error if the specified `condition.column` does not exist in the table.
+/// - Returns an error if the user does not have the `DELETE` privilege on the table, or the
+///   `SELECT` privilege on the condition's column.
+///
+/// ## Notes
+///
+/// - Without a condition, every row is deleted.
+/// - Rows hidden by row-level security are not deleted, so users only delete the rows they
+///   can select.
+/// - The delete is planned like a select, so a condition on an indexed column reads the rows
+///   to delete through the index.
+async fn delete_rows(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Extension(session): Extension<Session>,
+    Extension(audit): Extension<Audit>,
+    Extension(execution): Extension<Execution>,
+    Json(payload): Json<DeleteRequest>,
+) -> Response {
+    audit.table(&payload.table_name);
+    execution.statement(payload.to_sql());
+    let table_name = Some(payload.table_name.as_str());
+    let mut check = check_privilege(&state, &principal, Privilege::Delete, table_name, &[]).await;
+    if let (Ok(()), Some(condition)) = (&check, &payload.condition) {
+        let columns = [condition.column.as_str()];
+        check = check_privilege(&state, &principal, Privilege::Select, table_name, &columns).await;
+    }
+    if let Err(response) = check {
+        return response;
+    }
+    // The rows are planned and deleted under the lock they are removed in, so rows inserted
+    // meanwhile are kept
+    let deleted = state
+        .change_rows(&payload.table_name, |table, indexes| {
+            let plan = LogicalPlan::delete(table, payload.condition.as_ref())
+                .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+            let plan = match row_security(&principal, &session, table) {
+                Some(security) => plan.restrict(security.predicate()),
+                None => plan,
+            }
+            .optimize(table, indexes);
+            let output = plan.execute(&mut table.rows);
+            let operation = WalOperation::DeleteRows {
+                table_name: payload.table_name.clone(),
+                positions: output.positions.clone(),
+            };
+            Ok::<_, (StatusCode, String)>((output, operation))
+        })
+        .await;
+    let output = match deleted {
+        Some(Ok(output)) => output,
+        Some(Err((status, error))) => {
+            error!("{}", error);
+            return (status, Json(error)).into_response();
+        }
+        None => {
+            let error = format!("Table '{}' does not exist", payload.table_name);
+            error!("{}", error);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    };
+    execution.rows(output.scanned, output.rows.len());
+
+    match state.save().await {
+        Ok(_) => {
+            info!("Deleted Rows: {:?}", output.rows);
+            audit.rows(output.rows.len());
+            (StatusCode::OK, Json(output.rows.len())).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to save state: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to write a consistent snapshot of all tables
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/admin/snapshot
+/// ```
+///
+/// The tables are copied as of the last logged change and written to `snapshots/` in the data
+/// directory while the
+/// server keeps serving requests. Afterwards the current WAL segment is completed, so the
+/// archive contains every change up to the snapshot.
+///
+/// ## Returns
+///
+/// Returns a JSON object with the path of the snapshot, its LSN, its time and its number of
+/// tables.
+///
+/// ## Errors
+///
+/// - Returns an error if the snapshot or the WAL segment cannot be written.
+///
+/// ## Notes
+///
+/// - Restore a snapshot with `server restore --snapshot <PATH> --wal <ARCHIVE> --data-dir <DIR>`.
+/// - If the tables are kept in memory only, the snapshot has LSN 0 and no segment is archived.
+async fn create_snapshot(State(state): State<Arc<AppState>>) -> Response {
+    let snapshot = state.snapshot().await;
+    let (lsn, timestamp, table_count) = (snapshot.lsn, snapshot.timestamp, snapshot.tables.len());
+    let snapshot_dir = state.storage.path(SNAPSHOT_DIR);
+    let written =
+        tokio::task::spawn_blocking(move || backup::write_snapshot(&snapshot_dir, &snapshot))
+            .await
+            .map_err(Error::from)
+            .and_then(|written| written);
+    let path = match written {
+        Ok(path) => path,
+        Err(err) => {
+            let error = format!("Failed to write snapshot: {}", err);
+            error!("{}", error);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    match state.switch_wal().await {
+        Ok(_) => {
+            info!("Wrote snapshot {} at LSN {}", path.display(), lsn);
+            let info = SnapshotInfo {
+                path: path.display().to_string(),
+                lsn,
+                timestamp: timestamp.to_rfc3339(),
+                table_count,
+            };
+            (StatusCode::OK, Json(info)).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to switch WAL segment: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to get the state of the write-ahead log
+///
+/// # Example
+///
+/// ```
+/// curl http://localhost:3000/admin/wal
+/// ```
+///
+/// ## Returns
+///
+/// Returns a JSON object with the last LSN, the current segment and the archive directory.
+///
+/// ## Errors
+///
+/// - Returns an error if the tables are kept in memory only.
+async fn wal_status(State(state): State<Arc<AppState>>) -> Response {
+    match state.wal_status() {
+        Some(status) => (StatusCode::OK, Json(status)).into_response(),
+        None => wal_disabled_error(),
+    }
+}
+
+/// Returns the error response for a write-ahead log request while the tables are kept in memory
+/// only.
+fn wal_disabled_error() -> Response {
+    let error = "The write-ahead log is disabled, as the tables are kept in memory only";
+    error!("{}", error);
+    (StatusCode::CONFLICT, Json(error)).into_response()
+}
+
+/// Handler to complete the current WAL segment
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/admin/wal/switch
+/// ```
+///
+/// The segment is moved to the archive directory if one is configured. Nothing happens if it
+/// has no records yet.
+///
+/// ## Returns
+///
+/// Returns the state of the write-ahead log after the switch.
+///
+/// ## Errors
+///
+/// - Returns an error if the segment cannot be written or archived.
+/// - Returns an error if the tables are kept in memory only.
+async fn switch_wal(State(state): State<Arc<AppState>>) -> Response {
+    match state.switch_wal().await {
+        Ok(None) => wal_disabled_error(),
+        Ok(Some(status)) => {
+            info!("Switched to WAL segment {}", status.segment);
+            (StatusCode::OK, Json(status)).into_response()
+        }
+        Err(err) => {
+            let error = format!("Failed to switch WAL segment: {}", err);
+            error!("{}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to reset the execution statistics of statements
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/admin/statements/reset
+/// ```
+///
+/// Removes the statistics of every statement listed by `system.statement_stats`.
+///
+/// ## Returns
+///
+/// Returns a message with the number of statements whose statistics were removed.
+async fn reset_statements(State(state): State<Arc<AppState>>) -> Response {
+    let count = state.statistics.reset();
+    info!("Reset the statistics of {} statements", count);
+    let message = format!("Reset the statistics of {} statements", count);
+    (StatusCode::OK, Json(message)).into_response()
+}
+
+/// Helper function to check that the user of a request has a privilege, see [`Auth::check`]
+///
+/// ## Errors
+///
+/// - Returns the response to send if the user does not have the privilege.
+async fn check_privilege(
+    state: &AppState,
+    principal: &Principal,
+    privilege: Privilege,
+    table: Option<&str>,
+    columns: &[&str],
+) -> Result<(), Response> {
+    state
+        .auth
+        .check(principal, privilege, table, columns)
+        .await
+        .map_err(|error| {
+            error!("{}", error);
+            (StatusCode::FORBIDDEN, Json(error)).into_response()
+        })
+}
+
+/// Helper function to get the names of columns
+fn column_keys(columns: &[Column]) -> Vec<&str> {
+    columns.iter().map(|column| column.key.as_str()).collect()
+}
+
+/// Helper function to log a failure to update the grants after a table change, which has
+/// already been saved
+fn log_grants_error(result: Result<(), AuthError>) {
+    if let Err((_, error)) = result {
+        error!("Failed to update grants: {}", error);
+    }
+}
+
+/// Helper function to get the row-level security of a table for the user of a request, or
+/// `None` if it does not restrict them. Admins are never restricted.
+fn row_security(principal: &Principal, session: &Session, table: &Table) -> Option<RowSecurity> {
+    match principal.admin {
+        true => None,
+        false => RowSecurity::new(table, session),
+    }
+}
+
+/// Helper function to get the schema of a table with only the rows the principal may see
+/// counted, so the count does not reveal how many rows row-level security hides
+fn visible_schema(principal: &Principal, session: &Session, table: &Table) -> TableSchema {
+    let mut schema = table.schema();
+    if let Some(security) = row_security(principal, session, table) {
+        schema.row_count = table
+            .rows
+            .iter()
+            .filter(|row| security.visible(row))
+            .count();
+    }
+    schema
+}
+
+/// Handler to log in and get a bearer token
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/login -H "Content-Type: application/json" -d '{"username":"admin","password":"s3cret"}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `username`: The name of the user.
+/// - `password`: The password of the user.
+///
+/// ## Returns
+///
+/// Returns a JSON object with the token, the user name and the time the token expires.
+///
+/// ## Errors
+///
+/// - Returns an error if the user name or password is wrong.
+///
+/// ## Notes
+///
+/// - Send the token as `Authorization: Bearer <TOKEN>` instead of the password.
+/// - Tokens expire after `auth.token_ttl` and end when the server restarts.
+async fn login(State(state): State<Arc<AppState>>, Json(payload): Json<LoginRequest>) -> Response {
+    match state.auth.login(&payload.username, &payload.password).await {
+        Ok((token, expires_at)) => {
+            info!("User '{}' logged in", payload.username);
+            let response = LoginResponse {
+                token,
+                username: payload.username,
+                expires_at: expires_at.to_rfc3339(),
+            };
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(error) => {
+            error!("Failed login of user '{}': {}", payload.username, error);
+            (StatusCode::UNAUTHORIZED, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to revoke the bearer token of the request
+///
+/// # Example
+///
+/// ```
+/// curl -X POST http://localhost:3000/logout -H "Authorization: Bearer <TOKEN>"
+/// ```
+///
+/// ## Returns
+///
+/// Returns a success message if the token is revoked.
+///
+/// ## Errors
+///
+/// - Returns an error if the request is not authenticated with a bearer token.
+async fn logout(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    match auth::bearer_token(&headers) {
+        Some(token) if state.auth.logout(token) => {
+            info!("Revoked a token");
+            (StatusCode::OK, Json("Logged out")).into_response()
+        }
+        _ => {
+            let error = "Only bearer tokens can be revoked";
+            error!("{}", error);
+            (StatusCode::BAD_REQUEST, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to list the users
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret http://localhost:3000/users
+/// ```
+///
+/// ## Returns
+///
+/// Returns a JSON array of the users with their names and whether they are admins.
+///
+/// ## Notes
+///
+/// - Only admins may list the users.
+async fn get_users(State(state): State<Arc<AppState>>) -> Response {
+    (StatusCode::OK, Json(state.auth.users().await)).into_response()
+}
+
+/// Handler to create a user
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret -X POST http://localhost:3000/create_user -H "Content-Type: application/json" -d '{"username":"alice","password":"wonderland","admin":false}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `username`: The name of the user.
+/// - `password`: The password of the user, stored as a salted hash.
+/// - `admin`: Whether the user may manage users and use the admin API. Defaults to false.
+///
+/// ## Returns
+///
+/// Returns a success message if the user is created.
+///
+/// ## Errors
+///
+/// - Returns an error if the user exists, or the name or password is empty.
+/// - Returns an error if the users cannot be saved.
+///
+/// ## Notes
+///
+/// - Only admins may create users.
+async fn create_user(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateUserRequest>,
+) -> Response {
+    match state
+        .auth
+        .create_user(&payload.username, &payload.password, payload.admin)
+        .await
+    {
+        Ok(()) => {
+            info!("Created user: {}", payload.username);
+            (
+                StatusCode::OK,
+                Json(format!("Created user '{}'", payload.username)),
+            )
+                .into_response()
+        }
+        Err((status, error)) => {
+            error!("{}", error);
+            (status, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to change the password of a user
+///
+/// # Example
+///
+/// ```
+/// curl -u alice:wonderland -X POST http://localhost:3000/alter_user -H "Content-Type: application/json" -d '{"username":"alice","password":"looking-glass"}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `username`: The name of the user.
+/// - `password`: The new password of the user.
+///
+/// ## Returns
+///
+/// Returns a success message if the password is changed.
+///
+/// ## Errors
+///
+/// - Returns an error if the user does not exist or the password is empty.
+/// - Returns an error if the users cannot be saved.
+///
+/// ## Notes
+///
+/// - Users may change their own password, admins the password of every user.
+/// - The tokens of the user are revoked.
+async fn alter_user(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Json(payload): Json<AlterUserRequest>,
+) -> Response {
+    if !principal.admin && principal.username != payload.username {
+        let error = format!(
+            "User '{}' may not change the password of user '{}'",
+            principal.username, payload.username
+        );
+        error!("{}", error);
+        return (StatusCode::FORBIDDEN, Json(error)).into_response();
+    }
+    match state
+        .auth
+        .alter_user(&payload.username, &payload.password)
+        .await
+    {
+        Ok(()) => {
+            info!("Changed the password of user: {}", payload.username);
+            (
+                StatusCode::OK,
+                Json(format!(
+                    "Changed the password of user '{}'",
+                    payload.username
+                )),
+            )
+                .into_response()
+        }
+        Err((status, error)) => {
+            error!("{}", error);
+            (status, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to drop a user
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret -X POST http://localhost:3000/drop_user -H "Content-Type: application/json" -d '{"username":"alice"}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `username`: The name of the user to be dropped.
+///
+/// ## Returns
+///
+/// Returns a success message if the user is dropped.
+///
+/// ## Errors
+///
+/// - Returns an error if the user does not exist or is the last admin.
+/// - Returns an error if the users cannot be saved.
+///
+/// ## Notes
+///
+/// - Only admins may drop users. The tokens of the user are revoked.
+async fn drop_user(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<DropUserRequest>,
+) -> Response {
+    match state.auth.drop_user(&payload.username).await {
+        Ok(()) => {
+            info!("Dropped user: {}", payload.username);
+            (
+                StatusCode::OK,
+                Json(format!("Dropped user '{}'", payload.username)),
+            )
+                .into_response()
+        }
+        Err((status, error)) => {
+            error!("{}", error);
+            (status, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to list the roles
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret http://localhost:3000/roles
+/// ```
+///
+/// ## Returns
+///
+/// Returns a JSON array of the roles with the names of their members.
+///
+/// ## Notes
+///
+/// - Only admins may list the roles.
+async fn get_roles(State(state): State<Arc<AppState>>) -> Response {
+    (StatusCode::OK, Json(state.auth.roles().await)).into_response()
+}
+
+/// Handler to create a role
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret -X POST http://localhost:3000/create_role -H "Content-Type: application/json" -d '{"name":"analyst"}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `name`: The name of the role, which may not be the name of a user.
+///
+/// ## Returns
+///
+/// Returns a success message if the role is created.
+///
+/// ## Errors
+///
+/// - Returns an error if a user or role with the name exists, or the name is reserved.
+/// - Returns an error if the roles cannot be saved.
+///
+/// ## Notes
+///
+/// - Only admins may create roles.
+async fn create_role(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RoleRequest>,
+) -> Response {
+    match state.auth.create_role(&payload.name).await {
+        Ok(()) => {
+            info!("Created role: {}", payload.name);
+            (
+                StatusCode::OK,
+                Json(format!("Created role '{}'", payload.name)),
+            )
+                .into_response()
+        }
+        Err((status, error)) => {
+            error!("{}", error);
+            (status, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to drop a role
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret -X POST http://localhost:3000/drop_role -H "Content-Type: application/json" -d '{"name":"analyst"}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `name`: The name of the role to be dropped.
+///
+/// ## Returns
+///
+/// Returns a success message if the role is dropped.
+///
+/// ## Errors
+///
+/// - Returns an error if the role does not exist.
+/// - Returns an error if the roles cannot be saved.
+///
+/// ## Notes
+///
+/// - Only admins may drop roles. The role is revoked from its members and its privileges are
+///   revoked.
+async fn drop_role(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RoleRequest>,
+) -> Response {
+    match state.auth.drop_role(&payload.name).await {
+        Ok(()) => {
+            info!("Dropped role: {}", payload.name);
+            (
+                StatusCode::OK,
+                Json(format!("Dropped role '{}'", payload.name)),
+            )
+                .into_response()
+        }
+        Err((status, error)) => {
+            error!("{}", error);
+            (status, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to grant a role to a user
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret -X POST http://localhost:3000/grant_role -H "Content-Type: application/json" -d '{"role":"analyst","username":"alice"}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `role`: The name of the role.
+/// - `username`: The name of the user who is granted the role.
+///
+/// ## Returns
+///
+/// Returns a success message if the role is granted.
+///
+/// ## Errors
+///
+/// - Returns an error if the role or user does not exist.
+/// - Returns an error if the roles cannot be saved.
+///
+/// ## Notes
+///
+/// - Only admins may grant roles. The user has the privileges of the role from their next
+///   request on.
+async fn grant_role(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<GrantRoleRequest>,
+) -> Response {
+    match state
+        .auth
+        .grant_role(&payload.role, &payload.username)
+        .await
+    {
+        Ok(()) => {
+            info!(
+                "Granted role '{}' to user '{}'",
+                payload.role, payload.username
+            );
+            (
+                StatusCode::OK,
+                Json(format!(
+                    "Granted role '{}' to user '{}'",
+                    payload.role, payload.username
+                )),
+            )
+                .into_response()
+        }
+        Err((status, error)) => {
+            error!("{}", error);
+            (status, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to revoke a role from a user
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret -X POST http://localhost:3000/revoke_role -H "Content-Type: application/json" -d '{"role":"analyst","username":"alice"}'
+/// ```
+///
+/// ## Parameters
+///
+/// - `role`: The name of the role.
+/// - `username`: The name of the user the role is revoked from.
+///
+/// ## Returns
+///
+/// Returns a success message if the role is revoked.
+///
+/// ## Errors
+///
+/// - Returns an error if the role or user does not exist.
+/// - Returns an error if the roles cannot be saved.
+///
+/// ## Notes
+///
+/// - Only admins may revoke roles.
+async fn revoke_role(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<GrantRoleRequest>,
+) -> Response {
+    match state
+        .auth
+        .revoke_role(&payload.role, &payload.username)
+        .await
+    {
+        Ok(()) => {
+            info!(
+                "Revoked role '{}' from user '{}'",
+                payload.role, payload.username
+            );
+            (
+                StatusCode::OK,
+                Json(format!(
+                    "Revoked role '{}' from user '{}'",
+                    payload.role, payload.username
+                )),
+            )
+                .into_response()
+        }
+        Err((status, error)) => {
+            error!("{}", error);
+            (status, Json(error)).into_response()
+        }
+    }
+}
+
+/// Handler to list granted privileges
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret http://localhost:3000/grants
+/// curl -u admin:s3cret "http://localhost:3000/grants?grantee=analyst"
+/// ```
+///
+/// ## Parameters
+///
+/// - `grantee`: Optional. The user or role whose grants are listed.
+///
+/// ## Returns
+///
+/// Returns a JSON array of grants with the grantee, privilege, table and columns. A grant
+/// without a table is on the database, one without columns on the whole table.
+///
+/// ## Errors
+///
+/// - Returns an error if a user who is not an admin asks for the grants of another user or a
+///   role they do not have.
+///
+/// ## Notes
+///
+/// - Without `grantee`, admins get all grants and other users the grants of themselves and
+///   their roles.
+async fn get_grants(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<GrantsQuery>,
+) -> Response {
+    let grants = match query.grantee.as_deref() {
+        Some(grantee) if principal.admin || principal.grantees().contains(&grantee) => {
+            state.auth.grants(Some(grantee)).await
+        }
+        Some(grantee) => {
+            let error = format!(
+                "User '{}' may not list the grants of '{}'",
+                principal.username, grantee
+            );
+            error!("{}", error);
+            return (StatusCode::FORBIDDEN, Json(error)).into_response();
+        }
+        None if principal.admin => state.auth.grants(None).await,
+        None => state.auth.grants_of(&principal).await,
+    };
+    (StatusCode::OK, Json(grants)).into_response()
+}
+
+/// Handler to grant privileges to a user or role
+///
+/// # Example
 ///
 /// ```
-/// curl -X POST http://localhost:3000/select -H "Content-Type: application/json" -d '{"table_name":"test_table","columns":["test_key","test_key3"],"condition":{"column":"test_key","value":"true"}}'
+/// curl -u admin:s3cret -X POST http://localhost:3000/grant -H "Content-Type: application/json" -d '{"privileges":[{"privilege":"SELECT","columns":["id","name"]}],"table":"users","grantee":"analyst"}'
+/// curl -u admin:s3cret -X POST http://localhost:3000/grant -H "Content-Type: application/json" -d '{"privileges":[{"privilege":"CREATE"}],"grantee":"service"}'
 /// ```
 ///
-/// Retrieves rows from the specified table (`table_name`) optionally filtered by columns (`columns`) and a conditional (`condition`).
-/// If `columns` is not provided, all columns are selected (`SELECT *`).
-///
 /// ## Parameters
 ///
-/// - `table_name`: Name of the table from which rows are selected.
-/// - `columns`: Optional. List of columns to select. If not provided, all columns are selected.
-/// - `condition`: Optional. Specifies a condition to filter rows. Only rows matching this condition are returned.
+/// - `privileges`: The privileges, each `SELECT`, `INSERT`, `UPDATE`, `DELETE`, `CREATE`,
+///   `DROP` or `ALTER`, and optionally the columns it is limited to.
+/// - `table`: Optional. The table the privileges are granted on. Without it they are granted
+///   on the whole database.
+/// - `grantee`: The user or role the privileges are granted to.
 ///
 /// ## Returns
 ///
-/// Returns a JSON array of rows, where each row is represented as an array of strings (values of selected columns).
+/// Returns a success message if the privileges are granted.
 ///
 /// ## Errors
 ///
-/// - Returns an error if the specified `table_name` does not exist in the application state.
-/// - Returns an error if the specified `condition.column` does not exist in the table.
+/// - Returns an error if the grantee, table or a column does not exist.
+/// - Returns an error if a privilege cannot be limited to columns, or `CREATE` is granted on a
+///   table.
+/// - Returns an error if the grants cannot be saved.
 ///
 /// ## Notes
 ///
-/// - This handler supports flexible column selection and row filtering based on conditions.
-///
-async fn select(
+/// - Only admins may grant privileges.
+/// - `SELECT`, `INSERT` and `UPDATE` can be limited to columns.
+async fn grant(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<SelectRequest>,
+    Extension(audit): Extension<Audit>,
+    Json(payload): Json<GrantRequest>,
 ) -> Response {
-    if let Some(table) = state.get(payload.table_name.as_str()).await {
-        let rows = select_rows(&table, payload.columns, payload.condition.as_ref()).await;
-
-        match rows {
-            Ok(rows) => (StatusCode::OK, Json(rows)).into_response(),
-            Err(error) => {
-                error!("{}", error);
-                (StatusCode::BAD_REQUEST, Json(error)).into_response()
-            }
+    if let Some(table) = &payload.table {
+        audit.table(table);
+    }
+    if let Err(response) = check_grant_target(&state, &payload).await {
+        return response;
+    }
+    match state.auth.grant(&payload).await {
+        Ok(()) => {
+            info!("Granted {:?}", payload);
+            (
+                StatusCode::OK,
+                Json(format!("Granted privileges to '{}'", payload.grantee)),
+            )
+                .into_response()
+        }
+        Err((status, error)) => {
+            error!("{}", error);
+            (status, Json(error)).into_response()
         }
-    } else {
-        let error = format!("Table '{}' does not exist", payload.table_name);
-        error!("{}", error);
-        (StatusCode::NOT_FOUND, Json(error)).into_response()
     }
 }
 
-/// Helper function to select rows from a table based on specified conditions
+/// Handler to revoke privileges from a user or role
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret -X POST http://localhost:3000/revoke -H "Content-Type: application/json" -d '{"privileges":[{"privilege":"SELECT","columns":["name"]}],"table":"users","grantee":"analyst"}'
+/// ```
+///
 /// ## Parameters
 ///
-/// - `table_name`: Name of the table from which rows are selected.
-/// - `columns`: Optional. List of columns to select. If not provided, all columns are selected.
-/// - `condition`: Optional. Specifies a condition to filter rows. Only rows matching this condition are returned.
+/// - `privileges`, `table`, `grantee`: The privileges to revoke, as for `/grant`.
 ///
 /// ## Returns
 ///
-/// Returns a JSON array of rows, where each row is represented as an array of strings (values of selected columns).
+/// Returns a success message if the privileges are revoked.
 ///
 /// ## Errors
 ///
-/// - Returns an error if the specified `table_name` does not exist in the application state.
-/// - Returns an error if the specified `condition.column` does not exist in the table.
-async fn select_rows(
-    table: &Table,
-    columns: Option<Vec<String>>,
-    condition: Option<&Condition>,
-) -> Result<Vec<Row>, String> {
-    let mut rows = vec![];
-
-    for row in &table.rows {
-        if let Some(cond) = condition {
-            if let Some(col_index) = table.columns.iter().position(|col| col.key == cond.column) {
-                if row.values[col_index].as_string().unwrap_or_default() != cond.value {
-                    continue;
-                }
-            } else {
-                return Err(format!("Column '{}' not found", cond.column));
-            }
+/// - Returns an error if the grantee, table or a column does not exist.
+/// - Returns an error if columns are revoked from a privilege granted on the whole table.
+/// - Returns an error if the grants cannot be saved.
+///
+/// ## Notes
+///
+/// - Only admins may revoke privileges. Revoking a privilege that was not granted succeeds.
+async fn revoke(
+    State(state): State<Arc<AppState>>,
+    Extension(audit): Extension<Audit>,
+    Json(payload): Json<GrantRequest>,
+) -> Response {
+    if let Some(table) = &payload.table {
+        audit.table(table);
+    }
+    if let Err(response) = check_grant_target(&state, &payload).await {
+        return response;
+    }
+    match state.auth.revoke(&payload).await {
+        Ok(()) => {
+            info!("Revoked {:?}", payload);
+            (
+                StatusCode::OK,
+                Json(format!("Revoked privileges from '{}'", payload.grantee)),
+            )
+                .into_response()
         }
-
-        let mut selected_row = Row::new(vec![]);
-
-        if let Some(ref cols) = columns {
-            for col in cols {
-                if let Some(col_index) = table.columns.iter().position(|c| c.key == *col) {
-                    selected_row.add_value(row.values[col_index].clone());
-                } else {
-                    return Err(format!("Column '{}' not found", col));
-                }
-            }
-        } else {
-            // SELECT *
-            for value in &row.values {
-                selected_row.add_value(value.clone());
-            }
+        Err((status, error)) => {
+            error!("{}", error);
+            (status, Json(error)).into_response()
         }
-
-        rows.push(selected_row);
     }
+}
 
-    info!("Selected Rows: {:?}", rows);
-    Ok(rows)
+/// Helper function to check that the table and columns of a grant or revoke request exist
+async fn check_grant_target(state: &AppState, request: &GrantRequest) -> Result<(), Response> {
+    let Some(table_name) = &request.table else {
+        return Ok(());
+    };
+    let Some(schema) = state.schema(table_name).await else {
+        let error = format!("Table '{}' does not exist", table_name);
+        error!("{}", error);
+        return Err((StatusCode::NOT_FOUND, Json(error)).into_response());
+    };
+    let columns = request
+        .privileges
+        .iter()
+        .flat_map(|spec| spec.columns.iter().flatten());
+    for column in columns {
+        if !schema.columns.iter().any(|c| &c.key == column) {
+            let error = format!(
+                "Column '{}' does not exist in table '{}'",
+                column, table_name
+            );
+            error!("{}", error);
+            return Err((StatusCode::BAD_REQUEST, Json(error)).into_response());
+        }
+    }
+    Ok(())
 }
 
-/// Handler to update rows in a table based on specified conditions
+/// Handler to check that the server is up
 ///
 /// # Example
 ///
 /// ```
-/// curl -X POST http://localhost:3000/update_table -H "Content-Type: application/json" -d '{"table_name":"test_table","condition":{"column":"test_key","value":"true"},"updates":[{"column":"test_key3","value":"updated_value"},{"column":"test_key2","value":"17.78"}]}'
+/// curl http://localhost:3000/health
 /// ```
 ///
-/// Updates rows in the specified table (`table_name`) optionally filtered by a condition (`condition`).
+/// ## Returns
 ///
-/// ## Parameters
+/// Returns `"OK"` as long as the server answers requests.
 ///
-/// - `table_name`: Name of the table from which rows are updated.
-/// - `condition`: Optional. Specifies a condition to filter rows. Only rows matching this condition are updated.
-/// - `updates`: List of updates to apply to the filtered rows. Each update specifies a column and a new value.
+/// ## Notes
+///
+/// - No credentials are needed, so that it can be used as a liveness probe.
+async fn health() -> Response {
+    (StatusCode::OK, Json("OK")).into_response()
+}
+
+/// Handler to check that the server can serve requests
+///
+/// # Example
+///
+/// ```
+/// curl http://localhost:3000/ready
+/// ```
 ///
 /// ## Returns
 ///
-/// Returns a success message if the update is successful.
+/// Returns `{"ready":true,"reason":null}` if the server can write to its data directory.
 ///
 /// ## Errors
 ///
-/// - Returns an error if the specified `table_name` does not exist in the application state.
-/// - Returns an error if the specified `condition.column` does not exist in the table.
-/// - Returns an error if any of the `updates` specify a column that does not exist in the table.
+/// - Returns `503 Service Unavailable` with the reason if the server is shutting down or the
+///   data directory is not writable.
 ///
 /// ## Notes
 ///
-/// - This handler supports flexible row filtering based on conditions and updates multiple columns at once.
-async fn update_table(
+/// - The server only accepts requests once the tables are loaded, so they are always loaded when
+///   this endpoint answers.
+/// - No credentials are needed, so that it can be used as a readiness probe.
+async fn ready(State(state): State<Arc<AppState>>) -> Response {
+    if state.shutdown.is_triggered() {
+        let readiness = Readiness {
+            ready: false,
+            reason: Some("The server is shutting down".to_string()),
+        };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(readiness)).into_response();
+    }
+    match state.check_writable().await {
+        Ok(()) => {
+            let readiness = Readiness {
+                ready: true,
+                reason: None,
+            };
+            (StatusCode::OK, Json(readiness)).into_response()
+        }
+        Err(err) => {
+            let reason = format!("The data directory is not writable: {}", err);
+            error!("{}", reason);
+            let readiness = Readiness {
+                ready: false,
+                reason: Some(reason),
+            };
+            (StatusCode::SERVICE_UNAVAILABLE, Json(readiness)).into_response()
+        }
+    }
+}
+
+/// Handler to get information about the server
+///
+/// # Example
+///
+/// ```
+/// curl http://localhost:3000/info
+/// ```
+///
+/// ## Returns
+///
+/// Returns a JSON object with the version and build profile of the server, its uptime in
+/// seconds, its data directory and persistence, the number of tables, the size of the files it
+/// stores in bytes and the enabled optional features.
+///
+/// ## Notes
+///
+/// - The size includes `db.json`, `users.json`, the write-ahead log, snapshots and the audit log.
+async fn info(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<UpdateRequest>,
+    Extension(features): Extension<Vec<&'static str>>,
 ) -> Response {
-    if let Some(mut table) = state.get(payload.table_name.as_str()).await {
-        // Fetch rows that match the condition
-        let rows = select_rows(&table, None, payload.condition.as_ref()).await;
-
-        match rows {
-            Ok(mut selected_rows) => {
-                // Update logic for rows that match the condition
-                for row in &mut selected_rows {
-                    for update in &payload.updates {
-                        if let Some(col_index) = table
-                            .columns
-                            .iter()
-                            .position(|col| col.key == update.column)
-                        {
-                            row.values[col_index] = Value::from(update.value.clone());
-                        } else {
-                            let error = format!("Column '{}' not found", update.column);
-                            error!("{}", error);
-                            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
-                        }
-                    }
-                }
+    let storage_bytes = match state.storage_size().await {
+        Ok(size) => size,
+        Err(err) => {
+            let error = format!("Failed to get the size of the data directory: {}", err);
+            error!("{}", error);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+    let info = ServerInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        profile: match cfg!(debug_assertions) {
+            true => "debug",
+            false => "release",
+        }
+        .to_string(),
+        uptime_seconds: state.metrics.uptime().as_secs_f64(),
+        data_dir: state.storage.data_dir.display().to_string(),
+        persistence: match state.storage.persistence {
+            Persistence::Disk => "disk",
+            Persistence::Memory => "memory",
+        }
+        .to_string(),
+        table_count: state.schemas().await.len(),
+        storage_bytes,
+        features: features.into_iter().map(str::to_string).collect(),
+    };
+    (StatusCode::OK, Json(info)).into_response()
+}
 
-                // Apply the updates back to the original table rows
-                for row in &mut table.rows {
-                    if let Some(condition) = &payload.condition {
-                        if let Some(col_index) = table
-                            .columns
-                            .iter()
-                            .position(|col| col.key == condition.column)
-                        {
-                            if row.values[col_index].as_string().unwrap_or_default()
-                                == condition.value
-                            {
-                                for update in &payload.updates {
-                                    if let Some(update_col_index) = table
-                                        .columns
-                                        .iter()
-                                        .position(|col| col.key == update.column)
-                                    {
-                                        row.values[update_col_index] =
-                                            Value::from(update.value.clone());
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+/// Handler to get the metrics of the server in the Prometheus text format
+///
+/// # Example
+///
+/// ```
+/// curl -u admin:s3cret http://localhost:3000/metrics
+/// ```
+///
+/// ## Returns
+///
+/// Returns the request counts, latencies and errors per route, the time spent waiting for the
+/// tables' lock, the row count of every table, the durations and sizes of persistence writes
+/// and the uptime of the server, see [`metrics`].
+///
+/// ## Notes
+///
+/// - Only admins may get the metrics, as they name every table. Prometheus can scrape them
+///   with the `basic_auth` of an admin.
+async fn get_metrics(State(state): State<Arc<AppState>>) -> Response {
+    let tables: Vec<(String, usize)> = state
+        .schemas()
+        .await
+        .into_iter()
+        .map(|schema| (schema.name, schema.row_count))
+        .collect();
+    (
+        [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        state.metrics.encode(&tables),
+    )
+        .into_response()
+}
 
-                // Drop the current table
-                state.drop_table(&*payload.table_name.clone()).await;
+/// The file written and removed in the data directory to check that it is writable
+const READY_PROBE_FILE: &str = ".ready";
 
-                // Create the updated table
-                let updated_table = Table {
-                    name: payload.table_name.clone(),
-                    columns: table.columns.clone(),
-                    rows: table.rows.clone(),
-                };
+/// Helper function to get the size of a file, or of the files in a directory, which is 0 if it
+/// does not exist
+fn disk_usage(path: &std::path::Path) -> Result<u64, Error> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        size += disk_usage(&entry?.path())?;
+    }
+    Ok(size)
+}
 
-                // Create the updated table in the state
-                state.create(updated_table).await;
+/// A change of the tables that could not be written to the write-ahead log, and so was not
+/// applied
+#[derive(Debug)]
+struct LogError(Error);
 
-                info!("Updated Rows: {:?}", selected_rows);
-                (StatusCode::OK, Json("Rows updated successfully")).into_response()
-            }
-            Err(error) => {
-                error!("{}", error);
-                (StatusCode::BAD_REQUEST, Json(error)).into_response()
-            }
-        }
-    } else {
-        let error = format!("Table '{}' does not exist", payload.table_name);
-        error!("{}", error);
-        (StatusCode::NOT_FOUND, Json(error)).into_response()
+impl From<LogError> for (StatusCode, String) {
+    fn from(LogError(error): LogError) -> Self {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to write the write-ahead log: {}", error),
+        )
     }
 }
 
 /// Application state holding tables
+///
+/// Every change of the tables is written to the write-ahead log while the tables are locked and
+/// before it is applied, so the order of the log matches the order the changes were applied in
+/// and no applied change is lost in a crash.
 #[derive(Clone)]
 struct AppState {
     tables: Arc<Mutex<Vec<Table>>>,
+    /// The write-ahead log, or `None` if the tables are kept in memory only
+    wal: Option<Arc<StdMutex<Wal>>>,
+    storage: Arc<StorageConfig>,
+    /// Held while saving, so that an older state never overwrites a newer one
+    save_lock: Arc<Mutex<()>>,
+    auth: Arc<Auth>,
+    audit: Arc<AuditLog>,
+    metrics: Arc<Metrics>,
+    statistics: Arc<Statistics>,
+    shutdown: Shutdown,
+    /// The indexes of tables by name, built when a query first needs them after a change
+    indexes: Arc<StdMutex<HashMap<String, Vec<Arc<Index>>>>>,
 }
 
 impl AppState {
-    /// Create a new instance of AppState
-    pub fn new() -> Self {
+    /// Create a new instance of AppState logging its changes to `wal`
+    pub fn new(
+        tables: Vec<Table>,
+        storage: StorageConfig,
+        wal: Option<Wal>,
+        auth: Auth,
+        audit: AuditLog,
+        statistics: Statistics,
+    ) -> Self {
         AppState {
-            tables: Arc::new(Mutex::new(Vec::new())),
+            tables: Arc::new(Mutex::new(tables)),
+            wal: wal.map(|wal| Arc::new(StdMutex::new(wal))),
+            storage: Arc::new(storage),
+            save_lock: Arc::new(Mutex::new(())),
+            auth: Arc::new(auth),
+            audit: Arc::new(audit),
+            metrics: Arc::new(Metrics::new()),
+            statistics: Arc::new(statistics),
+            shutdown: Shutdown::new(),
+            indexes: Arc::new(StdMutex::new(HashMap::new())),
         }
     }
 
-    /// Load application state from file
-    pub async fn load() -> Result<Self, Error> {
-        let file = File::open("db.json")
-            .await
-            .map_err(|_| Error::new(io::ErrorKind::NotFound, "File not found"))?;
-        let mut reader = BufReader::new(file);
-        let mut contents = String::new();
-        reader.read_to_string(&mut contents).await?;
-        let tables: Vec<Table> = serde_json::from_str(&contents)?;
-        Ok(AppState {
-            tables: Arc::new(Mutex::new(tables)),
+    /// Load application state from the data directory, starting without tables if there are none
+    ///
+    /// The changes written to the write-ahead log after `db.json` was last saved are replayed,
+    /// and the tables are saved again if there were any.
+    pub async fn load(
+        storage: &StorageConfig,
+        wal: Option<Wal>,
+        auth: Auth,
+        audit: AuditLog,
+        statistics: Statistics,
+    ) -> Result<Self, Error> {
+        let storage = storage.clone();
+        if storage.persistence == Persistence::Memory {
+            return Ok(AppState::new(
+                Vec::new(),
+                storage,
+                wal,
+                auth,
+                audit,
+                statistics,
+            ));
+        }
+        let last_lsn = wal.as_ref().map_or(0, Wal::last_lsn);
+        let snapshot = match File::open(storage.path(DATABASE_FILE)).await {
+            Ok(file) => {
+                let mut reader = BufReader::new(file);
+                let mut contents = String::new();
+                reader.read_to_string(&mut contents).await?;
+                backup::parse_database(&contents, last_lsn)?
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Snapshot {
+                lsn: 0,
+                timestamp: Utc::now(),
+                tables: Vec::new(),
+            },
+            Err(err) => return Err(err),
+        };
+        let Some(mut wal) = wal else {
+            return Ok(AppState::new(
+                snapshot.tables,
+                storage,
+                None,
+                auth,
+                audit,
+                statistics,
+            ));
+        };
+
+        let (tables, wal, lsn, replayed) = tokio::task::spawn_blocking(move || {
+            let records = wal.records_after(snapshot.lsn).map_err(Error::other)?;
+            let mut tables = snapshot.tables;
+            let (lsn, replayed) =
+                backup::replay(&mut tables, snapshot.lsn, records, RestoreTarget::Latest)
+                    .map_err(Error::other)?;
+            wal.continue_after(lsn)?;
+            if replayed == 0 {
+                wal.saved(lsn)?;
+            }
+            Ok::<_, Error>((tables, wal, lsn, replayed))
         })
+        .await??;
+        let state = AppState::new(tables, storage, Some(wal), auth, audit, statistics);
+        if replayed > 0 {
+            info!(
+                "Replayed {} changes from the write-ahead log up to LSN {}",
+                replayed, lsn
+            );
+            state.save().await?;
+        }
+        Ok(state)
     }
 
-    /// Save application state to file
+    /// Save application state to file together with the LSN of the last change it contains
+    ///
+    /// The file is replaced in one step, so it is never left half written. Completed WAL
+    /// segments with only older changes are not needed to recover anymore and are removed
+    /// afterwards, unless they are archived.
     pub async fn save(&self) -> Result<(), Error> {
-        let tables = self.get_all().await;
-        let contents = serde_json::to_string(&tables)?;
+        if self.storage.persistence == Persistence::Memory {
+            return Ok(());
+        }
+        let _saving = self.save_lock.lock().await;
+        let snapshot = self.snapshot().await;
+        let contents = serde_json::to_string(&snapshot)?;
+        let start = Instant::now();
+        let path = self.storage.path(DATABASE_FILE);
+        let partial = path.with_extension("json.partial");
         let file = OpenOptions::new()
             .create(true)
             .write(true)
             .truncate(true)
-            .open("db.json")
+            .open(&partial)
             .await?;
         let mut writer = io::BufWriter::new(file);
         writer.write_all(contents.as_bytes()).await?;
         writer.flush().await?;
+        if self.storage.fsync == FsyncPolicy::Always {
+            writer.get_ref().sync_all().await?;
+        }
+        tokio::fs::rename(&partial, &path).await?;
+        self.metrics
+            .written(metrics::DATABASE, start.elapsed(), contents.len());
+
+        let state = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let Some(mut wal) = state.wal() else {
+                return Ok(());
+            };
+            if state.storage.fsync == FsyncPolicy::Always {
+                // The renaming of `db.json` must be durable before the segments it replaces
+                // are removed
+                std::fs::File::open(&state.storage.data_dir)?.sync_all()?;
+            }
+            wal.saved(snapshot.lsn)
+        })
+        .await?
+    }
+
+    /// Save the tables and sync every written file to disk, whatever the fsync policy, before
+    /// the server exits
+    pub async fn close(&self) -> Result<(), Error> {
+        self.save().await?;
+        let state = self.clone();
+        tokio::task::spawn_blocking(move || {
+            state.audit.sync()?;
+            if state.storage.persistence == Persistence::Memory {
+                return Ok(());
+            }
+            if let Some(mut wal) = state.wal() {
+                wal.sync()?;
+            }
+            std::fs::File::open(state.storage.path(DATABASE_FILE))?.sync_all()?;
+            // Sync the directory too, so that the renaming of `db.json` is durable
+            std::fs::File::open(&state.storage.data_dir)?.sync_all()
+        })
+        .await?
+    }
+
+    /// Check that the data directory is writable by writing and removing a probe file
+    pub async fn check_writable(&self) -> Result<(), Error> {
+        if self.storage.persistence == Persistence::Memory {
+            return Ok(());
+        }
+        let probe = self.storage.path(READY_PROBE_FILE);
+        tokio::fs::write(&probe, b"ready").await?;
+        tokio::fs::remove_file(&probe).await
+    }
+
+    /// Get the size of the files stored by the server in bytes
+    pub async fn storage_size(&self) -> Result<u64, Error> {
+        if self.storage.persistence == Persistence::Memory {
+            return Ok(self.audit.size());
+        }
+        let state = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut size = state.audit.size();
+            for name in [DATABASE_FILE, USERS_FILE, WAL_DIR, SNAPSHOT_DIR] {
+                size += disk_usage(&state.storage.path(name))?;
+            }
+            Ok(size)
+        })
+        .await?
+    }
+
+    /// Lock the write-ahead log, or return `None` if there is none
+    fn wal(&self) -> Option<MutexGuard<'_, Wal>> {
+        self.wal
+            .as_ref()
+            .map(|wal| wal.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Write a change to the write-ahead log and apply it to the locked tables, leaving them
+    /// as they are if it cannot be written
+    fn log_and_apply(
+        &self,
+        tables: &mut Vec<Table>,
+        operation: WalOperation,
+    ) -> Result<(), LogError> {
+        if let Some(mut wal) = self.wal() {
+            let start = Instant::now();
+            let (_, written) = wal.log(&operation).map_err(LogError)?;
+            self.metrics.written(metrics::WAL, start.elapsed(), written);
+        }
+        let mut indexes = self.indexes();
+        match &operation {
+            WalOperation::PutTable { name, table } => {
+                indexes.remove(name);
+                indexes.remove(&table.name);
+            }
+            WalOperation::DropTable { name } => {
+                indexes.remove(name);
+            }
+            WalOperation::InsertRow { table_name, .. }
+            | WalOperation::UpdateRows { table_name, .. }
+            | WalOperation::DeleteRows { table_name, .. } => {
+                indexes.remove(table_name);
+            }
+        }
+        if let Err(error) = operation.apply(tables) {
+            error!("Failed to apply a change: {}", error);
+        }
         Ok(())
     }
 
-    /// Add a new table to the application state
-    pub async fn create(&self, table: Table) {
-        let mut lock = self.tables.lock().await;
-        lock.push(table);
+    /// Lock the tables, recording the time spent waiting for the lock
+    async fn lock_tables(&self) -> tokio::sync::MutexGuard<'_, Vec<Table>> {
+        let start = Instant::now();
+        let lock = self.tables.lock().await;
+        self.metrics.lock_waited(start.elapsed());
+        lock
+    }
+
+    /// Build a table from a create table request and add it, checking under the same lock
+    /// that no table with its name exists
+    pub async fn create_from(
+        &self,
+        request: CreateTableRequests,
+    ) -> Result<Table, (StatusCode, String)> {
+        let mut lock = self.lock_tables().await;
+        let table =
+            build_table(&lock, &[], request).map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+        let operation = WalOperation::PutTable {
+            name: table.name.clone(),
+            table: table.clone(),
+        };
+        self.log_and_apply(&mut lock, operation)?;
+        Ok(table)
+    }
+
+    /// Replace a specific table by name with a new version of it, which may be renamed
+    pub async fn replace(&self, table_name: &str, table: Table) -> Result<(), LogError> {
+        let mut lock = self.lock_tables().await;
+        let operation = WalOperation::PutTable {
+            name: table_name.to_string(),
+            table,
+        };
+        self.log_and_apply(&mut lock, operation)
+    }
+
+    /// Append a row to a specific table by name after `check` accepted it under the same lock,
+    /// or return `None` if the table does not exist
+    pub async fn insert_checked<E: From<LogError>>(
+        &self,
+        table_name: &str,
+        check: impl FnOnce(&Table) -> Result<Row, E>,
+    ) -> Option<Result<Row, E>> {
+        let mut lock = self.lock_tables().await;
+        let table = lock.iter().find(|table| table.name == table_name)?;
+        let row = match check(table) {
+            Ok(row) => row,
+            Err(error) => return Some(Err(error)),
+        };
+        let operation = WalOperation::InsertRow {
+            table_name: table_name.to_string(),
+            row: row.clone(),
+        };
+        if let Err(error) = self.log_and_apply(&mut lock, operation) {
+            return Some(Err(error.into()));
+        }
+        Some(Ok(row))
+    }
+
+    /// Change a copy of a specific table by name with `change`, which also gets the indexes of
+    /// its rows, and replace the table with it under the same lock. The table is left as it is
+    /// if `change` fails. Returns `None` if the table does not exist.
+    pub async fn update_with<T, E: From<LogError>>(
+        &self,
+        table_name: &str,
+        change: impl FnOnce(&mut Table, &[Arc<Index>]) -> Result<T, E>,
+    ) -> Option<Result<T, E>> {
+        let mut lock = self.lock_tables().await;
+        let table = lock.iter().find(|table| table.name == table_name)?;
+        let indexes = self.indexes_of(table);
+        let mut table = table.clone();
+        let result = change(&mut table, &indexes).and_then(|value| {
+            let operation = WalOperation::PutTable {
+                name: table_name.to_string(),
+                table,
+            };
+            self.log_and_apply(&mut lock, operation)?;
+            Ok(value)
+        });
+        Some(result)
+    }
+
+    /// Change the rows of a specific table by name with `change`, which also gets the indexes
+    /// of its rows and returns the change as a row operation for the write-ahead log, under the
+    /// same lock. `change` works on a copy of the table, so the table is left as it is if it
+    /// fails, and only the row operation is logged and applied to the table. Returns `None` if
+    /// the table does not exist.
+    pub async fn change_rows<T, E: From<LogError>>(
+        &self,
+        table_name: &str,
+        change: impl FnOnce(&mut Table, &[Arc<Index>]) -> Result<(T, WalOperation), E>,
+    ) -> Option<Result<T, E>> {
+        let mut lock = self.lock_tables().await;
+        let table = lock.iter().find(|table| table.name == table_name)?;
+        let indexes = self.indexes_of(table);
+        let result = change(&mut table.clone(), &indexes).and_then(|(value, operation)| {
+            self.log_and_apply(&mut lock, operation)?;
+            Ok(value)
+        });
+        Some(result)
+    }
+
+    /// Build a new table with `build` and add it under the same lock, unless a table with its
+    /// name exists, in which case `None` is returned. Nothing is added if `build` fails.
+    pub async fn create_with<T, E: From<LogError>>(
+        &self,
+        table_name: &str,
+        build: impl FnOnce(&mut Table) -> Result<T, E>,
+    ) -> Option<Result<T, E>> {
+        let mut lock = self.lock_tables().await;
+        if lock.iter().any(|table| table.name == table_name) {
+            return None;
+        }
+        let mut table = Table::new(table_name.to_string());
+        let result = build(&mut table).and_then(|value| {
+            let operation = WalOperation::PutTable {
+                name: table_name.to_string(),
+                table,
+            };
+            self.log_and_apply(&mut lock, operation)?;
+            Ok(value)
+        });
+        Some(result)
     }
 
     /// Get all tables from the application state
     pub async fn get_all(&self) -> Vec<Table> {
-        let lock = self.tables.lock().await;
+        let lock = self.lock_tables().await;
         lock.iter().cloned().collect()
     }
 
     /// Get a specific table from the application state by name
     pub async fn get(&self, table_name: &str) -> Option<Table> {
-        let lock = self.tables.lock().await;
+        let lock = self.lock_tables().await;
         lock.iter().find(|table| table.name == table_name).cloned()
     }
 
-    /// Drop a table from the application state by name
-    pub async fn drop_table(&self, table_name: &str) -> bool {
-        let mut lock = self.tables.lock().await;
-        if let Some(index) = lock.iter().position(|table| table.name == table_name) {
-            lock.remove(index);
-            true
-        } else {
-            false
+    /// Get a specific table by name together with the indexes of its rows, building them if
+    /// the table changed since they were last built
+    pub async fn get_indexed(&self, table_name: &str) -> Option<(Table, Vec<Arc<Index>>)> {
+        let lock = self.lock_tables().await;
+        let table = lock.iter().find(|table| table.name == table_name)?;
+        Some((table.clone(), self.indexes_of(table)))
+    }
+
+    /// Get the indexes of the rows of a locked table, building them if the table changed since
+    /// they were last built
+    fn indexes_of(&self, table: &Table) -> Vec<Arc<Index>> {
+        self.indexes()
+            .entry(table.name.clone())
+            .or_insert_with(|| Index::build(table))
+            .clone()
+    }
+
+    /// Lock the indexes of the tables
+    fn indexes(&self) -> MutexGuard<'_, HashMap<String, Vec<Arc<Index>>>> {
+        self.indexes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Drop a table from the application state by name, returning whether it existed
+    pub async fn drop_table(&self, table_name: &str) -> Result<bool, LogError> {
+        let mut lock = self.lock_tables().await;
+        if !lock.iter().any(|table| table.name == table_name) {
+            return Ok(false);
+        }
+        let operation = WalOperation::DropTable {
+            name: table_name.to_string(),
+        };
+        self.log_and_apply(&mut lock, operation)?;
+        Ok(true)
+    }
+
+    /// Get the schemas of all tables, without copying their rows
+    pub async fn schemas(&self) -> Vec<TableSchema> {
+        let lock = self.lock_tables().await;
+        lock.iter().map(Table::schema).collect()
+    }
+
+    /// Get the schema of a specific table by name, without copying its rows
+    pub async fn schema(&self, table_name: &str) -> Option<TableSchema> {
+        let lock = self.lock_tables().await;
+        lock.iter()
+            .find(|table| table.name == table_name)
+            .map(Table::schema)
+    }
+
+    /// Get the schemas of all tables as the principal sees them, counting only the rows
+    /// row-level security lets them see
+    pub async fn visible_schemas(
+        &self,
+        principal: &Principal,
+        session: &Session,
+    ) -> Vec<TableSchema> {
+        let lock = self.lock_tables().await;
+        lock.iter()
+            .map(|table| visible_schema(principal, session, table))
+            .collect()
+    }
+
+    /// Get the schema of a specific table by name as the principal sees it, counting only the
+    /// rows row-level security lets them see
+    pub async fn visible_schema(
+        &self,
+        table_name: &str,
+        principal: &Principal,
+        session: &Session,
+    ) -> Option<TableSchema> {
+        let lock = self.lock_tables().await;
+        lock.iter()
+            .find(|table| table.name == table_name)
+            .map(|table| visible_schema(principal, session, table))
+    }
+
+    /// Get a page of the rows of a specific table by name, or `None` if it does not exist
+    pub async fn rows_page(
+        &self,
+        table_name: &str,
+        query: &RowsQuery,
+        security: Option<&RowSecurity>,
+    ) -> Option<Result<RowsPage, String>> {
+        let lock = self.lock_tables().await;
+        lock.iter()
+            .find(|table| table.name == table_name)
+            .map(|table| rows_page(table, query, security))
+    }
+
+    /// Get a specific table by name with its definition but without copying its rows
+    pub async fn get_without_rows(&self, table_name: &str) -> Option<Table> {
+        let lock = self.lock_tables().await;
+        lock.iter()
+            .find(|table| table.name == table_name)
+            .map(without_rows)
+    }
+
+    /// Get up to `count` rows of a specific table by name, starting at row `start`
+    pub async fn rows_range(
+        &self,
+        table_name: &str,
+        start: usize,
+        count: usize,
+    ) -> Option<Vec<Row>> {
+        let lock = self.lock_tables().await;
+        lock.iter()
+            .find(|table| table.name == table_name)
+            .map(|table| table.rows.iter().skip(start).take(count).cloned().collect())
+    }
+
+    /// Write an SQL dump of the given tables, or of all tables, while holding the lock. Rows
+    /// hidden by the row-level security `security` returns for a table are left out.
+    pub async fn dump(
+        &self,
+        table_names: Option<&[String]>,
+        batch_size: usize,
+        security: impl Fn(&Table) -> Option<RowSecurity>,
+    ) -> Result<String, String> {
+        let lock = self.lock_tables().await;
+        let tables = match table_names {
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    lock.iter()
+                        .find(|table| table.name == *name)
+                        .ok_or_else(|| format!("Table '{}' does not exist", name))
+                })
+                .collect::<Result<Vec<&Table>, String>>()?,
+            None => lock.iter().collect(),
+        };
+        let tables: Vec<Cow<Table>> = tables
+            .into_iter()
+            .map(|table| match security(table) {
+                Some(security) => Cow::Owned(Table {
+                    rows: table
+                        .rows
+                        .iter()
+                        .filter(|row| security.visible(row))
+                        .cloned()
+                        .collect(),
+                    ..without_rows(table)
+                }),
+                None => Cow::Borrowed(table),
+            })
+            .collect();
+        Ok(dump::dump(tables.iter().map(Cow::as_ref), batch_size))
+    }
+
+    /// Execute the statements of a restored script, adding the created tables only if all succeed
+    pub async fn restore(
+        &self,
+        statements: Vec<Statement>,
+    ) -> Result<RestoreResult, (StatusCode, String)> {
+        let mut lock = self.lock_tables().await;
+        let (created, result) =
+            restore_tables(&lock, statements).map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+        for table in created {
+            let operation = WalOperation::PutTable {
+                name: table.name.clone(),
+                table,
+            };
+            self.log_and_apply(&mut lock, operation)?;
+        }
+        Ok(result)
+    }
+
+    /// Copy all tables together with the LSN of the last change they contain
+    pub async fn snapshot(&self) -> Snapshot {
+        let lock = self.lock_tables().await;
+        Snapshot {
+            lsn: self.wal().map_or(0, |wal| wal.last_lsn()),
+            timestamp: Utc::now(),
+            tables: lock.clone(),
+        }
+    }
+
+    /// Get the state of the write-ahead log, or `None` if there is none
+    pub fn wal_status(&self) -> Option<WalStatus> {
+        self.wal().map(|wal| WalStatus {
+            last_lsn: wal.last_lsn(),
+            segment: wal.segment().display().to_string(),
+            archive: wal.archive().map(|archive| archive.display().to_string()),
+        })
+    }
+
+    /// Complete the current WAL segment, archiving it, and start a new one
+    pub async fn switch_wal(&self) -> Result<Option<WalStatus>, Error> {
+        let state = self.clone();
+        tokio::task::spawn_blocking(move || state.wal().map(|mut wal| wal.switch()).transpose())
+            .await??;
+        Ok(self.wal_status())
+    }
+
+    /// Build an `information_schema` or `system` view, or return `None` if `view_name` is not
+    /// one
+    ///
+    /// The `information_schema` views only describe the tables the principal has a privilege
+    /// on, and `system.audit_log` only lists the principal's own requests unless they are an
+    /// admin. Of tables with row-level security policies, only the rows the principal may see
+    /// are counted.
+    pub async fn catalog_view(
+        &self,
+        view_name: &str,
+        principal: &Principal,
+        session: &Session,
+    ) -> Result<Option<Table>, Error> {
+        match catalog::system_view_name(view_name) {
+            Some("audit_log") => {
+                let audit = Arc::clone(&self.audit);
+                let mut entries = tokio::task::spawn_blocking(move || audit.entries()).await??;
+                if !principal.admin {
+                    entries.retain(|entry| entry.principal == principal.username);
+                }
+                return Ok(Some(catalog::audit_log(&entries)));
+            }
+            Some(_) => {
+                let mut statements = self.statistics.statements();
+                if !principal.admin {
+                    statements.retain(|stats| stats.principal == principal.username);
+                }
+                return Ok(Some(catalog::statement_stats(&statements)));
+            }
+            None => {}
+        }
+        let schemas = self.visible_schemas(principal, session).await;
+        let Some(mut view) = catalog::view(view_name, &schemas) else {
+            return Ok(None);
+        };
+        let visible = self.auth.visible_tables(principal).await;
+        if let Some(index) = view.columns.iter().position(|c| c.key == "table_name") {
+            view.rows.retain(|row| match &row.values[index] {
+                Value::Str(table) => catalog::is_catalog_name(table) || visible(table),
+                _ => true,
+            });
         }
+        Ok(Some(view))
     }
 }
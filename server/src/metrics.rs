@@ -0,0 +1,202 @@
+//! Prometheus metrics of the server, served at `/metrics` in the text exposition format.
+//!
+//! - `rust_db_http_requests_total`: Requests by method, route and status code.
+//! - `rust_db_http_request_duration_seconds`: A histogram of the time until the response
+//!   starts, by method and route.
+//! - `rust_db_http_errors_total`: Error responses by type, e.g. `not_found`.
+//! - `rust_db_lock_wait_seconds`: A histogram of the time spent waiting for the tables' lock.
+//! - `rust_db_table_rows`: The number of rows of each table.
+//! - `rust_db_persistence_write_duration_seconds`: A histogram of the time it takes to write
+//!   the write-ahead log (`wal`) and `db.json` (`database`), by file.
+//! - `rust_db_persistence_written_bytes_total`: The bytes written to those files, by file.
+//! - `rust_db_uptime_seconds`: The time since the server started.
+//!
+//! Requests that match no route are counted with the route `unmatched`, so that unknown paths
+//! do not create new time series.
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use prometheus::{
+    exponential_buckets, Encoder, Gauge, Histogram, HistogramOpts, HistogramVec, IntCounterVec,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The content type of the text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The file label of write-ahead log writes.
+pub const WAL: &str = "wal";
+
+/// The file label of `db.json` writes.
+pub const DATABASE: &str = "database";
+
+/// The metrics of the server.
+pub struct Metrics {
+    registry: Registry,
+    started: Instant,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    errors: IntCounterVec,
+    lock_wait: Histogram,
+    table_rows: IntGaugeVec,
+    write_duration: HistogramVec,
+    written_bytes: IntCounterVec,
+    uptime: Gauge,
+}
+
+impl Metrics {
+    /// Registers the metrics of the server, starting its uptime.
+    pub fn new() -> Metrics {
+        let registry = Registry::new();
+        let metrics = Metrics {
+            started: Instant::now(),
+            requests: IntCounterVec::new(
+                Opts::new("rust_db_http_requests_total", "HTTP requests"),
+                &["method", "route", "status"],
+            )
+            .expect("Invalid metric"),
+            request_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "rust_db_http_request_duration_seconds",
+                    "Time until the response of an HTTP request starts",
+                ),
+                &["method", "route"],
+            )
+            .expect("Invalid metric"),
+            errors: IntCounterVec::new(
+                Opts::new("rust_db_http_errors_total", "HTTP error responses"),
+                &["type"],
+            )
+            .expect("Invalid metric"),
+            lock_wait: Histogram::with_opts(
+                HistogramOpts::new(
+                    "rust_db_lock_wait_seconds",
+                    "Time spent waiting for the lock of the tables",
+                )
+                .buckets(exponential_buckets(0.000_01, 4.0, 10).expect("Invalid buckets")),
+            )
+            .expect("Invalid metric"),
+            table_rows: IntGaugeVec::new(
+                Opts::new("rust_db_table_rows", "Rows of a table"),
+                &["table"],
+            )
+            .expect("Invalid metric"),
+            write_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "rust_db_persistence_write_duration_seconds",
+                    "Time it takes to write and sync a file",
+                ),
+                &["file"],
+            )
+            .expect("Invalid metric"),
+            written_bytes: IntCounterVec::new(
+                Opts::new(
+                    "rust_db_persistence_written_bytes_total",
+                    "Bytes written to a file",
+                ),
+                &["file"],
+            )
+            .expect("Invalid metric"),
+            uptime: Gauge::new("rust_db_uptime_seconds", "Time since the server started")
+                .expect("Invalid metric"),
+            registry,
+        };
+        let collectors: [Box<dyn prometheus::core::Collector>; 8] = [
+            Box::new(metrics.requests.clone()),
+            Box::new(metrics.request_duration.clone()),
+            Box::new(metrics.errors.clone()),
+            Box::new(metrics.lock_wait.clone()),
+            Box::new(metrics.table_rows.clone()),
+            Box::new(metrics.write_duration.clone()),
+            Box::new(metrics.written_bytes.clone()),
+            Box::new(metrics.uptime.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("Metric registered twice");
+        }
+        metrics
+    }
+
+    /// Returns the time since the server started.
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Records the time spent waiting for the lock of the tables.
+    pub fn lock_waited(&self, wait: Duration) {
+        self.lock_wait.observe(wait.as_secs_f64());
+    }
+
+    /// Records a write of a persisted file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file label, [`WAL`] or [`DATABASE`].
+    /// * `duration` - The time the write took, including syncing.
+    /// * `bytes` - The number of bytes written.
+    pub fn written(&self, file: &str, duration: Duration, bytes: usize) {
+        self.write_duration
+            .with_label_values(&[file])
+            .observe(duration.as_secs_f64());
+        self.written_bytes
+            .with_label_values(&[file])
+            .inc_by(bytes as u64);
+    }
+
+    /// Encodes all metrics in the text exposition format.
+    ///
+    /// # Arguments
+    ///
+    /// * `tables` - The name and row count of every table. Tables that are not listed any more
+    ///   are removed from `rust_db_table_rows`.
+    pub fn encode(&self, tables: &[(String, usize)]) -> String {
+        self.uptime.set(self.uptime().as_secs_f64());
+        self.table_rows.reset();
+        for (name, row_count) in tables {
+            self.table_rows
+                .with_label_values(&[name])
+                .set(*row_count as i64);
+        }
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("Failed to encode the metrics");
+        String::from_utf8(buffer).expect("Metrics are valid UTF-8")
+    }
+}
+
+/// Middleware to count every request and measure the time until its response starts
+pub async fn track(State(metrics): State<Arc<Metrics>>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", MatchedPath::as_str)
+        .to_string();
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+    let status = response.status();
+    metrics
+        .request_duration
+        .with_label_values(&[&method, &route])
+        .observe(start.elapsed().as_secs_f64());
+    metrics
+        .requests
+        .with_label_values(&[&method, &route, status.as_str()])
+        .inc();
+    if status.is_client_error() || status.is_server_error() {
+        let error_type = status
+            .canonical_reason()
+            .unwrap_or("unknown")
+            .to_lowercase()
+            .replace([' ', '-'], "_");
+        metrics.errors.with_label_values(&[&error_type]).inc();
+    }
+    response
+}
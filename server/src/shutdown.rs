@@ -0,0 +1,59 @@
+//! Graceful shutdown on `SIGINT` and `SIGTERM`.
+//!
+//! When one of the signals arrives, the listeners stop accepting connections and the requests
+//! in flight are drained, for at most the configured shutdown timeout. Requests still running
+//! then are cancelled. Finally the tables are saved and every written file is synced to disk,
+//! whatever the fsync policy, before the process exits.
+use log::{error, info};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+
+/// Notifies the listeners that the server is shutting down.
+#[derive(Clone)]
+pub struct Shutdown(watch::Sender<bool>);
+
+impl Shutdown {
+    /// Creates a notifier that has not been triggered yet.
+    pub fn new() -> Shutdown {
+        Shutdown(watch::Sender::new(false))
+    }
+
+    /// Tells every listener to stop accepting connections.
+    pub fn trigger(&self) {
+        self.0.send_replace(true);
+    }
+
+    /// Whether the server is shutting down.
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Waits until the server is shutting down.
+    pub async fn triggered(&self) {
+        let mut receiver = self.0.subscribe();
+        // The sender is alive as long as `self` is, so waiting cannot fail
+        let _ = receiver.wait_for(|triggered| *triggered).await;
+    }
+}
+
+/// Waits for `SIGINT` or `SIGTERM`.
+///
+/// If the signals cannot be listened for, the error is logged and this never returns, so the
+/// server keeps running until it is killed.
+pub async fn signal_received() {
+    let (mut interrupt, mut terminate) = match (
+        signal(SignalKind::interrupt()),
+        signal(SignalKind::terminate()),
+    ) {
+        (Ok(interrupt), Ok(terminate)) => (interrupt, terminate),
+        (Err(err), _) | (_, Err(err)) => {
+            error!("Failed to listen for SIGINT and SIGTERM: {}", err);
+            return std::future::pending().await;
+        }
+    };
+    let name = tokio::select! {
+        _ = interrupt.recv() => "SIGINT",
+        _ = terminate.recv() => "SIGTERM",
+    };
+    info!("Received {}, shutting down", name);
+}
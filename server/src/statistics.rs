@@ -0,0 +1,175 @@
+//! The slow query log and the execution statistics of statements.
+//!
+//! Every request is timed until its response starts. Handlers of statements report the SQL of
+//! the statement and the rows it scanned and returned through the [`Execution`] extension of
+//! their request. Other requests are counted under their method and route. Requests that take
+//! at least the slow query threshold are logged with their full statement, or method and URI.
+//! Successful requests are counted in memory per user and normalised statement, for at most
+//! [`MAX_STATEMENTS`] statements, after which the least executed one is dropped.
+use crate::auth::Principal;
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use core::sql::lexer::normalize;
+use core::statistics::StatementStats;
+use log::warn;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// The largest number of statements statistics are kept for.
+pub const MAX_STATEMENTS: usize = 5000;
+
+/// The statement a request executes and the rows it scanned and returned, reported by its
+/// handler.
+#[derive(Clone, Default)]
+pub struct Execution(Arc<Mutex<Details>>);
+
+#[derive(Default)]
+struct Details {
+    statement: Option<String>,
+    rows: Option<(u64, u64)>,
+}
+
+impl Execution {
+    /// Records the SQL of the statement the request executes.
+    pub fn statement(&self, sql: String) {
+        self.details().statement = Some(sql);
+    }
+
+    /// Records the number of rows the request read from tables and the number of rows it
+    /// returned or changed.
+    pub fn rows(&self, scanned: usize, returned: usize) {
+        self.details().rows = Some((scanned as u64, returned as u64));
+    }
+
+    fn details(&self) -> MutexGuard<'_, Details> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The execution statistics of statements by user and normalised statement.
+pub struct Statistics {
+    slow_query_threshold: Option<Duration>,
+    statements: Mutex<HashMap<(String, String), StatementStats>>,
+}
+
+impl Statistics {
+    /// Creates statistics without executions.
+    ///
+    /// # Arguments
+    ///
+    /// * `slow_query_threshold` - The duration from which requests are logged, or `None` to
+    ///   log none.
+    pub fn new(slow_query_threshold: Option<Duration>) -> Statistics {
+        Statistics {
+            slow_query_threshold,
+            statements: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the statistics of all statements, the ones that took the most time first.
+    pub fn statements(&self) -> Vec<StatementStats> {
+        let mut statements: Vec<StatementStats> = self.lock().values().cloned().collect();
+        statements.sort_by(|a, b| b.total_time_ms.total_cmp(&a.total_time_ms));
+        statements
+    }
+
+    /// Removes the statistics of all statements and returns how many there were.
+    pub fn reset(&self) -> usize {
+        let mut statements = self.lock();
+        let count = statements.len();
+        statements.clear();
+        count
+    }
+
+    /// Helper function to add an execution of a normalised statement
+    fn add(&self, principal: &str, statement: &str, duration: Duration, rows: (u64, u64)) {
+        let mut statements = self.lock();
+        let key = (principal.to_string(), statement.to_string());
+        if !statements.contains_key(&key) && statements.len() >= MAX_STATEMENTS {
+            let least_executed = statements
+                .iter()
+                .min_by_key(|(_, stats)| stats.calls)
+                .map(|(key, _)| key.clone());
+            if let Some(least_executed) = least_executed {
+                statements.remove(&least_executed);
+            }
+        }
+        statements
+            .entry(key)
+            .or_insert_with(|| StatementStats::new(principal, statement))
+            .record(duration, rows.0, rows.1);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<(String, String), StatementStats>> {
+        self.statements
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Middleware to count every request in the statistics of its statement and log slow ones
+///
+/// It has to run after authentication, which provides the principal, and gives every request
+/// an [`Execution`] extension for its handler to report its statement and rows.
+pub async fn record(
+    State(statistics): State<Arc<Statistics>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let execution = Execution::default();
+    request.extensions_mut().insert(execution.clone());
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| request.uri().path(), MatchedPath::as_str);
+    let route = format!("{} {}", request.method(), route);
+    let uri = format!("{} {}", request.method(), request.uri());
+    let principal = request
+        .extensions()
+        .get::<Principal>()
+        .map(|principal| principal.username.clone())
+        .unwrap_or_default();
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+    let duration = start.elapsed();
+    let details = std::mem::take(&mut *execution.details());
+    let (text, statement) = match details.statement {
+        Some(sql) => {
+            let statement = normalize(&sql);
+            (sql, statement)
+        }
+        None => (uri, route),
+    };
+    if statistics
+        .slow_query_threshold
+        .is_some_and(|threshold| duration >= threshold)
+    {
+        let rows = match details.rows {
+            Some((scanned, returned)) => {
+                format!("{} rows scanned, {} rows returned", scanned, returned)
+            }
+            None => "rows not counted".to_string(),
+        };
+        warn!(
+            "Slow request by '{}' took {:.3} ms ({}, status {}): {}",
+            principal,
+            duration.as_secs_f64() * 1000.0,
+            rows,
+            response.status().as_u16(),
+            text
+        );
+    }
+    // Like failed statements, failed requests are only logged
+    if response.status().is_success() {
+        statistics.add(
+            &principal,
+            &statement,
+            duration,
+            details.rows.unwrap_or_default(),
+        );
+    }
+    response
+}
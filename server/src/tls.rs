@@ -0,0 +1,136 @@
+//! HTTPS with optional client certificate verification.
+//!
+//! The server's certificate chain and key are read from PEM files. If a client CA is
+//! configured, clients have to present a certificate issued by it (mutual TLS). The files are
+//! read again when the server receives `SIGHUP`, and when their modification time changes if
+//! a reload interval is configured, so certificates can be renewed without a restart. A reload
+//! that fails keeps the current certificates.
+use crate::config::TlsConfig;
+use axum_server::tls_rustls::RustlsConfig;
+use log::{error, info};
+use rustls::crypto::ring;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::time::interval;
+
+/// Reads the certificates and key of a TLS configuration.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or contains no certificate or key, or if the key
+/// does not belong to the certificate.
+pub fn load(config: &TlsConfig) -> Result<RustlsConfig, String> {
+    server_config(config).map(|server_config| RustlsConfig::from_config(Arc::new(server_config)))
+}
+
+/// Reloads the certificates on `SIGHUP` and, if a reload interval is configured, when the
+/// modification time of one of the files changes.
+pub fn spawn_reloader(config: TlsConfig, rustls: RustlsConfig) {
+    tokio::spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(err) => {
+                error!("Failed to listen for SIGHUP: {}", err);
+                return;
+            }
+        };
+        let mut ticks = config.reload_interval.map(interval);
+        let mut modified = modification_times(&config);
+        loop {
+            let tick = async {
+                match ticks.as_mut() {
+                    Some(ticks) => ticks.tick().await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                _ = hangup.recv() => info!("Received SIGHUP, reloading the TLS certificates"),
+                _ = tick => {
+                    let current = modification_times(&config);
+                    if current == modified {
+                        continue;
+                    }
+                    info!("The TLS certificate files changed, reloading them");
+                }
+            }
+            modified = modification_times(&config);
+            match server_config(&config) {
+                Ok(server_config) => {
+                    rustls.reload_from_config(Arc::new(server_config));
+                    info!("Reloaded the TLS certificates");
+                }
+                Err(err) => error!("Failed to reload the TLS certificates: {}", err),
+            }
+        }
+    });
+}
+
+/// Helper function to build the rustls configuration of the server
+fn server_config(config: &TlsConfig) -> Result<ServerConfig, String> {
+    let (Some(cert_path), Some(key_path)) = (&config.cert, &config.key) else {
+        return Err("TLS needs both a certificate and a key".to_string());
+    };
+    let provider = Arc::new(ring::default_provider());
+    let certs = read_certs(cert_path)?;
+    let key = read_key(key_path)?;
+
+    let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+    let builder = match &config.client_ca {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(ca_path)? {
+                roots.add(cert).map_err(|e| {
+                    format!("Invalid CA certificate in {}: {}", ca_path.display(), e)
+                })?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|e| e.to_string())?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut server_config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Invalid certificate or key: {}", e))?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server_config)
+}
+
+/// Helper function to read the certificates of a PEM file
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid certificate in {}: {}", path.display(), e))?;
+    match certs.is_empty() {
+        true => Err(format!("No certificate found in {}", path.display())),
+        false => Ok(certs),
+    }
+}
+
+/// Helper function to read the first private key of a PEM file
+fn read_key(path: &Path) -> Result<PrivateKeyDer<'static>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| format!("Invalid private key in {}: {}", path.display(), e))?
+        .ok_or_else(|| format!("No private key found in {}", path.display()))
+}
+
+/// Helper function to get the modification times of the files of a TLS configuration
+fn modification_times(config: &TlsConfig) -> Vec<Option<SystemTime>> {
+    [&config.cert, &config.key, &config.client_ca]
+        .into_iter()
+        .flatten()
+        .map(|path| path.metadata().and_then(|m| m.modified()).ok())
+        .collect()
+}
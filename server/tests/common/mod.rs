@@ -0,0 +1,135 @@
+//! A server spawned on a free local port for the integration tests, which each use a part of it.
+#![allow(dead_code)]
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// `admin:s3cret` in Base64, the credentials of the spawned servers.
+pub const CREDENTIALS: &str = "YWRtaW46czNjcmV0";
+
+/// A server running in a temporary data directory, killed and removed when dropped.
+pub struct Server {
+    pub child: Child,
+    pub port: u16,
+    pub data_dir: PathBuf,
+    args: Vec<String>,
+}
+
+impl Server {
+    /// Spawns a server in a new data directory named after the test.
+    pub fn spawn(name: &str) -> Server {
+        Server::spawn_with(name, &[])
+    }
+
+    /// Spawns a server in a new data directory named after the test, with further arguments.
+    pub fn spawn_with(name: &str, args: &[&str]) -> Server {
+        let server = Server::spawn_unready(name, args);
+        server.wait_until_ready();
+        server
+    }
+
+    /// Spawns a server like [`spawn_with`](Server::spawn_with) without waiting until it answers
+    /// `/health`, for servers that do not answer plain HTTP.
+    pub fn spawn_unready(name: &str, args: &[&str]) -> Server {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port")
+            .port();
+        let data_dir =
+            std::env::temp_dir().join(format!("rust-db-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&data_dir);
+        fs::create_dir_all(&data_dir).expect("Failed to create the data directory");
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Server {
+            child: start(&data_dir, port, &args),
+            port,
+            data_dir,
+            args,
+        }
+    }
+
+    /// Kills the server without giving it a chance to save anything.
+    pub fn kill(&mut self) {
+        self.child.kill().expect("Failed to kill the server");
+        self.child.wait().expect("Failed to wait for the server");
+    }
+
+    /// Starts the killed server again in the same data directory and on the same port.
+    pub fn restart(&mut self) {
+        self.child = start(&self.data_dir, self.port, &self.args);
+        self.wait_until_ready();
+    }
+
+    /// Waits until the server answers `/health`.
+    pub fn wait_until_ready(&self) {
+        let start = Instant::now();
+        while self.request("GET", "/health", "").is_none() {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "Server did not start"
+            );
+            sleep(Duration::from_millis(50));
+        }
+    }
+
+    /// Sends a request and returns the response, or `None` if the server cannot be reached.
+    pub fn request(&self, method: &str, path: &str, body: &str) -> Option<String> {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).ok()?;
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nAuthorization: Basic {}\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            CREDENTIALS,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).ok()?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).ok()?;
+        Some(response)
+    }
+
+    /// Sends a request the server has to answer with `status` and returns the body of the
+    /// response.
+    pub fn expect(&self, method: &str, path: &str, body: &str, status: u16) -> String {
+        let response = self
+            .request(method, path, body)
+            .expect("Server unreachable");
+        assert!(
+            response.starts_with(&format!("HTTP/1.1 {}", status)),
+            "{}",
+            response
+        );
+        let (_, body) = response
+            .split_once("\r\n\r\n")
+            .expect("Response without a body");
+        body.to_string()
+    }
+}
+
+/// Spawns the server binary.
+fn start(data_dir: &Path, port: u16, args: &[String]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_server"))
+        .arg("--data-dir")
+        .arg(data_dir)
+        .args(["--bind", &format!("127.0.0.1:{}", port)])
+        .args(["--admin-password", "s3cret"])
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to spawn the server")
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.data_dir);
+    }
+}
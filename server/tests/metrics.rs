@@ -0,0 +1,108 @@
+//! Sends requests to a server spawned on a free local port and checks the series `/metrics`
+//! reports for them.
+mod common;
+
+use common::Server;
+
+/// Scrapes `/metrics` and returns the body of the response.
+fn scrape(server: &Server) -> String {
+    server.expect("GET", "/metrics", "", 200)
+}
+
+/// Returns the value of `series`, given with its labels, or `None` if it is not reported.
+fn sample(metrics: &str, series: &str) -> Option<f64> {
+    metrics.lines().find_map(|line| {
+        let value = line.strip_prefix(series)?.strip_prefix(' ')?;
+        Some(value.parse().expect("Invalid sample value"))
+    })
+}
+
+#[test]
+fn metrics_count_requests_and_report_tables() {
+    let server = Server::spawn("metrics");
+    server.expect(
+        "POST",
+        "/tables/orders/csv?create=true",
+        "id,item\n1,pen\n2,ink\n",
+        200,
+    );
+    server.expect("GET", "/no_such_route", "", 404);
+
+    let metrics = scrape(&server);
+    let csv = r#"method="POST",route="/tables/:name/csv""#;
+    assert_eq!(
+        sample(
+            &metrics,
+            &format!("rust_db_http_requests_total{{{},status=\"200\"}}", csv)
+        ),
+        Some(1.0),
+        "{}",
+        metrics
+    );
+    assert_eq!(
+        sample(
+            &metrics,
+            r#"rust_db_http_requests_total{method="GET",route="unmatched",status="404"}"#
+        ),
+        Some(1.0),
+        "{}",
+        metrics
+    );
+    assert_eq!(
+        sample(&metrics, r#"rust_db_http_errors_total{type="not_found"}"#),
+        Some(1.0),
+        "{}",
+        metrics
+    );
+
+    // The import falls into every bucket from the first that covers its duration on
+    let count = format!("rust_db_http_request_duration_seconds_count{{{}}}", csv);
+    let infinity = format!(
+        "rust_db_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}}",
+        csv
+    );
+    let sum = format!("rust_db_http_request_duration_seconds_sum{{{}}}", csv);
+    assert_eq!(sample(&metrics, &count), Some(1.0), "{}", metrics);
+    assert_eq!(sample(&metrics, &infinity), Some(1.0), "{}", metrics);
+    assert!(
+        sample(&metrics, &sum).is_some_and(|sum| sum > 0.0),
+        "{}",
+        metrics
+    );
+    let bucket = format!("rust_db_http_request_duration_seconds_bucket{{{},", csv);
+    let buckets = metrics
+        .lines()
+        .filter(|line| line.starts_with(&bucket))
+        .map(|line| line.rsplit(' ').next().unwrap().parse::<f64>().unwrap())
+        .collect::<Vec<f64>>();
+    assert!(buckets.len() > 1, "{}", metrics);
+    assert!(
+        buckets.windows(2).all(|pair| pair[0] <= pair[1]),
+        "{}",
+        metrics
+    );
+
+    assert_eq!(
+        sample(&metrics, r#"rust_db_table_rows{table="orders"}"#),
+        Some(2.0),
+        "{}",
+        metrics
+    );
+    assert!(
+        sample(&metrics, "rust_db_uptime_seconds").is_some_and(|uptime| uptime > 0.0),
+        "{}",
+        metrics
+    );
+
+    // The scrape itself is counted once it is answered
+    let metrics = scrape(&server);
+    assert!(
+        sample(
+            &metrics,
+            r#"rust_db_http_requests_total{method="GET",route="/metrics",status="200"}"#
+        )
+        .is_some_and(|count| count >= 1.0),
+        "{}",
+        metrics
+    );
+}
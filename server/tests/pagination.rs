@@ -0,0 +1,66 @@
+//! Pages through the rows of a table with cursors while rows are deleted between the pages.
+mod common;
+
+use common::Server;
+use serde_json::Value;
+
+/// Fetches a page of the `people` table ordered by age, and returns the ids of its rows and its
+/// cursor.
+fn page(server: &Server, cursor: Option<&str>) -> (Vec<i64>, Option<String>) {
+    let mut path = "/tables/people/rows?limit=2&order_by=age".to_string();
+    if let Some(cursor) = cursor {
+        path += &format!("&cursor={}", cursor);
+    }
+    let page: Value = serde_json::from_str(&server.expect("GET", &path, "", 200)).unwrap();
+    let ids = page["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| row["values"][0]["Int"].as_i64().unwrap())
+        .collect();
+    (ids, page["next_cursor"].as_str().map(str::to_string))
+}
+
+fn delete(server: &Server, id: i64) {
+    let body = format!(
+        r#"{{"table_name":"people","condition":{{"column":"id","value":"{}"}}}}"#,
+        id
+    );
+    server.expect("POST", "/delete", &body, 200);
+}
+
+#[test]
+fn deletes_between_pages_neither_skip_nor_repeat_rows() {
+    let server = Server::spawn("pagination");
+    let column = |key: &str, primary_key: bool| {
+        format!(
+            r#"{{"table_name":"people","key":"{}","primary_key":{},"non_null":false,"unique":false,"foreign_key":null,"data_type":"Int"}}"#,
+            key, primary_key
+        )
+    };
+    let create = format!(
+        r#"{{"name":"people","insert_column_requests":[{},{}]}}"#,
+        column("id", true),
+        column("age", false)
+    );
+    server.expect("POST", "/create_table", &create, 200);
+    // Every row has the same age, and the ids are not in insertion order
+    server.expect(
+        "POST",
+        "/tables/people/csv",
+        "id,age\n5,30\n3,30\n1,30\n4,30\n2,30\n6,40\n",
+        200,
+    );
+
+    let (ids, cursor) = page(&server, None);
+    assert_eq!(ids, [1, 2]);
+    // Deleting rows inserted before the rows of the next page moves them to other positions
+    delete(&server, 1);
+    delete(&server, 5);
+    let (ids, cursor) = page(&server, cursor.as_deref());
+    assert_eq!(ids, [3, 4]);
+    delete(&server, 3);
+    let (ids, cursor) = page(&server, cursor.as_deref());
+    assert_eq!(ids, [6]);
+    assert_eq!(cursor, None);
+}
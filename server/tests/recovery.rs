@@ -0,0 +1,84 @@
+//! Kills the server and restarts it, recovering the changes `db.json` misses from the
+//! write-ahead log.
+mod common;
+
+use common::Server;
+use serde_json::Value;
+use std::fs;
+
+/// Returns the ids and ages of the rows of the `people` table, ordered by id.
+fn people(server: &Server) -> Vec<(i64, i64)> {
+    let page = server.expect("GET", "/tables/people/rows?order_by=id", "", 200);
+    let page: Value = serde_json::from_str(&page).unwrap();
+    page["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| {
+            let value = |index: usize| row["values"][index]["Int"].as_i64().unwrap();
+            (value(0), value(1))
+        })
+        .collect()
+}
+
+#[test]
+fn changes_missing_from_the_database_file_are_replayed_after_a_crash() {
+    let mut server = Server::spawn("recovery");
+    let column = |key: &str, primary_key: bool| {
+        format!(
+            r#"{{"table_name":"people","key":"{}","primary_key":{},"non_null":false,"unique":false,"foreign_key":null,"data_type":"Int"}}"#,
+            key, primary_key
+        )
+    };
+    let create = format!(
+        r#"{{"name":"people","insert_column_requests":[{},{}]}}"#,
+        column("id", true),
+        column("age", false)
+    );
+    server.expect("POST", "/create_table", &create, 200);
+    server.expect(
+        "POST",
+        "/tables/people/csv",
+        "id,age\n1,30\n2,40\n3,50\n",
+        200,
+    );
+    let database = server.data_dir.join("db.json");
+    let saved = fs::read(&database).unwrap();
+
+    server.expect(
+        "POST",
+        "/update_table",
+        r#"{"table_name":"people","condition":{"column":"id","value":"2"},"updates":[{"column":"age","value":"41"}]}"#,
+        200,
+    );
+    server.expect(
+        "POST",
+        "/delete",
+        r#"{"table_name":"people","condition":{"column":"id","value":"1"}}"#,
+        200,
+    );
+    server.expect(
+        "POST",
+        "/insert_row",
+        r#"{"table_name":"people","row":{"values":[{"Int":4},{"Int":60}]}}"#,
+        200,
+    );
+    assert_eq!(people(&server), [(2, 41), (3, 50), (4, 60)]);
+
+    // A crash before `db.json` is rewritten leaves it without the latest changes
+    server.kill();
+    fs::write(&database, saved).unwrap();
+    server.restart();
+    assert_eq!(people(&server), [(2, 41), (3, 50), (4, 60)]);
+
+    // The recovered changes are saved, and new changes continue the log
+    server.expect(
+        "POST",
+        "/delete",
+        r#"{"table_name":"people","condition":{"column":"id","value":"3"}}"#,
+        200,
+    );
+    server.kill();
+    server.restart();
+    assert_eq!(people(&server), [(2, 41), (4, 60)]);
+}
@@ -0,0 +1,62 @@
+//! Sends `SIGINT` and `SIGTERM` to a server spawned on a free local port and checks that it
+//! saves its tables and exits cleanly.
+mod common;
+
+use common::Server;
+use std::fs;
+use std::process::{Command, ExitStatus};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+impl Server {
+    fn signal(&self, signal: &str) {
+        let status = Command::new("kill")
+            .args([&format!("-{}", signal), &self.child.id().to_string()])
+            .status()
+            .expect("Failed to run kill");
+        assert!(status.success());
+    }
+
+    fn wait(&mut self) -> ExitStatus {
+        let start = Instant::now();
+        loop {
+            if let Some(status) = self
+                .child
+                .try_wait()
+                .expect("Failed to wait for the server")
+            {
+                return status;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "Server did not exit"
+            );
+            sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+/// Creates a table, sends `signal` and checks that the server saved the table and exited.
+fn shut_down_with(signal: &str) {
+    let mut server = Server::spawn(&signal.to_lowercase());
+    let response = server
+        .request("POST", "/create", r#"{"name":"orders"}"#)
+        .expect("Server unreachable");
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+
+    server.signal(signal);
+    assert!(server.wait().success());
+    assert!(server.request("GET", "/health", "").is_none());
+    let saved = fs::read_to_string(server.data_dir.join("db.json")).expect("db.json missing");
+    assert!(saved.contains("\"orders\""), "{}", saved);
+}
+
+#[test]
+fn sigterm_saves_tables_and_exits() {
+    shut_down_with("TERM");
+}
+
+#[test]
+fn sigint_saves_tables_and_exits() {
+    shut_down_with("INT");
+}
@@ -0,0 +1,208 @@
+//! Serves HTTPS with certificates generated at test time, requiring client certificates
+//! issued by a test CA, and reloads a replaced certificate on `SIGHUP`.
+mod common;
+
+use common::{Server, CREDENTIALS};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+};
+use rustls::crypto::ring;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// A CA with the certificates it issued, whose PEM files are removed when dropped.
+struct Certificates {
+    dir: PathBuf,
+    ca: Certificate,
+    ca_key: KeyPair,
+    client: Certificate,
+    client_key: KeyPair,
+}
+
+impl Certificates {
+    /// Generates a CA, a certificate for the server at `localhost` and a client certificate,
+    /// and writes the server's certificate and key and the CA certificate to files.
+    fn generate(name: &str) -> Certificates {
+        let dir =
+            std::env::temp_dir().join(format!("rust-db-{}-certs-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).expect("Failed to create the certificate directory");
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().unwrap();
+        let ca = params.self_signed(&ca_key).unwrap();
+        fs::write(dir.join("ca.crt"), ca.pem()).unwrap();
+
+        let mut params = CertificateParams::new(vec!["client".to_string()]).unwrap();
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        let client_key = KeyPair::generate().unwrap();
+        let client = params.signed_by(&client_key, &ca, &ca_key).unwrap();
+
+        let certificates = Certificates {
+            dir,
+            ca,
+            ca_key,
+            client,
+            client_key,
+        };
+        certificates.issue_server_certificate();
+        certificates
+    }
+
+    /// Issues a new certificate for the server, replacing the files of the current one.
+    ///
+    /// # Returns
+    ///
+    /// Returns the new certificate.
+    fn issue_server_certificate(&self) -> CertificateDer<'static> {
+        let mut params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        let key = KeyPair::generate().unwrap();
+        let cert = params.signed_by(&key, &self.ca, &self.ca_key).unwrap();
+        fs::write(self.dir.join("server.key"), key.serialize_pem()).unwrap();
+        fs::write(self.dir.join("server.crt"), cert.pem()).unwrap();
+        cert.der().clone()
+    }
+
+    /// Returns the arguments that make a server use the certificates and require client
+    /// certificates issued by the CA.
+    fn args(&self) -> Vec<String> {
+        [
+            ("--tls-cert", "server.crt"),
+            ("--tls-key", "server.key"),
+            ("--tls-client-ca", "ca.crt"),
+        ]
+        .into_iter()
+        .flat_map(|(flag, file)| [flag.to_string(), self.dir.join(file).display().to_string()])
+        .collect()
+    }
+
+    /// Builds a client trusting the CA, which presents the client certificate if
+    /// `with_certificate` is set.
+    fn client(&self, with_certificate: bool) -> Arc<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.ca.der().clone()).unwrap();
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+        let config = match with_certificate {
+            true => {
+                let key = PrivatePkcs8KeyDer::from(self.client_key.serialize_der());
+                builder
+                    .with_client_auth_cert(
+                        vec![self.client.der().clone()],
+                        PrivateKeyDer::from(key),
+                    )
+                    .unwrap()
+            }
+            false => builder.with_no_client_auth(),
+        };
+        Arc::new(config)
+    }
+}
+
+impl Drop for Certificates {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Requests `/health` over HTTPS.
+///
+/// # Returns
+///
+/// Returns the certificate the server presented and the response.
+///
+/// # Errors
+///
+/// Returns an error if the server cannot be reached, or the handshake or request fails.
+fn health(
+    server: &Server,
+    client: Arc<ClientConfig>,
+) -> Result<(CertificateDer<'static>, String), String> {
+    let tcp = TcpStream::connect(("127.0.0.1", server.port)).map_err(|e| e.to_string())?;
+    let name = ServerName::try_from("localhost").unwrap();
+    let connection = ClientConnection::new(client, name).map_err(|e| e.to_string())?;
+    let mut stream = StreamOwned::new(connection, tcp);
+    let request = format!(
+        "GET /health HTTP/1.1\r\nHost: localhost\r\nAuthorization: Basic {}\r\n\
+         Connection: close\r\n\r\n",
+        CREDENTIALS
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|e| e.to_string())?;
+    let certificate = stream.conn.peer_certificates().unwrap()[0].clone();
+    Ok((certificate, response))
+}
+
+/// Spawns a server with the certificates and waits until it answers over HTTPS.
+fn spawn(name: &str, certificates: &Certificates) -> Server {
+    let args = certificates.args();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let server = Server::spawn_unready(name, &args);
+    let start = Instant::now();
+    while health(&server, certificates.client(true)).is_err() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "Server did not start"
+        );
+        sleep(Duration::from_millis(50));
+    }
+    server
+}
+
+#[test]
+fn clients_with_a_certificate_of_the_trusted_ca_are_served() {
+    let certificates = Certificates::generate("tls-handshake");
+    let server = spawn("tls-handshake", &certificates);
+    let (_, response) = health(&server, certificates.client(true)).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+}
+
+#[test]
+fn clients_without_a_certificate_are_rejected() {
+    let certificates = Certificates::generate("tls-no-client-cert");
+    let server = spawn("tls-no-client-cert", &certificates);
+    let error = health(&server, certificates.client(false)).unwrap_err();
+    assert!(error.contains("CertificateRequired"), "{}", error);
+}
+
+#[test]
+fn a_replaced_certificate_is_served_after_sighup() {
+    let certificates = Certificates::generate("tls-reload");
+    let server = spawn("tls-reload", &certificates);
+    let (served, _) = health(&server, certificates.client(true)).unwrap();
+    let replaced = certificates.issue_server_certificate();
+    assert_ne!(served, replaced);
+
+    let status = Command::new("kill")
+        .args(["-HUP", &server.child.id().to_string()])
+        .status()
+        .expect("Failed to run kill");
+    assert!(status.success());
+    let start = Instant::now();
+    loop {
+        let (served, _) = health(&server, certificates.client(true)).unwrap();
+        if served == replaced {
+            break;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "The replaced certificate was not served"
+        );
+        sleep(Duration::from_millis(50));
+    }
+}
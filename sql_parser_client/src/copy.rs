@@ -0,0 +1,278 @@
+use core::csv::CsvOptions;
+use core::request_types::CsvImportOptions;
+
+/// What a `\copy` command reads from or writes to the server.
+#[derive(Debug, PartialEq)]
+pub enum CopySource {
+    /// A table, optionally restricted to some of its columns.
+    Table { name: String, columns: Vec<String> },
+    /// The text of a `SELECT` statement, only valid for `TO`.
+    Query(String),
+}
+
+/// The direction and local end of a `\copy` command.
+#[derive(Debug, PartialEq)]
+pub enum CopyDirection {
+    /// Imports the rows of a file.
+    From(String),
+    /// Exports the rows to a file, or to the output of the client without one.
+    To(Option<String>),
+}
+
+/// A parsed `\copy` command.
+#[derive(Debug)]
+pub struct CopyCommand {
+    pub source: CopySource,
+    pub direction: CopyDirection,
+    pub options: CsvOptions,
+    pub import: CsvImportOptions,
+}
+
+/// A word of a `\copy` command.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    /// A single-quoted string, with doubled quotes unescaped.
+    Literal(String),
+    /// The text between matching parentheses, kept as written.
+    Group(String),
+    Comma,
+}
+
+/// Parses the arguments of a `\copy` command.
+///
+/// ```text
+/// \copy table [(column, ...)] FROM 'file' [WITH] [(option [value], ...)]
+/// \copy table [(column, ...)] TO 'file' | STDOUT [WITH] [(option [value], ...)]
+/// \copy (SELECT ...) TO 'file' | STDOUT [WITH] [(option [value], ...)]
+/// ```
+///
+/// The options are `FORMAT csv`, `HEADER [boolean]`, `DELIMITER 'c'`, `QUOTE 'c'`,
+/// `NULL 'text'` and, for imports, `TYPES 'column:TYPE, ...'`, `INFER [boolean]` and
+/// `CREATE [boolean]`. Without a value, a boolean option is switched on.
+///
+/// # Parameters
+///
+/// - `arguments`: The text after `\copy`.
+///
+/// # Returns
+///
+/// Returns the parsed command, or a description of the first syntax error.
+pub fn parse_copy(arguments: &str) -> Result<CopyCommand, String> {
+    let mut tokens = tokenize(arguments.trim().trim_end_matches(';'))?
+        .into_iter()
+        .peekable();
+
+    let source = match tokens.next() {
+        Some(Token::Word(name)) => {
+            let columns = match tokens.next_if(|token| matches!(token, Token::Group(_))) {
+                Some(Token::Group(columns)) => columns
+                    .split(',')
+                    .map(|column| column.trim().to_string())
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if columns.iter().any(String::is_empty) {
+                return Err("Empty column name in the column list".into());
+            }
+            CopySource::Table { name, columns }
+        }
+        Some(Token::Group(query)) => CopySource::Query(query),
+        _ => return Err("Expected a table name or a (SELECT ...) query after '\\copy'".into()),
+    };
+
+    let direction = match (tokens.next(), tokens.next()) {
+        (Some(Token::Word(word)), Some(Token::Literal(path)))
+            if word.eq_ignore_ascii_case("FROM") =>
+        {
+            CopyDirection::From(path)
+        }
+        (Some(Token::Word(word)), Some(Token::Word(target)))
+            if word.eq_ignore_ascii_case("FROM") && target.eq_ignore_ascii_case("STDIN") =>
+        {
+            return Err("FROM STDIN is not supported, copy from a file instead".into())
+        }
+        (Some(Token::Word(word)), Some(Token::Literal(path)))
+            if word.eq_ignore_ascii_case("TO") =>
+        {
+            CopyDirection::To(Some(path))
+        }
+        (Some(Token::Word(word)), Some(Token::Word(target)))
+            if word.eq_ignore_ascii_case("TO") && target.eq_ignore_ascii_case("STDOUT") =>
+        {
+            CopyDirection::To(None)
+        }
+        _ => return Err("Expected FROM 'file', TO 'file' or TO STDOUT".into()),
+    };
+    if matches!(source, CopySource::Query(_)) && matches!(direction, CopyDirection::From(_)) {
+        return Err("A query can only be copied TO a file".into());
+    }
+
+    tokens.next_if(|token| matches!(token, Token::Word(word) if word.eq_ignore_ascii_case("WITH")));
+    // The options may be enclosed in parentheses, as in `WITH (HEADER, DELIMITER ';')`
+    let options_text = match tokens.next_if(|token| matches!(token, Token::Group(_))) {
+        Some(Token::Group(text)) => Some(text),
+        _ => None,
+    };
+    let option_tokens = match options_text {
+        Some(text) => {
+            if let Some(token) = tokens.next() {
+                return Err(format!("Unexpected {:?} after the options", token));
+            }
+            tokenize(&text)?
+        }
+        None => tokens.collect(),
+    };
+
+    let mut command = CopyCommand {
+        source,
+        direction,
+        options: CsvOptions::default(),
+        import: CsvImportOptions::default(),
+    };
+    apply_options(&mut command, option_tokens)?;
+    Ok(command)
+}
+
+/// Returns the value of a boolean option word, e.g. `on`.
+fn boolean(word: &str) -> Option<bool> {
+    match word.to_lowercase().as_str() {
+        "true" | "on" | "1" => Some(true),
+        "false" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Applies the options of a `\copy` command.
+///
+/// Options may be separated by commas or only by spaces, as in `WITH CSV HEADER`.
+fn apply_options(command: &mut CopyCommand, tokens: Vec<Token>) -> Result<(), String> {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let name = match token {
+            Token::Word(name) => name.to_lowercase(),
+            Token::Comma => continue,
+            token => return Err(format!("Expected an option name, found {:?}", token)),
+        };
+
+        // The value of a boolean option is optional
+        if matches!(name.as_str(), "header" | "infer" | "create") {
+            let value = match tokens.peek() {
+                Some(Token::Word(word) | Token::Literal(word)) => boolean(word),
+                _ => None,
+            };
+            if value.is_some() {
+                tokens.next();
+            }
+            let value = value.unwrap_or(true);
+            match name.as_str() {
+                "header" => command.options.header = value,
+                "infer" => command.import.infer = value,
+                _ => command.import.create = value,
+            }
+            continue;
+        }
+        if name == "csv" {
+            continue;
+        }
+
+        let text = match tokens.next() {
+            Some(Token::Literal(text) | Token::Word(text)) => text,
+            _ if ["format", "delimiter", "quote", "null", "types"].contains(&name.as_str()) => {
+                return Err(format!("Option {} needs a value", name.to_uppercase()))
+            }
+            _ => return Err(format!("Unknown option '{}'", name)),
+        };
+        let character = || {
+            // `'\t'` is accepted for a tab, which is hard to type in a terminal
+            if text == "\\t" {
+                return Ok('\t');
+            }
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(format!(
+                    "Option {} must be a single character",
+                    name.to_uppercase()
+                )),
+            }
+        };
+        match name.as_str() {
+            "format" if text.eq_ignore_ascii_case("csv") => {}
+            "format" => {
+                return Err(format!(
+                    "Unsupported format '{}', only csv is supported",
+                    text
+                ))
+            }
+            "delimiter" => command.options.delimiter = character()?,
+            "quote" => command.options.quote = character()?,
+            "null" => command.options.null = text,
+            "types" => command.import.types = Some(text),
+            _ => return Err(format!("Unknown option '{}'", name)),
+        }
+    }
+    Ok(())
+}
+
+/// Splits the arguments of a `\copy` command into tokens.
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            ',' => tokens.push(Token::Comma),
+            '\'' => {
+                let mut literal = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            literal.push('\'');
+                            chars.next();
+                        }
+                        Some('\'') => break,
+                        Some(inner) => literal.push(inner),
+                        None => return Err("Unterminated quoted string".into()),
+                    }
+                }
+                tokens.push(Token::Literal(literal));
+            }
+            '(' => {
+                let mut group = String::new();
+                let mut depth = 1;
+                let mut quote = None;
+                loop {
+                    let Some(inner) = chars.next() else {
+                        return Err("Missing closing parenthesis".into());
+                    };
+                    match (quote, inner) {
+                        (Some(q), inner) if inner == q => quote = None,
+                        (Some(_), _) => {}
+                        (None, '\'' | '"' | '`') => quote = Some(inner),
+                        (None, '(') => depth += 1,
+                        (None, ')') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        (None, _) => {}
+                    }
+                    group.push(inner);
+                }
+                tokens.push(Token::Group(group.trim().to_string()));
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(next) = chars.next_if(|next| {
+                    !next.is_whitespace() && !matches!(next, ',' | '(' | ')' | '\'')
+                }) {
+                    word.push(next);
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,262 @@
+use core::catalog;
+use core::sql::lexer::is_complete;
+use core::sql::parser::RESERVED_KEYWORDS;
+use core::table::TableSchema;
+use rustyline::completion::{Completer, Pair};
+use rustyline::config::Configurer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{ColorMode, Context, Editor, Helper};
+use std::borrow::Cow;
+
+/// Words that are completed in addition to the reserved keywords.
+const EXTRA_KEYWORDS: &[&str] = &[
+    "ADMIN",
+    "ALL",
+    "ALTER",
+    "ANALYZE",
+    "AUTO_INCREMENT",
+    "BIGINT",
+    "BOOL",
+    "BOOLEAN",
+    "BY",
+    "CURRENT_USER",
+    "DELETE",
+    "DESC",
+    "DESCRIBE",
+    "EXIT",
+    "EXPLAIN",
+    "FLOAT",
+    "FOR",
+    "GRANT",
+    "GRANTS",
+    "IDENTIFIED",
+    "INDEX",
+    "INT",
+    "INTEGER",
+    "JOIN",
+    "KEY",
+    "POLICY",
+    "ON",
+    "PRIVILEGES",
+    "RESET",
+    "REVOKE",
+    "ROLE",
+    "ROLES",
+    "SHOW",
+    "STRING",
+    "TABLES",
+    "TEXT",
+    "USER",
+    "USERS",
+    "USING",
+    "VARCHAR",
+    "WITH",
+];
+
+/// Keywords after which only table names are suggested.
+const TABLE_KEYWORDS: &[&str] = &[
+    "DESC",
+    "DESCRIBE",
+    "FROM",
+    "INTO",
+    "JOIN",
+    "ON",
+    "REFERENCES",
+    "TABLE",
+    "TO",
+    "UPDATE",
+];
+
+/// Line editor helper providing tab completion and multi-line input.
+///
+/// Input is only submitted once it ends with a `;` or `\G`, except for `exit` and
+/// backslash commands.
+/// Table and column names are taken from `tables`, which the REPL refreshes
+/// after every successful command.
+#[derive(Default)]
+pub struct SqlHelper {
+    pub tables: Vec<TableSchema>,
+}
+
+impl SqlHelper {
+    /// Returns the completions for `prefix`.
+    ///
+    /// # Parameters
+    ///
+    /// - `prefix`: The partial word under the cursor.
+    /// - `previous`: The word before it, if any.
+    /// - `qualifier`: The table or schema name before a `.`, for input such as `users.na`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the sorted candidates. Keywords are completed in lowercase if the prefix is lowercase.
+    fn candidates(
+        &self,
+        prefix: &str,
+        previous: Option<&str>,
+        qualifier: Option<&str>,
+    ) -> Vec<String> {
+        let matches = |word: &str| {
+            word.len() >= prefix.len()
+                && word.is_char_boundary(prefix.len())
+                && word[..prefix.len()].eq_ignore_ascii_case(prefix)
+        };
+
+        let tables = self.tables.iter().map(|table| table.name.clone());
+        let columns_of = |table: &TableSchema| {
+            table
+                .columns
+                .iter()
+                .map(|column| column.key.clone())
+                .collect::<Vec<String>>()
+        };
+
+        let mut words: Vec<String> = if qualifier
+            .is_some_and(|qualifier| qualifier.eq_ignore_ascii_case(catalog::SCHEMA_NAME))
+        {
+            catalog::VIEW_NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        } else if let Some(qualifier) = qualifier {
+            self.tables
+                .iter()
+                .filter(|table| table.name == qualifier)
+                .flat_map(columns_of)
+                .collect()
+        } else if previous.is_some_and(|word| {
+            TABLE_KEYWORDS
+                .iter()
+                .any(|keyword| keyword.eq_ignore_ascii_case(word))
+        }) {
+            tables.chain([catalog::SCHEMA_NAME.to_string()]).collect()
+        } else {
+            let lowercase = !prefix.is_empty() && prefix.chars().all(|c| !c.is_uppercase());
+            RESERVED_KEYWORDS
+                .iter()
+                .chain(EXTRA_KEYWORDS)
+                .map(|keyword| match lowercase {
+                    true => keyword.to_lowercase(),
+                    false => keyword.to_string(),
+                })
+                .chain(tables)
+                .chain([catalog::SCHEMA_NAME.to_string()])
+                .chain(self.tables.iter().flat_map(columns_of))
+                .collect()
+        };
+
+        words.retain(|word| matches(word));
+        words.sort();
+        words.dedup();
+        words
+    }
+}
+
+impl Completer for SqlHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let before = &line[..pos];
+        let start = before
+            .char_indices()
+            .rev()
+            .take_while(|&(_, c)| is_word_char(c))
+            .last()
+            .map_or(pos, |(index, _)| index);
+        let prefix = &before[start..];
+
+        let rest = &before[..start];
+        let qualifier = rest.strip_suffix('.').map(|rest| {
+            let table_start = rest.rfind(|c: char| !is_word_char(c)).map_or(0, |i| i + 1);
+            &rest[table_start..]
+        });
+        let previous = rest
+            .split(|c: char| !is_word_char(c))
+            .rfind(|word| !word.is_empty());
+
+        let candidates = self
+            .candidates(prefix, previous, qualifier)
+            .into_iter()
+            .map(|word| Pair {
+                display: word.clone(),
+                replacement: word,
+            })
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Validator for SqlHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let input = ctx.input().trim();
+        if input.is_empty()
+            || input.eq_ignore_ascii_case("EXIT")
+            || input.starts_with('\\')
+            || input.ends_with("\\G")
+            || is_complete(input)
+        {
+            Ok(ValidationResult::Valid(None))
+        } else {
+            Ok(ValidationResult::Incomplete)
+        }
+    }
+}
+
+impl Hinter for SqlHelper {
+    type Hint = String;
+}
+
+impl Highlighter for SqlHelper {}
+
+impl Helper for SqlHelper {}
+
+/// Line editor helper hiding the input, for reading passwords.
+struct PasswordHelper;
+
+impl Completer for PasswordHelper {
+    type Candidate = Pair;
+}
+
+impl Validator for PasswordHelper {}
+
+impl Hinter for PasswordHelper {
+    type Hint = String;
+}
+
+impl Highlighter for PasswordHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        Cow::Owned(" ".repeat(line.chars().count()))
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        true
+    }
+}
+
+impl Helper for PasswordHelper {}
+
+/// Reads a password from the terminal without showing it or adding it to the history.
+///
+/// # Parameters
+///
+/// - `prompt`: The prompt, e.g. `Password for admin: `.
+///
+/// # Returns
+///
+/// Returns the password, or an error if the terminal cannot be read or input is interrupted.
+pub fn read_password(prompt: &str) -> rustyline::Result<String> {
+    let mut editor: Editor<PasswordHelper, DefaultHistory> = Editor::new()?;
+    editor.set_helper(Some(PasswordHelper));
+    editor.set_color_mode(ColorMode::Forced);
+    editor.set_auto_add_history(false);
+    editor.readline(prompt)
+}
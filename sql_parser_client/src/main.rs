@@ -1,51 +1,459 @@
+use clap::Parser;
+use copy::{CopyDirection, CopySource};
+use core::catalog;
 use core::client_functions::*;
 use core::request_types::*;
 use core::row::Row;
+use core::sql::ast::{
+    BinaryOp, CreateIndex, CreateTable, CreateUser, Delete, Expr, Insert, Join, Select, SelectItem,
+    Statement, Update,
+};
+use core::sql::parse_statement;
+use core::table::TableSchema;
 use core::value::Value;
 use log::{debug, error, info, LevelFilter};
 use reqwest::Client;
-use std::io::{self, Write};
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::Editor;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+mod copy;
+mod helper;
+mod output;
+mod schema;
+mod script;
+
+use helper::SqlHelper;
+use output::{footer, render, OutputFormat};
+use script::split_statements;
+
+/// Interactive SQL client for the Rust Database.
+///
+/// Without `--execute` or `--file`, statements are read from stdin if it is not a terminal,
+/// and from an interactive prompt otherwise.
+#[derive(Parser)]
+#[command(name = "sql_parser_client")]
+struct Args {
+    /// Executes the given statements and exits. Can be repeated.
+    #[arg(short, long, value_name = "SQL")]
+    execute: Vec<String>,
+
+    /// Executes the statements in a file and exits. Use `-` to read from stdin.
+    #[arg(short, long, value_name = "PATH")]
+    file: Option<PathBuf>,
+
+    /// Keeps executing the remaining statements after a statement fails.
+    #[arg(long)]
+    continue_on_error: bool,
+
+    /// Host name of the server.
+    #[arg(short = 'H', long, env = "RUST_DB_HOST", default_value = "localhost")]
+    host: String,
+
+    /// Port of the server.
+    #[arg(short, long, env = "RUST_DB_PORT", default_value_t = 3000)]
+    port: u16,
+
+    /// Connects with HTTPS. Implied by `--ca-cert` and `--cert`.
+    #[arg(long, env = "RUST_DB_TLS")]
+    tls: bool,
+
+    /// PEM file of CA certificates to trust, e.g. the self-signed certificate of the server.
+    #[arg(long, env = "RUST_DB_CA_CERT", value_name = "PATH")]
+    ca_cert: Option<PathBuf>,
+
+    /// PEM file of the client certificate, for servers that verify clients.
+    #[arg(long, env = "RUST_DB_CERT", value_name = "PATH", requires = "key")]
+    cert: Option<PathBuf>,
+
+    /// PEM file of the client certificate's private key, in PKCS #8 format.
+    #[arg(long, env = "RUST_DB_KEY", value_name = "PATH", requires = "cert")]
+    key: Option<PathBuf>,
+
+    /// Initial output format: table, vertical, csv, tsv, json or markdown.
+    #[arg(long, env = "RUST_DB_FORMAT", default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+
+    /// User to log in as.
+    #[arg(short, long, env = "RUST_DB_USER")]
+    user: Option<String>,
+
+    /// Password of the user. Prompted for if it is not given and the terminal is interactive.
+    #[arg(
+        long,
+        env = "RUST_DB_PASSWORD",
+        hide_env_values = true,
+        requires = "user"
+    )]
+    password: Option<String>,
+}
+
+/// Client-side settings changed by backslash commands.
+struct Session {
+    /// The format query results are printed in.
+    format: OutputFormat,
+    /// Whether the footer after a result shows the elapsed time, toggled with `\timing`.
+    timing: bool,
+    /// The file results are written to instead of stdout, set with `\o`.
+    output: Option<File>,
+    /// The user logged in with `--user`, if any.
+    user: Option<String>,
+    /// Whether `EXIT` or `\q` asked to stop executing statements.
+    quit: bool,
+}
+
+impl Session {
+    /// Prints a result in `format` to the session's output.
+    ///
+    /// Formats meant to be read by people are followed by the row count and, if timing is
+    /// enabled, the elapsed time.
+    ///
+    /// # Parameters
+    ///
+    /// - `format`: The output format.
+    /// - `columns`: The column headers.
+    /// - `rows`: The rows.
+    /// - `elapsed`: The time it took to produce the result.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if writing to the output file fails.
+    fn print_result(
+        &mut self,
+        format: OutputFormat,
+        columns: &[String],
+        rows: &[Row],
+        elapsed: Duration,
+    ) -> Result<(), String> {
+        let mut text = render(format, columns, rows);
+        if format.is_human_readable() {
+            text += &footer(rows.len(), self.timing.then_some(elapsed));
+            text += "\n";
+        }
+        self.print(&text)
+    }
+
+    /// Writes `text` to the session's output.
+    fn print(&mut self, text: &str) -> Result<(), String> {
+        match &mut self.output {
+            Some(file) => file.write_all(text.as_bytes()).map_err(|e| e.to_string()),
+            None => {
+                print!("{}", text);
+                Ok(())
+            }
+        }
+    }
+}
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    let interactive = args.execute.is_empty() && args.file.is_none() && io::stdin().is_terminal();
+
+    // Scripts only log warnings and errors, so that their output is just the query results
     env_logger::builder()
-        .filter_level(LevelFilter::Info)
+        .filter_level(match interactive {
+            true => LevelFilter::Info,
+            false => LevelFilter::Warn,
+        })
         .format_timestamp_millis()
+        .parse_default_env()
         .init();
 
-    let client = Client::new();
+    let tls = TlsOptions {
+        ca_cert: args.ca_cert.clone(),
+        client_cert: args.cert.clone(),
+        client_key: args.key.clone(),
+    };
+    let https = args.tls || tls.ca_cert.is_some() || tls.client_cert.is_some();
+    let scheme = if https { "https" } else { "http" };
+    set_server_url(&format!("{}://{}:{}", scheme, args.host, args.port));
+    let client = match build_client(&tls) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Failed to set up the connection: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
 
-    if let Err(e) = client.post("http://localhost:3000").send().await {
+    if let Err(e) = ping(&client).await {
         error!("Error, is the server on? :{}", e);
-        return;
+        return ExitCode::FAILURE;
+    }
+    if let Some(user) = &args.user {
+        if let Err(e) = login_command(&client, user, args.password.clone()).await {
+            error!("Failed to log in as '{}': {}", user, e);
+            return ExitCode::FAILURE;
+        }
     }
 
+    let mut session = Session {
+        format: args.format,
+        timing: true,
+        output: None,
+        user: args.user.clone(),
+        quit: false,
+    };
+
+    let code = if interactive {
+        run_interactive(&client, &mut session).await;
+        ExitCode::SUCCESS
+    } else {
+        run_batch(&client, &mut session, &args).await
+    };
+    if args.user.is_some() {
+        if let Err(e) = logout(&client).await {
+            debug!("Failed to log out: {}", e);
+        }
+    }
+    code
+}
+
+/// Logs in and authenticates the following requests with the issued token.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `user`: The user to log in as.
+/// - `password`: The password, or `None` to prompt for it.
+///
+/// # Returns
+///
+/// Returns an error if there is no password and no terminal to prompt on, or the login fails.
+async fn login_command(
+    client: &Client,
+    user: &str,
+    password: Option<String>,
+) -> Result<(), String> {
+    let password = match password {
+        Some(password) => password,
+        None if io::stdin().is_terminal() => {
+            helper::read_password(&format!("Password for {}: ", user)).map_err(|e| e.to_string())?
+        }
+        None => return Err("No password given, use --password or RUST_DB_PASSWORD".to_string()),
+    };
+    let response = login(client, user, &password)
+        .await
+        .map_err(|e| e.to_string())?;
+    set_credentials(Some(Credentials::Bearer(response.token)));
+    info!(
+        "Logged in as '{}' until {}",
+        response.username, response.expires_at
+    );
+    Ok(())
+}
+
+/// Executes the statements given with `--execute`, then those of `--file` or stdin.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `session`: The client-side settings.
+/// - `args`: The command line arguments.
+///
+/// # Returns
+///
+/// Returns `ExitCode::FAILURE` if a script could not be read or any statement failed.
+async fn run_batch(client: &Client, session: &mut Session, args: &Args) -> ExitCode {
+    let mut sources: Vec<(String, String)> = args
+        .execute
+        .iter()
+        .map(|sql| ("-e".to_string(), sql.clone()))
+        .collect();
+
+    let file = match &args.file {
+        Some(path) if path.as_os_str() != "-" => Some(path),
+        Some(_) => None,
+        None if args.execute.is_empty() => None,
+        None => return run_sources(client, session, sources, args.continue_on_error).await,
+    };
+    let script = match file {
+        Some(path) => fs::read_to_string(path).map(|script| (path.display().to_string(), script)),
+        None => {
+            let mut script = String::new();
+            io::stdin()
+                .read_to_string(&mut script)
+                .map(|_| ("stdin".to_string(), script))
+        }
+    };
+    match script {
+        Ok(script) => sources.push(script),
+        Err(e) => {
+            error!("Failed to read script: {}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    run_sources(client, session, sources, args.continue_on_error).await
+}
+
+/// Executes the statements of each source in order, until one quits.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `session`: The client-side settings.
+/// - `sources`: Pairs of a name used in error messages and the SQL text.
+/// - `continue_on_error`: Whether to keep going after a statement fails.
+///
+/// # Returns
+///
+/// Returns `ExitCode::FAILURE` if any statement failed.
+async fn run_sources(
+    client: &Client,
+    session: &mut Session,
+    sources: Vec<(String, String)>,
+    continue_on_error: bool,
+) -> ExitCode {
+    let mut failed = false;
+    for (name, sql) in sources {
+        if !run_script(client, session, &name, &sql, continue_on_error).await {
+            failed = true;
+            if !continue_on_error {
+                break;
+            }
+        }
+        if session.quit {
+            break;
+        }
+    }
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+/// Executes the statements of a script, logging failed statements with their line, until one
+/// quits.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `session`: The client-side settings.
+/// - `name`: The name of the script used in error messages.
+/// - `sql`: The text of the script.
+/// - `continue_on_error`: Whether to keep going after a statement fails.
+///
+/// # Returns
+///
+/// Returns `true` if all executed statements succeeded.
+async fn run_script(
+    client: &Client,
+    session: &mut Session,
+    name: &str,
+    sql: &str,
+    continue_on_error: bool,
+) -> bool {
+    let mut succeeded = true;
+    for statement in split_statements(sql) {
+        if let Err(e) = parse_and_execute_command(client, session, &statement.text).await {
+            error!("{}:{}: {}", name, statement.line, e);
+            succeeded = false;
+            if !continue_on_error {
+                break;
+            }
+        }
+        if session.quit {
+            break;
+        }
+    }
+    succeeded
+}
+
+/// Reads and executes statements from an interactive prompt until the user quits.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `session`: The client-side settings.
+async fn run_interactive(client: &Client, session: &mut Session) {
     greet_and_list_operations();
 
+    let mut editor = match Editor::<SqlHelper, DefaultHistory>::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            error!("Failed to initialize the line editor: {}", e);
+            return;
+        }
+    };
+    editor.set_helper(Some(SqlHelper::default()));
+    refresh_schema(client, &mut editor).await;
+
+    let history = history_path();
+    if editor.load_history(&history).is_err() {
+        debug!("No history found at {}", history.display());
+    }
+
     loop {
         println!(" ");
-        println!("Enter a command: ");
-        io::stdout().flush().expect("Failed to flush stdout");
-
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read line");
+        let input = match editor.readline("sql> ") {
+            Ok(line) => line,
+            // Ctrl-C discards the current input
+            Err(ReadlineError::Interrupted) => continue,
+            // Ctrl-D quits
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                error!("Failed to read line: {}", e);
+                break;
+            }
+        };
         let input = input.trim();
 
         if input.is_empty() {
             continue;
         }
 
-        match parse_and_execute_command(&client, input).await {
+        if let Err(e) = editor.add_history_entry(input) {
+            debug!("Failed to add history entry: {}", e);
+        }
+        if let Err(e) = editor.save_history(&history) {
+            debug!("Failed to save history to {}: {}", history.display(), e);
+        }
+
+        match parse_and_execute_command(client, session, input).await {
             Ok(_) => {
-                info!("Operation successful! You can view the results at http://localhost:3000/");
+                refresh_schema(client, &mut editor).await;
             }
             Err(e) => {
                 error!("{}", e);
                 print_syntax_example();
             }
         }
+        if session.quit {
+            break;
+        }
+    }
+}
+
+/// Returns the path of the history file, `~/.sql_parser_client_history`.
+///
+/// Falls back to the current directory if `HOME` is not set.
+fn history_path() -> PathBuf {
+    let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+    home.join(".sql_parser_client_history")
+}
+
+/// Reloads the table and column names used for tab completion.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `editor`: The line editor whose helper is updated.
+async fn refresh_schema(client: &Client, editor: &mut Editor<SqlHelper, DefaultHistory>) {
+    match get_tables(client).await {
+        Ok(tables) => {
+            if let Some(helper) = editor.helper_mut() {
+                helper.tables = tables;
+            }
+        }
+        Err(e) => debug!("Failed to load tables for completion: {}", e),
     }
 }
 
@@ -57,8 +465,15 @@ fn greet_and_list_operations() {
     println!("2. INSERT INTO table_name (column1, column2) VALUES (value1, value2)");
     println!("3. SELECT column1, column2 FROM table_name WHERE condition");
     println!("4. UPDATE table_name SET column1 = value1 WHERE condition");
-    println!("5. RENAME TABLE old_table_name TO new_table_name");
-    println!("6. DROP TABLE table_name");
+    println!("5. DELETE FROM table_name WHERE condition");
+    println!("6. RENAME TABLE old_table_name TO new_table_name");
+    println!("7. DROP TABLE table_name");
+    println!("8. SHOW TABLES");
+    println!("9. DESCRIBE table_name");
+    println!("Statements end with ';' and may span multiple lines. Press Tab to complete keywords, tables and columns.");
+    println!(
+        "End a SELECT with \\G instead of ';' to show it vertically. Type \\? for client commands."
+    );
     println!("Type 'exit' to quit.");
 }
 
@@ -67,8 +482,10 @@ fn print_syntax_example() {
     info!("\nExample Syntax:");
 
     // Example for CREATE TABLE
-    println!("1. CREATE TABLE table_name (column1 TYPE, column2 TYPE, ...)");
-    println!("   Example: CREATE TABLE users (id INT, name STRING, email STRING)");
+    println!("1. CREATE TABLE table_name (column1 TYPE [constraints], column2 TYPE, ..., [table_constraints])");
+    println!("   Example: CREATE TABLE users (id INT PRIMARY KEY AUTO_INCREMENT, name STRING NOT NULL, email STRING UNIQUE)");
+    println!("   Column constraints: PRIMARY KEY, NOT NULL, UNIQUE, DEFAULT value, CHECK (expr), REFERENCES table (column), AUTO_INCREMENT");
+    println!("   Table constraints: PRIMARY KEY (a, b), UNIQUE (a, b), FOREIGN KEY (a) REFERENCES table (column)");
 
     // Example for INSERT INTO
     println!("2. INSERT INTO table_name (column1, column2, ...) VALUES (value1, value2, ...)");
@@ -77,20 +494,55 @@ fn print_syntax_example() {
     );
 
     // Example for SELECT
-    println!("3. SELECT column1, column2, ... FROM table_name [WHERE condition]");
+    println!("3. SELECT column1, column2, ... FROM table_name [JOIN other_table ON table_name.column = other_table.column] [WHERE condition]");
     println!("   Example: SELECT id, name FROM users WHERE email = 'alice@example.com'");
+    println!("   Example: SELECT users.name, orders.total FROM users JOIN orders ON users.id = orders.user_id");
+    println!("   Output formats: \\format table | vertical | csv | tsv | json | markdown");
+    println!("   Prefix with EXPLAIN to show the plan, or EXPLAIN ANALYZE to also run it and show actual rows and times");
 
     // Example for UPDATE
     println!("4. UPDATE table_name SET column1 = value1, column2 = value2, ... [WHERE condition]");
     println!("   Example: UPDATE users SET name = 'Alice Smith' WHERE id = 1");
 
+    // Example for DELETE
+    println!("5. DELETE FROM table_name [WHERE condition]");
+    println!("   Example: DELETE FROM users WHERE id = 1");
+
     // Example for RENAME TABLE
-    println!("5. RENAME TABLE old_table_name TO new_table_name");
+    println!("6. RENAME TABLE old_table_name TO new_table_name");
     println!("   Example: RENAME TABLE users TO customers");
 
     // Example for DROP TABLE
-    println!("6. DROP TABLE table_name");
+    println!("7. DROP TABLE table_name");
     println!("   Example: DROP TABLE customers");
+
+    // Example for SHOW TABLES
+    println!("8. SHOW TABLES");
+
+    // Example for DESCRIBE
+    println!("9. DESCRIBE table_name");
+    println!("   Example: DESCRIBE users");
+}
+
+/// Prints the client commands starting with a backslash.
+fn print_backslash_commands() {
+    println!("Client commands:");
+    println!("  \\dt               List tables with their number of columns and rows");
+    println!("  \\d [table]        Describe a table, or list tables without an argument");
+    println!("  \\format [name]    Show or set the output format: table, vertical, csv, tsv, json, markdown");
+    println!("  \\timing [on|off]  Toggle showing the elapsed time after results");
+    println!("  \\o [file]         Write results to a file, or back to stdout without an argument");
+    println!("  \\i file           Execute the statements in a file");
+    println!("  \\dump [table ...] Write an SQL dump of all or the given tables to the output");
+    println!("  \\restore file     Restore an SQL dump on the server, all at once");
+    println!("  \\copy table [(columns)] FROM 'file' [WITH (options)]");
+    println!("                    Import a CSV file into a table");
+    println!("  \\copy table | (SELECT ...) TO 'file' | STDOUT [WITH (options)]");
+    println!("                    Export a table or query result as CSV");
+    println!("                    Options: HEADER [bool], DELIMITER 'c', QUOTE 'c', NULL 'text',");
+    println!("                    TYPES 'column:TYPE, ...', INFER [bool], CREATE [bool]");
+    println!("  \\q                Quit");
+    println!("  \\?                Show this help");
 }
 
 /// Parses and executes a command.
@@ -98,36 +550,190 @@ fn print_syntax_example() {
 /// # Parameters
 ///
 /// - `client`: The HTTP client.
-/// - `command`: The command to parse and execute.
+/// - `session`: The client-side settings.
+/// - `command`: The command to parse and execute. A `SELECT` ending with `\G` is printed vertically.
 ///
 /// # Returns
 ///
 /// Returns a `Result` indicating whether the command was executed successfully.
-async fn parse_and_execute_command(client: &Client, command: &str) -> Result<(), String> {
-    let parts: Vec<&str> = command.split_whitespace().collect();
-    if parts.is_empty() {
-        return Err("Invalid command".into());
+async fn parse_and_execute_command(
+    client: &Client,
+    session: &mut Session,
+    command: &str,
+) -> Result<(), String> {
+    if command
+        .trim_end_matches(';')
+        .trim()
+        .eq_ignore_ascii_case("EXIT")
+    {
+        return exit_command(session);
+    }
+
+    if let Some(arguments) = command.strip_prefix("\\copy") {
+        if arguments.starts_with(char::is_whitespace) {
+            return copy_command(client, session, arguments).await;
+        }
+    }
+    if command.starts_with('\\') {
+        return backslash_command(client, session, command).await;
     }
 
-    match parts[0].to_uppercase().as_str() {
-        "CREATE" => create_table_command(client, parts, command).await,
-        "INSERT" => insert_into_command(client, command).await,
-        "SELECT" => select_command(client, command).await,
-        "UPDATE" => update_command(client, command).await,
-        "RENAME" => rename_table_command(client, parts).await,
-        "DROP" => drop_table_command(client, parts).await,
-        "EXIT" => exit_command(),
-        _ => Err("Unknown command".into()),
+    let (command, format) = match command.strip_suffix("\\G") {
+        Some(command) => (command, OutputFormat::Vertical),
+        None => (command, session.format),
+    };
+
+    match parse_statement(command).map_err(|e| e.to_string())? {
+        Statement::CreateTable(create) => create_table_command(client, create).await?,
+        Statement::CreateIndex(index) => create_index_command(client, index).await?,
+        Statement::Insert(insert) => insert_into_command(client, insert).await?,
+        Statement::Select(statement) => {
+            return select_command(client, session, statement, format).await
+        }
+        Statement::Explain { analyze, select } => {
+            return explain_command(client, session, select, analyze, format).await
+        }
+        Statement::ShowTables => {
+            let start = Instant::now();
+            let tables = get_tables(client).await.map_err(|e| e.to_string())?;
+            let (columns, rows) = schema::show_tables(&tables);
+            return session.print_result(format, &columns, &rows, start.elapsed());
+        }
+        Statement::Describe { table_name } => {
+            let start = Instant::now();
+            let table = find_table(client, &table_name).await?;
+            let (columns, rows) = schema::describe(&table);
+            return session.print_result(format, &columns, &rows, start.elapsed());
+        }
+        Statement::Update(update) => update_command(client, update).await?,
+        Statement::Delete(delete) => delete_command(client, delete).await?,
+        Statement::RenameTable {
+            current_name,
+            new_name,
+        } => rename_table_command(client, current_name, new_name).await?,
+        Statement::DropTable { name } => drop_table_command(client, name).await?,
+        Statement::CreateUser(CreateUser {
+            name,
+            password,
+            admin,
+        }) => {
+            let request = CreateUserRequest {
+                username: name,
+                password,
+                admin,
+            };
+            create_user(client, &request)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        Statement::AlterUser { name, password } => {
+            let request = AlterUserRequest {
+                username: name,
+                password,
+            };
+            alter_user(client, &request)
+                .await
+                .map_err(|e| e.to_string())?;
+            // Changing the password revokes the session's token
+            if session.user.as_ref() == Some(&request.username) {
+                login_command(client, &request.username, Some(request.password)).await?;
+            }
+        }
+        Statement::DropUser { name } => {
+            let request = DropUserRequest { username: name };
+            drop_user(client, &request)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        Statement::ShowUsers => {
+            let start = Instant::now();
+            let users = get_users(client).await.map_err(|e| e.to_string())?;
+            let (columns, rows) = schema::show_users(&users);
+            return session.print_result(format, &columns, &rows, start.elapsed());
+        }
+        Statement::CreateRole { name } => create_role(client, &RoleRequest { name })
+            .await
+            .map_err(|e| e.to_string())?,
+        Statement::DropRole { name } => drop_role(client, &RoleRequest { name })
+            .await
+            .map_err(|e| e.to_string())?,
+        Statement::GrantRole { role, user } => {
+            let request = GrantRoleRequest {
+                role,
+                username: user,
+            };
+            grant_role(client, &request)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        Statement::RevokeRole { role, user } => {
+            let request = GrantRoleRequest {
+                role,
+                username: user,
+            };
+            revoke_role(client, &request)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        Statement::Grant(privileges) => grant(client, &GrantRequest::from(privileges))
+            .await
+            .map_err(|e| e.to_string())?,
+        Statement::Revoke(privileges) => revoke(client, &GrantRequest::from(privileges))
+            .await
+            .map_err(|e| e.to_string())?,
+        Statement::ShowRoles => {
+            let start = Instant::now();
+            let roles = get_roles(client).await.map_err(|e| e.to_string())?;
+            let (columns, rows) = schema::show_roles(&roles);
+            return session.print_result(format, &columns, &rows, start.elapsed());
+        }
+        Statement::ShowGrants { grantee } => {
+            let start = Instant::now();
+            let grants = get_grants(client, &GrantsQuery { grantee })
+                .await
+                .map_err(|e| e.to_string())?;
+            let (columns, rows) = schema::show_grants(&grants);
+            return session.print_result(format, &columns, &rows, start.elapsed());
+        }
+        Statement::CreatePolicy { table_name, policy } => {
+            let request = CreatePolicyRequest { table_name, policy };
+            create_policy(client, &request)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        Statement::DropPolicy { name, table_name } => {
+            let request = DropPolicyRequest { table_name, name };
+            drop_policy(client, &request)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        Statement::Set { name, value } => {
+            set_setting(&name, Some(&value))?;
+            info!("Set '{}' to '{}'", name, value);
+            return Ok(());
+        }
+        Statement::Reset { name } => {
+            match name {
+                Some(name) => set_setting(&name, None)?,
+                None => reset_settings(),
+            }
+            return Ok(());
+        }
     }
+    info!(
+        "Operation successful! You can view the results at {}/",
+        server_url()
+    );
+    Ok(())
 }
 
-/// Handles the CREATE TABLE command.
+/// Handles a client command starting with a backslash.
 ///
 /// # Parameters
 ///
 /// - `client`: The HTTP client.
-/// - `parts`: The parts of the command.
-/// - `command`: The full command.
+/// - `session`: The client-side settings.
+/// - `command`: The command, e.g. `\format csv`.
 ///
 /// # Returns
 ///
@@ -136,68 +742,210 @@ async fn parse_and_execute_command(client: &Client, command: &str) -> Result<(),
 /// # Example
 ///
 /// ```
-/// CREATE TABLE users (id INT, name STRING, email STRING)
+/// \d users
 /// ```
-async fn create_table_command(
+async fn backslash_command(
     client: &Client,
-    parts: Vec<&str>,
+    session: &mut Session,
     command: &str,
 ) -> Result<(), String> {
-    if parts.get(1).map(|s| *s) == Some("TABLE") {
-        if let (Some(table_name), Some(columns_part)) = (
-            parts.get(2),
-            command.split('(').nth(1).and_then(|s| s.split(')').next()),
-        ) {
-            let columns: Vec<&str> = columns_part.split(',').map(|s| s.trim()).collect();
-            let column_requests: Result<Vec<InsertColumnRequest>, String> = columns
-                .iter()
-                .map(|&col| {
-                    let parts: Vec<&str> = col.split_whitespace().collect();
-                    if parts.len() < 2 {
-                        return Err("Syntax error in column definition".into());
-                    }
-                    let column_name = parts[0].to_string();
-                    let column_type = parts[1].to_uppercase();
-                    let (primary_key, non_null, unique) = (false, false, false);
-                    let (_column_type, foreign_key) = match column_type.as_str() {
-                        "INT" => (Value::Int(0), None),
-                        "FLOAT" => (Value::Float(0.0), None),
-                        "STRING" => (Value::Str("".to_string()), None),
-                        "BOOL" => (Value::Bool(false), None),
-                        _ => return Err("Unsupported column type".into()),
-                    };
-                    Ok(InsertColumnRequest {
-                        table_name: table_name.to_string(),
-                        key: column_name,
-                        primary_key,
-                        non_null,
-                        unique,
-                        foreign_key,
-                    })
-                })
-                .collect();
-            let column_requests = column_requests?;
-            let request = CreateTableRequests {
-                name: table_name.to_string(),
-                insert_column_requests: column_requests,
+    let mut words = command.trim_end_matches(';').split_whitespace();
+    let name = words.next().unwrap_or_default();
+    if name == "\\dump" {
+        let tables: Vec<&str> = words.collect();
+        return dump_command(client, session, &tables).await;
+    }
+    let argument = words.next();
+    if words.next().is_some() {
+        return Err(format!("Too many arguments for '{}'", name));
+    }
+
+    match (name, argument) {
+        ("\\dt", None) | ("\\d", None) => {
+            let start = Instant::now();
+            let tables = get_tables(client).await.map_err(|e| e.to_string())?;
+            let (columns, rows) = schema::list_tables(&tables);
+            session.print_result(session.format, &columns, &rows, start.elapsed())
+        }
+        ("\\d", Some(table_name)) => {
+            let start = Instant::now();
+            let table = find_table(client, table_name).await?;
+            let (columns, rows) = schema::describe(&table);
+            session.print_result(session.format, &columns, &rows, start.elapsed())?;
+            session.print(&schema::constraints(&table))
+        }
+        ("\\timing", argument) => {
+            session.timing = match argument {
+                None => !session.timing,
+                Some(value) if value.eq_ignore_ascii_case("on") => true,
+                Some(value) if value.eq_ignore_ascii_case("off") => false,
+                Some(value) => return Err(format!("Expected 'on' or 'off', found '{}'", value)),
             };
-            create_table(client, &request)
-                .await
-                .map_err(|e| e.to_string())
-        } else {
-            Err("Syntax error: CREATE TABLE table_name (column_definitions)".into())
+            println!("Timing is {}", if session.timing { "on" } else { "off" });
+            Ok(())
         }
-    } else {
-        Err("Syntax error: CREATE TABLE table_name (column_definitions)".into())
+        ("\\o", Some(path)) => {
+            let file =
+                File::create(path).map_err(|e| format!("Failed to open '{}': {}", path, e))?;
+            session.output = Some(file);
+            info!("Writing results to '{}'", path);
+            Ok(())
+        }
+        ("\\o", None) => {
+            session.output = None;
+            info!("Writing results to stdout");
+            Ok(())
+        }
+        ("\\i", Some(path)) => {
+            let sql = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+            // Boxed, as the script's statements are executed by the caller of this function
+            match Box::pin(run_script(client, session, path, &sql, false)).await {
+                true => Ok(()),
+                false => Err(format!("Execution of '{}' stopped after an error", path)),
+            }
+        }
+        ("\\i", None) => Err("Missing file name for '\\i'".into()),
+        ("\\restore", Some(path)) => {
+            let script = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+            let result = restore(client, script).await.map_err(|e| e.to_string())?;
+            info!(
+                "Restored {} tables ({}) with {} rows and {} indexes",
+                result.tables.len(),
+                result.tables.join(", "),
+                result.row_count,
+                result.index_count
+            );
+            Ok(())
+        }
+        ("\\restore", None) => Err("Missing file name for '\\restore'".into()),
+        ("\\q", None) => exit_command(session),
+        ("\\?", None) => {
+            print_backslash_commands();
+            Ok(())
+        }
+        ("\\format", argument) => {
+            match argument {
+                Some(name) => {
+                    session.format = name.parse()?;
+                    info!("Output format is {}", session.format);
+                }
+                None => println!("Output format is {}", session.format),
+            }
+            Ok(())
+        }
+        (name, Some(_)) if ["\\dt", "\\q", "\\?"].contains(&name) => {
+            Err(format!("'{}' does not take an argument", name))
+        }
+        _ => Err(format!("Unknown command '{}', type \\? for help", name)),
+    }
+}
+
+/// Handles the `\dump` command.
+///
+/// The dump is written to the session's output, so `\o file` followed by `\dump` writes it
+/// to a file. It can be restored with `\restore file`, or replayed statement by statement
+/// with `\i file`.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `session`: The client-side settings.
+/// - `tables`: The tables to dump, or all tables if empty.
+///
+/// # Returns
+///
+/// Returns a `Result` indicating whether the command was executed successfully.
+///
+/// # Example
+///
+/// ```
+/// \dump users orders
+/// ```
+async fn dump_command(
+    client: &Client,
+    session: &mut Session,
+    tables: &[&str],
+) -> Result<(), String> {
+    let options = DumpOptions {
+        tables: (!tables.is_empty()).then(|| tables.join(",")),
+        batch_size: None,
+    };
+    let script = dump(client, &options).await.map_err(|e| e.to_string())?;
+    session.print(&script)
+}
+
+/// Handles the CREATE TABLE command.
+///
+/// `PRIMARY KEY (a, b)` and `UNIQUE (a, b)` become table-level constraints of the request;
+/// unnamed unique constraints are named `<table>_<columns>_key`. A
+/// `FOREIGN KEY (a) REFERENCES t (b)` becomes the foreign key of column `a`; foreign keys over
+/// several columns are rejected.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `create`: The parsed statement.
+///
+/// # Returns
+///
+/// Returns a `Result` indicating whether the command was executed successfully.
+///
+/// # Example
+///
+/// ```
+/// CREATE TABLE users (id INT PRIMARY KEY AUTO_INCREMENT, name STRING NOT NULL, email STRING UNIQUE)
+/// ```
+async fn create_table_command(client: &Client, create: CreateTable) -> Result<(), String> {
+    let request = CreateTableRequests::try_from(create)?;
+    create_table(client, &request)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Handles the CREATE INDEX command.
+///
+/// Only unique indexes are supported; they are stored as unique constraints of the table.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `index`: The parsed statement.
+///
+/// # Returns
+///
+/// Returns a `Result` indicating whether the command was executed successfully.
+///
+/// # Example
+///
+/// ```
+/// CREATE UNIQUE INDEX users_email_key ON users (email)
+/// ```
+async fn create_index_command(client: &Client, index: CreateIndex) -> Result<(), String> {
+    if !index.unique {
+        return Err("Only unique indexes are supported, use CREATE UNIQUE INDEX".into());
     }
+    let request = CreateIndexRequest {
+        table_name: index.table_name,
+        name: index.name,
+        columns: index.columns,
+    };
+    create_index(client, &request)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 /// Handles the INSERT INTO command.
 ///
+/// Every row of a multi-row `VALUES` list is sent as a separate insert. With a column list,
+/// the values are placed at the positions of the named columns, and omitted columns get
+/// their default value or NULL.
+///
 /// # Parameters
 ///
 /// - `client`: The HTTP client.
-/// - `command`: The full command.
+/// - `insert`: The parsed statement.
 ///
 /// # Returns
 ///
@@ -206,77 +954,73 @@ async fn create_table_command(
 /// # Example
 ///
 /// ```
-/// INSERT INTO users (id, name, email) VALUES (1, "Alice", "alice@example.com")
+/// INSERT INTO users (id, name, email) VALUES (1, 'Alice', 'alice@example.com')
 /// ```
-async fn insert_into_command(client: &Client, command: &str) -> Result<(), String> {
-    let parts: Vec<&str> = command.split_whitespace().collect();
-    if parts.get(1).map(|s| *s) == Some("INTO") {
-        if let (Some(table_name), Some(values_part)) =
-            (parts.get(2), command.split("VALUES").nth(1))
-        {
-            let columns_start = command
-                .find('(')
-                .ok_or("Syntax error: Missing opening parenthesis")?;
-            let columns_end = command
-                .find(')')
-                .ok_or("Syntax error: Missing closing parenthesis")?;
-            let columns_str = &command[columns_start + 1..columns_end].trim();
-            let columns: Vec<&str> = columns_str.split(',').map(|s| s.trim()).collect();
-
-            let values_start = values_part
-                .find('(')
-                .ok_or("Syntax error: Missing opening parenthesis for values")?;
-            let values_end = values_part
-                .find(')')
-                .ok_or("Syntax error: Missing closing parenthesis for values")?;
-            let values_str = &values_part[values_start + 1..values_end].trim();
-            let values: Vec<&str> = values_str.split(',').map(|s| s.trim()).collect();
-
-            if columns.len() != values.len() {
-                return Err("Column count does not match value count".into());
-            }
+async fn insert_into_command(client: &Client, insert: Insert) -> Result<(), String> {
+    // Position of each listed column in the table, and the values of the omitted columns
+    let layout = if insert.columns.is_empty() {
+        None
+    } else {
+        let table = find_table(client, &insert.table_name).await?;
+        let positions = insert
+            .columns
+            .iter()
+            .map(|name| {
+                table
+                    .columns
+                    .iter()
+                    .position(|column| column.key == *name)
+                    .ok_or_else(|| format!("Column '{}' not found", name))
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
+        let defaults: Vec<Value> = table
+            .columns
+            .iter()
+            .map(|column| column.default.clone().unwrap_or(Value::Null))
+            .collect();
+        Some((positions, defaults))
+    };
 
-            let row_values: Vec<Value> = values
-                .iter()
-                .map(|&v| {
-                    if v == "NULL" {
-                        Value::Null
-                    } else if let Ok(val) = v.parse::<i64>() {
-                        Value::Int(val)
-                    } else if let Ok(val) = v.parse::<f64>() {
-                        Value::Float(val)
-                    } else if v == "true" {
-                        Value::Bool(true)
-                    } else if v == "false" {
-                        Value::Bool(false)
-                    } else {
-                        Value::Str(v.trim_matches(|c| c == '"' || c == '\'').to_string())
-                    }
-                })
-                .collect();
-
-            let row = Row::new(row_values);
-            let request = InsertRowRequest {
-                table_name: table_name.to_string(),
-                row,
-            };
-            insert_row(client, &request)
-                .await
-                .map_err(|e| e.to_string())
-        } else {
-            Err("Syntax error: INSERT INTO table_name (columns) VALUES (values)".into())
+    for values in insert.rows {
+        if !insert.columns.is_empty() && insert.columns.len() != values.len() {
+            return Err("Column count does not match value count".into());
         }
-    } else {
-        Err("Syntax error: INSERT INTO table_name (columns) VALUES (values)".into())
+
+        let mut row_values = values
+            .iter()
+            .map(literal)
+            .collect::<Result<Vec<Value>, String>>()?;
+
+        if let Some((positions, defaults)) = &layout {
+            let mut ordered = defaults.clone();
+            for (position, value) in positions.iter().zip(row_values) {
+                ordered[*position] = value;
+            }
+            row_values = ordered;
+        }
+
+        let request = InsertRowRequest {
+            table_name: insert.table_name.clone(),
+            row: Row::new(row_values),
+        };
+        insert_row(client, &request)
+            .await
+            .map_err(|e| e.to_string())?;
     }
+    Ok(())
 }
 
 /// Handles the SELECT command.
 ///
+/// The rows are printed in `format`, followed by the row count and elapsed time for
+/// formats meant to be read by people.
+///
 /// # Parameters
 ///
 /// - `client`: The HTTP client.
-/// - `command`: The full command.
+/// - `session`: The client-side settings.
+/// - `statement`: The parsed statement.
+/// - `format`: The output format.
 ///
 /// # Returns
 ///
@@ -285,63 +1029,343 @@ async fn insert_into_command(client: &Client, command: &str) -> Result<(), Strin
 /// # Example
 ///
 /// ```
-/// SELECT id, name FROM users WHERE email = "alice@example.com"
+/// SELECT id, name FROM users WHERE email = 'alice@example.com'
+/// SELECT users.name, orders.total FROM users JOIN orders ON users.id = orders.user_id
 /// ```
-async fn select_command(client: &Client, command: &str) -> Result<(), String> {
-    let parts: Vec<&str> = command.split_whitespace().collect();
-    let mut parts_iter = parts.iter().skip(1); // Skip the "SELECT" keyword
-
-    let columns_part_vec: Vec<&str> = parts_iter
-        .by_ref()
-        .take_while(|&&s| s.to_uppercase() != "FROM")
-        .map(|&s| s)
-        .collect();
-    let columns_part = columns_part_vec.join(" ");
-    if columns_part.is_empty() {
-        return Err("Syntax error: Missing columns".into());
+async fn select_command(
+    client: &Client,
+    session: &mut Session,
+    mut statement: Select,
+    format: OutputFormat,
+) -> Result<(), String> {
+    let start = Instant::now();
+    if let Some(join) = statement.join.take() {
+        let request = join_request(statement, join)?;
+
+        debug!("JoinRequest: {:?}", request);
+
+        let rows = select_join(client, &request)
+            .await
+            .map_err(|e| e.to_string())?;
+        let headers = match &request.columns {
+            Some(columns) => columns.clone(),
+            None => {
+                let mut headers = Vec::new();
+                for table_name in [&request.table_name, &request.join_table] {
+                    let columns = column_names(client, table_name).await?;
+                    headers.extend(
+                        columns
+                            .into_iter()
+                            .map(|column| format!("{}.{}", table_name, column)),
+                    );
+                }
+                headers
+            }
+        };
+        return session.print_result(format, &headers, &rows, start.elapsed());
     }
-    let columns: Vec<&str> = columns_part.split(',').map(|s| s.trim()).collect();
+    let request = select_request(statement)?;
 
-    let from_keyword_index = parts
-        .iter()
-        .position(|&s| s.to_uppercase() == "FROM")
-        .ok_or("Syntax error: Missing FROM keyword")?;
-    let table_name = parts
-        .get(from_keyword_index + 1)
-        .ok_or("Syntax error: Missing table name")?;
+    debug!("SelectRequest: {:?}", request);
 
-    let mut condition = None;
+    let rows = select(client, &request).await.map_err(|e| e.to_string())?;
+    let headers = match &request.columns {
+        Some(columns) => columns.clone(),
+        None => column_names(client, &request.table_name).await?,
+    };
 
-    if let Some(where_index) = parts.iter().position(|&s| s.to_uppercase() == "WHERE") {
-        let where_parts: Vec<&str> = parts.iter().skip(where_index + 1).map(|s| *s).collect();
+    session.print_result(format, &headers, &rows, start.elapsed())
+}
 
-        if where_parts.len() < 3 {
-            return Err("Syntax error: Incomplete WHERE clause".into());
+/// Handles the EXPLAIN command.
+///
+/// The plan is printed as a single `QUERY PLAN` column with one line per row, children
+/// indented below their parent.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `session`: The client-side settings.
+/// - `statement`: The parsed SELECT statement to explain.
+/// - `analyze`: Whether to run the query, for `EXPLAIN ANALYZE`.
+/// - `format`: The output format.
+///
+/// # Returns
+///
+/// Returns a `Result` indicating whether the command was executed successfully.
+///
+/// # Example
+///
+/// ```
+/// EXPLAIN ANALYZE SELECT id, name FROM users WHERE email = 'alice@example.com'
+/// ```
+async fn explain_command(
+    client: &Client,
+    session: &mut Session,
+    mut statement: Select,
+    analyze: bool,
+    format: OutputFormat,
+) -> Result<(), String> {
+    let start = Instant::now();
+    let explanation = match statement.join.take() {
+        Some(join) => {
+            let request = join_request(statement, join)?;
+            debug!("JoinRequest: {:?}", request);
+            explain_join(client, &request, analyze).await
         }
+        None => {
+            let request = select_request(statement)?;
+            debug!("SelectRequest: {:?}", request);
+            explain(client, &request, analyze).await
+        }
+    }
+    .map_err(|e| e.to_string())?;
+    let rows: Vec<Row> = explanation
+        .lines()
+        .into_iter()
+        .map(|line| Row::new(vec![Value::Str(line)]))
+        .collect();
 
-        let condition_column = where_parts[0].to_string();
-        let _operator = where_parts[1].to_string();
-        let condition_value = where_parts[2..].join(" ");
+    session.print_result(format, &["QUERY PLAN".to_string()], &rows, start.elapsed())
+}
 
-        let condition_value = condition_value
-            .trim_matches(|c| c == '"' || c == '\'')
-            .to_string();
+/// Builds the select request of a SELECT statement.
+///
+/// # Parameters
+///
+/// - `statement`: The parsed statement.
+///
+/// # Returns
+///
+/// Returns the request, or an error if the statement selects anything but plain columns.
+fn select_request(statement: Select) -> Result<SelectRequest, String> {
+    Ok(SelectRequest {
+        columns: columns(&statement.projection)?,
+        table_name: statement.table_name,
+        condition: condition(statement.selection)?,
+    })
+}
 
-        condition = Some(Condition {
-            column: condition_column,
-            value: condition_value,
-        });
+/// Builds the join request of a SELECT statement with a JOIN.
+///
+/// # Parameters
+///
+/// - `statement`: The parsed statement, without its join.
+/// - `join`: The join of the statement.
+///
+/// # Returns
+///
+/// Returns the request, or an error if the `ON` condition does not compare a column of
+/// each table or the statement cannot be expressed as a join request.
+fn join_request(statement: Select, join: Join) -> Result<JoinRequest, String> {
+    let invalid = || {
+        format!(
+            "The JOIN condition has to compare a column of '{}' with a column of '{}', found '{}'",
+            statement.table_name, join.table_name, join.on
+        )
+    };
+    let Expr::Binary {
+        left,
+        op: BinaryOp::Eq,
+        right,
+    } = &join.on
+    else {
+        return Err(invalid());
+    };
+    let (Expr::Column(first), Expr::Column(second)) = (&**left, &**right) else {
+        return Err(invalid());
+    };
+    // Columns are named by their table, which may be given in either order
+    let column_of = |table: &str, name: &str| {
+        name.strip_prefix(table)?
+            .strip_prefix('.')
+            .map(str::to_string)
+    };
+    let (left_column, right_column) = match (
+        column_of(&statement.table_name, first),
+        column_of(&join.table_name, second),
+        column_of(&statement.table_name, second),
+        column_of(&join.table_name, first),
+    ) {
+        (Some(left), Some(right), _, _) | (_, _, Some(left), Some(right)) => (left, right),
+        _ => return Err(invalid()),
+    };
+
+    Ok(JoinRequest {
+        columns: columns(&statement.projection)?,
+        table_name: statement.table_name,
+        join_table: join.table_name,
+        on: JoinCondition {
+            left_column,
+            right_column,
+        },
+        condition: condition(statement.selection)?,
+    })
+}
+
+/// Converts the items of a `SELECT` list into the columns of a request.
+///
+/// # Parameters
+///
+/// - `projection`: The parsed items.
+///
+/// # Returns
+///
+/// Returns `None` for `*`, or an error if an item is not a plain column name.
+fn columns(projection: &[SelectItem]) -> Result<Option<Vec<String>>, String> {
+    if projection == [SelectItem::Wildcard] {
+        return Ok(None);
     }
+    let columns = projection
+        .iter()
+        .map(|item| match item {
+            SelectItem::Expr {
+                expr: Expr::Column(name),
+                alias: None,
+            } => Ok(name.clone()),
+            SelectItem::Wildcard => Err("'*' cannot be combined with other columns".into()),
+            SelectItem::Expr { expr, .. } => Err(format!(
+                "Only plain column names can be selected, found '{}'",
+                expr
+            )),
+        })
+        .collect::<Result<Vec<String>, String>>()?;
+    Ok(Some(columns))
+}
 
-    let request = SelectRequest {
-        table_name: table_name.to_string(),
-        columns: Some(columns.iter().map(|&col| col.to_string()).collect()),
-        condition,
+/// Handles the `\copy` command, which imports a CSV file into a table or exports a table or
+/// query result as CSV.
+///
+/// Files are read and written by the client, so they are local to the client rather than
+/// the server.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `session`: The client-side settings. `TO STDOUT` writes to the output set with `\o`.
+/// - `arguments`: The text after `\copy`.
+///
+/// # Returns
+///
+/// Returns a `Result` indicating whether the command was executed successfully.
+///
+/// # Example
+///
+/// ```
+/// \copy users FROM 'users.csv' WITH (HEADER, DELIMITER ';')
+/// \copy (SELECT id, name FROM users WHERE active = true) TO 'active.csv'
+/// ```
+async fn copy_command(
+    client: &Client,
+    session: &mut Session,
+    arguments: &str,
+) -> Result<(), String> {
+    let mut command = copy::parse_copy(arguments)?;
+
+    let path = match command.direction {
+        CopyDirection::From(path) => {
+            let CopySource::Table { name, columns } = command.source else {
+                return Err("A query can only be copied TO a file".into());
+            };
+            let csv = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+            if !columns.is_empty() {
+                command.import.columns = Some(columns.join(","));
+            }
+            import_csv(client, &name, csv, &command.options, &command.import)
+                .await
+                .map_err(|e| e.to_string())?;
+            return Ok(());
+        }
+        CopyDirection::To(path) => path,
     };
 
-    debug!("SelectRequest: {:?}", request);
+    let csv = match command.source {
+        CopySource::Table { name, columns } if columns.is_empty() => {
+            export_csv(client, &name, &command.options).await
+        }
+        CopySource::Table { name, columns } => {
+            let request = SelectRequest {
+                table_name: name,
+                columns: Some(columns),
+                condition: None,
+            };
+            select_csv(client, &request, &command.options).await
+        }
+        CopySource::Query(query) => match parse_statement(&query).map_err(|e| e.to_string())? {
+            Statement::Select(Select { join: Some(_), .. }) => {
+                return Err("Queries with a JOIN cannot be copied".into())
+            }
+            Statement::Select(statement) => {
+                select_csv(client, &select_request(statement)?, &command.options).await
+            }
+            _ => return Err("Only SELECT queries can be copied".into()),
+        },
+    }
+    .map_err(|e| e.to_string())?;
 
-    select(client, &request).await.map_err(|e| e.to_string())
+    match path {
+        Some(path) => {
+            fs::write(&path, &csv).map_err(|e| format!("Failed to write '{}': {}", path, e))?;
+            // Counted by parsing, as quoted fields may span several lines
+            let records = core::csv::parse(&csv, &command.options).map_or(0, |r| r.len());
+            let rows = records.saturating_sub(usize::from(command.options.header));
+            info!("Exported {} rows to '{}'", rows, path);
+            Ok(())
+        }
+        None => session.print(&csv),
+    }
+}
+
+/// Looks up the column names of a table or view in `information_schema.columns`, used as
+/// headers for `SELECT *`.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `table_name`: The name of the table.
+///
+/// # Returns
+///
+/// Returns the column names in table order, or an error if the table does not exist.
+async fn column_names(client: &Client, table_name: &str) -> Result<Vec<String>, String> {
+    // View names are matched case-insensitively, but listed in lowercase
+    let table_name = match catalog::is_catalog_name(table_name) {
+        true => table_name.to_lowercase(),
+        false => table_name.to_string(),
+    };
+    let request = SelectRequest {
+        table_name: format!("{}.columns", catalog::SCHEMA_NAME),
+        columns: Some(vec!["column_name".to_string()]),
+        condition: Some(Condition {
+            column: "table_name".to_string(),
+            value: table_name.clone(),
+        }),
+    };
+    let rows = select(client, &request).await.map_err(|e| e.to_string())?;
+    if rows.is_empty() {
+        return Err(format!("Table '{}' does not exist", table_name));
+    }
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| row.values.first().and_then(Value::as_string))
+        .collect())
+}
+
+/// Fetches the schema of a table, e.g. to describe its columns.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `table_name`: The name of the table.
+///
+/// # Returns
+///
+/// Returns the schema of the table, or an error if it does not exist.
+async fn find_table(client: &Client, table_name: &str) -> Result<TableSchema, String> {
+    get_table(client, table_name)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 /// Handles the UPDATE command.
@@ -349,7 +1373,7 @@ async fn select_command(client: &Client, command: &str) -> Result<(), String> {
 /// # Parameters
 ///
 /// - `client`: The HTTP client.
-/// - `command`: The full command.
+/// - `update`: The parsed statement.
 ///
 /// # Returns
 ///
@@ -358,76 +1382,65 @@ async fn select_command(client: &Client, command: &str) -> Result<(), String> {
 /// # Example
 ///
 /// ```
-/// UPDATE users SET name = "Alicia" WHERE email = "alice@example.com"
+/// UPDATE users SET name = 'Alicia' WHERE email = 'alice@example.com'
 /// ```
-async fn update_command(client: &Client, command: &str) -> Result<(), String> {
-    let parts: Vec<&str> = command.split_whitespace().collect();
-    if let (Some(table_name), Some(set_index)) = (
-        parts.get(1),
-        parts.iter().position(|&s| s.to_uppercase() == "SET"),
-    ) {
-        let condition_index = parts
-            .iter()
-            .position(|&s| s.to_uppercase() == "WHERE")
-            .unwrap_or(parts.len());
-        let updates_str = parts[set_index + 1..condition_index].join(" ");
-        let updates: Vec<&str> = updates_str.split(',').map(|s| s.trim()).collect();
-
-        let updates: Result<Vec<UpdateColumnRequest>, String> = updates
-            .iter()
-            .map(|&update| {
-                let key_value: Vec<&str> = update.split('=').map(|s| s.trim()).collect();
-                if key_value.len() != 2 {
-                    return Err("Syntax error in UPDATE clause".into());
-                }
-                Ok(UpdateColumnRequest {
-                    column: key_value[0].to_string(),
-                    value: key_value[1]
-                        .trim_matches(|c| c == '"' || c == '\'')
-                        .to_string(),
-                })
+async fn update_command(client: &Client, update: Update) -> Result<(), String> {
+    let updates = update
+        .assignments
+        .iter()
+        .map(|assignment| {
+            Ok(UpdateColumnRequest {
+                column: assignment.column.clone(),
+                value: literal(&assignment.value)?.as_string(),
             })
-            .collect();
+        })
+        .collect::<Result<Vec<UpdateColumnRequest>, String>>()?;
 
-        let condition = if condition_index < parts.len() {
-            // Collect condition parts after the WHERE clause
-            let where_parts: Vec<&str> =
-                parts.iter().skip(condition_index + 1).map(|s| *s).collect();
-            if where_parts.len() < 3 {
-                return Err("Syntax error: Incomplete WHERE clause".into());
-            }
+    let request = UpdateRequest {
+        table_name: update.table_name,
+        condition: condition(update.selection)?,
+        updates,
+    };
 
-            let condition_column = where_parts[0].to_string();
-            let _operator = where_parts[1].to_string(); // The operator is not used here, but could be parsed
-            let condition_value = where_parts[2..].join(" ").trim_matches('"').to_string();
+    // Print request for debugging
+    debug!("UpdateRequest: {:?}", request);
 
-            Some(Condition {
-                column: condition_column,
-                value: condition_value,
-            })
-        } else {
-            None
-        };
+    // Execute the request
+    update_table(client, &request)
+        .await
+        .map_err(|e| e.to_string())
+}
 
-        let request = UpdateRequest {
-            table_name: table_name.to_string(),
-            condition,
-            updates: updates?,
-        };
+/// Handles the DELETE command.
+///
+/// # Parameters
+///
+/// - `client`: The HTTP client.
+/// - `delete`: The parsed statement.
+///
+/// # Returns
+///
+/// Returns a `Result` indicating whether the command was executed successfully.
+///
+/// # Example
+///
+/// ```
+/// DELETE FROM users WHERE email = 'alice@example.com'
+/// ```
+async fn delete_command(client: &Client, delete: Delete) -> Result<(), String> {
+    let request = DeleteRequest {
+        table_name: delete.table_name,
+        condition: condition(delete.selection)?,
+    };
 
-        // Print request for debugging
-        debug!("UpdateRequest: {:?}", request);
+    // Print request for debugging
+    debug!("DeleteRequest: {:?}", request);
 
-        // Execute the request
-        update_table(client, &request)
-            .await
-            .map_err(|e| e.to_string())
-    } else {
-        Err(
-            "Syntax error: UPDATE table_name SET column=value[,column2=value2] [WHERE condition]"
-                .into(),
-        )
-    }
+    // Execute the request
+    delete_rows(client, &request)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
 }
 
 /// Handles the RENAME TABLE command.
@@ -435,7 +1448,8 @@ async fn update_command(client: &Client, command: &str) -> Result<(), String> {
 /// # Parameters
 ///
 /// - `client`: The HTTP client.
-/// - `parts`: The parts of the command.
+/// - `current_name`: The current name of the table.
+/// - `new_name`: The new name of the table.
 ///
 /// # Returns
 ///
@@ -446,18 +1460,18 @@ async fn update_command(client: &Client, command: &str) -> Result<(), String> {
 /// ```
 /// RENAME TABLE users TO customers
 /// ```
-async fn rename_table_command(client: &Client, parts: Vec<&str>) -> Result<(), String> {
-    if let (Some(old_name), Some(new_name)) = (parts.get(2), parts.get(4)) {
-        let request = RenameTableRequest {
-            current_name: old_name.to_string(),
-            new_name: new_name.to_string(),
-        };
-        rename_table(client, &request)
-            .await
-            .map_err(|e| e.to_string())
-    } else {
-        Err("Syntax error: RENAME TABLE old_table_name TO new_table_name".into())
-    }
+async fn rename_table_command(
+    client: &Client,
+    current_name: String,
+    new_name: String,
+) -> Result<(), String> {
+    let request = RenameTableRequest {
+        current_name,
+        new_name,
+    };
+    rename_table(client, &request)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 /// Handles the DROP TABLE command.
@@ -465,7 +1479,7 @@ async fn rename_table_command(client: &Client, parts: Vec<&str>) -> Result<(), S
 /// # Parameters
 ///
 /// - `client`: The HTTP client.
-/// - `parts`: The parts of the command.
+/// - `name`: The name of the table to drop.
 ///
 /// # Returns
 ///
@@ -476,34 +1490,88 @@ async fn rename_table_command(client: &Client, parts: Vec<&str>) -> Result<(), S
 /// ```
 /// DROP TABLE customers
 /// ```
-async fn drop_table_command(client: &Client, parts: Vec<&str>) -> Result<(), String> {
-    if parts.get(1).map(|s| *s) == Some("TABLE") {
-        if let Some(table_name) = parts.get(2) {
-            let request = DropTableRequest {
-                name: table_name.to_string(),
-            };
-            drop_table(client, &request)
-                .await
-                .map_err(|e| e.to_string())
-        } else {
-            Err("Syntax error: DROP TABLE table_name".into())
-        }
-    } else {
-        Err("Syntax error: DROP TABLE table_name".into())
+async fn drop_table_command(client: &Client, name: String) -> Result<(), String> {
+    let request = DropTableRequest { name };
+    drop_table(client, &request)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Extracts the constant value of a literal expression.
+///
+/// # Parameters
+///
+/// - `expr`: The expression, which must be a literal such as `42` or `'text'`.
+///
+/// # Returns
+///
+/// Returns the literal's value, or an error for any other kind of expression.
+fn literal(expr: &Expr) -> Result<Value, String> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        _ => Err(format!("Expected a literal value, found '{}'", expr)),
+    }
+}
+
+/// Converts a parsed `WHERE` clause into the server's `Condition`.
+///
+/// The server only supports equality between a column and a literal, so
+/// `column = value` (in either order) is the only accepted form.
+///
+/// # Parameters
+///
+/// - `selection`: The parsed `WHERE` expression, if any.
+///
+/// # Returns
+///
+/// Returns the condition, or an error if the expression cannot be expressed as one.
+fn condition(selection: Option<Expr>) -> Result<Option<Condition>, String> {
+    let Some(expr) = selection else {
+        return Ok(None);
+    };
+
+    if let Expr::Binary {
+        left,
+        op: BinaryOp::Eq,
+        right,
+    } = &expr
+    {
+        let (column, value) = match (left.as_ref(), right.as_ref()) {
+            (Expr::Column(column), Expr::Literal(value))
+            | (Expr::Literal(value), Expr::Column(column)) => (column, value),
+            _ => return Err(format!("Unsupported WHERE clause '{}'", expr)),
+        };
+        return Ok(Some(Condition {
+            column: column.clone(),
+            value: value.as_string().unwrap_or_default(),
+        }));
     }
+
+    Err(format!(
+        "Unsupported WHERE clause '{}', only 'column = value' is supported",
+        expr
+    ))
 }
 
 /// Exits the program.
 ///
+/// The statements after it are not executed. The client logs out and exits with the status of
+/// the statements executed before, so a script ending with `\q` still reports their errors.
+///
+/// # Parameters
+///
+/// - `session`: The client-side settings, which are marked to quit.
+///
 /// # Returns
 ///
-/// Returns a `Result` indicating whether the exit was successful.
+/// Returns `Ok(())`.
 ///
 /// # Example
 ///
 /// ```
 /// EXIT
 /// ```
-fn exit_command() -> Result<(), String> {
-    std::process::exit(0);
+fn exit_command(session: &mut Session) -> Result<(), String> {
+    session.quit = true;
+    Ok(())
 }
@@ -0,0 +1,308 @@
+use core::csv::{self, CsvOptions};
+use core::row::Row;
+use core::value::Value;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How query results are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// An aligned ASCII table.
+    #[default]
+    Table,
+    /// One `column: value` line per column, with a separator line per row (like MySQL's `\G`).
+    Vertical,
+    /// Comma separated values with a header line. NULL is an empty field.
+    Csv,
+    /// Tab separated values with a header line. NULL is `\N`.
+    Tsv,
+    /// A JSON array with one object per row.
+    Json,
+    /// A GitHub flavored markdown table.
+    Markdown,
+}
+
+impl OutputFormat {
+    /// All formats, in the order they are listed in help texts.
+    pub const ALL: [OutputFormat; 6] = [
+        OutputFormat::Table,
+        OutputFormat::Vertical,
+        OutputFormat::Csv,
+        OutputFormat::Tsv,
+        OutputFormat::Json,
+        OutputFormat::Markdown,
+    ];
+
+    /// Returns whether the format is meant to be read by people rather than programs.
+    ///
+    /// Only these formats are followed by the row count and timing footer.
+    pub fn is_human_readable(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Table | OutputFormat::Vertical | OutputFormat::Markdown
+        )
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Vertical => "vertical",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Json => "json",
+            OutputFormat::Markdown => "markdown",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("md") {
+            return Ok(OutputFormat::Markdown);
+        }
+        OutputFormat::ALL
+            .into_iter()
+            .find(|format| format.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<String> = OutputFormat::ALL.iter().map(|f| f.to_string()).collect();
+                format!(
+                    "Unknown output format '{}', expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Renders a query result.
+///
+/// # Parameters
+///
+/// - `format`: The output format.
+/// - `columns`: The column headers.
+/// - `rows`: The rows, with one value per column.
+///
+/// # Returns
+///
+/// Returns the rendered result, ending with a newline unless it is empty.
+pub fn render(format: OutputFormat, columns: &[String], rows: &[Row]) -> String {
+    match format {
+        OutputFormat::Table => render_table(columns, rows),
+        OutputFormat::Vertical => render_vertical(columns, rows),
+        OutputFormat::Csv => render_csv(columns, rows),
+        OutputFormat::Tsv => render_tsv(columns, rows),
+        OutputFormat::Json => render_json(columns, rows),
+        OutputFormat::Markdown => render_markdown(columns, rows),
+    }
+}
+
+/// Formats the footer printed after a result, e.g. `2 rows in set (0.004 sec)`.
+///
+/// # Parameters
+///
+/// - `row_count`: The number of rows in the result.
+/// - `elapsed`: The time the query took, or `None` to leave it out.
+pub fn footer(row_count: usize, elapsed: Option<Duration>) -> String {
+    let count = match row_count {
+        0 => "Empty set".to_string(),
+        1 => "1 row in set".to_string(),
+        n => format!("{} rows in set", n),
+    };
+    match elapsed {
+        Some(elapsed) => format!("{} ({:.3} sec)", count, elapsed.as_secs_f64()),
+        None => count,
+    }
+}
+
+/// Returns the display text of a value, or `None` for NULL.
+///
+/// Floats always show a decimal point so they can be told apart from integers.
+fn display(value: &Value) -> Option<String> {
+    match value {
+        Value::Float(f) => Some(format!("{:?}", f)),
+        other => other.as_string(),
+    }
+}
+
+/// Returns the display text of the value at `index`, showing NULL as `NULL`.
+///
+/// Line breaks and tabs are escaped so that every row stays on one line.
+fn cell(row: &Row, index: usize) -> String {
+    match row.values.get(index).and_then(display) {
+        Some(text) => text
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+            .replace('\t', "\\t"),
+        None => "NULL".to_string(),
+    }
+}
+
+fn is_numeric(row: &Row, index: usize) -> bool {
+    matches!(row.values.get(index), Some(Value::Int(_) | Value::Float(_)))
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn render_table(columns: &[String], rows: &[Row]) -> String {
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| (0..columns.len()).map(|i| cell(row, i)).collect())
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| width(&row[i]))
+                .chain([width(column)])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let separator: String = widths
+        .iter()
+        .map(|w| format!("+{}", "-".repeat(w + 2)))
+        .collect::<String>()
+        + "+\n";
+
+    let mut out = separator.clone();
+    for (column, w) in columns.iter().zip(&widths) {
+        out += &format!("| {}{} ", column, " ".repeat(w - width(column)));
+    }
+    out += "|\n";
+    out += &separator;
+    for (row, texts) in rows.iter().zip(&cells) {
+        for (i, (text, w)) in texts.iter().zip(&widths).enumerate() {
+            let padding = " ".repeat(w - width(text));
+            // Numbers are right-aligned, everything else left-aligned
+            match is_numeric(row, i) {
+                true => out += &format!("| {}{} ", padding, text),
+                false => out += &format!("| {}{} ", text, padding),
+            }
+        }
+        out += "|\n";
+    }
+    if !rows.is_empty() {
+        out += &separator;
+    }
+    out
+}
+
+fn render_vertical(columns: &[String], rows: &[Row]) -> String {
+    let name_width = columns.iter().map(|c| width(c)).max().unwrap_or(0);
+    let mut out = String::new();
+    for (n, row) in rows.iter().enumerate() {
+        out += &format!("{} {}. row {}\n", "*".repeat(27), n + 1, "*".repeat(27));
+        for (i, column) in columns.iter().enumerate() {
+            out += &format!(
+                "{}{}: {}\n",
+                " ".repeat(name_width - width(column)),
+                column,
+                cell(row, i)
+            );
+        }
+    }
+    out
+}
+
+/// Renders CSV with the default dialect, so the output can be imported with `\copy`.
+fn render_csv(columns: &[String], rows: &[Row]) -> String {
+    csv::write(columns, rows, &CsvOptions::default())
+}
+
+fn tsv_field(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn render_tsv(columns: &[String], rows: &[Row]) -> String {
+    let mut out = columns
+        .iter()
+        .map(|c| tsv_field(c))
+        .collect::<Vec<String>>()
+        .join("\t")
+        + "\n";
+    for row in rows {
+        let fields: Vec<String> = (0..columns.len())
+            .map(|i| match row.values.get(i).and_then(display) {
+                Some(text) => tsv_field(&text),
+                None => "\\N".to_string(),
+            })
+            .collect();
+        out += &fields.join("\t");
+        out += "\n";
+    }
+    out
+}
+
+fn json_value(value: Option<&Value>) -> serde_json::Value {
+    match value {
+        Some(Value::Str(s)) => serde_json::Value::from(s.as_str()),
+        Some(Value::Bool(b)) => serde_json::Value::from(*b),
+        Some(Value::Int(i)) => serde_json::Value::from(*i),
+        Some(Value::Float(f)) => serde_json::Value::from(*f),
+        Some(Value::Null) | None => serde_json::Value::Null,
+    }
+}
+
+/// Renders one object per line. The objects are built by hand to keep the column order.
+fn render_json(columns: &[String], rows: &[Row]) -> String {
+    if rows.is_empty() {
+        return "[]\n".to_string();
+    }
+    let objects: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let members: Vec<String> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    format!(
+                        "{}:{}",
+                        serde_json::Value::from(column.as_str()),
+                        json_value(row.values.get(i))
+                    )
+                })
+                .collect();
+            format!("  {{{}}}", members.join(","))
+        })
+        .collect();
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+fn markdown_field(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn render_markdown(columns: &[String], rows: &[Row]) -> String {
+    let header: Vec<String> = columns.iter().map(|c| markdown_field(c)).collect();
+    let mut out = format!("| {} |\n", header.join(" | "));
+    let alignments: Vec<&str> = (0..columns.len())
+        .map(
+            |i| match rows.first().is_some_and(|row| is_numeric(row, i)) {
+                true => "---:",
+                false => "---",
+            },
+        )
+        .collect();
+    out += &format!("| {} |\n", alignments.join(" | "));
+    for row in rows {
+        let fields: Vec<String> = (0..columns.len())
+            .map(|i| markdown_field(&cell(row, i)))
+            .collect();
+        out += &format!("| {} |\n", fields.join(" | "));
+    }
+    out
+}
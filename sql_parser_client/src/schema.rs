@@ -0,0 +1,234 @@
+use core::catalog::{table_constraints, ConstraintType};
+use core::privilege::Grant;
+use core::request_types::{RoleInfo, UserInfo};
+use core::row::Row;
+use core::table::TableSchema;
+use core::value::Value;
+
+/// A result to be printed: column headers and rows of values.
+pub type ResultSet = (Vec<String>, Vec<Row>);
+
+fn headers(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+/// Lists the table names, as printed by `SHOW TABLES`.
+///
+/// # Parameters
+///
+/// - `tables`: All tables of the database.
+pub fn show_tables(tables: &[TableSchema]) -> ResultSet {
+    let mut names: Vec<&str> = tables.iter().map(|table| table.name.as_str()).collect();
+    names.sort();
+    let rows = names
+        .into_iter()
+        .map(|name| Row::new(vec![Value::from(name)]))
+        .collect();
+    (headers(&["Tables"]), rows)
+}
+
+/// Lists the users, whether they are admins and their roles, as printed by `SHOW USERS`.
+///
+/// # Parameters
+///
+/// - `users`: All users of the server.
+pub fn show_users(users: &[UserInfo]) -> ResultSet {
+    let mut users: Vec<&UserInfo> = users.iter().collect();
+    users.sort_by(|a, b| a.username.cmp(&b.username));
+    let rows = users
+        .into_iter()
+        .map(|user| {
+            Row::new(vec![
+                Value::from(user.username.as_str()),
+                Value::Bool(user.admin),
+                Value::from(user.roles.join(", ").as_str()),
+            ])
+        })
+        .collect();
+    (headers(&["User", "Admin", "Roles"]), rows)
+}
+
+/// Lists the roles and their members, as printed by `SHOW ROLES`.
+///
+/// # Parameters
+///
+/// - `roles`: All roles of the server.
+pub fn show_roles(roles: &[RoleInfo]) -> ResultSet {
+    let mut roles: Vec<&RoleInfo> = roles.iter().collect();
+    roles.sort_by(|a, b| a.name.cmp(&b.name));
+    let rows = roles
+        .into_iter()
+        .map(|role| {
+            Row::new(vec![
+                Value::from(role.name.as_str()),
+                Value::from(role.members.join(", ").as_str()),
+            ])
+        })
+        .collect();
+    (headers(&["Role", "Members"]), rows)
+}
+
+/// Lists grants as `GRANT` statements, as printed by `SHOW GRANTS`.
+///
+/// # Parameters
+///
+/// - `grants`: The grants to list.
+pub fn show_grants(grants: &[Grant]) -> ResultSet {
+    let rows = grants
+        .iter()
+        .map(|grant| Row::new(vec![Value::from(grant.to_string().as_str())]))
+        .collect();
+    (headers(&["Grants"]), rows)
+}
+
+/// Lists the tables with their number of columns and rows, as printed by `\dt`.
+///
+/// # Parameters
+///
+/// - `tables`: All tables of the database.
+pub fn list_tables(tables: &[TableSchema]) -> ResultSet {
+    let mut tables: Vec<&TableSchema> = tables.iter().collect();
+    tables.sort_by(|a, b| a.name.cmp(&b.name));
+    let rows = tables
+        .into_iter()
+        .map(|table| {
+            Row::new(vec![
+                Value::from(table.name.as_str()),
+                Value::Int(table.columns.len() as i64),
+                Value::Int(table.row_count as i64),
+            ])
+        })
+        .collect();
+    (headers(&["Name", "Columns", "Rows"]), rows)
+}
+
+/// Describes the columns of a table, as printed by `DESCRIBE`.
+///
+/// `Key` is `PRI` for primary key columns, `UNI` for columns with a single-column unique
+/// constraint and `MUL` for foreign key columns.
+///
+/// # Parameters
+///
+/// - `table`: The table to describe.
+pub fn describe(table: &TableSchema) -> ResultSet {
+    let rows = table
+        .columns
+        .iter()
+        .map(|column| {
+            let key = if table.primary_key.contains(&column.key) {
+                "PRI"
+            } else if table
+                .unique_constraints
+                .iter()
+                .any(|constraint| constraint.columns == [column.key.clone()])
+            {
+                "UNI"
+            } else if column.foreign_key.is_some() {
+                "MUL"
+            } else {
+                ""
+            };
+
+            let mut extra = Vec::new();
+            if column.auto_increment {
+                extra.push("auto_increment".to_string());
+            }
+            if let Some(check) = &column.check {
+                extra.push(format!("CHECK ({})", check));
+            }
+            if let Some(foreign_key) = &column.foreign_key {
+                extra.push(format!(
+                    "REFERENCES {}({})",
+                    foreign_key.table_name, foreign_key.column
+                ));
+            }
+
+            Row::new(vec![
+                Value::from(column.key.as_str()),
+                column
+                    .data_type
+                    .map_or(Value::Null, |data_type| Value::Str(data_type.to_string())),
+                Value::from(match column.non_null {
+                    true => "NO",
+                    false => "YES",
+                }),
+                Value::from(key),
+                column
+                    .default
+                    .as_ref()
+                    .map_or(Value::Null, |default| Value::Str(default.to_sql())),
+                Value::Str(extra.join(", ")),
+            ])
+        })
+        .collect();
+    (
+        headers(&["Field", "Type", "Null", "Key", "Default", "Extra"]),
+        rows,
+    )
+}
+
+/// Lists the indexes, foreign keys, check constraints and row-level security policies of a
+/// table, as printed after the columns by `\d`.
+///
+/// Primary keys and unique constraints are listed as indexes, since they are enforced
+/// through unique keys.
+///
+/// # Parameters
+///
+/// - `table`: The table to describe.
+///
+/// # Returns
+///
+/// Returns one section per kind of constraint the table has and one for its policies, each
+/// ending with a newline.
+pub fn constraints(table: &TableSchema) -> String {
+    let mut indexes = Vec::new();
+    let mut foreign_keys = Vec::new();
+    let mut checks = Vec::new();
+    for constraint in table_constraints(table) {
+        let definition = format!(
+            "{} {} ({})",
+            constraint.name,
+            constraint.constraint_type.as_str(),
+            constraint.columns.join(", ")
+        );
+        match constraint.constraint_type {
+            ConstraintType::PrimaryKey | ConstraintType::Unique => indexes.push(definition),
+            ConstraintType::ForeignKey => {
+                if let Some(references) = constraint.references {
+                    foreign_keys.push(format!(
+                        "{} REFERENCES {}({})",
+                        definition, references.table_name, references.column
+                    ));
+                }
+            }
+            ConstraintType::Check => {
+                if let Some(check) = constraint.check {
+                    checks.push(format!("{} CHECK ({})", constraint.name, check));
+                }
+            }
+        }
+    }
+
+    let policies = table
+        .policies
+        .iter()
+        .map(|policy| policy.to_sql(&table.name))
+        .collect();
+
+    let mut out = String::new();
+    for (title, lines) in [
+        ("Indexes", indexes),
+        ("Foreign-key constraints", foreign_keys),
+        ("Check constraints", checks),
+        ("Policies", policies),
+    ] {
+        if !lines.is_empty() {
+            out += &format!("{}:\n", title);
+            for line in lines {
+                out += &format!("    {}\n", line);
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,235 @@
+/// A statement or backslash command read from a script.
+#[derive(Debug, PartialEq)]
+pub struct ScriptStatement {
+    /// The text of the statement, including its `;` or `\G` terminator.
+    pub text: String,
+    /// The 1-based line the statement starts on.
+    pub line: usize,
+}
+
+/// Splits a script into statements.
+///
+/// Statements end with `;` or `\G` outside of string literals, quoted identifiers and
+/// comments. A line starting with a backslash command such as `\format csv` is a statement
+/// of its own. Text after the last terminator is returned as a final statement.
+///
+/// # Parameters
+///
+/// - `script`: The text of the script.
+///
+/// # Returns
+///
+/// Returns the non-empty statements in order. Comments before a statement are dropped.
+pub fn split_statements(script: &str) -> Vec<ScriptStatement> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut start_line = 1;
+    let mut line = 1;
+    let mut chars = script.chars().peekable();
+
+    // Pushes the current statement unless it only consists of its terminator, whitespace
+    // and comments
+    let mut finish = |current: &mut String, start_line: usize| {
+        let body = current.trim_end();
+        let body = body
+            .strip_suffix(';')
+            .or_else(|| body.strip_suffix("\\G"))
+            .unwrap_or(body);
+        if has_content(body) {
+            statements.push(ScriptStatement {
+                text: current.trim().to_string(),
+                line: start_line,
+            });
+        }
+        current.clear();
+    };
+
+    while let Some(c) = chars.next() {
+        // Whitespace and comments between statements are dropped
+        if !has_content(&current) {
+            start_line = line;
+            current.clear();
+        }
+        current.push(c);
+        match c {
+            '\n' => line += 1,
+            '\'' | '"' | '`' => {
+                while let Some(inner) = chars.next() {
+                    current.push(inner);
+                    if inner == '\n' {
+                        line += 1;
+                    }
+                    if inner == '\\' && c != '`' {
+                        if let Some(escaped) = chars.next() {
+                            if escaped == '\n' {
+                                line += 1;
+                            }
+                            current.push(escaped);
+                        }
+                    } else if inner == c {
+                        // A doubled quote is an escaped quote, not the end of the literal
+                        if chars.peek() == Some(&c) {
+                            current.push(c);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for inner in chars.by_ref() {
+                    if inner == '\n' {
+                        line += 1;
+                        current.push(inner);
+                        break;
+                    }
+                    current.push(inner);
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                // The opening `*` cannot also be the start of the closing `*/`
+                current.push('*');
+                chars.next();
+                let mut previous = ' ';
+                for inner in chars.by_ref() {
+                    current.push(inner);
+                    if inner == '\n' {
+                        line += 1;
+                    }
+                    if previous == '*' && inner == '/' {
+                        break;
+                    }
+                    previous = inner;
+                }
+            }
+            ';' => finish(&mut current, start_line),
+            '\\' if chars.peek() == Some(&'G') => {
+                current.push('G');
+                chars.next();
+                finish(&mut current, start_line);
+            }
+            '\\' if current.trim() == "\\" => {
+                for inner in chars.by_ref() {
+                    if inner == '\n' {
+                        line += 1;
+                        break;
+                    }
+                    current.push(inner);
+                }
+                finish(&mut current, start_line);
+            }
+            _ => {}
+        }
+    }
+    finish(&mut current, start_line);
+    statements
+}
+
+/// Returns whether `text` contains anything but whitespace and comments.
+fn has_content(text: &str) -> bool {
+    let mut rest = text.trim_start();
+    loop {
+        if let Some(comment) = rest.strip_prefix("--") {
+            match comment.find('\n') {
+                Some(end) => rest = comment[end..].trim_start(),
+                None => return false,
+            }
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            match comment.find("*/") {
+                Some(end) => rest = comment[end + 2..].trim_start(),
+                None => return false,
+            }
+        } else {
+            return !rest.is_empty();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(script: &str) -> Vec<String> {
+        split_statements(script)
+            .into_iter()
+            .map(|statement| statement.text)
+            .collect()
+    }
+
+    #[test]
+    fn statements_end_at_semicolons_outside_literals_and_comments() {
+        let statements = split_statements(
+            "SELECT 'a;b', `c;d` FROM t;\n\
+             -- skipped; entirely\n\
+             INSERT INTO t (a) VALUES ('it''s; \\';');\n\
+             /* one; */ SELECT\n  1;",
+        );
+        assert_eq!(
+            statements,
+            [
+                ScriptStatement {
+                    text: "SELECT 'a;b', `c;d` FROM t;".to_string(),
+                    line: 1
+                },
+                ScriptStatement {
+                    text: "INSERT INTO t (a) VALUES ('it''s; \\';');".to_string(),
+                    line: 3
+                },
+                ScriptStatement {
+                    text: "SELECT\n  1;".to_string(),
+                    line: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn vertical_terminator_ends_a_statement() {
+        assert_eq!(
+            texts("SELECT * FROM t\\G SELECT 1;\\G"),
+            ["SELECT * FROM t\\G", "SELECT 1;"]
+        );
+        // Inside a literal `\G` is only an escape
+        assert_eq!(texts("SELECT '\\G;'\\G"), ["SELECT '\\G;'\\G"]);
+    }
+
+    #[test]
+    fn backslash_commands_take_the_rest_of_the_line() {
+        let statements = split_statements("\\format csv\nSELECT a FROM t;\n  \\timing on");
+        assert_eq!(
+            statements,
+            [
+                ScriptStatement {
+                    text: "\\format csv".to_string(),
+                    line: 1
+                },
+                ScriptStatement {
+                    text: "SELECT a FROM t;".to_string(),
+                    line: 2
+                },
+                ScriptStatement {
+                    text: "\\timing on".to_string(),
+                    line: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn block_comments_need_their_own_closing_star() {
+        // The lexer reads `/*/` as the start of a comment, so the `;` must not end it
+        assert_eq!(texts("/*/ ; */ SELECT 1;"), ["SELECT 1;"]);
+        assert_eq!(texts("/**/ SELECT 1; /* ; */"), ["SELECT 1;"]);
+    }
+
+    #[test]
+    fn unterminated_input_is_the_final_statement() {
+        assert_eq!(
+            texts("SELECT 1;\nSELECT 'open; still open"),
+            ["SELECT 1;", "SELECT 'open; still open"]
+        );
+        assert_eq!(texts("SELECT 1; /* open ; "), ["SELECT 1;"]);
+        assert!(split_statements(" ;\n-- only a comment\n;").is_empty());
+    }
+}